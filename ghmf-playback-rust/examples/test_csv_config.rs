@@ -29,12 +29,12 @@ fn main() {
             // Test fixture lookup
             println!("\n💡 Sample Fixtures:");
             if let Some(fixture) = config.get_fixture(1) {
-                println!("  Fixture 1: {} at DMX channel {} ({:?})", 
-                    fixture.note, fixture.dmx_channel, fixture.format);
+                println!("  Fixture 1: {} at DMX channel {} ({})", 
+                    fixture.note, fixture.dmx_channel, fixture.profile.name);
             }
             if let Some(fixture) = config.get_fixture(42) {
-                println!("  Fixture 42: {} at DMX channel {} ({:?})", 
-                    fixture.note, fixture.dmx_channel, fixture.format);
+                println!("  Fixture 42: {} at DMX channel {} ({})", 
+                    fixture.note, fixture.dmx_channel, fixture.profile.name);
             }
             
             // Test FCW mapping
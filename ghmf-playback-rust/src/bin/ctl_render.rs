@@ -0,0 +1,107 @@
+//! Render a CTL show to DMX and PLC output offline, for regression diffing
+//!
+//! Usage:
+//!   ctl_render <song.ctl> <config_dir> <fps> [-o out.txt] [--capture out.dmxrec] [--white calibrated|min|off] [--curve linear|s_curve|square_law|exponential] [--pyro]
+//!
+//! The config directory must hold legacy_colors.json, dmx_mapping.json and
+//! light_groups.json. Output goes to stdout unless `-o` is given. Firework
+//! channels stay at zero unless `--pyro` renders the show as if armed.
+
+use anyhow::{bail, Context, Result};
+use ghmf_playback::commands::{render_ctl, CtlFile};
+use ghmf_playback::config::CsvConfig;
+use ghmf_playback::dmx::DmxRecorder;
+use ghmf_playback::lighting::{FadeCurve, FixtureManager};
+use ghmf_playback::lighting::color::WhiteStrategy;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+fn usage() -> ! {
+    eprintln!("Usage:");
+    eprintln!("  ctl_render <song.ctl> <config_dir> <fps> [-o out.txt] [--capture out.dmxrec] [--white calibrated|min|off] [--curve linear|s_curve|square_law|exponential] [--pyro]");
+    std::process::exit(2);
+}
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() < 3 {
+        usage();
+    }
+
+    let ctl_path = &args[0];
+    let config_dir = &args[1];
+    let frame_rate: f64 = args[2].parse().with_context(|| format!("Invalid frame rate '{}'", args[2]))?;
+
+    let mut output_path = None;
+    let mut capture_path = None;
+    let mut strategy = WhiteStrategy::Calibrated;
+    let mut curve = FadeCurve::Linear;
+    let mut pyro = false;
+    let mut rest = args[3..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-o" => output_path = Some(rest.next().unwrap_or_else(|| usage()).clone()),
+            "--capture" => capture_path = Some(rest.next().unwrap_or_else(|| usage()).clone()),
+            "--white" => {
+                let value = rest.next().unwrap_or_else(|| usage());
+                strategy = WhiteStrategy::from_setting(value);
+                if strategy.as_setting() != value {
+                    bail!("Unknown white strategy '{}'", value);
+                }
+            }
+            "--curve" => {
+                let value = rest.next().unwrap_or_else(|| usage());
+                curve = FadeCurve::from_setting(value)
+                    .with_context(|| format!("Unknown fade curve '{}'", value))?;
+            }
+            "--pyro" => pyro = true,
+            other => bail!("Unknown argument '{}'", other),
+        }
+    }
+
+    let config = CsvConfig::load_from_dir(config_dir)
+        .with_context(|| format!("Failed to load config from {}", config_dir))?;
+    let ctl = CtlFile::load(ctl_path).with_context(|| format!("Failed to load {}", ctl_path))?;
+
+    let mut fm = FixtureManager::new(config);
+    fm.set_white_strategy(strategy);
+    fm.set_default_fade_curve(curve);
+    if pyro {
+        fm.arm_pyro();
+        fm.set_pyro_live(true);
+    }
+
+    let song_name = Path::new(ctl_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| ctl_path.clone());
+
+    let mut recorder = match &capture_path {
+        Some(path) => Some(DmxRecorder::create(path, &song_name)?),
+        None => None,
+    };
+
+    let mut out: Box<dyn Write> = match &output_path {
+        Some(path) => Box::new(BufWriter::new(
+            std::fs::File::create(path).with_context(|| format!("Failed to create {}", path))?,
+        )),
+        None => Box::new(BufWriter::new(std::io::stdout().lock())),
+    };
+
+    writeln!(out, "# {} @ {} fps, {}", song_name, frame_rate, strategy.label())?;
+    let summary = render_ctl(&ctl, &mut fm, frame_rate, &mut out, recorder.as_mut())?;
+    out.flush()?;
+
+    if let Some(recorder) = recorder {
+        recorder.finish()?;
+    }
+
+    eprintln!(
+        "Rendered {} frames ({:.1}s), {} PLC commands, {} held to water limits",
+        summary.frames,
+        summary.end_ms as f64 / 1000.0,
+        summary.plc_commands,
+        summary.water_warnings
+    );
+    Ok(())
+}
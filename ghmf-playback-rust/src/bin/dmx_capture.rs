@@ -0,0 +1,120 @@
+//! Inspect DMX capture files recorded by Fountain Director
+//!
+//! Usage:
+//!   dmx_capture info <file.dmxrec>
+//!   dmx_capture dump <file.dmxrec> [first_channel] [last_channel]
+//!   dmx_capture diff <left.dmxrec> <right.dmxrec>
+
+use anyhow::{bail, Context, Result};
+use ghmf_playback::dmx::DmxRecording;
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.first().map(String::as_str) {
+        Some("info") if args.len() == 2 => info(&args[1]),
+        Some("dump") if args.len() >= 2 => {
+            let first: usize = args.get(2).map(|a| a.parse()).transpose()?.unwrap_or(1);
+            let last: usize = args.get(3).map(|a| a.parse()).transpose()?.unwrap_or(first + 15);
+            if first == 0 || last > 512 || first > last {
+                bail!("Channel range must be within 1-512");
+            }
+            dump(&args[1], first, last)
+        }
+        Some("diff") if args.len() == 3 => diff(&args[1], &args[2]),
+        _ => {
+            eprintln!("Usage:");
+            eprintln!("  dmx_capture info <file.dmxrec>");
+            eprintln!("  dmx_capture dump <file.dmxrec> [first_channel] [last_channel]");
+            eprintln!("  dmx_capture diff <left.dmxrec> <right.dmxrec>");
+            std::process::exit(2);
+        }
+    }
+}
+
+fn load(path: &str) -> Result<DmxRecording> {
+    DmxRecording::load(path).with_context(|| format!("Failed to load {}", path))
+}
+
+fn format_time(time_ms: u32) -> String {
+    format!("{:02}:{:02}.{:03}", time_ms / 60_000, (time_ms / 1000) % 60, time_ms % 1000)
+}
+
+fn info(path: &str) -> Result<()> {
+    let recording = load(path)?;
+
+    let started = chrono::DateTime::from_timestamp_millis(recording.started_unix_ms as i64)
+        .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    let duration_s = recording.duration_ms() as f32 / 1000.0;
+
+    println!("Label:    {}", recording.label);
+    println!("Started:  {}", started);
+    println!("Duration: {} ({:.1}s)", format_time(recording.duration_ms()), duration_s);
+    println!("Frames:   {}", recording.frame_count());
+    if duration_s > 0.0 {
+        println!("Rate:     {:.1} fps", recording.frame_count() as f32 / duration_s);
+    }
+
+    if !recording.markers.is_empty() {
+        println!();
+        println!("Markers:");
+        for (time_ms, text) in &recording.markers {
+            println!("  {}  {}", format_time(*time_ms), text);
+        }
+    }
+
+    Ok(())
+}
+
+fn dump(path: &str, first: usize, last: usize) -> Result<()> {
+    let recording = load(path)?;
+    let mut markers = recording.markers.iter().peekable();
+    let mut previous: Option<Vec<u8>> = None;
+
+    println!("{:>9}  channels {}-{}", "time", first, last);
+    for frame in recording.frames() {
+        while let Some((time_ms, text)) = markers.next_if(|(t, _)| *t <= frame.time_ms) {
+            println!("{}  --- {} ---", format_time(*time_ms), text);
+        }
+
+        // Only print frames where the selected channels changed
+        let values = &frame.data[first - 1..last];
+        if previous.as_deref() == Some(values) {
+            continue;
+        }
+        previous = Some(values.to_vec());
+
+        let line: Vec<String> = values.iter().map(|v| format!("{:3}", v)).collect();
+        println!("{}  {}", format_time(frame.time_ms), line.join(" "));
+    }
+
+    Ok(())
+}
+
+fn diff(left_path: &str, right_path: &str) -> Result<()> {
+    let left = load(left_path)?;
+    let right = load(right_path)?;
+
+    match left.first_difference(&right) {
+        None => {
+            println!("No differences ({} frames compared)", left.frame_count());
+            Ok(())
+        }
+        Some(difference) => {
+            println!(
+                "First difference at {}: channel {} is {} in {} but {} in {}",
+                format_time(difference.time_ms),
+                difference.channel,
+                difference.left,
+                left_path,
+                difference.right,
+                right_path
+            );
+            if let Some(marker) = left.marker_at(difference.time_ms) {
+                println!("During: {}", marker);
+            }
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! Stand-in for the fountain PLC, for rehearsal and testing without hardware
+//!
+//! Usage:
+//!   plc_sim [--bind ip:port] [--delay ms] [--silent] [--stall-every n] [--drop-every n]
+//!
+//! Listens on the PLC address from the settings file unless `--bind` is
+//! given, acknowledges batches the way the PLC does and redraws the water
+//! state as commands arrive. `--delay` holds every reply, `--silent` never
+//! replies, `--stall-every` leaves every nth batch unacknowledged and
+//! `--drop-every` closes the connection after every nth batch.
+
+use anyhow::{bail, Context, Result};
+use ghmf_playback::config::{Settings, WaterMap};
+use ghmf_playback::plc::simulator::{PlcSimulator, SimOptions};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+fn usage() -> ! {
+    eprintln!("Usage:");
+    eprintln!("  plc_sim [--bind ip:port] [--delay ms] [--silent] [--stall-every n] [--drop-every n]");
+    std::process::exit(2);
+}
+
+fn number(rest: &mut std::slice::Iter<String>, name: &str) -> Result<u32> {
+    let value = rest.next().unwrap_or_else(|| usage());
+    value.parse().with_context(|| format!("Invalid {} '{}'", name, value))
+}
+
+/// Simulator state shared by the connection and the display
+struct Shared {
+    sim: PlcSimulator,
+    client: Option<String>,
+    connections: u32,
+    started: Instant,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let settings = Settings::load();
+    let mut bind = format!("{}:{}", settings.plc_ip_address, settings.plc_port);
+    let mut options = SimOptions::default();
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--bind" => bind = rest.next().unwrap_or_else(|| usage()).clone(),
+            "--delay" => options.reply_delay = Duration::from_millis(number(&mut rest, "delay")? as u64),
+            "--silent" => options.silent = true,
+            "--stall-every" => options.stall_every = Some(number(&mut rest, "stall count")?),
+            "--drop-every" => options.drop_every = Some(number(&mut rest, "drop count")?),
+            "-h" | "--help" => usage(),
+            other => bail!("Unknown argument '{}'", other),
+        }
+    }
+
+    let water = WaterMap::load_from_dir("Config").unwrap_or_else(|e| {
+        eprintln!("{:#}; using the built-in water addresses", e);
+        WaterMap::builtin()
+    });
+
+    let listener = TcpListener::bind(&bind).await
+        .with_context(|| format!("Failed to listen on {} (set the PLC address to 127.0.0.1 or pass --bind)", bind))?;
+    let shared = Arc::new(Mutex::new(Shared {
+        sim: PlcSimulator::new(options).with_water_map(water),
+        client: None,
+        connections: 0,
+        started: Instant::now(),
+    }));
+
+    let display = Arc::clone(&shared);
+    let bind_label = bind.clone();
+    tokio::spawn(async move {
+        loop {
+            if let Ok(shared) = display.lock() {
+                draw(&bind_label, &shared);
+            }
+            tokio::time::sleep(Duration::from_millis(250)).await;
+        }
+    });
+
+    // The real PLC serves one client at a time
+    loop {
+        let (socket, peer) = listener.accept().await?;
+        if let Ok(mut shared) = shared.lock() {
+            shared.client = Some(peer.to_string());
+            shared.connections += 1;
+        }
+        serve(socket, &shared).await;
+        if let Ok(mut shared) = shared.lock() {
+            shared.client = None;
+        }
+    }
+}
+
+/// Handle one client until it disconnects or a drop is injected
+async fn serve(socket: TcpStream, shared: &Arc<Mutex<Shared>>) {
+    let (read_half, mut write_half) = socket.into_split();
+    let mut lines = BufReader::new(read_half).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        let (response, delay) = {
+            let Ok(mut shared) = shared.lock() else { return };
+            let delay = shared.sim.options().reply_delay;
+            (shared.sim.handle_line(&line, Instant::now()), delay)
+        };
+
+        if !response.replies.is_empty() && !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        for reply in response.replies {
+            if write_half.write_all(format!("{}\r\n", reply).as_bytes()).await.is_err() {
+                return;
+            }
+        }
+        if response.disconnect {
+            let _ = write_half.shutdown().await;
+            return;
+        }
+    }
+}
+
+fn draw(bind: &str, shared: &Shared) {
+    let mut out = String::from("\x1b[2J\x1b[H");
+    let uptime = shared.started.elapsed().as_secs();
+    out.push_str(&format!("PLC simulator on {}   up {:02}:{:02}\n", bind, uptime / 60, uptime % 60));
+    out.push_str(&format!(
+        "Client: {}   connections: {}   batches: {}\n",
+        shared.client.as_deref().unwrap_or("none"),
+        shared.connections,
+        shared.sim.batches(),
+    ));
+
+    let options = shared.sim.options();
+    let mut faults = Vec::new();
+    if !options.reply_delay.is_zero() {
+        faults.push(format!("delay {}ms", options.reply_delay.as_millis()));
+    }
+    if options.silent {
+        faults.push("silent".to_string());
+    }
+    if let Some(n) = options.stall_every {
+        faults.push(format!("stall every {}", n));
+    }
+    if let Some(n) = options.drop_every {
+        faults.push(format!("drop every {}", n));
+    }
+    if !faults.is_empty() {
+        out.push_str(&format!("Injecting: {}\n", faults.join(", ")));
+    }
+    out.push_str(&format!("Last: {}\n\n", shared.sim.last_line()));
+
+    if shared.sim.outputs().is_empty() {
+        out.push_str("All water off\n");
+    }
+    for (address, output) in shared.sim.outputs() {
+        let state = if output.is_on() { "ON " } else { "off" };
+        out.push_str(&format!(
+            "{:03}  {}  {:>3}  {:>5.1}s ago\n",
+            address,
+            state,
+            output.data,
+            output.changed_at.elapsed().as_secs_f32(),
+        ));
+    }
+
+    print!("{}", out);
+    use std::io::Write;
+    let _ = std::io::stdout().flush();
+}
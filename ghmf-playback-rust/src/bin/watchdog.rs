@@ -0,0 +1,138 @@
+//! Stops the water if the player goes quiet mid-song
+//!
+//! Usage:
+//!   watchdog [--port n] [--timeout secs]
+//!
+//! Run alongside the player with `watchdog_enabled` on. The player sends a
+//! heartbeat to 127.0.0.1 on the watchdog port while a song is running; if
+//! they stop, or only report the player stale, for longer than the timeout
+//! without an idle or exit message, this sends the E-stop sequence to the
+//! PLC and the failsafe look to the DMX interface from its own connections,
+//! holding the interface until the player is heard again. A PLC or
+//! interface the player still holds open may refuse them.
+
+use anyhow::{bail, Context, Result};
+use ghmf_playback::config::{CsvConfig, Settings, WaterMap};
+use ghmf_playback::dmx::DmxDevice;
+use ghmf_playback::failsafe::{self, FailsafeLook, Heartbeat, WatchdogState};
+use ghmf_playback::lighting::color::WhiteStrategy;
+use ghmf_playback::plc::PlcClient;
+use std::net::{Ipv4Addr, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// How long to wait for the PLC to take the E-stop, connect included
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn usage() -> ! {
+    eprintln!("Usage:");
+    eprintln!("  watchdog [--port n] [--timeout secs]");
+    std::process::exit(2);
+}
+
+fn main() -> Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let settings = Settings::load();
+    let mut port = settings.watchdog_port;
+    let mut timeout = settings.watchdog_timeout_secs;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--port" => {
+                let value = value();
+                port = value.parse().with_context(|| format!("Invalid port '{}'", value))?;
+            }
+            "--timeout" => {
+                let value = value();
+                timeout = value.parse().with_context(|| format!("Invalid timeout '{}'", value))?;
+            }
+            "-h" | "--help" => usage(),
+            other => bail!("Unknown argument '{}'", other),
+        }
+    }
+
+    let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, port))
+        .with_context(|| format!("Failed to listen on port {}", port))?;
+    socket.set_read_timeout(Some(Duration::from_millis(200)))?;
+    tracing::info!("Watching for player heartbeats on port {} ({} s timeout)", port, timeout);
+
+    let mut state = WatchdogState::new(Duration::from_secs_f32(timeout.max(0.5)));
+    let mut buf = [0u8; 64];
+    // Holds the failsafe look on the rig until the player is back
+    let mut failsafe_dmx: Option<DmxDevice> = None;
+    let mut stale = false;
+    loop {
+        if let Ok((len, _)) = socket.recv_from(&mut buf) {
+            let message = String::from_utf8_lossy(&buf[..len]);
+            match Heartbeat::parse(&message) {
+                Some(Heartbeat::Stale) => {
+                    if !stale && state.is_armed() {
+                        tracing::warn!("Player is up but has stopped running the show");
+                    }
+                    stale = true;
+                    state.heard(Heartbeat::Stale, Instant::now());
+                }
+                Some(beat) => {
+                    stale = false;
+                    if failsafe_dmx.take().is_some() {
+                        tracing::info!("Player is back; releasing the DMX interface");
+                    }
+                    if beat == Heartbeat::Playing && !state.is_armed() {
+                        tracing::info!("Player running a song; armed");
+                    }
+                    state.heard(beat, Instant::now());
+                }
+                None => tracing::warn!("Ignoring '{}'", message.trim()),
+            }
+        }
+        if state.check(Instant::now()) {
+            tracing::error!("Player silent for {} s mid-song; sending E-stop", timeout);
+            failsafe_dmx = stop(&settings);
+        }
+    }
+}
+
+/// Stop the water and set the failsafe look from fresh connections
+///
+/// Returns the DMX interface carrying the look; an Open DMX line only
+/// keeps it while the driver is running.
+fn stop(settings: &Settings) -> Option<DmxDevice> {
+    let config = CsvConfig::load_from_dir("Config")
+        .map_err(|e| tracing::warn!("Failed to load config: {:#}", e))
+        .ok();
+    let water = WaterMap::load_from_dir("Config").unwrap_or_else(|_| WaterMap::builtin());
+
+    let plc = PlcClient::from_settings(settings, &water);
+    let sender = plc.take_sender().map(|sender| {
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            rt.block_on(sender.run());
+        })
+    });
+    if plc.emergency_stop_blocking(&settings.estop_sequence, STOP_TIMEOUT) {
+        tracing::warn!("E-stop sent: {}", settings.estop_sequence.join(" "));
+    } else {
+        tracing::error!("PLC did not take the E-stop");
+    }
+    // Closing the client lets the sender disconnect
+    drop(plc);
+    if let Some(sender) = sender {
+        let _ = sender.join();
+    }
+
+    let look = FailsafeLook::from_setting(&settings.failsafe_look);
+    let white = WhiteStrategy::from_setting(&settings.white_strategy);
+    let (true, Some(frame)) = (settings.dmx_enabled, look.frame(config.as_ref(), white)) else {
+        return None;
+    };
+    let mut dmx = DmxDevice::from_settings(settings);
+    if !failsafe::send_frame(&mut dmx, &frame, failsafe::FRAME_TIMEOUT) {
+        return None;
+    }
+    tracing::warn!("Failsafe look sent: {}", look.as_setting());
+    Some(dmx)
+}
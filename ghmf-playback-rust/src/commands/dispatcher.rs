@@ -0,0 +1,134 @@
+use super::CtlCommand;
+use crate::config::SCENE_RECALL_ADDRESS;
+use crate::lighting::FixtureManager;
+use std::time::Instant;
+use tracing::warn;
+
+/// A CTL command after dispatch, formatted as it appears in the file
+#[derive(Debug, Clone, PartialEq)]
+pub struct DispatchedCommand {
+    pub text: String,
+    pub is_water: bool,
+}
+
+/// Check if FCW address starts a fade (the next command holds the target color)
+pub fn is_fade_command(fcw_address: u16) -> bool {
+    // Fade commands are base address + 100 (e.g., 17->117, 18->118)
+    // Or hex color fades are 600 series (e.g., 507->607)
+    matches!(fcw_address, 100..=199 | 600..=699)
+}
+
+/// Format a command in raw CTL form: "XXX-YYY" or "XXX-RRGGBB"
+pub fn format_command(cmd: &CtlCommand) -> String {
+    if cmd.is_hex_color {
+        format!("{:03}-{}", cmd.fcw_address, cmd.hex_color.as_deref().unwrap_or("???"))
+    } else {
+        format!("{:03}-{:03}", cmd.fcw_address, cmd.data)
+    }
+}
+
+/// Apply the lighting side of one CTL line to the fixture manager
+///
+/// Fade commands consume the following command as their target. Commands
+/// water_map.json sends to the PLC are left for the caller to route there,
+/// and water-only ones skip the fixtures; every command is returned in
+/// order so it can be logged or queued. Scene recalls add the
+/// scene's water commands right after the recall.
+pub fn dispatch_commands(fm: &mut FixtureManager, commands: &[&CtlCommand], now: Instant) -> Vec<DispatchedCommand> {
+    let mut dispatched = Vec::with_capacity(commands.len());
+
+    let mut i = 0;
+    while i < commands.len() {
+        let cmd = commands[i];
+        let route = fm.config.water.route(cmd.fcw_address);
+        let is_water = route.to_plc();
+        dispatched.push(DispatchedCommand {
+            text: format_command(cmd),
+            is_water,
+        });
+
+        if is_fade_command(cmd.fcw_address) && i + 1 < commands.len() {
+            // This is a fade command - next command contains the target color
+            let fade_duration_ms = cmd.data as u64 * 100; // data is in tenths of seconds
+            let target_cmd = commands[i + 1];
+
+            // 100-199 fades regular FCW addresses (100 -> 0, 117 -> 17, etc.)
+            // 600-699 fades individual fixtures in 500-series (604 -> 504, 617 -> 517, etc.)
+            let base_address = cmd.fcw_address - 100;
+
+            dispatched.push(DispatchedCommand {
+                text: format_command(target_cmd),
+                is_water,
+            });
+
+            if base_address == SCENE_RECALL_ADDRESS {
+                // 175-<tenths> 075-<id> crossfades to a scene
+                let water = fm.recall_scene_at(target_cmd.data, fade_duration_ms, now);
+                dispatched.extend(water.into_iter().map(|text| DispatchedCommand { text, is_water: true }));
+            } else {
+                let result = fade_target(fm, target_cmd)
+                    .and_then(|(r, g, b)| fm.start_fade_at(base_address, r, g, b, fade_duration_ms, now));
+                if let Err(e) = result {
+                    warn!("Fade command execution error: {}", e);
+                }
+            }
+
+            // Skip the next command since we consumed it as the fade target
+            i += 2;
+            continue;
+        }
+
+        if cmd.fcw_address == SCENE_RECALL_ADDRESS && !cmd.is_hex_color {
+            let water = fm.recall_scene_at(cmd.data, 0, now);
+            dispatched.extend(water.into_iter().map(|text| DispatchedCommand { text, is_water: true }));
+            i += 1;
+            continue;
+        }
+
+        if !route.to_lights() {
+            i += 1;
+            continue;
+        }
+
+        let result = if cmd.is_hex_color {
+            match &cmd.hex_color {
+                Some(hex) => fm.execute_hex_command(cmd.fcw_address, hex),
+                None => Ok(()),
+            }
+        } else {
+            fm.execute_fcw_command_at(cmd.fcw_address, cmd.data, now)
+        };
+
+        if let Err(e) = result {
+            warn!("Command execution error: {}", e);
+        }
+
+        i += 1;
+    }
+
+    dispatched
+}
+
+/// Resolve the RGB target of a fade from a hex color or color index
+fn fade_target(fm: &FixtureManager, target_cmd: &CtlCommand) -> anyhow::Result<(u8, u8, u8)> {
+    if target_cmd.is_hex_color {
+        let hex = target_cmd.hex_color.as_deref()
+            .ok_or_else(|| anyhow::anyhow!("Missing hex color"))?;
+        let hex_clean = hex.trim_start_matches('#');
+        if hex_clean.len() != 6 {
+            anyhow::bail!("Invalid hex color length");
+        }
+        match (
+            u8::from_str_radix(&hex_clean[0..2], 16),
+            u8::from_str_radix(&hex_clean[2..4], 16),
+            u8::from_str_radix(&hex_clean[4..6], 16),
+        ) {
+            (Ok(r), Ok(g), Ok(b)) => Ok((r, g, b)),
+            _ => Err(anyhow::anyhow!("Invalid hex color")),
+        }
+    } else {
+        let color = fm.config.get_color(target_cmd.data)
+            .ok_or_else(|| anyhow::anyhow!("Color not found"))?;
+        color.to_rgb().map_err(|_| anyhow::anyhow!("Color conversion failed"))
+    }
+}
@@ -2,11 +2,20 @@ mod command;
 mod command_file;
 mod executor;
 mod ctl_file;
+mod dispatcher;
+mod render;
+mod scene_export;
+mod water_guard;
 
 pub use command::{Command, CommandLine};
 pub use command_file::CommandFile;
 pub use executor::CommandExecutor;
 pub use ctl_file::{CtlFile, CtlCommand, CtlLine};
+pub use dispatcher::{dispatch_commands, format_command, is_fade_command, DispatchedCommand};
+pub use render::{render_ctl, RenderSummary};
+pub use scene_export::{scene_to_ctl_line, SceneExport};
+pub use water_guard::{check_ctl, Checked, WaterGuard, WaterWarning};
+pub(crate) use water_guard::{parse_command, RESET_ADDRESS};
 
 use thiserror::Error;
 
@@ -0,0 +1,226 @@
+use super::dispatcher::dispatch_commands;
+use super::{CtlFile, WaterGuard};
+use crate::dmx::{DmxRecorder, DmxUniverse};
+use crate::lighting::FixtureManager;
+use anyhow::Result;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+/// Totals from an offline render
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderSummary {
+    pub frames: u64,
+    pub plc_commands: usize,
+    /// Water commands the guard clamped or skipped
+    pub water_warnings: usize,
+    pub end_ms: u64,
+}
+
+/// Render a CTL show offline, frame by frame, on a simulated clock
+///
+/// Each CTL line is executed at its exact timestamp, then DMX is sampled at
+/// `frame_rate`. The output is plain text meant for diffing between builds:
+/// PLC lines carry the water commands in the order they would be queued, and
+/// DMX lines list only the channels that changed since the previous frame.
+/// Water commands pass through the water guard, as in live playback.
+/// Rendering continues past the last line until every fade has finished
+/// and every held switch-off has gone out.
+pub fn render_ctl<W: Write>(
+    ctl: &CtlFile,
+    fm: &mut FixtureManager,
+    frame_rate: f64,
+    out: &mut W,
+    mut capture: Option<&mut DmxRecorder>,
+) -> Result<RenderSummary> {
+    if !(frame_rate > 0.0 && frame_rate <= 1000.0) {
+        anyhow::bail!("Frame rate must be between 0 and 1000 fps");
+    }
+
+    let base = Instant::now();
+    let frame_interval_ms = 1000.0 / frame_rate;
+    let mut summary = RenderSummary::default();
+    let mut previous = [0u8; 512];
+    let mut next_line = 0;
+    let mut guard = WaterGuard::new(fm.config.water.clone());
+
+    loop {
+        let time_ms = (summary.frames as f64 * frame_interval_ms).round() as u64;
+
+        while next_line < ctl.lines.len() && ctl.lines[next_line].time_ms <= time_ms {
+            let line = &ctl.lines[next_line];
+            next_line += 1;
+            if line.is_blank || line.commands.is_empty() {
+                continue;
+            }
+
+            for sent in guard.due_commands(line.time_ms) {
+                writeln!(out, "{} PLC {}", format_time(line.time_ms), sent)?;
+                summary.plc_commands += 1;
+            }
+
+            let commands: Vec<_> = line.commands.iter().collect();
+            let now = base + Duration::from_millis(line.time_ms);
+            for cmd in dispatch_commands(fm, &commands, now) {
+                if cmd.is_water {
+                    let checked = guard.check(line.time_ms, &cmd.text);
+                    if checked.reason().is_some() {
+                        summary.water_warnings += 1;
+                    }
+                    if let Some(sent) = checked.command() {
+                        writeln!(out, "{} PLC {}", format_time(line.time_ms), sent)?;
+                        summary.plc_commands += 1;
+                    }
+                }
+            }
+        }
+
+        for sent in guard.due_commands(time_ms) {
+            writeln!(out, "{} PLC {}", format_time(time_ms), sent)?;
+            summary.plc_commands += 1;
+        }
+
+        fm.update_fades_at(base + Duration::from_millis(time_ms));
+        let mut universe = DmxUniverse::new();
+        fm.apply_to_dmx(&mut universe)?;
+
+        let changes: Vec<String> = universe.as_slice().iter()
+            .zip(previous.iter())
+            .enumerate()
+            .filter(|(_, (new, old))| new != old)
+            .map(|(i, (new, _))| format!("{}={}", i + 1, new))
+            .collect();
+        if !changes.is_empty() {
+            writeln!(out, "{} DMX {}", format_time(time_ms), changes.join(" "))?;
+            previous.copy_from_slice(universe.as_slice());
+        }
+
+        if let Some(recorder) = capture.as_deref_mut() {
+            recorder.record_at(time_ms as u32, &universe)?;
+        }
+
+        summary.frames += 1;
+        summary.end_ms = time_ms;
+
+        if next_line >= ctl.lines.len() && time_ms >= ctl.total_duration_ms && !fm.is_fading() && !guard.is_holding() {
+            break;
+        }
+    }
+
+    Ok(summary)
+}
+
+fn format_time(time_ms: u64) -> String {
+    format!("{:02}:{:02}.{:03}", time_ms / 60_000, (time_ms / 1000) % 60, time_ms % 1000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{CtlCommand, CtlLine};
+    use crate::lighting::color::WhiteStrategy;
+    use crate::config::CsvConfig;
+
+    fn line(time_ms: u64, commands: &[&str]) -> CtlLine {
+        CtlLine {
+            time_ms,
+            commands: commands.iter().map(|c| CtlCommand::parse(time_ms, c).unwrap()).collect(),
+            is_blank: false,
+        }
+    }
+
+    fn render(ctl: &CtlFile, strategy: WhiteStrategy) -> (String, RenderSummary) {
+        let mut fm = FixtureManager::new(CsvConfig::test_rig());
+        fm.set_white_strategy(strategy);
+        let mut out = Vec::new();
+        let summary = render_ctl(ctl, &mut fm, 10.0, &mut out, None).unwrap();
+        (String::from_utf8(out).unwrap(), summary)
+    }
+
+    #[test]
+    fn test_render_changes_and_plc_stream() {
+        let ctl = CtlFile {
+            version: "1".to_string(),
+            lines: vec![
+                line(0, &["017-001", "001-005"]),
+                line(500, &["017-002"]),
+            ],
+            total_duration_ms: 500,
+        };
+
+        let (text, summary) = render(&ctl, WhiteStrategy::MinExtraction);
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines[0], "00:00.000 PLC 001-005");
+        assert_eq!(lines[1], "00:00.000 DMX 1=255");
+        // White is carried entirely by the W channel in RGBW mode
+        assert_eq!(lines[2], "00:00.500 DMX 1=0 4=255");
+        assert_eq!(lines.len(), 3);
+        assert_eq!(summary.plc_commands, 1);
+        assert_eq!(summary.frames, 6);
+    }
+
+    #[test]
+    fn test_render_runs_fades_to_completion() {
+        let ctl = CtlFile {
+            version: "1".to_string(),
+            lines: vec![
+                line(0, &["017-001"]),
+                // Fade to black over one second
+                line(1000, &["117-010", "017-000"]),
+            ],
+            total_duration_ms: 1000,
+        };
+
+        let (text, summary) = render(&ctl, WhiteStrategy::Off);
+
+        assert!(text.contains("00:01.500 DMX 1=127") || text.contains("00:01.500 DMX 1=128"));
+        assert!(text.trim_end().ends_with("DMX 1=0"));
+        assert!(summary.end_ms >= 2000);
+    }
+
+    #[test]
+    fn test_render_is_deterministic() {
+        let ctl = CtlFile {
+            version: "1".to_string(),
+            lines: vec![
+                line(0, &["017-002"]),
+                line(300, &["117-005", "017-001"]),
+            ],
+            total_duration_ms: 300,
+        };
+
+        assert_eq!(render(&ctl, WhiteStrategy::Calibrated), render(&ctl, WhiteStrategy::Calibrated));
+    }
+
+    #[test]
+    fn test_fire_channels_need_arming() {
+        use crate::lighting::pyro::PyroEventKind;
+
+        let config = CsvConfig::test_rig().with_fireworks();
+        let ctl = CtlFile {
+            version: "1".to_string(),
+            lines: vec![line(0, &["900-002"]), line(500, &["900-000"])],
+            total_duration_ms: 500,
+        };
+        let render_fire = |arm: bool| {
+            let mut fm = FixtureManager::new(config.clone());
+            if arm {
+                fm.arm_pyro();
+                fm.set_pyro_live(true);
+            }
+            let mut out = Vec::new();
+            render_ctl(&ctl, &mut fm, 10.0, &mut out, None).unwrap();
+            let kinds: Vec<PyroEventKind> = fm.take_pyro_events().into_iter().map(|e| e.kind).collect();
+            (String::from_utf8(out).unwrap(), kinds)
+        };
+
+        let (text, events) = render_fire(false);
+        assert!(!text.contains("10="));
+        assert_eq!(events, vec![PyroEventKind::Blocked { fixture_num: 54, reason: "disarmed" }]);
+
+        let (text, events) = render_fire(true);
+        assert!(text.contains("00:00.000 DMX 10=255"));
+        assert!(text.contains("00:00.500 DMX 10=0"));
+        assert_eq!(events, vec![PyroEventKind::Armed, PyroEventKind::Fired { fixture_num: 54 }]);
+    }
+}
@@ -0,0 +1,124 @@
+use crate::config::{CsvConfig, FcwDirective, Scene};
+use crate::lighting::FixtureManager;
+use std::collections::HashMap;
+
+/// A scene written out as one CTL line
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneExport {
+    pub line: String,
+    /// Fixtures with no single-fixture FCW address, left out of the line
+    pub skipped: Vec<u16>,
+}
+
+/// Write a scene as a CTL line at `time_ms` for pasting into a show
+///
+/// Each fixture gets a hex command on its single-fixture address, preferring
+/// addresses that don't lock. White is folded back into RGB so the line
+/// plays back the same under any white strategy; fixture corrections are
+/// applied again on playback.
+pub fn scene_to_ctl_line(scene: &Scene, config: &CsvConfig, time_ms: u64) -> SceneExport {
+    let addresses = single_fixture_addresses(config);
+    let mut commands = Vec::new();
+    let mut skipped = Vec::new();
+
+    for (&fixture_num, &[r, g, b, w]) in &scene.fixtures {
+        let Some(address) = addresses.get(&fixture_num) else {
+            skipped.push(fixture_num);
+            continue;
+        };
+
+        let white_point = config.get_fixture(fixture_num)
+            .filter(|fixture| fixture.profile.has_white())
+            .map(|fixture| fixture.profile.white_point())
+            .unwrap_or([0.0; 3]);
+        let fold = |value: u8, share: f32| (value as f32 + w as f32 * share).round().min(255.0) as u8;
+
+        commands.push(format!(
+            "{:03}-{:02X}{:02X}{:02X}",
+            address,
+            fold(r, white_point[0]),
+            fold(g, white_point[1]),
+            fold(b, white_point[2]),
+        ));
+    }
+    commands.extend(scene.water.iter().cloned());
+
+    SceneExport {
+        line: format!("{} {}", format_ctl_time(time_ms), commands.join(" ")),
+        skipped,
+    }
+}
+
+/// Fixture# -> lowest FCW address that drives only that fixture
+fn single_fixture_addresses(config: &CsvConfig) -> HashMap<u16, u16> {
+    let mut mappings: Vec<_> = config.fcw_mappings.values().collect();
+    // Non-lockable addresses first, then by address
+    mappings.sort_by_key(|m| (FixtureManager::is_lockable_address(m.fcw_address), m.fcw_address));
+
+    let mut addresses = HashMap::new();
+    for mapping in mappings {
+        let mut fixtures = mapping.fixture_directives.iter()
+            .filter(|(_, directive)| matches!(directive, FcwDirective::On));
+        if let (Some((&fixture_num, _)), None) = (fixtures.next(), fixtures.next()) {
+            addresses.entry(fixture_num).or_insert(mapping.fcw_address);
+        }
+    }
+    addresses
+}
+
+/// Format a time the way CTL files write it: "MM:SS.T"
+fn format_ctl_time(time_ms: u64) -> String {
+    format!("{:02}:{:02}.{}", time_ms / 60_000, (time_ms / 1000) % 60, (time_ms % 1000) / 100)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{FcwMapping, FixtureDefinition, FixtureProfile, ProfileLibrary};
+    use std::collections::BTreeMap;
+
+    fn mapping(fcw_address: u16, fixtures: &[u16]) -> FcwMapping {
+        FcwMapping {
+            fcw_address,
+            water_directive: FcwDirective::Off,
+            fixture_directives: fixtures.iter().map(|&f| (f, FcwDirective::On)).collect(),
+            fade_curve: None,
+        }
+    }
+
+    #[test]
+    fn test_scene_to_ctl_line() {
+        let mut config = CsvConfig {
+            colors: HashMap::new(),
+            fixtures: HashMap::new(),
+            fcw_mappings: HashMap::new(),
+            profiles: ProfileLibrary::builtin(),
+            effects: Default::default(),
+            layout: Default::default(),
+            scenes: Default::default(),
+            water: Default::default(),
+        };
+        config.fixtures.insert(4, FixtureDefinition {
+            fixture_number: 4,
+            note: String::new(),
+            dmx_channel: 1,
+            profile: FixtureProfile::default(),
+            correction: Default::default(),
+        });
+        // 504 locks, so fixture 4 is written on 544
+        for m in [mapping(17, &[4, 6]), mapping(504, &[4]), mapping(544, &[4]), mapping(506, &[6])] {
+            config.fcw_mappings.insert(m.fcw_address, m);
+        }
+
+        let scene = Scene {
+            id: 1,
+            name: "Test".to_string(),
+            fixtures: BTreeMap::from([(4, [255, 0, 0, 100]), (6, [0, 0, 255, 0]), (9, [1, 2, 3, 0])]),
+            water: vec!["001-005".to_string()],
+        };
+
+        let export = scene_to_ctl_line(&scene, &config, 83_450);
+        assert_eq!(export.line, "01:23.4 544-FF6464 506-0000FF 001-005");
+        assert_eq!(export.skipped, vec![9]);
+    }
+}
@@ -0,0 +1,573 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Instant;
+use super::{format_command, CtlFile};
+use crate::config::{WaterDevice, WaterMap};
+use crate::weather::WindLimit;
+
+/// Address whose `000` turns every water output off
+pub(crate) const RESET_ADDRESS: u16 = 99;
+
+/// What the guard did with one water command
+#[derive(Debug, Clone, PartialEq)]
+pub enum Checked {
+    /// Safe as written (or not a water command)
+    Pass(String),
+    /// Sent with its value pulled back into the device's limits
+    Clamped { command: String, reason: String },
+    /// Not sent; the device keeps its current state
+    Rejected { reason: String },
+    /// A switch-off inside the device's dwell; not sent now, but it goes
+    /// out from `due_commands` once the dwell is over
+    Held { reason: String },
+}
+
+impl Checked {
+    /// The command to send, if any
+    pub fn command(&self) -> Option<&str> {
+        match self {
+            Checked::Pass(command) | Checked::Clamped { command, .. } => Some(command),
+            Checked::Rejected { .. } | Checked::Held { .. } => None,
+        }
+    }
+
+    pub fn reason(&self) -> Option<&str> {
+        match self {
+            Checked::Pass(_) => None,
+            Checked::Clamped { reason, .. } | Checked::Rejected { reason } | Checked::Held { reason } => Some(reason),
+        }
+    }
+}
+
+/// Last value the guard let through to one device
+#[derive(Debug, Clone, PartialEq)]
+struct DeviceState {
+    data: u16,
+    /// What the show last asked for, before any limits
+    requested: u16,
+    /// When the value last changed
+    changed_at: u64,
+    /// When the device last turned on or off
+    toggled_at: u64,
+    /// When a held switch-off is due
+    off_at: Option<u64>,
+    /// Short of `requested` because of `max_rate`; steps go out from `due_commands`
+    ramping: bool,
+}
+
+/// Why a command cannot go out now
+enum Refusal {
+    /// Switching on too soon after switching off
+    Rejected(String),
+    /// Switching off too soon after switching on; it can go at `off_at`
+    Held { off_at: u64, reason: String },
+}
+
+/// Holds water commands to the limits in water_map.json
+///
+/// Values outside a device's range are clamped, and a running device
+/// changing faster than its `max_rate` ramps there at that rate, with the
+/// steps coming out of `due_commands`. Turning a device on within
+/// `min_dwell_ms` of switching it off is rejected; turning it off that
+/// soon after switching it on is held until the dwell is over, so a pump
+/// is never left running. While a wind limit is set, height
+/// devices are scaled and capped first. `099-000` always passes and counts
+/// as switching everything off.
+#[derive(Debug, Clone)]
+pub struct WaterGuard {
+    water: WaterMap,
+    devices: BTreeMap<u16, DeviceState>,
+    /// Last value let through to each water address since the last reset
+    sent: BTreeMap<u16, u16>,
+    wind: Option<WindLimit>,
+    epoch: Instant,
+}
+
+impl WaterGuard {
+    pub fn new(water: WaterMap) -> Self {
+        Self { water, devices: BTreeMap::new(), sent: BTreeMap::new(), wind: None, epoch: Instant::now() }
+    }
+
+    /// Commands that set the water back to what the guard has let out since
+    /// the last `099-000`, in address order, without the reset itself
+    pub fn water_state(&self) -> Vec<String> {
+        self.sent.iter()
+            .map(|(address, data)| format!("{:03}-{:03}", address, data))
+            .collect()
+    }
+
+    /// Scale heights for the wind from now on; None lifts the limit
+    pub fn set_wind_limit(&mut self, wind: Option<WindLimit>) {
+        self.wind = wind;
+    }
+
+    pub fn wind_limit(&self) -> Option<&WindLimit> {
+        self.wind.as_ref()
+    }
+
+    /// Commands that bring running heights down to the current wind limit
+    ///
+    /// Heights are only ever lowered here; when the wind eases they come
+    /// back up with the show's next command.
+    pub fn wind_commands(&mut self, at_ms: u64) -> Vec<Checked> {
+        let running: Vec<(u16, u16, u16)> = self.devices.iter()
+            .filter(|(address, state)| state.data != 0 && self.water.device(**address).is_some_and(|device| device.height))
+            .map(|(address, state)| (*address, state.data, state.requested))
+            .collect();
+
+        let mut commands = Vec::new();
+        for (address, current, requested) in running {
+            let lower = self.water.device(address)
+                .and_then(|device| self.evaluate(device, address, at_ms, requested).ok())
+                .is_some_and(|(value, _)| value < current);
+            if lower {
+                commands.push(self.check(at_ms, &format!("{:03}-{:03}", address, requested)));
+            }
+        }
+        commands
+    }
+
+    /// `wind_commands` for live playback
+    pub fn wind_commands_now(&mut self) -> Vec<Checked> {
+        let at_ms = self.epoch.elapsed().as_millis() as u64;
+        self.wind_commands(at_ms)
+    }
+
+    /// Check a command sent now, for live playback
+    pub fn check_now(&mut self, command: &str) -> Checked {
+        let at_ms = self.epoch.elapsed().as_millis() as u64;
+        self.check(at_ms, command)
+    }
+
+    /// Held switch-offs whose dwell is over as of `at_ms`, and the next
+    /// step of any rate-limited ramp, to send now
+    ///
+    /// Call before checking anything sent at `at_ms`, so these go out
+    /// ahead of later commands.
+    pub fn due_commands(&mut self, at_ms: u64) -> Vec<String> {
+        let due: Vec<u16> = self.devices.iter()
+            .filter(|(_, state)| state.off_at.is_some_and(|off_at| off_at <= at_ms))
+            .map(|(address, _)| *address)
+            .collect();
+        let mut commands: Vec<String> = due.into_iter()
+            .map(|address| {
+                self.record(address, 0, 0, at_ms, false);
+                format!("{:03}-000", address)
+            })
+            .collect();
+
+        let ramping: Vec<(u16, u16, u16)> = self.devices.iter()
+            .filter(|(_, state)| state.ramping)
+            .map(|(address, state)| (*address, state.data, state.requested))
+            .collect();
+        for (address, current, requested) in ramping {
+            let Some(device) = self.water.device(address) else { continue };
+            let Ok((value, _)) = self.evaluate(device, address, at_ms, requested) else { continue };
+            if value != current {
+                let ramping = value != self.unrated(device, at_ms, requested);
+                self.record(address, requested, value, at_ms, ramping);
+                commands.push(format!("{:03}-{:03}", address, value));
+            }
+        }
+        commands
+    }
+
+    /// `due_commands` for live playback
+    pub fn due_commands_now(&mut self) -> Vec<String> {
+        let at_ms = self.epoch.elapsed().as_millis() as u64;
+        self.due_commands(at_ms)
+    }
+
+    /// True while a switch-off is waiting for its dwell or a ramp is
+    /// short of its target
+    pub fn is_holding(&self) -> bool {
+        self.devices.values().any(|state| state.off_at.is_some() || state.ramping)
+    }
+
+    /// Everything was switched off outside the guard, e.g. by an E-stop
+    pub fn reset_now(&mut self) {
+        let at_ms = self.epoch.elapsed().as_millis() as u64;
+        self.reset(at_ms);
+    }
+
+    /// Check an "XXX-YYY" command sent at `at_ms`
+    ///
+    /// Times must not go backwards; commands without a constrained device
+    /// pass unchanged.
+    pub fn check(&mut self, at_ms: u64, command: &str) -> Checked {
+        let command = command.trim();
+        let Some((address, data)) = parse_command(command) else {
+            return Checked::Pass(command.to_string());
+        };
+        if address == RESET_ADDRESS && data == 0 {
+            self.reset(at_ms);
+            return Checked::Pass(command.to_string());
+        }
+        let Some(device) = self.water.device(address).filter(|device| device.is_constrained()) else {
+            if address != RESET_ADDRESS && self.water.is_water(address) {
+                self.sent.insert(address, data);
+            }
+            return Checked::Pass(command.to_string());
+        };
+
+        match self.evaluate(device, address, at_ms, data) {
+            Err(Refusal::Rejected(reason)) => Checked::Rejected { reason },
+            Err(Refusal::Held { off_at, reason }) => {
+                if let Some(state) = self.devices.get_mut(&address) {
+                    state.requested = data;
+                    state.off_at = Some(off_at);
+                    state.ramping = false;
+                }
+                Checked::Held { reason }
+            }
+            Ok((value, reason)) => {
+                let ramping = value != self.unrated(device, at_ms, data);
+                self.record(address, data, value, at_ms, ramping);
+                if value == data {
+                    Checked::Pass(command.to_string())
+                } else {
+                    Checked::Clamped { command: format!("{:03}-{:03}", address, value), reason }
+                }
+            }
+        }
+    }
+
+    /// The value the device may have, and why it differs from `data`; Err
+    /// when the command must not be sent now
+    fn evaluate(&self, device: &WaterDevice, address: u16, at_ms: u64, data: u16) -> Result<(u16, String), Refusal> {
+        let state = self.devices.get(&address);
+        let (value, mut reasons) = limit(device, state, self.wind.as_ref(), at_ms, data);
+        let label = label(device, address);
+
+        if let Some(state) = state {
+            let switching = (state.data != 0) != (value != 0);
+            let since = at_ms.saturating_sub(state.toggled_at);
+            if switching && since < device.min_dwell_ms {
+                if value != 0 {
+                    return Err(Refusal::Rejected(format!(
+                        "{} switched on {} ms after switching off; needs {} ms",
+                        label, since, device.min_dwell_ms,
+                    )));
+                }
+                return Err(Refusal::Held {
+                    off_at: state.toggled_at + device.min_dwell_ms,
+                    reason: format!(
+                        "{} switched off {} ms after switching on; held until it has run {} ms",
+                        label, since, device.min_dwell_ms,
+                    ),
+                });
+            }
+        }
+
+        reasons.insert(0, format!("{} {:03} -> {:03}", label, data, value));
+        Ok((value, reasons.join(", ")))
+    }
+
+    /// Where `data` ends up once any rate limit has caught up
+    fn unrated(&self, device: &WaterDevice, at_ms: u64, data: u16) -> u16 {
+        limit(device, None, self.wind.as_ref(), at_ms, data).0
+    }
+
+    fn record(&mut self, address: u16, requested: u16, value: u16, at_ms: u64, ramping: bool) {
+        self.sent.insert(address, value);
+        match self.devices.get_mut(&address) {
+            Some(state) if state.data == value => {
+                state.requested = requested;
+                state.off_at = None;
+                state.ramping = ramping;
+            }
+            Some(state) => {
+                state.requested = requested;
+                state.off_at = None;
+                state.ramping = ramping;
+                if (state.data != 0) != (value != 0) {
+                    state.toggled_at = at_ms;
+                }
+                state.data = value;
+                state.changed_at = at_ms;
+            }
+            None => {
+                self.devices.insert(address, DeviceState {
+                    data: value,
+                    requested,
+                    changed_at: at_ms,
+                    toggled_at: at_ms,
+                    off_at: None,
+                    ramping,
+                });
+            }
+        }
+    }
+
+    /// Everything that was running has just been switched off
+    fn reset(&mut self, at_ms: u64) {
+        self.sent.clear();
+        for state in self.devices.values_mut() {
+            state.off_at = None;
+            state.ramping = false;
+            if state.data != 0 {
+                state.data = 0;
+                state.requested = 0;
+                state.changed_at = at_ms;
+                state.toggled_at = at_ms;
+            }
+        }
+    }
+}
+
+/// Apply the wind, range and rate limits; returns the value and why it changed
+fn limit(device: &WaterDevice, state: Option<&DeviceState>, wind: Option<&WindLimit>, at_ms: u64, data: u16) -> (u16, Vec<String>) {
+    let mut reasons = Vec::new();
+    let mut value = data;
+
+    if let Some(wind) = wind.filter(|_| device.height && value != 0) {
+        let mut scaled = (value as f32 * wind.scale).round() as u16;
+        if let Some(max) = wind.max {
+            scaled = scaled.min(max);
+        }
+        if scaled != value {
+            value = scaled;
+            reasons.push(wind.reason.clone());
+        }
+    }
+
+    // Zero is always off, whatever the running range
+    if value != 0 && !(device.min..=device.max).contains(&value) {
+        value = value.clamp(device.min, device.max);
+        reasons.push(format!("outside {}-{}", device.min, device.max));
+    }
+
+    // Rate limits apply while running; switching is covered by the dwell
+    if let (Some(rate), Some(state)) = (device.max_rate, state) {
+        if state.data != 0 && value != 0 {
+            let elapsed = at_ms.saturating_sub(state.changed_at) as f32 / 1000.0;
+            let step = (rate * elapsed).floor() as u16;
+            let limited = if value > state.data {
+                value.min(state.data.saturating_add(step))
+            } else {
+                value.max(state.data.saturating_sub(step))
+            };
+            if limited != value {
+                value = limited;
+                reasons.push(format!("faster than {}/s", rate));
+            }
+        }
+    }
+    (value, reasons)
+}
+
+fn label(device: &WaterDevice, address: u16) -> String {
+    if device.name.is_empty() {
+        format!("{:03}", address)
+    } else {
+        format!("{:03} ({})", address, device.name)
+    }
+}
+
+/// "AAA-DDD"; None for hex colors and anything else
+pub(crate) fn parse_command(command: &str) -> Option<(u16, u16)> {
+    let (address, data) = command.split_once('-')?;
+    Some((address.trim().parse().ok()?, data.trim().parse().ok()?))
+}
+
+/// A command the guard will change or hold back, found when a CTL loads
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaterWarning {
+    pub time_ms: u64,
+    pub command: String,
+    pub checked: Checked,
+}
+
+impl fmt::Display for WaterWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.time_ms / 1000;
+        write!(f, "{:02}:{:02}.{} {} ", seconds / 60, seconds % 60, (self.time_ms % 1000) / 100, self.command)?;
+        match &self.checked {
+            Checked::Pass(_) => write!(f, "passes"),
+            Checked::Clamped { command, reason } => write!(f, "sent as {}: {}", command, reason),
+            Checked::Rejected { reason } => write!(f, "skipped: {}", reason),
+            Checked::Held { reason } => write!(f, "held: {}", reason),
+        }
+    }
+}
+
+/// Run a show's water commands through a fresh guard, as playback from
+/// the start would
+pub fn check_ctl(ctl: &CtlFile, water: &WaterMap) -> Vec<WaterWarning> {
+    let mut guard = WaterGuard::new(water.clone());
+    let mut warnings = Vec::new();
+    for line in ctl.lines.iter().filter(|line| !line.is_blank) {
+        guard.due_commands(line.time_ms);
+        for cmd in line.commands.iter().filter(|cmd| !cmd.is_hex_color && water.is_water(cmd.fcw_address)) {
+            let command = format_command(cmd);
+            let checked = guard.check(line.time_ms, &command);
+            if !matches!(checked, Checked::Pass(_)) {
+                warnings.push(WaterWarning { time_ms: line.time_ms, command, checked });
+            }
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guard() -> WaterGuard {
+        WaterGuard::new(WaterMap::parse(r#"{
+            "routes": [
+                { "addresses": "33", "route": "water", "name": "Module 1 pump", "min_dwell_ms": 5000 },
+                { "addresses": "87", "route": "water", "name": "Center height", "min": 100, "max": 500, "max_rate": 200, "height": true },
+                { "addresses": "1", "route": "water" },
+                { "addresses": "99", "route": "both" }
+            ]
+        }"#).unwrap())
+    }
+
+    #[test]
+    fn test_pump_dwell() {
+        let mut guard = guard();
+        assert_eq!(guard.check(0, "033-001"), Checked::Pass("033-001".to_string()));
+        let checked = guard.check(2000, "033-000");
+        assert!(matches!(checked, Checked::Held { .. }));
+        assert!(checked.reason().unwrap().contains("033 (Module 1 pump) switched off 2000 ms after switching on"));
+        assert!(guard.due_commands(4999).is_empty());
+        assert_eq!(guard.due_commands(5000), vec!["033-000".to_string()]);
+        assert!(!guard.is_holding());
+
+        // A reset always goes out, and counts as switching off
+        assert_eq!(guard.check(6000, "033-001").command(), None);
+        assert_eq!(guard.check(10_000, "033-001").command(), Some("033-001"));
+        assert_eq!(guard.check(11_000, "099-000").command(), Some("099-000"));
+        assert_eq!(guard.check(12_000, "033-001").command(), None);
+
+        // Unconstrained and non-water commands pass untouched
+        assert_eq!(guard.check(12_000, "001-005").command(), Some("001-005"));
+        assert_eq!(guard.check(12_000, "507-FF0000").command(), Some("507-FF0000"));
+    }
+
+    #[test]
+    fn test_water_state_since_last_reset() {
+        let mut guard = guard();
+        guard.check(0, "001-005");
+        guard.check(0, "099-000");
+        guard.check(100, "087-600");
+        guard.check(100, "033-001");
+        guard.check(100, "001-007");
+        guard.check(100, "507-FF0000");
+        // Held back, so still on as far as the PLC knows
+        assert!(matches!(guard.check(200, "033-000"), Checked::Held { .. }));
+
+        assert_eq!(guard.water_state(), vec!["001-007", "033-001", "087-500"]);
+        guard.check(300, "099-000");
+        assert!(guard.water_state().is_empty());
+    }
+
+    #[test]
+    fn test_held_off_is_cancelled_by_switching_back_on() {
+        let mut guard = guard();
+        guard.check(0, "033-001");
+        assert!(matches!(guard.check(1000, "033-000"), Checked::Held { .. }));
+        // The show wants it running again before the dwell is over
+        assert_eq!(guard.check(3000, "033-002").command(), Some("033-002"));
+        assert!(guard.due_commands(6000).is_empty());
+
+        assert!(matches!(guard.check(4000, "033-000"), Checked::Held { .. }));
+        assert_eq!(guard.check(4500, "099-000").command(), Some("099-000"));
+        assert!(!guard.is_holding());
+    }
+
+    #[test]
+    fn test_height_range_and_rate() {
+        let mut guard = guard();
+        let checked = guard.check(0, "087-600");
+        assert_eq!(checked.command(), Some("087-500"));
+        assert!(checked.reason().unwrap().contains("outside 100-500"));
+
+        // 200/s allows 100 in half a second
+        assert_eq!(guard.check(500, "087-200").command(), Some("087-400"));
+        assert_eq!(guard.check(1500, "087-300").command(), Some("087-300"));
+        assert_eq!(guard.check(1500, "087-000").command(), Some("087-000"));
+        assert_eq!(guard.check(1600, "087-050").command(), Some("087-100"));
+    }
+
+    #[test]
+    fn test_rate_limit_ramps_to_target() {
+        let mut guard = guard();
+        assert_eq!(guard.check(0, "087-100").command(), Some("087-100"));
+        assert_eq!(guard.check(250, "087-500").command(), Some("087-150"));
+        assert!(guard.is_holding());
+
+        let mut sent = Vec::new();
+        for at_ms in (300..=3000).step_by(100) {
+            sent.extend(guard.due_commands(at_ms));
+        }
+        assert_eq!(sent.first().map(String::as_str), Some("087-160"));
+        assert_eq!(sent.last().map(String::as_str), Some("087-500"));
+        assert!(!guard.is_holding());
+
+        // Two commands too close for a whole step still get there
+        assert_eq!(guard.check(3000, "087-400").command(), Some("087-400"));
+        assert_eq!(guard.check(3002, "087-300").command(), Some("087-400"));
+        assert!(guard.due_commands(3004).is_empty());
+        assert_eq!(guard.due_commands(3600), vec!["087-300".to_string()]);
+        assert!(!guard.is_holding());
+    }
+
+    #[test]
+    fn test_wind_lowers_heights() {
+        let mut guard = guard();
+        assert_eq!(guard.check(0, "087-400").command(), Some("087-400"));
+        assert_eq!(guard.check(0, "033-001").command(), Some("033-001"));
+
+        guard.set_wind_limit(Some(WindLimit { scale: 0.5, max: None, reason: "wind Caution".to_string() }));
+        let lowered = guard.wind_commands(10_000);
+        assert_eq!(lowered.len(), 1);
+        assert_eq!(lowered[0].command(), Some("087-200"));
+        assert!(lowered[0].reason().unwrap().ends_with("400 -> 200, wind Caution"));
+        assert_eq!(guard.check(11_000, "087-500").command(), Some("087-250"));
+
+        // Easing off waits for the show's next command
+        guard.set_wind_limit(None);
+        assert!(guard.wind_commands(12_000).is_empty());
+        assert_eq!(guard.check(13_000, "087-400").command(), Some("087-400"));
+    }
+
+    #[test]
+    fn test_check_ctl() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("show.ctl");
+        std::fs::write(&path, "V2\n00:01.0 033-001 017-002\n00:02.0 033-000\n00:09.0 087-900\n").unwrap();
+        let ctl = CtlFile::load(&path).unwrap();
+
+        let warnings = check_ctl(&ctl, &guard().water);
+        assert_eq!(warnings.len(), 2);
+        assert!(matches!(warnings[0].checked, Checked::Held { .. }));
+        assert!(warnings[1].to_string().starts_with("00:09.0 087-900 sent as 087-500"));
+    }
+
+    #[test]
+    fn test_early_switch_off_at_the_end_still_goes_out() {
+        use crate::commands::render_ctl;
+        use crate::config::CsvConfig;
+        use crate::lighting::FixtureManager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("show.ctl");
+        std::fs::write(&path, "V2\n00:00.0 033-001\n00:02.0 033-000\n").unwrap();
+        let ctl = CtlFile::load(&path).unwrap();
+
+        let mut config = CsvConfig::test_rig();
+        config.water = guard().water;
+        let mut fm = FixtureManager::new(config);
+        let mut out = Vec::new();
+        let summary = render_ctl(&ctl, &mut fm, 10.0, &mut out, None).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        assert!(!text.contains("00:02.000 PLC"));
+        assert!(text.contains("00:05.000 PLC 033-000"));
+        assert_eq!(summary.plc_commands, 2);
+        assert_eq!(summary.water_warnings, 1);
+        assert!(summary.end_ms >= 5000);
+    }
+}
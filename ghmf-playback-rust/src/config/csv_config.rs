@@ -3,6 +3,12 @@ use std::fs::File;
 use std::path::Path;
 use csv::ReaderBuilder;
 use anyhow::{Context, Result};
+use super::effect_library::EffectLibrary;
+use super::fixture_layout::FixtureLayout;
+use super::scene_library::SceneLibrary;
+use super::water_map::WaterMap;
+use super::fixture_profile::{FixtureProfile, ProfileLibrary, PROFILE_RGBW, PROFILE_SINGLE};
+use crate::lighting::fade::FadeCurve;
 
 /// Color definition from legacy_colors.json
 #[derive(Debug, Clone)]
@@ -28,32 +34,53 @@ impl ColorDefinition {
     }
 }
 
-/// DMX fixture format
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum FixtureFormat {
-    RGB,   // 3 channels: R, G, B
-    RGBW,  // 4 channels: R, G, B, W
-    X,     // Single channel
+/// Per-fixture output calibration, stored with the fixture in dmx_mapping.json
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct FixtureCorrection {
+    pub trim: [f32; 4],       // Multiplier per emitter (R, G, B, W)
+    pub gamma: f32,           // Output curve exponent, 1.0 = linear
+    pub max_output: [u8; 4],  // Highest level per emitter (R, G, B, W)
 }
 
-impl FixtureFormat {
-    pub fn channel_count(&self) -> usize {
-        match self {
-            FixtureFormat::RGB => 3,
-            FixtureFormat::RGBW => 4,
-            FixtureFormat::X => 1,
+impl Default for FixtureCorrection {
+    fn default() -> Self {
+        Self {
+            trim: [1.0; 4],
+            gamma: 1.0,
+            max_output: [255; 4],
         }
     }
 }
 
+impl FixtureCorrection {
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+    
+    /// Apply trim, then gamma, then the output cap to an RGBW color
+    pub fn apply(&self, (r, g, b, w): (u8, u8, u8, u8)) -> (u8, u8, u8, u8) {
+        if self.is_identity() {
+            return (r, g, b, w);
+        }
+        
+        let gamma = self.gamma.clamp(0.1, 10.0);
+        let correct = |i: usize, value: u8| {
+            let level = (value as f32 / 255.0 * self.trim[i]).clamp(0.0, 1.0).powf(gamma);
+            ((level * 255.0).round() as u8).min(self.max_output[i])
+        };
+        (correct(0, r), correct(1, g), correct(2, b), correct(3, w))
+    }
+}
+
 /// DMX fixture definition from DMXMap.csv
 #[derive(Debug, Clone)]
 pub struct FixtureDefinition {
     pub fixture_number: u16,
     pub note: String,
     pub dmx_channel: u16,
-    pub format: FixtureFormat,
-    pub corrections: Vec<f32>,  // Color correction multipliers
+    pub profile: FixtureProfile,
+    pub correction: FixtureCorrection,
 }
 
 /// FCW command directive
@@ -83,8 +110,9 @@ impl FcwDirective {
 #[derive(Debug, Clone)]
 pub struct FcwMapping {
     pub fcw_address: u16,
-    pub water_directive: FcwDirective,  // Second column
+    pub water_directive: FcwDirective,  // On when water_map.json also sends the address to the PLC
     pub fixture_directives: HashMap<u16, FcwDirective>, // Fixture# -> Directive
+    pub fade_curve: Option<FadeCurve>, // Group default curve for fades on this address
 }
 
 /// Configuration manager for all CSV files
@@ -93,6 +121,11 @@ pub struct CsvConfig {
     pub colors: HashMap<u16, ColorDefinition>,
     pub fixtures: HashMap<u16, FixtureDefinition>,
     pub fcw_mappings: HashMap<u16, FcwMapping>,
+    pub profiles: ProfileLibrary,
+    pub effects: EffectLibrary,
+    pub layout: FixtureLayout,
+    pub scenes: SceneLibrary,
+    pub water: WaterMap,
 }
 
 impl CsvConfig {
@@ -100,17 +133,55 @@ impl CsvConfig {
     pub fn load_from_dir<P: AsRef<Path>>(dir: P) -> Result<Self> {
         let dir = dir.as_ref();
         
+        let profiles = ProfileLibrary::load_from_dir(dir)?;
         let colors = Self::load_legacy_colors_json(dir.join("legacy_colors.json"))?;
-        let fixtures = Self::load_dmx_map_json(dir.join("dmx_mapping.json"))?;
-        let fcw_mappings = Self::load_light_groups_json(dir.join("light_groups.json"))?;
+        let fixtures = Self::load_dmx_map_json(dir.join("dmx_mapping.json"), &profiles)?;
+        let mut fcw_mappings = Self::load_light_groups_json(dir.join("light_groups.json"))?;
+        let effects = EffectLibrary::load_from_dir(dir)?;
+        let layout = FixtureLayout::load_from_dir(dir)?;
+        let scenes = SceneLibrary::load_from_dir(dir)?;
+        let water = WaterMap::load_from_dir(dir)?;
+        Self::check_fcw_map(dir.join("FCWMap.CSV"), &water);
+        
+        // Light groups on an address that also goes to the PLC
+        for mapping in fcw_mappings.values_mut() {
+            if water.is_water(mapping.fcw_address) {
+                mapping.water_directive = FcwDirective::On;
+            }
+        }
         
         Ok(Self {
             colors,
             fixtures,
             fcw_mappings,
+            profiles,
+            effects,
+            layout,
+            scenes,
+            water,
         })
     }
     
+    /// Warn about every address where FCWMap.CSV's Water column and the water
+    /// map disagree; the water map is what gets routed
+    fn check_fcw_map<P: AsRef<Path>>(path: P, water: &WaterMap) {
+        let Ok(file) = File::open(path.as_ref()) else {
+            return;
+        };
+        match water.fcw_map_mismatches(file) {
+            Ok(mismatches) => {
+                for (address, csv_water) in mismatches {
+                    if csv_water {
+                        tracing::warn!("FCWMap.CSV marks {:03} as water but the water map sends it to the lights only", address);
+                    } else {
+                        tracing::warn!("The water map sends {:03} to the PLC but FCWMap.CSV does not mark it as water", address);
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("Failed to check FCWMap.CSV against the water map: {:#}", e),
+        }
+    }
+    
     /// Load legacy colors from JSON file
     fn load_legacy_colors_json<P: AsRef<Path>>(path: P) -> Result<HashMap<u16, ColorDefinition>> {
         let json_data = std::fs::read_to_string(path.as_ref())
@@ -141,7 +212,7 @@ impl CsvConfig {
     }
     
     /// Load DMXMap.csv
-    fn load_dmx_map<P: AsRef<Path>>(path: P) -> Result<HashMap<u16, FixtureDefinition>> {
+    fn load_dmx_map<P: AsRef<Path>>(path: P, profiles: &ProfileLibrary) -> Result<HashMap<u16, FixtureDefinition>> {
         let file = File::open(path.as_ref())
             .context(format!("Failed to open DMXMap.csv at {:?}", path.as_ref()))?;
         
@@ -164,35 +235,28 @@ impl CsvConfig {
             let dmx_channel: u16 = record[2].trim().parse()
                 .context("Failed to parse DMX channel")?;
             
-            let format = match record[3].trim().to_uppercase().as_str() {
-                "RGB" => FixtureFormat::RGB,
-                "RGBW" => FixtureFormat::RGBW,
-                "X" => FixtureFormat::X,
-                other => {
-                    tracing::warn!("Unknown fixture format: {}, defaulting to RGB", other);
-                    FixtureFormat::RGB
+            let profile = match profiles.resolve(&record[3]) {
+                Some(profile) => profile.clone(),
+                None => {
+                    tracing::warn!("Unknown fixture profile: {}, defaulting to RGB", record[3].trim());
+                    profiles.resolve("RGB").cloned().unwrap_or_default()
                 }
             };
             
-            // Parse correction values (columns 4+)
-            let mut corrections = Vec::new();
-            for i in 4..record.len() {
-                if let Ok(val) = record[i].trim().parse::<f32>() {
-                    corrections.push(val);
+            // Parse correction multipliers (columns 4+) as R, G, B, W trims
+            let mut correction = FixtureCorrection::default();
+            for (trim, field) in correction.trim.iter_mut().zip(record.iter().skip(4)) {
+                if let Ok(val) = field.trim().parse::<f32>() {
+                    *trim = val;
                 }
             }
             
-            // Ensure we have enough corrections for the format
-            while corrections.len() < format.channel_count() {
-                corrections.push(1.0);
-            }
-            
             fixtures.insert(fixture_number, FixtureDefinition {
                 fixture_number,
                 note,
                 dmx_channel,
-                format,
-                corrections,
+                profile,
+                correction,
             });
         }
         
@@ -201,12 +265,16 @@ impl CsvConfig {
     }
     
     /// Load DMX mappings from dmx_mapping.json
-    fn load_dmx_map_json<P: AsRef<Path>>(path: P) -> Result<HashMap<u16, FixtureDefinition>> {
+    fn load_dmx_map_json<P: AsRef<Path>>(path: P, profiles: &ProfileLibrary) -> Result<HashMap<u16, FixtureDefinition>> {
         #[derive(serde::Deserialize)]
         struct DmxMapping {
             fixture_id: u16,
             fixture_name: String,
             start_channel: u16,
+            #[serde(default)]
+            profile: Option<String>,
+            #[serde(default)]
+            correction: FixtureCorrection,
         }
         
         #[derive(serde::Deserialize)]
@@ -223,22 +291,30 @@ impl CsvConfig {
         let mut fixtures = HashMap::new();
         
         for mapping in dmx_file.mappings {
-            // Determine format based on fixture name or ID patterns
-            let format = if mapping.fixture_name.to_lowercase().contains("firework") || mapping.fixture_id >= 900 {
-                FixtureFormat::X
-            } else {
-                // Most fixtures are RGBW now
-                FixtureFormat::RGBW
-            };
+            let named_profile = mapping.profile.as_deref().and_then(|name| {
+                let profile = profiles.resolve(name);
+                if profile.is_none() {
+                    tracing::warn!("Fixture {} uses unknown profile '{}'", mapping.fixture_id, name);
+                }
+                profile
+            });
             
-            let corrections = vec![1.0; format.channel_count()];
+            // The DMX Map panel stores a profile with every mapping; only files
+            // saved before profiles existed fall back to a guess from name or ID
+            let profile = named_profile.or_else(|| {
+                if mapping.fixture_name.to_lowercase().contains("firework") || mapping.fixture_id >= 900 {
+                    profiles.get(PROFILE_SINGLE)
+                } else {
+                    profiles.get(PROFILE_RGBW)
+                }
+            }).cloned().unwrap_or_default();
             
             fixtures.insert(mapping.fixture_id, FixtureDefinition {
                 fixture_number: mapping.fixture_id,
                 note: mapping.fixture_name,
                 dmx_channel: mapping.start_channel,
-                format,
-                corrections,
+                profile,
+                correction: mapping.correction,
             });
         }
         
@@ -254,6 +330,8 @@ impl CsvConfig {
             fcw_code: String,
             fcw_fade_code: String,
             fixture_ids: Vec<u8>,
+            #[serde(default)]
+            fade_curve: Option<String>,
         }
         
         #[derive(serde::Deserialize)]
@@ -271,6 +349,14 @@ impl CsvConfig {
         let group_count = config.groups.len();
         
         for group in config.groups {
+            let fade_curve = group.fade_curve.as_deref().and_then(|name| {
+                let curve = FadeCurve::from_setting(name);
+                if curve.is_none() {
+                    tracing::warn!("Unknown fade curve '{}' for light group '{}'", name, group.name);
+                }
+                curve
+            });
+            
             // Parse the FCW code (e.g., "017" -> 17)
             if let Ok(fcw_code) = group.fcw_code.parse::<u16>() {
                 let mut fixture_directives = HashMap::new();
@@ -284,6 +370,7 @@ impl CsvConfig {
                     fcw_address: fcw_code,
                     water_directive: FcwDirective::Off,
                     fixture_directives,
+                    fade_curve,
                 });
             }
             
@@ -300,6 +387,7 @@ impl CsvConfig {
                     fcw_address: fade_code,
                     water_directive: FcwDirective::Off,
                     fixture_directives,
+                    fade_curve,
                 });
             }
         }
@@ -324,6 +412,20 @@ impl CsvConfig {
         self.fcw_mappings.get(&fcw_address)
     }
     
+    /// Firework fixtures: named "Fireworks", numbered 900 and up, or driven by a 900-series address
+    pub fn pyro_fixtures(&self) -> Vec<u16> {
+        let mut fixtures: Vec<u16> = self.fixtures.values()
+            .filter(|f| f.note.to_lowercase().contains("firework") || f.fixture_number >= 900)
+            .map(|f| f.fixture_number)
+            .chain(self.fcw_mappings.values()
+                .filter(|m| (900..=999).contains(&m.fcw_address))
+                .flat_map(|m| m.fixture_directives.keys().copied()))
+            .collect();
+        fixtures.sort_unstable();
+        fixtures.dedup();
+        fixtures
+    }
+    
     /// Get all fixtures affected by an FCW command with a specific directive
     pub fn get_affected_fixtures(&self, fcw_address: u16) -> Vec<(u16, FcwDirective)> {
         if let Some(mapping) = self.get_fcw_mapping(fcw_address) {
@@ -337,6 +439,59 @@ impl CsvConfig {
     }
 }
 
+#[cfg(test)]
+impl CsvConfig {
+    /// A one-fixture rig for tests: black, red and white as colors 0-2, and
+    /// RGBW fixture 1 on channel 1, switched on by light group 17
+    pub(crate) fn test_rig() -> Self {
+        let mut colors = HashMap::new();
+        for (index, hex) in [(0, "000000"), (1, "FF0000"), (2, "FFFFFF")] {
+            colors.insert(index, ColorDefinition {
+                index,
+                hex_color: hex.to_string(),
+                description: String::new(),
+            });
+        }
+
+        let mut fixtures = HashMap::new();
+        fixtures.insert(1, FixtureDefinition {
+            fixture_number: 1,
+            note: String::new(),
+            dmx_channel: 1,
+            profile: FixtureProfile::default(),
+            correction: Default::default(),
+        });
+
+        let mut fcw_mappings = HashMap::new();
+        fcw_mappings.insert(17, FcwMapping {
+            fcw_address: 17,
+            water_directive: FcwDirective::Off,
+            fixture_directives: HashMap::from([(1, FcwDirective::On)]),
+            fade_curve: None,
+        });
+
+        Self { colors, fixtures, fcw_mappings, profiles: ProfileLibrary::builtin(), effects: Default::default(), layout: Default::default(), scenes: Default::default(), water: Default::default() }
+    }
+
+    /// Adds firework fixture 54 on channel 10, fired by 900
+    pub(crate) fn with_fireworks(mut self) -> Self {
+        self.fixtures.insert(54, FixtureDefinition {
+            fixture_number: 54,
+            note: "Fireworks".to_string(),
+            dmx_channel: 10,
+            profile: self.profiles.get(PROFILE_SINGLE).unwrap().clone(),
+            correction: Default::default(),
+        });
+        self.fcw_mappings.insert(900, FcwMapping {
+            fcw_address: 900,
+            water_directive: FcwDirective::Off,
+            fixture_directives: HashMap::from([(54, FcwDirective::On)]),
+            fade_curve: None,
+        });
+        self
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -355,6 +510,48 @@ mod tests {
         assert_eq!(b, 0);
     }
     
+    #[test]
+    fn test_fixture_correction() {
+        let identity = FixtureCorrection::default();
+        assert_eq!(identity.apply((10, 128, 200, 255)), (10, 128, 200, 255));
+        
+        let correction = FixtureCorrection {
+            trim: [0.5, 1.0, 2.0, 1.0],
+            gamma: 1.0,
+            max_output: [255, 255, 255, 200],
+        };
+        assert_eq!(correction.apply((200, 100, 100, 255)), (100, 100, 200, 200));
+        
+        let gamma = FixtureCorrection { gamma: 2.0, ..Default::default() };
+        assert_eq!(gamma.apply((0, 128, 255, 0)), (0, 64, 255, 0));
+    }
+    
+    #[test]
+    fn test_fixture_correction_json_defaults() {
+        let correction: FixtureCorrection = serde_json::from_str(r#"{ "gamma": 2.2 }"#).unwrap();
+        assert_eq!(correction.trim, [1.0; 4]);
+        assert_eq!(correction.max_output, [255; 4]);
+        assert_eq!(correction.gamma, 2.2);
+    }
+    
+    #[test]
+    fn test_dmx_map_profile_wins_over_guess() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dmx_mapping.json");
+        std::fs::write(&path, r#"{ "mappings": [
+            { "fixture_id": 55, "fixture_name": "Pump relay", "start_channel": 200, "profile": "Single Channel" },
+            { "fixture_id": 56, "fixture_name": "Firework tower wash", "start_channel": 210, "profile": "Generic RGB" },
+            { "fixture_id": 54, "fixture_name": "Fireworks", "start_channel": 220 },
+            { "fixture_id": 1, "fixture_name": "Mod 1 Front Left", "start_channel": 1 }
+        ] }"#).unwrap();
+
+        let fixtures = CsvConfig::load_dmx_map_json(&path, &ProfileLibrary::builtin()).unwrap();
+        assert_eq!(fixtures[&55].profile.name, PROFILE_SINGLE);
+        assert_eq!(fixtures[&56].profile.name, "Generic RGB");
+        assert_eq!(fixtures[&54].profile.name, PROFILE_SINGLE);
+        assert_eq!(fixtures[&1].profile.name, PROFILE_RGBW);
+    }
+    
     #[test]
     fn test_fcw_directive_parsing() {
         assert_eq!(FcwDirective::from_str("XXX"), FcwDirective::On);
@@ -0,0 +1,201 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use super::fixture_layout::Direction;
+
+/// Pattern an effect draws across its fixtures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EffectKind {
+    /// A lit block stepping across the fixtures once per period
+    Chase,
+    /// Every fixture breathing in and out together
+    Pulse,
+    /// Hue wheel spread across the fixtures
+    Rainbow,
+    /// Every fixture flashing together
+    Strobe,
+    /// Random fixtures sparkling and decaying
+    Twinkle,
+    /// Color filling the layout along `direction`
+    Wipe,
+    /// Soft band of color travelling along `direction`
+    Band,
+    /// Ring expanding from the center of the layout
+    Burst,
+    /// `color` to `color2` across the layout, scrolling along `direction`
+    Gradient,
+}
+
+impl EffectKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            EffectKind::Chase => "Chase",
+            EffectKind::Pulse => "Pulse",
+            EffectKind::Rainbow => "Rainbow",
+            EffectKind::Strobe => "Strobe",
+            EffectKind::Twinkle => "Twinkle",
+            EffectKind::Wipe => "Wipe",
+            EffectKind::Band => "Band",
+            EffectKind::Burst => "Burst",
+            EffectKind::Gradient => "Gradient",
+        }
+    }
+
+    /// Rendered from fixture positions in lights_layout.json
+    pub fn is_spatial(&self) -> bool {
+        matches!(self, EffectKind::Wipe | EffectKind::Band | EffectKind::Burst | EffectKind::Gradient)
+    }
+}
+
+/// Effect from effects.json, started from a show with `070-<id>`
+///
+/// Spatial effects with neither `group` nor `fixtures` run on every fixture
+/// placed in lights_layout.json.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EffectDefinition {
+    pub id: u16,
+    pub name: String,
+    pub kind: EffectKind,
+    /// FCW address of the light group the effect runs on
+    #[serde(default)]
+    pub group: Option<u16>,
+    /// Explicit fixture order; overrides `group` when given
+    #[serde(default)]
+    pub fixtures: Vec<u16>,
+    /// Legacy color index (ignored by rainbow)
+    #[serde(default = "default_color")]
+    pub color: u16,
+    /// Gradient: color index at the far end
+    #[serde(default)]
+    pub color2: u16,
+    /// Length of one cycle
+    #[serde(default = "default_period_ms")]
+    pub period_ms: u64,
+    /// Chase: fixtures lit at once
+    #[serde(default = "default_width")]
+    pub width: usize,
+    /// Strobe: fraction of the period the flash is on
+    #[serde(default = "default_duty")]
+    pub duty: f32,
+    /// Twinkle: fraction of fixtures lit per period
+    #[serde(default = "default_density")]
+    pub density: f32,
+    /// Spatial effects: axis of travel (burst is always center-out)
+    #[serde(default)]
+    pub direction: Direction,
+    /// Wipe, band and burst: soft edge width as a fraction of the layout
+    #[serde(default = "default_band_width")]
+    pub band_width: f32,
+}
+
+fn default_color() -> u16 {
+    2
+}
+
+fn default_period_ms() -> u64 {
+    1000
+}
+
+fn default_width() -> usize {
+    1
+}
+
+fn default_duty() -> f32 {
+    0.1
+}
+
+fn default_density() -> f32 {
+    0.3
+}
+
+fn default_band_width() -> f32 {
+    0.2
+}
+
+/// Effects defined in effects.json, keyed by id
+#[derive(Debug, Clone, Default)]
+pub struct EffectLibrary {
+    effects: BTreeMap<u16, EffectDefinition>,
+}
+
+impl EffectLibrary {
+    /// Load effects.json from `dir`; a missing file gives an empty library
+    pub fn load_from_dir<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct EffectFile {
+            effects: Vec<EffectDefinition>,
+        }
+
+        let mut library = Self::default();
+        let path = dir.as_ref().join("effects.json");
+        if !path.exists() {
+            return Ok(library);
+        }
+
+        let json_data = std::fs::read_to_string(&path)
+            .context(format!("Failed to read effects.json at {:?}", path))?;
+        let file: EffectFile = serde_json::from_str(&json_data)
+            .context("Failed to parse effects.json")?;
+
+        for effect in file.effects {
+            if !(1..=999).contains(&effect.id) || effect.period_ms == 0 {
+                tracing::warn!("Skipping effect '{}': id must be 1-999 and period above 0", effect.name);
+                continue;
+            }
+            library.insert(effect);
+        }
+
+        tracing::info!("Loaded {} effects from effects.json", library.effects.len());
+        Ok(library)
+    }
+
+    pub fn insert(&mut self, effect: EffectDefinition) {
+        self.effects.insert(effect.id, effect);
+    }
+
+    pub fn get(&self, id: u16) -> Option<&EffectDefinition> {
+        self.effects.get(&id)
+    }
+
+    /// All effects, sorted by id
+    pub fn effects(&self) -> impl Iterator<Item = &EffectDefinition> {
+        self.effects.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_effects_json() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("effects.json"), r#"{
+            "effects": [
+                { "id": 1, "name": "Module 1 chase", "kind": "chase", "group": 17, "period_ms": 600 },
+                { "id": 2, "name": "Peacock rainbow", "kind": "rainbow", "fixtures": [49, 48, 47] },
+                { "id": 3, "name": "Rising band", "kind": "band", "direction": "bottom_to_top" },
+                { "id": 0, "name": "Bad id", "kind": "pulse" }
+            ]
+        }"#).unwrap();
+
+        let library = EffectLibrary::load_from_dir(dir.path()).unwrap();
+        assert_eq!(library.effects().count(), 3);
+
+        let chase = library.get(1).unwrap();
+        assert_eq!(chase.kind, EffectKind::Chase);
+        assert_eq!(chase.group, Some(17));
+        assert_eq!(chase.width, 1);
+        assert_eq!(library.get(2).unwrap().fixtures, vec![49, 48, 47]);
+        assert_eq!(library.get(3).unwrap().direction, Direction::BottomToTop);
+        assert!(library.get(3).unwrap().kind.is_spatial());
+    }
+
+    #[test]
+    fn test_missing_file_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(EffectLibrary::load_from_dir(dir.path()).unwrap().effects().count(), 0);
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::path::Path;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Axis a spatial effect travels along
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    #[default]
+    LeftToRight,
+    RightToLeft,
+    TopToBottom,
+    BottomToTop,
+    CenterOut,
+    OutsideIn,
+}
+
+/// Fixture positions from lights_layout.json (row 0 is the top of the fountain)
+#[derive(Debug, Clone, Default)]
+pub struct FixtureLayout {
+    positions: HashMap<u16, (usize, usize)>, // Fixture# -> (row, col)
+}
+
+impl FixtureLayout {
+    /// Load lights_layout.json from `dir`; a missing file gives an empty layout
+    pub fn load_from_dir<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct LayoutFile {
+            cells: HashMap<String, u16>, // "row_col" -> fixture#
+        }
+
+        let path = dir.as_ref().join("lights_layout.json");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let json_data = std::fs::read_to_string(&path)
+            .context(format!("Failed to read lights_layout.json at {:?}", path))?;
+        let file: LayoutFile = serde_json::from_str(&json_data)
+            .context("Failed to parse lights_layout.json")?;
+
+        let mut layout = Self::default();
+        for (key, fixture_num) in file.cells {
+            match key.split_once('_').and_then(|(r, c)| Some((r.parse().ok()?, c.parse().ok()?))) {
+                Some((row, col)) => layout.place(fixture_num, row, col),
+                None => tracing::warn!("Skipping lights layout cell '{}'", key),
+            }
+        }
+
+        tracing::info!("Loaded {} fixture positions from lights_layout.json", layout.positions.len());
+        Ok(layout)
+    }
+
+    pub fn place(&mut self, fixture_num: u16, row: usize, col: usize) {
+        self.positions.insert(fixture_num, (row, col));
+    }
+
+    pub fn position(&self, fixture_num: u16) -> Option<(usize, usize)> {
+        self.positions.get(&fixture_num).copied()
+    }
+
+    /// Every placed fixture, sorted by number
+    pub fn fixtures(&self) -> Vec<u16> {
+        let mut fixtures: Vec<u16> = self.positions.keys().copied().collect();
+        fixtures.sort_unstable();
+        fixtures
+    }
+
+    /// Where a fixture sits along `direction`, from 0.0 (start) to 1.0 (end)
+    ///
+    /// Positions are scaled to the bounds of all placed fixtures. Center-out
+    /// distances are measured in grid cells so bursts stay round.
+    pub fn coordinate(&self, fixture_num: u16, direction: Direction) -> Option<f32> {
+        let (row, col) = self.position(fixture_num)?;
+        let (min_row, max_row, min_col, max_col) = self.bounds()?;
+
+        let scale = |v: usize, min: usize, max: usize| {
+            if max > min { (v - min) as f32 / (max - min) as f32 } else { 0.5 }
+        };
+        let x = scale(col, min_col, max_col);
+        let y = scale(row, min_row, max_row);
+
+        let radial = || {
+            let (cx, cy) = ((min_col + max_col) as f32 / 2.0, (min_row + max_row) as f32 / 2.0);
+            let max_distance = ((max_col - min_col) as f32 / 2.0).hypot((max_row - min_row) as f32 / 2.0);
+            if max_distance > 0.0 {
+                (col as f32 - cx).hypot(row as f32 - cy) / max_distance
+            } else {
+                0.0
+            }
+        };
+
+        Some(match direction {
+            Direction::LeftToRight => x,
+            Direction::RightToLeft => 1.0 - x,
+            Direction::TopToBottom => y,
+            Direction::BottomToTop => 1.0 - y,
+            Direction::CenterOut => radial(),
+            Direction::OutsideIn => 1.0 - radial(),
+        })
+    }
+
+    /// (min_row, max_row, min_col, max_col) of all placed fixtures
+    fn bounds(&self) -> Option<(usize, usize, usize, usize)> {
+        let rows = self.positions.values().map(|&(r, _)| r);
+        let cols = self.positions.values().map(|&(_, c)| c);
+        Some((rows.clone().min()?, rows.max()?, cols.clone().min()?, cols.max()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coordinates_follow_direction() {
+        let mut layout = FixtureLayout::default();
+        layout.place(1, 0, 0);
+        layout.place(2, 2, 4);
+        layout.place(3, 4, 8);
+
+        assert_eq!(layout.coordinate(1, Direction::LeftToRight), Some(0.0));
+        assert_eq!(layout.coordinate(2, Direction::LeftToRight), Some(0.5));
+        assert_eq!(layout.coordinate(3, Direction::RightToLeft), Some(0.0));
+        assert_eq!(layout.coordinate(3, Direction::TopToBottom), Some(1.0));
+        assert_eq!(layout.coordinate(2, Direction::CenterOut), Some(0.0));
+        assert_eq!(layout.coordinate(1, Direction::CenterOut), Some(1.0));
+        assert_eq!(layout.coordinate(9, Direction::LeftToRight), None);
+    }
+
+    #[test]
+    fn test_load_layout_json() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("lights_layout.json"), r#"{ "cells": { "5_0": 1, "3_26": 41, "bad": 7 } }"#).unwrap();
+
+        let layout = FixtureLayout::load_from_dir(dir.path()).unwrap();
+        assert_eq!(layout.position(1), Some((5, 0)));
+        assert_eq!(layout.fixtures(), vec![1, 41]);
+    }
+}
@@ -0,0 +1,319 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// What a single DMX channel of a fixture does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelRole {
+    Red,
+    Green,
+    Blue,
+    White,
+    Amber,
+    Uv,
+    /// Single-channel brightness (highest of R, G, B, W)
+    Intensity,
+    /// Master dimmer, held fully open
+    Dimmer,
+    /// Shutter/strobe, held at 0 (no strobe)
+    Strobe,
+    RedFine,
+    GreenFine,
+    BlueFine,
+    WhiteFine,
+    AmberFine,
+    IntensityFine,
+    DimmerFine,
+    /// Channel always output at this value (mode selects, macros off, etc.)
+    Fixed(u8),
+}
+
+impl ChannelRole {
+    /// Short label for grids and lists
+    pub fn label(&self) -> String {
+        match self {
+            ChannelRole::Red => "R".to_string(),
+            ChannelRole::Green => "G".to_string(),
+            ChannelRole::Blue => "B".to_string(),
+            ChannelRole::White => "W".to_string(),
+            ChannelRole::Amber => "A".to_string(),
+            ChannelRole::Uv => "UV".to_string(),
+            ChannelRole::Intensity => "I".to_string(),
+            ChannelRole::Dimmer => "Dim".to_string(),
+            ChannelRole::Strobe => "Strb".to_string(),
+            ChannelRole::RedFine => "R fine".to_string(),
+            ChannelRole::GreenFine => "G fine".to_string(),
+            ChannelRole::BlueFine => "B fine".to_string(),
+            ChannelRole::WhiteFine => "W fine".to_string(),
+            ChannelRole::AmberFine => "A fine".to_string(),
+            ChannelRole::IntensityFine => "I fine".to_string(),
+            ChannelRole::DimmerFine => "Dim fine".to_string(),
+            ChannelRole::Fixed(value) => format!("={}", value),
+        }
+    }
+}
+
+/// Ordered channel layout of a fixture model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FixtureProfile {
+    pub name: String,
+    pub channels: Vec<ChannelRole>,
+    /// Measured color of the W emitter as R, G, B levels (0.0-1.0); neutral if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub white_point: Option<[f32; 3]>,
+}
+
+impl Default for FixtureProfile {
+    fn default() -> Self {
+        use ChannelRole::*;
+        Self::new(PROFILE_RGBW, vec![Red, Green, Blue, White])
+    }
+}
+
+impl FixtureProfile {
+    pub fn new(name: &str, channels: Vec<ChannelRole>) -> Self {
+        Self {
+            name: name.to_string(),
+            channels,
+            white_point: None,
+        }
+    }
+
+    pub fn channel_count(&self) -> usize {
+        self.channels.len()
+    }
+
+    fn has_any(&self, roles: &[ChannelRole]) -> bool {
+        self.channels.iter().any(|c| roles.contains(c))
+    }
+
+    /// Whether the fixture has a white emitter
+    pub fn has_white(&self) -> bool {
+        self.has_any(&[ChannelRole::White, ChannelRole::WhiteFine])
+    }
+
+    /// W emitter color used for calibrated RGBW conversion
+    pub fn white_point(&self) -> [f32; 3] {
+        self.white_point.unwrap_or([1.0, 1.0, 1.0])
+    }
+
+    /// Whether the profile drives any color emitters (as opposed to a plain dimmer/relay)
+    pub fn is_color(&self) -> bool {
+        self.has_any(&[ChannelRole::Red, ChannelRole::Green, ChannelRole::Blue])
+    }
+
+    /// Render an RGBW color into this profile's channel values
+    pub fn render(&self, (r, g, b, w): (u8, u8, u8, u8)) -> Vec<u8> {
+        let wide = |v: u8| v as u16 * 257;
+        self.render_levels([wide(r), wide(g), wide(b), wide(w)])
+    }
+
+    /// Render 16-bit RGBW levels into this profile's channel values
+    ///
+    /// Fixtures without a white emitter get W folded back into R, G and B.
+    /// Fixtures with an amber emitter take the red/green overlap as amber.
+    /// Coarse/fine pairs carry the full 16-bit level; 8-bit-only fixtures get
+    /// the level rounded to the nearest step.
+    pub fn render_levels(&self, [r, g, b, w]: [u16; 4]) -> Vec<u8> {
+        let intensity = r.max(g).max(b).max(w);
+        let (mut r, mut g, b, w) = if self.has_white() {
+            (r, g, b, w)
+        } else {
+            (r.saturating_add(w), g.saturating_add(w), b.saturating_add(w), 0)
+        };
+
+        let mut a = 0;
+        if self.has_any(&[ChannelRole::Amber, ChannelRole::AmberFine]) {
+            a = r.min(g.saturating_mul(2));
+            r -= a;
+            g -= a / 2;
+        }
+
+        let fine_pairs = |coarse: ChannelRole, fine: ChannelRole| self.channels.contains(&coarse) && self.channels.contains(&fine);
+        let coarse = |v: u16, has_fine: bool| if has_fine { (v >> 8) as u8 } else { ((v as u32 + 128) / 257) as u8 };
+        let fine = |v: u16| (v & 0xFF) as u8;
+
+        self.channels
+            .iter()
+            .map(|role| match role {
+                ChannelRole::Red => coarse(r, fine_pairs(ChannelRole::Red, ChannelRole::RedFine)),
+                ChannelRole::Green => coarse(g, fine_pairs(ChannelRole::Green, ChannelRole::GreenFine)),
+                ChannelRole::Blue => coarse(b, fine_pairs(ChannelRole::Blue, ChannelRole::BlueFine)),
+                ChannelRole::White => coarse(w, fine_pairs(ChannelRole::White, ChannelRole::WhiteFine)),
+                ChannelRole::Amber => coarse(a, fine_pairs(ChannelRole::Amber, ChannelRole::AmberFine)),
+                ChannelRole::Uv => 0,
+                ChannelRole::Intensity => coarse(intensity, fine_pairs(ChannelRole::Intensity, ChannelRole::IntensityFine)),
+                ChannelRole::Dimmer => 255,
+                ChannelRole::Strobe => 0,
+                ChannelRole::RedFine => fine(r),
+                ChannelRole::GreenFine => fine(g),
+                ChannelRole::BlueFine => fine(b),
+                ChannelRole::WhiteFine => fine(w),
+                ChannelRole::AmberFine => fine(a),
+                ChannelRole::IntensityFine => fine(intensity),
+                ChannelRole::DimmerFine => 255,
+                ChannelRole::Fixed(value) => *value,
+            })
+            .collect()
+    }
+}
+
+/// Built-in profiles plus any defined in fixture_profiles.json
+#[derive(Debug, Clone)]
+pub struct ProfileLibrary {
+    profiles: BTreeMap<String, FixtureProfile>,
+}
+
+pub const PROFILE_RGB: &str = "Generic RGB";
+pub const PROFILE_RGBW: &str = "Generic RGBW";
+pub const PROFILE_SINGLE: &str = "Single Channel";
+
+impl Default for ProfileLibrary {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl ProfileLibrary {
+    /// Profiles that are always available
+    pub fn builtin() -> Self {
+        use ChannelRole::*;
+
+        let mut library = Self { profiles: BTreeMap::new() };
+        for profile in [
+            FixtureProfile::new(PROFILE_RGB, vec![Red, Green, Blue]),
+            FixtureProfile::new(PROFILE_RGBW, vec![Red, Green, Blue, White]),
+            FixtureProfile::new(PROFILE_SINGLE, vec![Intensity]),
+            FixtureProfile::new("RGBW + Dimmer + Strobe", vec![Red, Green, Blue, White, Dimmer, Strobe]),
+            FixtureProfile::new("RGBAW + UV", vec![Red, Green, Blue, Amber, White, Uv]),
+            FixtureProfile::new("RGBW 16-bit", vec![Red, RedFine, Green, GreenFine, Blue, BlueFine, White, WhiteFine]),
+        ] {
+            library.insert(profile);
+        }
+        library
+    }
+
+    /// Load the built-in profiles and add fixture_profiles.json from `dir` if present
+    pub fn load_from_dir<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct ProfileFile {
+            profiles: Vec<FixtureProfile>,
+        }
+
+        let mut library = Self::builtin();
+        let path = dir.as_ref().join("fixture_profiles.json");
+        if !path.exists() {
+            return Ok(library);
+        }
+
+        let json_data = std::fs::read_to_string(&path)
+            .context(format!("Failed to read fixture_profiles.json at {:?}", path))?;
+        let file: ProfileFile = serde_json::from_str(&json_data)
+            .context("Failed to parse fixture_profiles.json")?;
+
+        let count = file.profiles.len();
+        for profile in file.profiles {
+            if profile.channels.is_empty() || profile.channels.len() > 512 {
+                tracing::warn!("Skipping fixture profile '{}' with {} channels", profile.name, profile.channels.len());
+                continue;
+            }
+            library.insert(profile);
+        }
+
+        tracing::info!("Loaded {} fixture profiles from fixture_profiles.json", count);
+        Ok(library)
+    }
+
+    pub fn insert(&mut self, profile: FixtureProfile) {
+        self.profiles.insert(profile.name.to_lowercase(), profile);
+    }
+
+    /// Look up a profile by name (case-insensitive)
+    pub fn get(&self, name: &str) -> Option<&FixtureProfile> {
+        self.profiles.get(&name.trim().to_lowercase())
+    }
+
+    /// Resolve a name or a legacy DMXMap format code (RGB, RGBW, X)
+    pub fn resolve(&self, name: &str) -> Option<&FixtureProfile> {
+        match name.trim().to_uppercase().as_str() {
+            "RGB" => self.get(PROFILE_RGB),
+            "RGBW" => self.get(PROFILE_RGBW),
+            "X" => self.get(PROFILE_SINGLE),
+            _ => self.get(name),
+        }
+    }
+
+    /// All profiles, sorted by name
+    pub fn profiles(&self) -> impl Iterator<Item = &FixtureProfile> {
+        self.profiles.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rgbw_profile_passes_color_through() {
+        let library = ProfileLibrary::builtin();
+        let profile = library.get(PROFILE_RGBW).unwrap();
+        assert_eq!(profile.render((10, 20, 30, 40)), vec![10, 20, 30, 40]);
+    }
+
+    #[test]
+    fn test_rgb_profile_folds_white_into_rgb() {
+        let library = ProfileLibrary::builtin();
+        let profile = library.resolve("rgb").unwrap();
+        assert_eq!(profile.render((0, 100, 0, 200)), vec![200, 255, 200]);
+    }
+
+    #[test]
+    fn test_single_channel_uses_brightest_emitter() {
+        let library = ProfileLibrary::builtin();
+        let profile = library.resolve("X").unwrap();
+        assert_eq!(profile.render((0, 0, 0, 255)), vec![255]);
+    }
+
+    #[test]
+    fn test_fine_fixed_and_control_channels() {
+        use ChannelRole::*;
+        let profile = FixtureProfile::new("Test", vec![Dimmer, Red, RedFine, Strobe, Fixed(42), Uv]);
+        assert_eq!(profile.render((128, 0, 0, 0)), vec![255, 128, 128, 0, 42, 0]);
+    }
+
+    #[test]
+    fn test_16bit_levels_use_fine_channels() {
+        let library = ProfileLibrary::builtin();
+        let wide = library.get("RGBW 16-bit").unwrap();
+        assert_eq!(wide.render_levels([0x1234, 0, 0, 0xFFFF]), vec![0x12, 0x34, 0, 0, 0, 0, 0xFF, 0xFF]);
+
+        // 8-bit fixtures round to the nearest step
+        let narrow = library.get(PROFILE_RGBW).unwrap();
+        assert_eq!(narrow.render_levels([0x1234, 0, 0, 0xFFFF]), vec![18, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_amber_takes_red_green_overlap() {
+        let library = ProfileLibrary::builtin();
+        let profile = library.get("RGBAW + UV").unwrap();
+        // Yellow: amber carries it, leaving some green
+        assert_eq!(profile.render((255, 255, 0, 0)), vec![0, 128, 0, 255, 0, 0]);
+        // Pure red has no amber component
+        assert_eq!(profile.render((255, 0, 0, 0)), vec![255, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_profiles_file_roundtrip() {
+        let json = r#"{ "profiles": [ { "name": "Par 7ch", "channels": ["dimmer", "red", "green", "blue", "white", "strobe", { "fixed": 0 }] } ] }"#;
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("fixture_profiles.json"), json).unwrap();
+
+        let library = ProfileLibrary::load_from_dir(dir.path()).unwrap();
+        let profile = library.get("par 7CH").unwrap();
+        assert_eq!(profile.channel_count(), 7);
+        assert_eq!(profile.channels[6], ChannelRole::Fixed(0));
+        assert!(library.get(PROFILE_RGBW).is_some());
+    }
+}
@@ -1,10 +1,27 @@
 mod csv_config;
+mod effect_library;
+mod fixture_layout;
+mod fixture_profile;
+mod scene_library;
+mod water_map;
 
 pub use csv_config::{
-    CsvConfig, ColorDefinition, FixtureDefinition, FixtureFormat,
+    CsvConfig, ColorDefinition, FixtureDefinition, FixtureCorrection,
     FcwMapping, FcwDirective,
 };
+pub use effect_library::{EffectDefinition, EffectKind, EffectLibrary};
+pub use fixture_layout::{Direction, FixtureLayout};
+pub use scene_library::{Scene, SceneLibrary, SCENE_RECALL_ADDRESS};
+pub use water_map::{WaterDevice, WaterMap, WaterRoute};
+pub(crate) use water_map::parse_range;
+pub use fixture_profile::{
+    ChannelRole, FixtureProfile, ProfileLibrary,
+    PROFILE_RGB, PROFILE_RGBW, PROFILE_SINGLE,
+};
 
+use crate::dmx::MergeRange;
+use crate::maintenance::ServiceInterval;
+use crate::weather::WindBand;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
@@ -14,12 +31,49 @@ pub struct Settings {
     pub audio_device_id: Option<String>,
     pub audio_latency_ms: u32,
     pub dmx_enabled: bool,
-    #[serde(default = "default_use_rgbw")]
-    pub use_rgbw: bool,
+    #[serde(default = "default_dmx_device")]
+    pub dmx_device: String, // "pro" or "open"
+    #[serde(default)]
+    pub dmx_port_name: String, // Empty = auto-detect
+    #[serde(default = "default_open_dmx_break_us")]
+    pub open_dmx_break_us: u32,
+    #[serde(default = "default_open_dmx_mab_us")]
+    pub open_dmx_mab_us: u32,
+    #[serde(default)]
+    pub dmx_recording_enabled: bool,
+    #[serde(default = "default_dmx_recordings_folder")]
+    pub dmx_recordings_folder: String,
+    #[serde(default = "default_white_strategy")]
+    pub white_strategy: String, // "calibrated", "min" or "off"
+    /// Pre-white_strategy setting, only read to migrate old files
+    #[serde(default, skip_serializing)]
+    pub use_rgbw: Option<bool>,
+    #[serde(default = "default_fade_curve")]
+    pub fade_curve: String, // "linear", "s_curve", "square_law" or "exponential"
+    #[serde(default = "default_idle_look")]
+    pub idle_look: String, // "off", "scene:<id>" or "effect:<id>"
+    #[serde(default = "default_idle_fade_secs")]
+    pub idle_fade_secs: f32,
+    #[serde(default = "default_pyro_audit_file")]
+    pub pyro_audit_file: String,
     pub plc_enabled: bool,
+    #[serde(default = "default_plc_protocol")]
+    pub plc_protocol: String, // "ascii", "modbus" or "serial"
     pub plc_ip_address: String,
     pub plc_port: u16,
-    pub plc_port_name: Option<String>,
+    pub plc_port_name: Option<String>, // Serial port for the "serial" protocol
+    #[serde(default = "default_plc_baud_rate")]
+    pub plc_baud_rate: u32,
+    #[serde(default = "default_plc_parity")]
+    pub plc_parity: String, // "none", "even" or "odd"
+    #[serde(default = "default_plc_heartbeat_secs")]
+    pub plc_heartbeat_secs: f32, // 0 turns the heartbeat and silence timeout off
+    #[serde(default = "default_plc_ack_timeout_ms")]
+    pub plc_ack_timeout_ms: u64, // 0 sends batches without waiting for ACK
+    #[serde(default = "default_plc_max_retries")]
+    pub plc_max_retries: u32,
+    #[serde(default = "default_plc_reconnect_policy")]
+    pub plc_reconnect_policy: String, // "replay" or "drop" commands queued while the link was down
     pub last_playlist: Option<String>,
     #[serde(default = "default_production_folder")]
     pub production_folder: String,
@@ -46,10 +100,160 @@ pub struct Settings {
     pub sacn_interface_ip: String,
     #[serde(default)]
     pub sacn_filter_mode: String, // "all" or "900only"
+    // Network DMX input (console takeover)
+    #[serde(default)]
+    pub dmx_input_enabled: bool,
+    #[serde(default = "default_dmx_input_protocol")]
+    pub dmx_input_protocol: String, // "sacn" or "artnet"
+    #[serde(default)]
+    pub dmx_input_interface_ip: String,
+    #[serde(default = "default_dmx_input_universe")]
+    pub dmx_input_universe: u16,
+    #[serde(default = "default_dmx_input_timeout_ms")]
+    pub dmx_input_timeout_ms: u64,
+    #[serde(default)]
+    pub dmx_merge_ranges: Vec<MergeRange>,
+    // Wind limiting
+    #[serde(default = "default_wind_source")]
+    pub wind_source: String, // "off", "weather_gov", "tcp", "serial" or "file"
+    #[serde(default)]
+    pub wind_source_address: String, // host:port, serial port or file path
+    #[serde(default = "default_wind_poll_secs")]
+    pub wind_poll_secs: f32,
+    #[serde(default = "default_wind_release_secs")]
+    pub wind_release_secs: f32, // How long the wind must stay down before limits ease
+    #[serde(default = "default_wind_stale_secs")]
+    pub wind_stale_secs: f32,
+    #[serde(default)]
+    pub wind_bands: Vec<WindBand>,
+    // E-stop
+    #[serde(default = "default_estop_sequence")]
+    pub estop_sequence: Vec<String>, // Water commands sent on E-stop, shutdown and crash
+    #[serde(default = "default_failsafe_look")]
+    pub failsafe_look: String, // "blackout", "hold" or "scene:<id>"
+    #[serde(default)]
+    pub watchdog_enabled: bool,
+    #[serde(default = "default_watchdog_port")]
+    pub watchdog_port: u16, // UDP port on localhost the watchdog listens on
+    #[serde(default = "default_watchdog_timeout_secs")]
+    pub watchdog_timeout_secs: f32,
+    // Maintenance
+    #[serde(default = "default_maintenance_file")]
+    pub maintenance_file: String, // Run hours and starts, kept between runs
+    #[serde(default)]
+    pub service_intervals: Vec<ServiceInterval>,
+}
+
+fn default_white_strategy() -> String {
+    "calibrated".to_string()
+}
+
+fn default_fade_curve() -> String {
+    "linear".to_string()
+}
+
+fn default_idle_look() -> String {
+    "off".to_string()
+}
+
+fn default_idle_fade_secs() -> f32 {
+    3.0
+}
+
+fn default_pyro_audit_file() -> String {
+    "Logs/pyro_audit.csv".to_string()
+}
+
+fn default_plc_protocol() -> String {
+    "ascii".to_string()
+}
+
+fn default_plc_baud_rate() -> u32 {
+    9600
+}
+
+fn default_plc_parity() -> String {
+    "none".to_string()
+}
+
+fn default_plc_heartbeat_secs() -> f32 {
+    0.0
+}
+
+fn default_plc_ack_timeout_ms() -> u64 {
+    0
+}
+
+fn default_plc_max_retries() -> u32 {
+    2
+}
+
+fn default_plc_reconnect_policy() -> String {
+    "replay".to_string()
+}
+
+fn default_wind_source() -> String {
+    "off".to_string()
 }
 
-fn default_use_rgbw() -> bool {
-    true
+fn default_wind_poll_secs() -> f32 {
+    60.0
+}
+
+fn default_wind_release_secs() -> f32 {
+    120.0
+}
+
+fn default_wind_stale_secs() -> f32 {
+    300.0
+}
+
+fn default_estop_sequence() -> Vec<String> {
+    vec!["099-000".to_string()]
+}
+
+fn default_failsafe_look() -> String {
+    "blackout".to_string()
+}
+
+fn default_watchdog_port() -> u16 {
+    47800
+}
+
+fn default_watchdog_timeout_secs() -> f32 {
+    5.0
+}
+
+fn default_maintenance_file() -> String {
+    "Logs/maintenance.json".to_string()
+}
+
+fn default_dmx_device() -> String {
+    "pro".to_string()
+}
+
+fn default_open_dmx_break_us() -> u32 {
+    176
+}
+
+fn default_open_dmx_mab_us() -> u32 {
+    12
+}
+
+fn default_dmx_recordings_folder() -> String {
+    "Recordings".to_string()
+}
+
+fn default_dmx_input_protocol() -> String {
+    "sacn".to_string()
+}
+
+fn default_dmx_input_universe() -> u16 {
+    1
+}
+
+fn default_dmx_input_timeout_ms() -> u64 {
+    2500
 }
 
 fn default_production_folder() -> String {
@@ -94,11 +298,29 @@ impl Default for Settings {
             audio_device_id: None,
             audio_latency_ms: 100,
             dmx_enabled: true,
-            use_rgbw: true,
+            dmx_device: default_dmx_device(),
+            dmx_port_name: String::new(),
+            open_dmx_break_us: default_open_dmx_break_us(),
+            open_dmx_mab_us: default_open_dmx_mab_us(),
+            dmx_recording_enabled: false,
+            dmx_recordings_folder: default_dmx_recordings_folder(),
+            white_strategy: default_white_strategy(),
+            use_rgbw: None,
+            fade_curve: default_fade_curve(),
+            idle_look: default_idle_look(),
+            idle_fade_secs: default_idle_fade_secs(),
+            pyro_audit_file: default_pyro_audit_file(),
             plc_enabled: false,
+            plc_protocol: default_plc_protocol(),
             plc_ip_address: "192.168.1.10".to_string(),
             plc_port: 444,
             plc_port_name: None,
+            plc_baud_rate: default_plc_baud_rate(),
+            plc_parity: default_plc_parity(),
+            plc_heartbeat_secs: default_plc_heartbeat_secs(),
+            plc_ack_timeout_ms: default_plc_ack_timeout_ms(),
+            plc_max_retries: default_plc_max_retries(),
+            plc_reconnect_policy: default_plc_reconnect_policy(),
             last_playlist: None,
             production_folder: default_production_folder(),
             testing_folder: default_testing_folder(),
@@ -112,6 +334,25 @@ impl Default for Settings {
             sacn_enabled: false,
             sacn_interface_ip: String::new(),
             sacn_filter_mode: "all".to_string(),
+            dmx_input_enabled: false,
+            dmx_input_protocol: default_dmx_input_protocol(),
+            dmx_input_interface_ip: String::new(),
+            dmx_input_universe: default_dmx_input_universe(),
+            dmx_input_timeout_ms: default_dmx_input_timeout_ms(),
+            dmx_merge_ranges: Vec::new(),
+            wind_source: default_wind_source(),
+            wind_source_address: String::new(),
+            wind_poll_secs: default_wind_poll_secs(),
+            wind_release_secs: default_wind_release_secs(),
+            wind_stale_secs: default_wind_stale_secs(),
+            wind_bands: Vec::new(),
+            estop_sequence: default_estop_sequence(),
+            failsafe_look: default_failsafe_look(),
+            watchdog_enabled: false,
+            watchdog_port: default_watchdog_port(),
+            watchdog_timeout_secs: default_watchdog_timeout_secs(),
+            maintenance_file: default_maintenance_file(),
+            service_intervals: Vec::new(),
         }
     }
 }
@@ -123,8 +364,11 @@ impl Settings {
         if path.exists() {
             match fs::read_to_string(&path) {
                 Ok(content) => {
-                    match toml::from_str(&content) {
-                        Ok(settings) => return settings,
+                    match toml::from_str::<Settings>(&content) {
+                        Ok(mut settings) => {
+                            settings.migrate_use_rgbw();
+                            return settings;
+                        }
                         Err(e) => eprintln!("Failed to parse settings: {}", e),
                     }
                 }
@@ -135,6 +379,16 @@ impl Settings {
         Self::default()
     }
 
+    /// Carry the RGB/RGBW choice from before strategies existed over to
+    /// `white_strategy`: RGBW extracted white as the minimum of R, G and B
+    fn migrate_use_rgbw(&mut self) {
+        match self.use_rgbw.take() {
+            Some(true) => self.white_strategy = "min".to_string(),
+            Some(false) => self.white_strategy = "off".to_string(),
+            None => {}
+        }
+    }
+
     pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
         let path = Self::config_path();
         
@@ -168,3 +422,20 @@ impl Settings {
 
 // Optional: Add dirs crate for cross-platform directory paths
 // Add to Cargo.toml: dirs = "5.0"
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_use_rgbw_migration() {
+        let migrated = |use_rgbw| {
+            let mut settings = Settings { use_rgbw, ..Settings::default() };
+            settings.migrate_use_rgbw();
+            (settings.white_strategy, settings.use_rgbw)
+        };
+        assert_eq!(migrated(Some(true)), ("min".to_string(), None));
+        assert_eq!(migrated(Some(false)), ("off".to_string(), None));
+        assert_eq!(migrated(None), ("calibrated".to_string(), None));
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use anyhow::{Context, Result};
+use crate::commands::{parse_command, RESET_ADDRESS};
+use serde::{Deserialize, Serialize};
+
+/// FCW address that recalls a scene (`075-<id>`, or `175-<tenths>` then `075-<id>` to fade)
+pub const SCENE_RECALL_ADDRESS: u16 = 75;
+
+/// A saved look from scenes.json
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scene {
+    pub id: u16,
+    pub name: String,
+    /// Fixture# -> output RGBW, already white-split and corrected
+    pub fixtures: BTreeMap<u16, [u8; 4]>,
+    /// Water commands ("XXX-YYY") sent to the PLC on recall
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub water: Vec<String>,
+}
+
+/// Scenes stored in scenes.json, keyed by id
+#[derive(Debug, Clone, Default)]
+pub struct SceneLibrary {
+    scenes: BTreeMap<u16, Scene>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SceneFile {
+    scenes: Vec<Scene>,
+}
+
+impl SceneLibrary {
+    /// Load scenes.json from `dir`; a missing file gives an empty library
+    pub fn load_from_dir<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let mut library = Self::default();
+        let path = dir.as_ref().join("scenes.json");
+        if !path.exists() {
+            return Ok(library);
+        }
+
+        let json_data = std::fs::read_to_string(&path)
+            .context(format!("Failed to read scenes.json at {:?}", path))?;
+        let file: SceneFile = serde_json::from_str(&json_data)
+            .context("Failed to parse scenes.json")?;
+
+        for mut scene in file.scenes {
+            if !(1..=999).contains(&scene.id) {
+                tracing::warn!("Skipping scene '{}': id must be 1-999", scene.name);
+                continue;
+            }
+            // A recalled reset would switch off everything the scene turns on
+            scene.water.retain(|command| parse_command(command).map(|(address, _)| address) != Some(RESET_ADDRESS));
+            library.insert(scene);
+        }
+
+        tracing::info!("Loaded {} scenes from scenes.json", library.scenes.len());
+        Ok(library)
+    }
+
+    /// Write every scene to scenes.json in `dir`
+    pub fn save_to_dir<P: AsRef<Path>>(&self, dir: P) -> Result<()> {
+        let path = dir.as_ref().join("scenes.json");
+        let file = SceneFile { scenes: self.scenes.values().cloned().collect() };
+        let json_data = serde_json::to_string_pretty(&file)?;
+        std::fs::write(&path, json_data)
+            .context(format!("Failed to write scenes.json at {:?}", path))?;
+        Ok(())
+    }
+
+    pub fn insert(&mut self, scene: Scene) {
+        self.scenes.insert(scene.id, scene);
+    }
+
+    pub fn remove(&mut self, id: u16) -> Option<Scene> {
+        self.scenes.remove(&id)
+    }
+
+    pub fn get(&self, id: u16) -> Option<&Scene> {
+        self.scenes.get(&id)
+    }
+
+    /// All scenes, sorted by id
+    pub fn scenes(&self) -> impl Iterator<Item = &Scene> {
+        self.scenes.values()
+    }
+
+    /// Lowest free id
+    pub fn next_id(&self) -> u16 {
+        (1..=999).find(|id| !self.scenes.contains_key(id)).unwrap_or(999)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scene(id: u16, name: &str) -> Scene {
+        Scene {
+            id,
+            name: name.to_string(),
+            fixtures: BTreeMap::from([(1, [255, 0, 0, 0]), (2, [0, 0, 0, 255])]),
+            water: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut library = SceneLibrary::default();
+        library.insert(scene(1, "Red wash"));
+        let mut with_water = scene(3, "Finale");
+        with_water.water = vec!["099-000".to_string(), "001-005".to_string()];
+        library.insert(with_water.clone());
+        library.save_to_dir(dir.path()).unwrap();
+        with_water.water.remove(0);
+
+        let loaded = SceneLibrary::load_from_dir(dir.path()).unwrap();
+        assert_eq!(loaded.scenes().count(), 2);
+        assert_eq!(loaded.get(1), Some(&scene(1, "Red wash")));
+        assert_eq!(loaded.get(3), Some(&with_water));
+        assert_eq!(loaded.next_id(), 2);
+    }
+
+    #[test]
+    fn test_missing_file_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let library = SceneLibrary::load_from_dir(dir.path()).unwrap();
+        assert_eq!(library.scenes().count(), 0);
+        assert_eq!(library.next_id(), 1);
+    }
+}
@@ -0,0 +1,317 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Where commands on an FCW address go
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WaterRoute {
+    /// Fixtures only; every address not in water_map.json
+    #[default]
+    Light,
+    /// The PLC only
+    Water,
+    /// The PLC and the fixtures, e.g. `099` resets water and holds the idle look
+    Both,
+}
+
+impl WaterRoute {
+    pub fn to_plc(&self) -> bool {
+        matches!(self, WaterRoute::Water | WaterRoute::Both)
+    }
+
+    pub fn to_lights(&self) -> bool {
+        matches!(self, WaterRoute::Light | WaterRoute::Both)
+    }
+}
+
+/// What a water address drives and the limits the guard holds it to
+///
+/// The defaults allow any FCW value with no dwell or rate limit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaterDevice {
+    /// What the address drives, e.g. "Module 1 pump"
+    pub name: String,
+    pub min: u16,
+    pub max: u16,
+    /// Shortest time between turning on and turning off again (or back on),
+    /// for motors that must not be cycled quickly
+    pub min_dwell_ms: u64,
+    /// Fastest a running device may change, in value units per second
+    pub max_rate: Option<f32>,
+    /// A jet height, scaled down by the wind limits
+    pub height: bool,
+}
+
+impl Default for WaterDevice {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            min: 0,
+            max: 999,
+            min_dwell_ms: 0,
+            max_rate: None,
+            height: false,
+        }
+    }
+}
+
+impl WaterDevice {
+    /// True when the guard has anything to check
+    pub fn is_constrained(&self) -> bool {
+        *self != WaterDevice { name: self.name.clone(), ..WaterDevice::default() }
+    }
+}
+
+/// Routes used when there is no water_map.json, as the PLC was wired before
+/// the map was configurable
+const BUILTIN_ROUTES: &[(u16, u16, WaterRoute)] = &[
+    (1, 13, WaterRoute::Water),
+    (33, 40, WaterRoute::Water),
+    (47, 48, WaterRoute::Water),
+    (87, 91, WaterRoute::Water),
+    (99, 99, WaterRoute::Both),
+    (217, 223, WaterRoute::Water),
+    (249, 255, WaterRoute::Water),
+    (700, 749, WaterRoute::Water),
+];
+
+/// Which FCW addresses go to the PLC, from water_map.json
+///
+/// The dispatcher, the PLC transports and the command displays all read
+/// this one map.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaterMap {
+    routes: BTreeMap<u16, WaterRoute>,
+    devices: BTreeMap<u16, WaterDevice>,
+}
+
+impl Default for WaterMap {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl WaterMap {
+    /// The routes the PLC had before water_map.json existed
+    pub fn builtin() -> Self {
+        let mut map = Self { routes: BTreeMap::new(), devices: BTreeMap::new() };
+        for &(first, last, route) in BUILTIN_ROUTES {
+            map.set_range(first, last, route, WaterDevice::default());
+        }
+        map
+    }
+
+    /// Load water_map.json from `dir`; a missing file gives the built-in map
+    pub fn load_from_dir<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let path = dir.as_ref().join("water_map.json");
+        if !path.exists() {
+            tracing::info!("No water_map.json, using the built-in water addresses");
+            return Ok(Self::builtin());
+        }
+
+        let json_data = std::fs::read_to_string(&path)
+            .context(format!("Failed to read water_map.json at {:?}", path))?;
+        let map = Self::parse(&json_data)?;
+        tracing::info!("Loaded {} water addresses from water_map.json", map.water_addresses().count());
+        Ok(map)
+    }
+
+    /// Parse water_map.json: `{"routes": [{"addresses": "1-13", "route": "water"}]}`
+    ///
+    /// `addresses` is one address or an inclusive range. Later entries
+    /// override earlier ones, so a range can be carved up. Water entries may
+    /// also give the device's `name`, `min`/`max` value, `min_dwell_ms`,
+    /// `max_rate` (units per second) and whether it is a wind-limited `height`.
+    pub fn parse(json_data: &str) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct RouteEntry {
+            addresses: String,
+            route: WaterRoute,
+            #[serde(default)]
+            name: String,
+            #[serde(default)]
+            min: Option<u16>,
+            #[serde(default)]
+            max: Option<u16>,
+            #[serde(default)]
+            min_dwell_ms: u64,
+            #[serde(default)]
+            max_rate: Option<f32>,
+            #[serde(default)]
+            height: bool,
+        }
+
+        #[derive(Deserialize)]
+        struct WaterMapFile {
+            routes: Vec<RouteEntry>,
+        }
+
+        let file: WaterMapFile = serde_json::from_str(json_data)
+            .context("Failed to parse water_map.json")?;
+
+        let mut map = Self { routes: BTreeMap::new(), devices: BTreeMap::new() };
+        for entry in file.routes {
+            let Some((first, last)) = parse_range(&entry.addresses) else {
+                tracing::warn!("Skipping water map entry '{}': expected an address or a range like 1-13", entry.addresses);
+                continue;
+            };
+
+            let defaults = WaterDevice::default();
+            let mut device = WaterDevice {
+                name: entry.name,
+                min: entry.min.unwrap_or(defaults.min),
+                max: entry.max.unwrap_or(defaults.max),
+                min_dwell_ms: entry.min_dwell_ms,
+                max_rate: entry.max_rate.filter(|rate| *rate > 0.0),
+                height: entry.height,
+            };
+            if device.min > device.max {
+                tracing::warn!("Ignoring value range for water addresses {}: min is above max", entry.addresses);
+                device.min = defaults.min;
+                device.max = defaults.max;
+            }
+            map.set_range(first, last, entry.route, device);
+        }
+        Ok(map)
+    }
+
+    fn set_range(&mut self, first: u16, last: u16, route: WaterRoute, device: WaterDevice) {
+        for address in first..=last {
+            if route == WaterRoute::Light {
+                self.routes.remove(&address);
+                self.devices.remove(&address);
+            } else {
+                self.routes.insert(address, route);
+                self.devices.insert(address, device.clone());
+            }
+        }
+    }
+
+    pub fn route(&self, fcw_address: u16) -> WaterRoute {
+        self.routes.get(&fcw_address).copied().unwrap_or_default()
+    }
+
+    /// The device on a water address; None for light-only addresses
+    pub fn device(&self, fcw_address: u16) -> Option<&WaterDevice> {
+        self.devices.get(&fcw_address)
+    }
+
+    /// True when commands on the address go to the PLC
+    pub fn is_water(&self, fcw_address: u16) -> bool {
+        self.route(fcw_address).to_plc()
+    }
+
+    /// True for an "XXX-YYY" command on a water address
+    pub fn is_water_command(&self, command: &str) -> bool {
+        command.split_once('-')
+            .and_then(|(address, _)| address.trim().parse::<u16>().ok())
+            .is_some_and(|address| self.is_water(address))
+    }
+
+    /// Every address that goes to the PLC, in order
+    pub fn water_addresses(&self) -> impl Iterator<Item = u16> + '_ {
+        self.routes.keys().copied()
+    }
+
+    /// Addresses where the Water column of an FCWMap.CSV disagrees with this
+    /// map, with whether the CSV marks them as water, in address order
+    ///
+    /// Rows whose FCW column is not a number are skipped.
+    pub fn fcw_map_mismatches<R: std::io::Read>(&self, fcw_map: R) -> Result<Vec<(u16, bool)>> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(true)
+            .flexible(true)
+            .from_reader(fcw_map);
+
+        let mut marked = BTreeMap::new();
+        for result in reader.records() {
+            let record = result.context("Failed to read FCWMap.CSV")?;
+            let Some(address) = record.get(0).and_then(|field| field.trim().parse::<u16>().ok()) else {
+                continue;
+            };
+            let water = record.get(1).is_some_and(|field| !field.trim().is_empty());
+            marked.insert(address, water);
+        }
+        for address in self.water_addresses() {
+            marked.entry(address).or_insert(false);
+        }
+
+        Ok(marked.into_iter()
+            .filter(|&(address, water)| water != self.is_water(address))
+            .collect())
+    }
+}
+
+/// "12" or "700-749"
+pub(crate) fn parse_range(text: &str) -> Option<(u16, u16)> {
+    let (first, last) = match text.split_once('-') {
+        Some((first, last)) => (first.trim().parse().ok()?, last.trim().parse().ok()?),
+        None => {
+            let address = text.trim().parse().ok()?;
+            (address, address)
+        }
+    };
+    (first <= last).then_some((first, last))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_water_map() {
+        let map = WaterMap::parse(r#"{
+            "routes": [
+                { "addresses": "1-13", "route": "water" },
+                { "addresses": "87", "route": "water", "name": "Center height", "max": 500, "max_rate": 250, "height": true },
+                { "addresses": "33", "route": "water", "min": 9, "max": 1 },
+                { "addresses": "99", "route": "both" },
+                { "addresses": "5", "route": "light" },
+                { "addresses": "20-10", "route": "water" },
+                { "addresses": "abc", "route": "water" }
+            ]
+        }"#).unwrap();
+
+        assert_eq!(map.route(1), WaterRoute::Water);
+        assert_eq!(map.route(5), WaterRoute::Light);
+        assert_eq!(map.route(99), WaterRoute::Both);
+        assert_eq!(map.route(17), WaterRoute::Light);
+        assert!(map.route(99).to_lights());
+        assert!(map.is_water_command("013-005"));
+        assert!(!map.is_water_command("017-002"));
+        assert!(!map.is_water_command("garbage"));
+        assert_eq!(map.water_addresses().count(), 15);
+
+        let height = map.device(87).unwrap();
+        assert_eq!((height.name.as_str(), height.min, height.max, height.max_rate), ("Center height", 0, 500, Some(250.0)));
+        assert!(height.is_constrained() && height.height);
+        assert!(!map.device(33).unwrap().is_constrained());
+        assert!(map.device(5).is_none());
+    }
+
+    #[test]
+    fn test_missing_file_is_builtin() {
+        let dir = tempfile::tempdir().unwrap();
+        let map = WaterMap::load_from_dir(dir.path()).unwrap();
+        assert_eq!(map, WaterMap::builtin());
+        assert!(map.is_water(700) && map.is_water(48) && !map.is_water(46));
+        assert_eq!(map.route(99), WaterRoute::Both);
+    }
+
+    #[test]
+    fn test_fcw_map_mismatches() {
+        let map = WaterMap::parse(r#"{
+            "routes": [
+                { "addresses": "1-2", "route": "water" },
+                { "addresses": "47", "route": "water" },
+                { "addresses": "99", "route": "both" }
+            ]
+        }"#).unwrap();
+        let csv = "FCW,Water,1,2\n0,XXX,,\n1,XXX,,\n2,XXX,,\n17,,X,X\n900,XXX,,\nnotes,,,\n";
+
+        let mismatches = map.fcw_map_mismatches(csv.as_bytes()).unwrap();
+        assert_eq!(mismatches, vec![(0, true), (47, false), (99, false), (900, true)]);
+    }
+}
@@ -0,0 +1,141 @@
+use super::{DmxError, DmxHealth, EnttecDmxPro, EnttecOpenDmx, OpenDmxTiming, SerialConnector, WidgetInfo};
+use crate::config::Settings;
+
+/// USB DMX interface type selected in settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DmxDeviceKind {
+    /// Enttec DMX USB Pro (widget generates DMX timing)
+    Pro,
+    /// Enttec Open DMX (host generates DMX timing over the FTDI UART)
+    Open,
+}
+
+impl DmxDeviceKind {
+    /// Parse the device name stored in settings ("pro" or "open")
+    pub fn from_setting(value: &str) -> Self {
+        match value.trim().to_lowercase().as_str() {
+            "open" | "opendmx" | "open_dmx" => DmxDeviceKind::Open,
+            _ => DmxDeviceKind::Pro,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DmxDeviceKind::Pro => "Enttec DMX USB Pro",
+            DmxDeviceKind::Open => "Enttec Open DMX",
+        }
+    }
+}
+
+/// A USB DMX interface of either supported type
+pub enum DmxDevice {
+    Pro(EnttecDmxPro),
+    Open(EnttecOpenDmx),
+}
+
+impl DmxDevice {
+    /// Open the interface the settings describe
+    ///
+    /// Both drivers keep trying to open a missing interface on their own
+    /// output thread, so this never waits on USB.
+    pub fn from_settings(settings: &Settings) -> Self {
+        let port_name = settings.dmx_port_name.as_str();
+        match DmxDeviceKind::from_setting(&settings.dmx_device) {
+            DmxDeviceKind::Pro => {
+                DmxDevice::Pro(EnttecDmxPro::with_connector(Box::new(SerialConnector::new(port_name))))
+            }
+            DmxDeviceKind::Open => {
+                let timing = OpenDmxTiming {
+                    break_us: settings.open_dmx_break_us,
+                    mab_us: settings.open_dmx_mab_us,
+                };
+                DmxDevice::Open(EnttecOpenDmx::new(port_name, timing))
+            }
+        }
+    }
+
+    pub fn kind(&self) -> DmxDeviceKind {
+        match self {
+            DmxDevice::Pro(_) => DmxDeviceKind::Pro,
+            DmxDevice::Open(_) => DmxDeviceKind::Open,
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        match self {
+            DmxDevice::Pro(dmx) => dmx.is_connected(),
+            DmxDevice::Open(dmx) => dmx.is_connected(),
+        }
+    }
+
+    /// Keep the last frame on the rig when output stops instead of blacking out
+    pub fn hold_on_stop(&mut self) {
+        match self {
+            DmxDevice::Pro(dmx) => dmx.hold_on_stop(),
+            DmxDevice::Open(dmx) => dmx.hold_on_stop(),
+        }
+    }
+
+    pub fn health(&self) -> DmxHealth {
+        match self {
+            DmxDevice::Pro(dmx) => dmx.health(),
+            DmxDevice::Open(dmx) => dmx.health(),
+        }
+    }
+
+    /// Widget parameters (only the Pro reports these)
+    pub fn widget_info(&self) -> Option<WidgetInfo> {
+        match self {
+            DmxDevice::Pro(dmx) => dmx.widget_info(),
+            DmxDevice::Open(_) => None,
+        }
+    }
+
+    /// Set a single DMX channel value (1-512)
+    pub fn set_channel(&mut self, channel: usize, value: u8) -> Result<(), DmxError> {
+        match self {
+            DmxDevice::Pro(dmx) => dmx.set_channel(channel, value),
+            DmxDevice::Open(dmx) => dmx.set_channel(channel, value),
+        }
+    }
+
+    /// Set multiple DMX channels at once
+    pub fn set_channels(&mut self, start_channel: usize, values: &[u8]) -> Result<(), DmxError> {
+        match self {
+            DmxDevice::Pro(dmx) => dmx.set_channels(start_channel, values),
+            DmxDevice::Open(dmx) => dmx.set_channels(start_channel, values),
+        }
+    }
+
+    /// Get current value of a DMX channel
+    pub fn get_channel(&self, channel: usize) -> Result<u8, DmxError> {
+        match self {
+            DmxDevice::Pro(dmx) => dmx.get_channel(channel),
+            DmxDevice::Open(dmx) => dmx.get_channel(channel),
+        }
+    }
+
+    /// Clear all DMX channels to 0
+    pub fn clear(&mut self) {
+        match self {
+            DmxDevice::Pro(dmx) => dmx.clear(),
+            DmxDevice::Open(dmx) => dmx.clear(),
+        }
+    }
+
+    /// Clear all DMX channels to 0 except those in the ignore list (1-indexed)
+    pub fn clear_except(&mut self, ignore_channels: &[u16]) {
+        match self {
+            DmxDevice::Pro(dmx) => dmx.clear_except(ignore_channels),
+            DmxDevice::Open(dmx) => dmx.clear_except(ignore_channels),
+        }
+    }
+
+    /// Send the current DMX universe to the hardware
+    pub fn send_dmx(&mut self) -> Result<(), DmxError> {
+        match self {
+            DmxDevice::Pro(dmx) => dmx.send_dmx(),
+            DmxDevice::Open(dmx) => dmx.send_dmx(),
+        }
+    }
+}
@@ -1,55 +1,227 @@
+use super::port::{DmxPort, PortConnector, SerialConnector};
 use super::{DmxError, DmxUniverse};
-use serialport::{SerialPort, SerialPortInfo, SerialPortType};
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 use tracing::{debug, info, warn};
 
 const START_BYTE: u8 = 0x7E;
 const END_BYTE: u8 = 0xE7;
+const GET_WIDGET_PARAMS_LABEL: u8 = 3;
 const SEND_DMX_LABEL: u8 = 6;
+const GET_SERIAL_NUMBER_LABEL: u8 = 10;
 const DMX_UNIVERSE_SIZE: usize = 512;
+const REPLY_TIMEOUT: Duration = Duration::from_millis(500);
+const DEFAULT_RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+/// How long the output thread sleeps with no new frame before checking
+/// whether it should reconnect or stop
+const WAKE_INTERVAL: Duration = Duration::from_millis(100);
 
-/// Enttec DMX USB Pro driver implementation
+/// Widget parameters and serial number reported by the Pro (labels 3 and 10)
+#[derive(Debug, Clone, PartialEq)]
+pub struct WidgetInfo {
+    pub firmware_version: u16,
+    pub break_time_us: f32,
+    pub mab_time_us: f32,
+    pub refresh_rate: u8, // Packets per second, 0 = as fast as possible
+    pub serial_number: Option<String>,
+}
+
+/// Live output health for the status bar
+#[derive(Debug, Clone, Default)]
+pub struct DmxHealth {
+    pub connected: bool,
+    pub port_name: Option<String>,
+    pub frames_per_second: f32,
+    pub frames_sent: u64,
+    pub errors: u64,
+    pub reconnects: u64,
+    pub last_error: Option<String>,
+}
+
+struct ProShared {
+    frame: [u8; DMX_UNIVERSE_SIZE],
+    /// A frame from `send_dmx` is waiting to be written
+    pending: bool,
+    health: DmxHealth,
+    widget_info: Option<WidgetInfo>,
+    reconnect_interval: Duration,
+    last_frame_at: Option<Instant>,
+    /// Leave the last frame on the widget when output stops
+    hold_on_stop: bool,
+}
+
+/// Enttec DMX USB Pro driver
+///
+/// The widget generates the DMX timing itself and keeps refreshing the last
+/// frame it was given. A background thread owns the port: it writes each
+/// frame `send_dmx()` hands it, reads the widget parameters on connect and
+/// reopens the widget after an unplug, so none of that waits on USB in the
+/// caller's thread.
 pub struct EnttecDmxPro {
-    port: Box<dyn SerialPort>,
     universe: DmxUniverse,
+    shared: Arc<(Mutex<ProShared>, Condvar)>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
 }
 
 impl EnttecDmxPro {
-    /// Create a new Enttec DMX USB Pro connection
-    pub fn new() -> Result<Self, DmxError> {
+    /// Start output on the first Enttec device found
+    pub fn new() -> Self {
         info!("Searching for Enttec DMX USB Pro device...");
-        
-        let ports = serialport::available_ports()?;
-        
-        // Try to find Enttec device
-        let enttec_port = ports.iter()
-            .find(|p| Self::is_enttec_device(p))
-            .ok_or(DmxError::DeviceNotFound)?;
-
-        info!("Found Enttec device: {}", enttec_port.port_name);
-
-        // Open serial port with Enttec settings
-        let port = serialport::new(&enttec_port.port_name, 57_600)
-            .timeout(Duration::from_millis(100))
-            .open()?;
-
-        Ok(Self {
-            port,
-            universe: DmxUniverse::new(),
-        })
+        Self::with_connector(Box::new(SerialConnector::new("")))
     }
 
-    /// Check if a serial port is an Enttec device
-    fn is_enttec_device(port_info: &SerialPortInfo) -> bool {
-        match &port_info.port_type {
-            SerialPortType::UsbPort(info) => {
-                // FTDI Vendor ID and common Enttec Product IDs
-                info.vid == 0x0403 && (info.pid == 0x6001 || info.pid == 0x6015)
+    /// Start output through the given connector
+    ///
+    /// The output thread connects straight away and keeps retrying while
+    /// the widget is missing.
+    pub fn with_connector(mut connector: Box<dyn PortConnector>) -> Self {
+        let shared = Arc::new((
+            Mutex::new(ProShared {
+                frame: [0; DMX_UNIVERSE_SIZE],
+                pending: false,
+                health: DmxHealth::default(),
+                widget_info: None,
+                reconnect_interval: DEFAULT_RECONNECT_INTERVAL,
+                last_frame_at: None,
+                hold_on_stop: false,
+            }),
+            Condvar::new(),
+        ));
+        let running = Arc::new(AtomicBool::new(true));
+
+        let thread_shared = Arc::clone(&shared);
+        let thread_running = Arc::clone(&running);
+
+        let thread = std::thread::spawn(move || {
+            let (lock, wake) = &*thread_shared;
+            let mut port: Option<Box<dyn DmxPort>> = None;
+            let mut last_connect_attempt: Option<Instant> = None;
+            let mut ever_connected = false;
+            let mut fps_window_start = Instant::now();
+            let mut fps_window_frames = 0u32;
+
+            while thread_running.load(Ordering::SeqCst) {
+                let reconnect_interval = lock.lock().unwrap().reconnect_interval;
+                if port.is_none() && last_connect_attempt.is_none_or(|t| t.elapsed() >= reconnect_interval) {
+                    last_connect_attempt = Some(Instant::now());
+                    match connector.connect() {
+                        Ok((port_name, mut new_port)) => {
+                            let widget_info = read_widget_info(new_port.as_mut(), &port_name);
+                            port = Some(new_port);
+                            let mut shared = lock.lock().unwrap();
+                            shared.health.connected = true;
+                            shared.health.port_name = Some(port_name);
+                            shared.widget_info = widget_info;
+                            // Whatever the caller last sent goes out on the new port
+                            shared.pending = true;
+                            if ever_connected {
+                                shared.health.reconnects += 1;
+                                info!("Enttec DMX USB Pro reconnected");
+                            }
+                            ever_connected = true;
+                        }
+                        Err(e) => debug!("Enttec connect failed: {}", e),
+                    }
+                }
+
+                let frame = {
+                    let mut shared = lock.lock().unwrap();
+                    if !shared.pending || port.is_none() {
+                        shared = wake.wait_timeout(shared, WAKE_INTERVAL).unwrap().0;
+                    }
+                    if !shared.pending || port.is_none() {
+                        continue;
+                    }
+                    shared.pending = false;
+                    shared.frame
+                };
+
+                let Some(active_port) = port.as_mut() else { continue };
+                match write_frame(active_port.as_mut(), &frame) {
+                    Ok(_) => {
+                        fps_window_frames += 1;
+                        let mut shared = lock.lock().unwrap();
+                        shared.health.frames_sent += 1;
+                        shared.last_frame_at = Some(Instant::now());
+                        let elapsed = fps_window_start.elapsed();
+                        if elapsed >= Duration::from_secs(1) {
+                            shared.health.frames_per_second = fps_window_frames as f32 / elapsed.as_secs_f32();
+                            fps_window_frames = 0;
+                            fps_window_start = Instant::now();
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Enttec DMX USB Pro write failed, will reconnect: {}", e);
+                        port = None;
+                        fps_window_frames = 0;
+                        fps_window_start = Instant::now();
+                        let mut shared = lock.lock().unwrap();
+                        shared.widget_info = None;
+                        shared.health.connected = false;
+                        shared.health.errors += 1;
+                        shared.health.last_error = Some(e.to_string());
+                        shared.health.frames_per_second = 0.0;
+                    }
+                }
             }
-            _ => false,
+
+            // Leave the rig dark when output stops, unless a failsafe look is latched
+            let hold_on_stop = lock.lock().map(|s| s.hold_on_stop).unwrap_or(false);
+            if let (false, Some(active_port)) = (hold_on_stop, port.as_mut()) {
+                let _ = write_frame(active_port.as_mut(), &[0; DMX_UNIVERSE_SIZE]);
+            }
+            info!("Enttec DMX USB Pro disconnected");
+        });
+
+        Self {
+            universe: DmxUniverse::new(),
+            shared,
+            running,
+            thread: Some(thread),
         }
     }
 
+    /// Minimum time between reconnect attempts while the widget is missing
+    pub fn set_reconnect_interval(&mut self, interval: Duration) {
+        if let Ok(mut shared) = self.shared.0.lock() {
+            shared.reconnect_interval = interval;
+        }
+    }
+
+    /// Keep the last frame on the rig when output stops instead of
+    /// blacking out; the widget goes on refreshing it by itself
+    pub fn hold_on_stop(&mut self) {
+        if let Ok(mut shared) = self.shared.0.lock() {
+            shared.hold_on_stop = true;
+        }
+    }
+
+    /// Check if the widget is currently open
+    pub fn is_connected(&self) -> bool {
+        self.shared.0.lock().map(|s| s.health.connected).unwrap_or(false)
+    }
+
+    /// Widget parameters read at the last (re)connect
+    pub fn widget_info(&self) -> Option<WidgetInfo> {
+        self.shared.0.lock().ok().and_then(|s| s.widget_info.clone())
+    }
+
+    /// Snapshot of output health
+    pub fn health(&self) -> DmxHealth {
+        let Ok(shared) = self.shared.0.lock() else {
+            return DmxHealth::default();
+        };
+        let mut health = shared.health.clone();
+        // Output has stalled if nothing was sent for a couple of seconds
+        if shared.last_frame_at.is_none_or(|t| t.elapsed() > Duration::from_secs(2)) {
+            health.frames_per_second = 0.0;
+        }
+        health
+    }
+
     /// Set a single DMX channel value (1-512)
     pub fn set_channel(&mut self, channel: usize, value: u8) -> Result<(), DmxError> {
         self.universe.set_channel(channel, value)
@@ -70,79 +242,389 @@ impl EnttecDmxPro {
         self.universe.clear();
         debug!("DMX universe cleared");
     }
-    
+
     /// Clear all DMX channels to 0 except those in the ignore list (1-indexed)
     pub fn clear_except(&mut self, ignore_channels: &[u16]) {
         self.universe.clear_except(ignore_channels);
         debug!("DMX universe cleared (ignoring {} channels)", ignore_channels.len());
     }
 
-    /// Send the current DMX universe to the hardware
+    /// Hand the current universe to the output thread
+    ///
+    /// Errs with `DeviceNotFound` while the widget is missing; the thread
+    /// keeps trying to reopen it and sends the latest frame once it does.
     pub fn send_dmx(&mut self) -> Result<(), DmxError> {
-        let packet = self.build_dmx_packet();
-        
-        self.port.write_all(&packet)
-            .map_err(|e| DmxError::CommError(e.to_string()))?;
-        
-        Ok(())
-    }
-
-    /// Build Enttec DMX USB Pro packet
-    fn build_dmx_packet(&self) -> Vec<u8> {
-        let mut packet = Vec::with_capacity(DMX_UNIVERSE_SIZE + 6);
-        
-        // Start byte
-        packet.push(START_BYTE);
-        
-        // Label (6 = Send DMX)
-        packet.push(SEND_DMX_LABEL);
-        
-        // Data length: 513 bytes (1 start code + 512 channels)
-        let length = (DMX_UNIVERSE_SIZE + 1) as u16;
-        packet.push((length & 0xFF) as u8);        // LSB
-        packet.push(((length >> 8) & 0xFF) as u8); // MSB
-        
-        // DMX Start Code (always 0x00 for DMX512)
-        packet.push(0x00);
-        
-        // DMX data (512 channels)
-        packet.extend_from_slice(self.universe.as_slice());
-        
-        // End byte
-        packet.push(END_BYTE);
-        
-        packet
-    }
-
-    /// Start continuous DMX output (call send_dmx() repeatedly)
-    pub fn start_continuous_output(&mut self, interval_ms: u64) -> Result<(), DmxError> {
-        // This would typically run in a separate thread
-        // For now, just document the usage pattern
-        info!("Starting continuous DMX output with {}ms interval", interval_ms);
-        Ok(())
+        let (lock, wake) = &*self.shared;
+        let mut shared = lock.lock()
+            .map_err(|_| DmxError::CommError("Enttec output thread panicked".to_string()))?;
+        shared.frame.copy_from_slice(self.universe.as_slice());
+        shared.pending = true;
+        wake.notify_one();
+
+        if shared.health.connected {
+            Ok(())
+        } else {
+            Err(DmxError::DeviceNotFound)
+        }
+    }
+}
+
+impl Default for EnttecDmxPro {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl Drop for EnttecDmxPro {
     fn drop(&mut self) {
-        // Clear DMX channels on disconnect
-        self.clear();
-        let _ = self.send_dmx();
-        info!("Enttec DMX USB Pro disconnected");
+        self.running.store(false, Ordering::SeqCst);
+        self.shared.1.notify_one();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Send one frame: start code followed by 512 channels
+fn write_frame(port: &mut dyn DmxPort, frame: &[u8; DMX_UNIVERSE_SIZE]) -> std::io::Result<()> {
+    let mut data = Vec::with_capacity(DMX_UNIVERSE_SIZE + 1);
+    data.push(0x00); // DMX Start Code
+    data.extend_from_slice(frame);
+    port.write_all(&build_message(SEND_DMX_LABEL, &data))?;
+    port.flush()
+}
+
+/// Read the widget parameters after connecting; a widget that doesn't
+/// answer still gets used
+fn read_widget_info(port: &mut dyn DmxPort, port_name: &str) -> Option<WidgetInfo> {
+    match query_widget_info(port) {
+        Ok(widget_info) => {
+            info!(
+                "Enttec widget on {}: firmware {}.{}, serial {}, break {:.0}us, MAB {:.0}us",
+                port_name,
+                widget_info.firmware_version >> 8,
+                widget_info.firmware_version & 0xFF,
+                widget_info.serial_number.as_deref().unwrap_or("unknown"),
+                widget_info.break_time_us,
+                widget_info.mab_time_us
+            );
+            Some(widget_info)
+        }
+        Err(e) => {
+            warn!("Could not read Enttec widget parameters: {}", e);
+            None
+        }
+    }
+}
+
+/// Query firmware, timing and serial number from the widget
+fn query_widget_info(port: &mut dyn DmxPort) -> Result<WidgetInfo, DmxError> {
+    // Label 3 takes the user configuration size to read back (none)
+    let params = request(port, GET_WIDGET_PARAMS_LABEL, &[0x00, 0x00])?;
+    if params.len() < 5 {
+        return Err(DmxError::CommError(format!(
+            "Widget parameters reply too short ({} bytes)",
+            params.len()
+        )));
     }
+
+    // Serial number is optional - older firmware does not answer label 10
+    let serial_number = match request(port, GET_SERIAL_NUMBER_LABEL, &[]) {
+        Ok(reply) if reply.len() >= 4 => Some(format_serial_number(&reply[..4])),
+        Ok(_) => None,
+        Err(e) => {
+            debug!("Enttec serial number query failed: {}", e);
+            None
+        }
+    };
+
+    Ok(WidgetInfo {
+        firmware_version: u16::from_le_bytes([params[0], params[1]]),
+        break_time_us: params[2] as f32 * 10.67,
+        mab_time_us: params[3] as f32 * 10.67,
+        refresh_rate: params[4],
+        serial_number,
+    })
+}
+
+/// Send a request and wait for the reply with the same label
+fn request(port: &mut dyn DmxPort, label: u8, data: &[u8]) -> Result<Vec<u8>, DmxError> {
+    port.write_all(&build_message(label, data))
+        .and_then(|_| port.flush())
+        .map_err(|e| DmxError::CommError(e.to_string()))?;
+
+    read_reply(port, label, REPLY_TIMEOUT)
+}
+
+/// Build an Enttec DMX USB Pro message: [0x7E][label][len LSB][len MSB][data...][0xE7]
+fn build_message(label: u8, data: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(data.len() + 5);
+    packet.push(START_BYTE);
+    packet.push(label);
+    let length = data.len() as u16;
+    packet.push((length & 0xFF) as u8);        // LSB
+    packet.push(((length >> 8) & 0xFF) as u8); // MSB
+    packet.extend_from_slice(data);
+    packet.push(END_BYTE);
+    packet
+}
+
+/// Read messages until one with `label` arrives or the timeout expires
+fn read_reply(port: &mut dyn DmxPort, label: u8, timeout: Duration) -> Result<Vec<u8>, DmxError> {
+    let deadline = Instant::now() + timeout;
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut chunk = [0u8; 600];
+
+    while Instant::now() < deadline {
+        match port.read(&mut chunk) {
+            Ok(0) => std::thread::sleep(Duration::from_millis(5)),
+            Ok(n) => buffer.extend_from_slice(&chunk[..n]),
+            Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut
+                || e.kind() == std::io::ErrorKind::WouldBlock => continue,
+            Err(e) => return Err(DmxError::CommError(e.to_string())),
+        }
+
+        // Parse complete messages, skipping anything unrelated (e.g. received DMX)
+        loop {
+            let start = match buffer.iter().position(|&b| b == START_BYTE) {
+                Some(start) => start,
+                None => {
+                    buffer.clear();
+                    break;
+                }
+            };
+            buffer.drain(..start);
+            if buffer.len() < 4 {
+                break;
+            }
+            let length = u16::from_le_bytes([buffer[2], buffer[3]]) as usize;
+            if buffer.len() < length + 5 {
+                break;
+            }
+            let message: Vec<u8> = buffer.drain(..length + 5).collect();
+            if message[length + 4] != END_BYTE {
+                continue; // Framing error - resync on the next start byte
+            }
+            if message[1] == label {
+                return Ok(message[4..4 + length].to_vec());
+            }
+        }
+    }
+
+    Err(DmxError::CommError(format!("No reply to label {} from widget", label)))
+}
+
+/// Serial number is four BCD bytes, least significant first
+fn format_serial_number(bytes: &[u8]) -> String {
+    bytes.iter().rev().map(|b| format!("{:02X}", b)).collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::VecDeque;
+    use std::io::{self, Read, Write};
+
+    /// Shared state of a fake USB widget
+    #[derive(Default)]
+    struct FakeWidget {
+        plugged_in: bool,
+        generation: u32, // Bumped on every unplug so stale ports start failing
+        written: Vec<u8>,
+        replies: VecDeque<u8>,
+        answer_queries: bool,
+    }
+
+    struct FakePort {
+        widget: Arc<Mutex<FakeWidget>>,
+        generation: u32,
+    }
+
+    impl Read for FakePort {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let mut widget = self.widget.lock().unwrap();
+            if widget.replies.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "timed out"));
+            }
+            let n = buf.len().min(widget.replies.len());
+            for byte in buf.iter_mut().take(n) {
+                *byte = widget.replies.pop_front().unwrap();
+            }
+            Ok(n)
+        }
+    }
+
+    impl Write for FakePort {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let mut widget = self.widget.lock().unwrap();
+            if !widget.plugged_in || widget.generation != self.generation {
+                return Err(io::Error::new(io::ErrorKind::BrokenPipe, "device removed"));
+            }
+            widget.written.extend_from_slice(buf);
+            if widget.answer_queries && buf.len() > 1 {
+                match buf[1] {
+                    GET_WIDGET_PARAMS_LABEL => {
+                        let reply = build_message(GET_WIDGET_PARAMS_LABEL, &[0x44, 0x01, 9, 1, 40]);
+                        widget.replies.extend(reply);
+                    }
+                    GET_SERIAL_NUMBER_LABEL => {
+                        let reply = build_message(GET_SERIAL_NUMBER_LABEL, &[0x78, 0x56, 0x34, 0x12]);
+                        widget.replies.extend(reply);
+                    }
+                    _ => {}
+                }
+            }
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    struct FakeConnector {
+        widget: Arc<Mutex<FakeWidget>>,
+    }
+
+    impl PortConnector for FakeConnector {
+        fn connect(&mut self) -> Result<(String, Box<dyn DmxPort>), DmxError> {
+            let widget = self.widget.lock().unwrap();
+            if !widget.plugged_in {
+                return Err(DmxError::DeviceNotFound);
+            }
+            Ok((
+                "fake0".to_string(),
+                Box::new(FakePort { widget: Arc::clone(&self.widget), generation: widget.generation }),
+            ))
+        }
+    }
+
+    fn fake_widget(answer_queries: bool) -> Arc<Mutex<FakeWidget>> {
+        Arc::new(Mutex::new(FakeWidget {
+            plugged_in: true,
+            answer_queries,
+            ..Default::default()
+        }))
+    }
+
+    fn unplug(widget: &Arc<Mutex<FakeWidget>>) {
+        let mut widget = widget.lock().unwrap();
+        widget.plugged_in = false;
+        widget.generation += 1;
+    }
 
     #[test]
     fn test_packet_structure() {
         let mut dmx = DmxUniverse::new();
         dmx.set_channel(1, 255).unwrap();
         dmx.set_channel(100, 128).unwrap();
-        
+
         // Packet should be: [0x7E][0x06][0x01][0x02][0x00][...512 channels...][0xE7]
-        // Total: 6 + 513 = 519 bytes
+        // Total: 4 header + 513 data + 1 end = 518 bytes
+        let mut data = vec![0x00];
+        data.extend_from_slice(dmx.as_slice());
+        let packet = build_message(SEND_DMX_LABEL, &data);
+        assert_eq!(packet.len(), 518);
+        assert_eq!(&packet[0..5], &[0x7E, 0x06, 0x01, 0x02, 0x00]);
+        assert_eq!(packet[5], 255);
+        assert_eq!(packet[104], 128);
+        assert_eq!(packet[517], 0xE7);
+    }
+
+    /// Poll until `done` holds, sending frames meanwhile; false on timeout
+    fn wait_for(dmx: &mut EnttecDmxPro, done: impl Fn(&EnttecDmxPro) -> bool) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(3);
+        while Instant::now() < deadline {
+            let _ = dmx.send_dmx();
+            if done(dmx) {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        false
+    }
+
+    #[test]
+    fn test_reads_widget_info() {
+        let widget = fake_widget(true);
+        let mut dmx = EnttecDmxPro::with_connector(Box::new(FakeConnector { widget }));
+        assert!(wait_for(&mut dmx, |dmx| dmx.widget_info().is_some()));
+
+        let info = dmx.widget_info().unwrap();
+        assert_eq!(info.firmware_version, 0x0144);
+        assert_eq!(info.refresh_rate, 40);
+        assert_eq!(info.serial_number.as_deref(), Some("12345678"));
+    }
+
+    #[test]
+    fn test_missing_widget_info_is_not_fatal() {
+        let widget = fake_widget(false);
+        let mut dmx = EnttecDmxPro::with_connector(Box::new(FakeConnector { widget }));
+        assert!(wait_for(&mut dmx, |dmx| dmx.health().frames_sent > 0));
+        assert!(dmx.is_connected());
+        assert!(dmx.widget_info().is_none());
+    }
+
+    #[test]
+    fn test_send_does_not_wait_for_widget() {
+        // The widget never answers, so connecting blocks its thread on the
+        // reply timeout; the caller must not
+        let widget = fake_widget(false);
+        let mut dmx = EnttecDmxPro::with_connector(Box::new(FakeConnector { widget }));
+        let started = Instant::now();
+        let _ = dmx.send_dmx();
+        assert!(started.elapsed() < REPLY_TIMEOUT / 2);
+    }
+
+    #[test]
+    fn test_reconnects_after_unplug() {
+        let widget = fake_widget(true);
+        let mut dmx = EnttecDmxPro::with_connector(Box::new(FakeConnector { widget: Arc::clone(&widget) }));
+        dmx.set_reconnect_interval(Duration::ZERO);
+        assert!(wait_for(&mut dmx, |dmx| dmx.health().frames_sent > 0));
+
+        // Unplugged: the write fails and is reported
+        unplug(&widget);
+        assert!(wait_for(&mut dmx, |dmx| !dmx.is_connected()));
+        let health = dmx.health();
+        assert_eq!(health.errors, 1);
+        assert!(health.last_error.is_some());
+        assert!(matches!(dmx.send_dmx(), Err(DmxError::DeviceNotFound)));
+
+        // Plugged back in: the thread reconnects and the latest frame goes out
+        widget.lock().unwrap().plugged_in = true;
+        dmx.set_channel(1, 200).unwrap();
+        assert!(wait_for(&mut dmx, |_| {
+            let written = widget.lock().unwrap().written.clone();
+            written.len() >= 514 && written[written.len() - 514..written.len() - 512] == [0x00, 200]
+        }));
+
+        let health = dmx.health();
+        assert!(health.connected);
+        assert_eq!(health.reconnects, 1);
+        assert!(dmx.widget_info().is_some());
+
+        // Stopping writes a final blackout frame
+        drop(dmx);
+        let written = widget.lock().unwrap().written.clone();
+        assert_eq!(written[written.len() - 514], 0x00);
+        assert!(written[written.len() - 513..written.len() - 1].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_reconnect_is_rate_limited() {
+        let widget = fake_widget(false);
+        let mut dmx = EnttecDmxPro::with_connector(Box::new(FakeConnector { widget: Arc::clone(&widget) }));
+        dmx.set_reconnect_interval(Duration::from_secs(60));
+        assert!(wait_for(&mut dmx, |dmx| dmx.health().frames_sent > 0));
+
+        unplug(&widget);
+        assert!(wait_for(&mut dmx, |dmx| !dmx.is_connected()));
+        widget.lock().unwrap().plugged_in = true;
+
+        // Connected at startup less than a minute ago, so no attempt yet
+        std::thread::sleep(WAKE_INTERVAL * 3);
+        assert!(dmx.send_dmx().is_err());
+        assert!(!dmx.is_connected());
     }
 }
@@ -0,0 +1,235 @@
+use super::DmxUniverse;
+use serde::{Deserialize, Serialize};
+
+const DMX_UNIVERSE_SIZE: usize = 512;
+
+/// How console input is combined with show output for a channel range
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergePolicy {
+    /// Highest takes precedence - the larger of show and console wins
+    Htp,
+    /// Latest takes precedence - whichever source changed the channel last wins
+    Ltp,
+    /// Console replaces the show whenever console data is being received
+    ConsoleWins,
+}
+
+impl MergePolicy {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MergePolicy::Htp => "HTP",
+            MergePolicy::Ltp => "LTP",
+            MergePolicy::ConsoleWins => "Console Wins",
+        }
+    }
+}
+
+/// Channel range (1-indexed, inclusive) that accepts console input
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MergeRange {
+    pub start_channel: u16,
+    pub end_channel: u16,
+    pub policy: MergePolicy,
+}
+
+impl MergeRange {
+    fn contains(&self, channel: usize) -> bool {
+        channel >= self.start_channel as usize && channel <= self.end_channel as usize
+    }
+}
+
+/// Which source is currently driving the output
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MergeSource {
+    Show,
+    Console,
+    Mixed,
+}
+
+impl MergeSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MergeSource::Show => "Show",
+            MergeSource::Console => "Console",
+            MergeSource::Mixed => "Show + Console",
+        }
+    }
+}
+
+/// Merges show output with console input per channel range
+pub struct DmxMerger {
+    ranges: Vec<MergeRange>,
+    last_show: [u8; DMX_UNIVERSE_SIZE],
+    last_console: [u8; DMX_UNIVERSE_SIZE],
+    console_owns: [bool; DMX_UNIVERSE_SIZE], // LTP ownership per channel
+    source: MergeSource,
+}
+
+impl Default for DmxMerger {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl DmxMerger {
+    pub fn new(ranges: Vec<MergeRange>) -> Self {
+        Self {
+            ranges,
+            last_show: [0; DMX_UNIVERSE_SIZE],
+            last_console: [0; DMX_UNIVERSE_SIZE],
+            console_owns: [false; DMX_UNIVERSE_SIZE],
+            source: MergeSource::Show,
+        }
+    }
+
+    /// Replace the configured ranges (resets LTP ownership)
+    pub fn set_ranges(&mut self, ranges: Vec<MergeRange>) {
+        if self.ranges != ranges {
+            self.ranges = ranges;
+            self.console_owns = [false; DMX_UNIVERSE_SIZE];
+        }
+    }
+
+    pub fn ranges(&self) -> &[MergeRange] {
+        &self.ranges
+    }
+
+    /// Source that drove the most recent merged frame
+    pub fn source(&self) -> MergeSource {
+        self.source
+    }
+
+    /// Merge a show frame with an optional console frame
+    ///
+    /// `console` is `None` when no console data has arrived recently; in that case
+    /// the show passes through untouched and LTP ownership returns to the show.
+    pub fn merge(&mut self, show: &DmxUniverse, console: Option<&[u8; DMX_UNIVERSE_SIZE]>) -> DmxUniverse {
+        let mut output = DmxUniverse::new();
+        output.as_mut_slice().copy_from_slice(show.as_slice());
+
+        let console = match console {
+            Some(console) => console,
+            None => {
+                self.console_owns = [false; DMX_UNIVERSE_SIZE];
+                self.last_show.copy_from_slice(show.as_slice());
+                self.source = MergeSource::Show;
+                return output;
+            }
+        };
+
+        let mut any_console = false;
+        let mut any_show = false;
+
+        for (idx, &console_value) in console.iter().enumerate() {
+            let show_value = show.get_channel_raw(idx);
+
+            let policy = self.ranges.iter()
+                .find(|r| r.contains(idx + 1))
+                .map(|r| r.policy);
+
+            let use_console = match policy {
+                None => false,
+                Some(MergePolicy::ConsoleWins) => true,
+                Some(MergePolicy::Htp) => console_value > show_value,
+                Some(MergePolicy::Ltp) => {
+                    if console_value != self.last_console[idx] {
+                        self.console_owns[idx] = true;
+                    } else if show_value != self.last_show[idx] {
+                        self.console_owns[idx] = false;
+                    }
+                    self.console_owns[idx]
+                }
+            };
+
+            if use_console {
+                output.as_mut_slice()[idx] = console_value;
+                any_console = true;
+            } else if policy.is_some() {
+                any_show = true;
+            }
+        }
+
+        self.last_show.copy_from_slice(show.as_slice());
+        self.last_console.copy_from_slice(console);
+
+        self.source = match (any_console, any_show) {
+            (true, false) => MergeSource::Console,
+            (true, true) => MergeSource::Mixed,
+            _ => MergeSource::Show,
+        };
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn show_with(channel: usize, value: u8) -> DmxUniverse {
+        let mut universe = DmxUniverse::new();
+        universe.set_channel(channel, value).unwrap();
+        universe
+    }
+
+    fn console_with(channel: usize, value: u8) -> [u8; DMX_UNIVERSE_SIZE] {
+        let mut data = [0u8; DMX_UNIVERSE_SIZE];
+        data[channel - 1] = value;
+        data
+    }
+
+    fn range(policy: MergePolicy) -> Vec<MergeRange> {
+        vec![MergeRange { start_channel: 1, end_channel: 10, policy }]
+    }
+
+    #[test]
+    fn test_no_console_passes_show_through() {
+        let mut merger = DmxMerger::new(range(MergePolicy::ConsoleWins));
+        let out = merger.merge(&show_with(1, 200), None);
+        assert_eq!(out.get_channel(1).unwrap(), 200);
+        assert_eq!(merger.source(), MergeSource::Show);
+    }
+
+    #[test]
+    fn test_htp_takes_highest() {
+        let mut merger = DmxMerger::new(range(MergePolicy::Htp));
+        let out = merger.merge(&show_with(1, 100), Some(&console_with(1, 150)));
+        assert_eq!(out.get_channel(1).unwrap(), 150);
+
+        let out = merger.merge(&show_with(1, 220), Some(&console_with(1, 150)));
+        assert_eq!(out.get_channel(1).unwrap(), 220);
+    }
+
+    #[test]
+    fn test_console_wins_only_inside_range() {
+        let mut merger = DmxMerger::new(range(MergePolicy::ConsoleWins));
+        let mut console = console_with(1, 0);
+        console[19] = 99; // channel 20, outside the range
+
+        let mut show = show_with(1, 255);
+        show.set_channel(20, 10).unwrap();
+
+        let out = merger.merge(&show, Some(&console));
+        assert_eq!(out.get_channel(1).unwrap(), 0);
+        assert_eq!(out.get_channel(20).unwrap(), 10);
+        assert_eq!(merger.source(), MergeSource::Console);
+    }
+
+    #[test]
+    fn test_ltp_follows_latest_change() {
+        let mut merger = DmxMerger::new(range(MergePolicy::Ltp));
+
+        // Console moves first - console takes the channel
+        let out = merger.merge(&show_with(1, 0), Some(&console_with(1, 80)));
+        assert_eq!(out.get_channel(1).unwrap(), 80);
+
+        // Console holds, show changes - show takes it back
+        let out = merger.merge(&show_with(1, 30), Some(&console_with(1, 80)));
+        assert_eq!(out.get_channel(1).unwrap(), 30);
+
+        // Console moves again
+        let out = merger.merge(&show_with(1, 30), Some(&console_with(1, 90)));
+        assert_eq!(out.get_channel(1).unwrap(), 90);
+    }
+}
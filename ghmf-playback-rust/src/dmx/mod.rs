@@ -1,10 +1,22 @@
 mod enttec;
+mod open_dmx;
+mod device;
+mod port;
 mod universe;
 mod sacn_output;
+mod network_input;
+mod merge;
+mod recorder;
 
-pub use enttec::EnttecDmxPro;
+pub use enttec::{EnttecDmxPro, WidgetInfo, DmxHealth};
+pub use open_dmx::{EnttecOpenDmx, OpenDmxTiming};
+pub use device::{DmxDevice, DmxDeviceKind};
+pub use port::{DmxPort, BreakPort, PortConnector, SerialConnector, OpenDmxConnector};
 pub use universe::DmxUniverse;
 pub use sacn_output::{SacnOutput, SacnFilterMode, get_network_interfaces};
+pub use network_input::{NetworkInput, InputProtocol, InputFrame, InputSource, InputState, parse_sacn_packet, parse_artnet_packet};
+pub use merge::{DmxMerger, MergePolicy, MergeRange, MergeSource};
+pub use recorder::{DmxRecorder, DmxRecording, RecordedFrame, FrameDifference};
 
 use thiserror::Error;
 
@@ -21,4 +33,10 @@ pub enum DmxError {
     
     #[error("Communication error: {0}")]
     CommError(String),
+    
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    
+    #[error("Invalid capture file: {0}")]
+    InvalidCapture(String),
 }
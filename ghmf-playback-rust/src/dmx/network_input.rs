@@ -0,0 +1,524 @@
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+const DMX_UNIVERSE_SIZE: usize = 512;
+const SACN_PORT: u16 = 5568;
+const ARTNET_PORT: u16 = 6454;
+const ACN_PACKET_IDENTIFIER: &[u8; 12] = b"ASC-E1.17\0\0\0";
+const ARTNET_ID: &[u8; 8] = b"Art-Net\0";
+const ARTNET_OP_DMX: u16 = 0x5000;
+/// E1.31 network data loss timeout; a source silent this long is gone
+const SOURCE_TIMEOUT: Duration = Duration::from_millis(2500);
+
+/// Network protocol used to receive DMX from an external console
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputProtocol {
+    Sacn,
+    ArtNet,
+}
+
+impl InputProtocol {
+    /// Parse the protocol name stored in settings ("sacn" or "artnet")
+    pub fn from_setting(value: &str) -> Self {
+        match value.trim().to_lowercase().as_str() {
+            "artnet" | "art-net" => InputProtocol::ArtNet,
+            _ => InputProtocol::Sacn,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            InputProtocol::Sacn => "sACN",
+            InputProtocol::ArtNet => "Art-Net",
+        }
+    }
+}
+
+/// A single DMX frame received from the network
+#[derive(Debug, Clone)]
+pub struct InputFrame {
+    pub universe: u16,
+    /// Sender's component ID (all zeros for Art-Net, which has none)
+    pub cid: [u8; 16],
+    pub priority: u8,
+    pub source_name: String,
+    pub data: [u8; DMX_UNIVERSE_SIZE],
+    /// The sender has stopped this stream (sACN Stream_Terminated); `data`
+    /// is blank
+    pub terminated: bool,
+}
+
+/// Parse an E1.31 (sACN) data packet
+///
+/// Returns `None` for anything that is not a DMX512 data packet with start code 0
+/// (sync packets, discovery packets, alternate start codes). A packet with
+/// Stream_Terminated set comes back as a blank frame marked `terminated`.
+pub fn parse_sacn_packet(packet: &[u8]) -> Option<InputFrame> {
+    if packet.len() < 126 {
+        return None;
+    }
+
+    // Root layer: preamble size, postamble size, ACN packet identifier, vector
+    if packet[0..2] != [0x00, 0x10] || packet[2..4] != [0x00, 0x00] {
+        return None;
+    }
+    if &packet[4..16] != ACN_PACKET_IDENTIFIER {
+        return None;
+    }
+    if packet[18..22] != [0x00, 0x00, 0x00, 0x04] {
+        return None; // VECTOR_ROOT_E131_DATA
+    }
+
+    let mut cid = [0u8; 16];
+    cid.copy_from_slice(&packet[22..38]);
+
+    // Framing layer
+    if packet[40..44] != [0x00, 0x00, 0x00, 0x02] {
+        return None; // VECTOR_E131_DATA_PACKET
+    }
+    let source_name = String::from_utf8_lossy(&packet[44..108])
+        .trim_end_matches('\0')
+        .to_string();
+    let priority = packet[108];
+    let options = packet[112];
+    let universe = u16::from_be_bytes([packet[113], packet[114]]);
+    if options & 0x40 != 0 {
+        // Stream_Terminated: the rest of the packet is not to be used
+        return Some(InputFrame {
+            universe,
+            cid,
+            priority,
+            source_name,
+            data: [0; DMX_UNIVERSE_SIZE],
+            terminated: true,
+        });
+    }
+
+    // DMP layer
+    if packet[117] != 0x02 {
+        return None; // VECTOR_DMP_SET_PROPERTY
+    }
+    let value_count = u16::from_be_bytes([packet[123], packet[124]]) as usize;
+    if value_count == 0 || packet[125] != 0x00 {
+        return None; // Non-DMX start code
+    }
+
+    let channel_count = (value_count - 1).min(DMX_UNIVERSE_SIZE).min(packet.len() - 126);
+    let mut data = [0u8; DMX_UNIVERSE_SIZE];
+    data[..channel_count].copy_from_slice(&packet[126..126 + channel_count]);
+
+    Some(InputFrame {
+        universe,
+        cid,
+        priority,
+        source_name,
+        data,
+        terminated: false,
+    })
+}
+
+/// Parse an Art-Net ArtDmx packet
+pub fn parse_artnet_packet(packet: &[u8]) -> Option<InputFrame> {
+    if packet.len() < 18 || &packet[0..8] != ARTNET_ID {
+        return None;
+    }
+
+    let opcode = u16::from_le_bytes([packet[8], packet[9]]);
+    if opcode != ARTNET_OP_DMX {
+        return None;
+    }
+
+    // Port-Address: Net (7 bits) + Sub-Net/Universe (8 bits)
+    let universe = ((packet[15] as u16 & 0x7F) << 8) | packet[14] as u16;
+    let length = u16::from_be_bytes([packet[16], packet[17]]) as usize;
+
+    let channel_count = length.min(DMX_UNIVERSE_SIZE).min(packet.len() - 18);
+    let mut data = [0u8; DMX_UNIVERSE_SIZE];
+    data[..channel_count].copy_from_slice(&packet[18..18 + channel_count]);
+
+    Some(InputFrame {
+        universe,
+        cid: [0; 16],
+        priority: 100,
+        source_name: "Art-Net".to_string(),
+        data,
+        terminated: false,
+    })
+}
+
+/// One sender on the input universe
+#[derive(Debug, Clone)]
+pub struct InputSource {
+    pub cid: [u8; 16],
+    pub name: String,
+    pub priority: u8,
+    pub data: [u8; DMX_UNIVERSE_SIZE],
+    pub last_received: Instant,
+}
+
+/// Latest state received from the network, shared with the receive thread
+#[derive(Debug, Clone)]
+pub struct InputState {
+    /// HTP of the highest-priority sources
+    pub data: [u8; DMX_UNIVERSE_SIZE],
+    pub last_received: Option<Instant>,
+    /// Sources currently in `data`
+    pub source_name: String,
+    pub packets_received: u64,
+    pub sources: Vec<InputSource>,
+}
+
+impl Default for InputState {
+    fn default() -> Self {
+        Self {
+            data: [0; DMX_UNIVERSE_SIZE],
+            last_received: None,
+            source_name: String::new(),
+            packets_received: 0,
+            sources: Vec::new(),
+        }
+    }
+}
+
+impl InputState {
+    /// Take a frame for this universe and work out the output again
+    ///
+    /// Follows E1.31 priority: only sources at the highest priority heard
+    /// in the last 2.5 s count, and those are merged HTP, so a backup
+    /// console at a lower priority takes over only once the primary goes
+    /// quiet. A terminated stream drops its source at once rather than
+    /// after the timeout. Art-Net carries no priority or source ID, so every
+    /// Art-Net sender counts as one source.
+    pub fn receive(&mut self, frame: InputFrame, now: Instant) {
+        self.sources.retain(|source| now.saturating_duration_since(source.last_received) < SOURCE_TIMEOUT);
+        self.packets_received += 1;
+        if frame.terminated {
+            if let Some(index) = self.sources.iter().position(|source| source.cid == frame.cid) {
+                let source = self.sources.remove(index);
+                info!("DMX input source {} stopped sending", source.name);
+            }
+            self.merge(now);
+            return;
+        }
+
+        match self.sources.iter_mut().find(|source| source.cid == frame.cid) {
+            Some(source) => {
+                source.name = frame.source_name;
+                source.priority = frame.priority;
+                source.data = frame.data;
+                source.last_received = now;
+            }
+            None => self.sources.push(InputSource {
+                cid: frame.cid,
+                name: frame.source_name,
+                priority: frame.priority,
+                data: frame.data,
+                last_received: now,
+            }),
+        }
+        self.merge(now);
+    }
+
+    /// HTP of the top-priority sources into `data`; with none left there is
+    /// no input
+    fn merge(&mut self, now: Instant) {
+        if self.sources.is_empty() {
+            self.data = [0; DMX_UNIVERSE_SIZE];
+            self.last_received = None;
+            self.source_name.clear();
+            return;
+        }
+
+        let top = self.sources.iter().map(|source| source.priority).max().unwrap_or(0);
+        let mut data = [0u8; DMX_UNIVERSE_SIZE];
+        let mut names = Vec::new();
+        for source in self.sources.iter().filter(|source| source.priority == top) {
+            for (level, &value) in data.iter_mut().zip(source.data.iter()) {
+                *level = (*level).max(value);
+            }
+            names.push(source.name.as_str());
+        }
+        let source_name = names.join(", ");
+
+        self.data = data;
+        self.last_received = Some(now);
+        if self.source_name != source_name {
+            if self.sources.len() > names.len() {
+                info!("DMX input source: {} at priority {} ({} lower-priority ignored)", source_name, top, self.sources.len() - names.len());
+            } else {
+                debug!("DMX input source: {}", source_name);
+            }
+            self.source_name = source_name;
+        }
+    }
+}
+
+/// Receives sACN or Art-Net DMX from a lighting console on a background thread
+pub struct NetworkInput {
+    protocol: InputProtocol,
+    interface_ip: String,
+    universe: u16,
+    state: Arc<Mutex<InputState>>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Default for NetworkInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NetworkInput {
+    pub fn new() -> Self {
+        Self {
+            protocol: InputProtocol::Sacn,
+            interface_ip: String::new(),
+            universe: 1,
+            state: Arc::new(Mutex::new(InputState::default())),
+            running: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+
+    /// Start listening on the given interface for one universe
+    pub fn start(&mut self, protocol: InputProtocol, interface_ip: &str, universe: u16) -> Result<(), String> {
+        self.stop();
+
+        let ip_addr: Ipv4Addr = if interface_ip.is_empty() {
+            Ipv4Addr::UNSPECIFIED
+        } else {
+            interface_ip
+                .parse()
+                .map_err(|e| format!("Invalid IP address: {}", e))?
+        };
+
+        let port = match protocol {
+            InputProtocol::Sacn => SACN_PORT,
+            InputProtocol::ArtNet => ARTNET_PORT,
+        };
+
+        // sACN picks the interface when joining its multicast group; Art-Net
+        // has no group, so the socket itself is bound to the interface
+        let bind_ip = match protocol {
+            InputProtocol::Sacn => Ipv4Addr::UNSPECIFIED,
+            InputProtocol::ArtNet => ip_addr,
+        };
+        let socket = UdpSocket::bind(SocketAddr::from((bind_ip, port)))
+            .map_err(|e| format!("Failed to bind {} input port {}: {}", protocol.label(), port, e))?;
+
+        if protocol == InputProtocol::Sacn {
+            // sACN multicast group for the universe: 239.255.<hi>.<lo>
+            let group = Ipv4Addr::new(239, 255, (universe >> 8) as u8, (universe & 0xFF) as u8);
+            socket
+                .join_multicast_v4(&group, &ip_addr)
+                .map_err(|e| format!("Failed to join multicast group {}: {}", group, e))?;
+        }
+
+        socket
+            .set_read_timeout(Some(Duration::from_millis(250)))
+            .map_err(|e| format!("Failed to configure input socket: {}", e))?;
+
+        *self.state.lock().unwrap() = InputState::default();
+        self.protocol = protocol;
+        self.interface_ip = interface_ip.to_string();
+        self.universe = universe;
+        self.running.store(true, Ordering::SeqCst);
+
+        let state = Arc::clone(&self.state);
+        let running = Arc::clone(&self.running);
+
+        self.thread = Some(std::thread::spawn(move || {
+            let mut buffer = [0u8; 1144];
+
+            while running.load(Ordering::SeqCst) {
+                let len = match socket.recv(&mut buffer) {
+                    Ok(len) => len,
+                    Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock
+                        || e.kind() == std::io::ErrorKind::TimedOut => continue,
+                    Err(e) => {
+                        warn!("DMX input receive error: {}", e);
+                        continue;
+                    }
+                };
+
+                let frame = match protocol {
+                    InputProtocol::Sacn => parse_sacn_packet(&buffer[..len]),
+                    InputProtocol::ArtNet => parse_artnet_packet(&buffer[..len]),
+                };
+
+                if let Some(frame) = frame {
+                    if frame.universe != universe {
+                        continue;
+                    }
+
+                    if let Ok(mut state) = state.lock() {
+                        state.receive(frame, Instant::now());
+                    }
+                }
+            }
+        }));
+
+        info!("{} input listening for universe {}", protocol.label(), universe);
+        Ok(())
+    }
+
+    /// Stop listening
+    pub fn stop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+            info!("{} input stopped", self.protocol.label());
+        }
+    }
+
+    /// Check if the receive thread is running
+    pub fn is_active(&self) -> bool {
+        self.thread.is_some()
+    }
+
+    pub fn protocol(&self) -> InputProtocol {
+        self.protocol
+    }
+
+    pub fn interface_ip(&self) -> &str {
+        &self.interface_ip
+    }
+
+    pub fn universe(&self) -> u16 {
+        self.universe
+    }
+
+    /// Get the most recent console frame if one arrived within `timeout`
+    pub fn latest_frame(&self, timeout: Duration) -> Option<[u8; DMX_UNIVERSE_SIZE]> {
+        let state = self.state.lock().ok()?;
+        match state.last_received {
+            Some(t) if t.elapsed() <= timeout => Some(state.data),
+            _ => None,
+        }
+    }
+
+    /// Snapshot of the shared receive state
+    pub fn state(&self) -> InputState {
+        self.state.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+}
+
+impl Drop for NetworkInput {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sacn_packet(universe: u16, values: &[u8]) -> Vec<u8> {
+        let mut p = vec![0u8; 126 + values.len()];
+        p[0..2].copy_from_slice(&[0x00, 0x10]);
+        p[4..16].copy_from_slice(ACN_PACKET_IDENTIFIER);
+        p[18..22].copy_from_slice(&[0, 0, 0, 4]);
+        p[40..44].copy_from_slice(&[0, 0, 0, 2]);
+        p[44..51].copy_from_slice(b"Console");
+        p[108] = 100;
+        p[113..115].copy_from_slice(&universe.to_be_bytes());
+        p[117] = 0x02;
+        p[123..125].copy_from_slice(&((values.len() + 1) as u16).to_be_bytes());
+        p[126..].copy_from_slice(values);
+        p
+    }
+
+    #[test]
+    fn test_parse_sacn_packet() {
+        let frame = parse_sacn_packet(&sacn_packet(3, &[255, 128, 7])).unwrap();
+        assert_eq!(frame.universe, 3);
+        assert_eq!(frame.source_name, "Console");
+        assert_eq!(&frame.data[0..4], &[255, 128, 7, 0]);
+    }
+
+    #[test]
+    fn test_highest_priority_source_wins() {
+        let frame = |cid: u8, priority: u8, level: u8| {
+            let mut packet = sacn_packet(1, &[level, level]);
+            packet[22] = cid;
+            packet[108] = priority;
+            parse_sacn_packet(&packet).unwrap()
+        };
+        let start = Instant::now();
+        let mut state = InputState::default();
+
+        // The backup at a lower priority is ignored while the primary runs
+        state.receive(frame(1, 150, 40), start);
+        state.receive(frame(2, 100, 255), start);
+        assert_eq!(&state.data[0..2], &[40, 40]);
+        assert_eq!(state.sources.len(), 2);
+
+        // Sources at the same priority merge HTP
+        state.receive(frame(3, 150, 90), start);
+        assert_eq!(&state.data[0..2], &[90, 90]);
+
+        // Once the primaries go quiet the backup takes over
+        let later = start + Duration::from_secs(2);
+        state.receive(frame(2, 100, 255), later);
+        assert_eq!(state.data[0], 90);
+        state.receive(frame(2, 100, 200), later + Duration::from_secs(1));
+        assert_eq!(&state.data[0..2], &[200, 200]);
+        assert_eq!(state.sources.len(), 1);
+    }
+
+    #[test]
+    fn test_terminated_stream_drops_its_source() {
+        let mut packet = sacn_packet(1, &[255]);
+        let start = Instant::now();
+        let mut state = InputState::default();
+        state.receive(parse_sacn_packet(&packet).unwrap(), start);
+        packet[22] = 2;
+        packet[126] = 30;
+        state.receive(parse_sacn_packet(&packet).unwrap(), start);
+        assert_eq!(state.data[0], 255);
+
+        packet[22] = 0;
+        packet[112] = 0x40;
+        let terminated = parse_sacn_packet(&packet).unwrap();
+        assert!(terminated.terminated);
+        assert_eq!(terminated.data[0], 0);
+        state.receive(terminated, start);
+        assert_eq!(state.data[0], 30);
+        assert_eq!(state.sources.len(), 1);
+
+        // The last one going leaves no input rather than its final levels
+        packet[22] = 2;
+        state.receive(parse_sacn_packet(&packet).unwrap(), start);
+        assert!(state.sources.is_empty());
+        assert_eq!(state.last_received, None);
+    }
+
+    #[test]
+    fn test_parse_artnet_packet() {
+        let mut p = Vec::new();
+        p.extend_from_slice(ARTNET_ID);
+        p.extend_from_slice(&ARTNET_OP_DMX.to_le_bytes());
+        p.extend_from_slice(&[0, 14, 0, 0]); // ProtVer, Sequence, Physical
+        p.extend_from_slice(&[0x12, 0x01]); // SubUni, Net
+        p.extend_from_slice(&2u16.to_be_bytes());
+        p.extend_from_slice(&[10, 20]);
+
+        let frame = parse_artnet_packet(&p).unwrap();
+        assert_eq!(frame.universe, 0x0112);
+        assert_eq!(&frame.data[0..3], &[10, 20, 0]);
+    }
+
+    #[test]
+    fn test_parse_artnet_rejects_other_opcodes() {
+        let mut p = Vec::new();
+        p.extend_from_slice(ARTNET_ID);
+        p.extend_from_slice(&0x2000u16.to_le_bytes()); // ArtPoll
+        p.extend_from_slice(&[0; 8]);
+        assert!(parse_artnet_packet(&p).is_none());
+    }
+}
@@ -0,0 +1,349 @@
+use super::port::{BreakPort, OpenDmxConnector, PortConnector};
+use super::{DmxError, DmxHealth, DmxUniverse};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+const DMX_UNIVERSE_SIZE: usize = 512;
+const FRAME_INTERVAL: Duration = Duration::from_millis(25);
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Break and mark-after-break lengths generated on the line
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OpenDmxTiming {
+    pub break_us: u32,
+    pub mab_us: u32,
+}
+
+impl Default for OpenDmxTiming {
+    fn default() -> Self {
+        // DMX512 minimums are 92us break / 12us MAB; a longer break suits more fixtures
+        Self {
+            break_us: 176,
+            mab_us: 12,
+        }
+    }
+}
+
+struct OpenDmxShared {
+    frame: [u8; DMX_UNIVERSE_SIZE],
+    health: DmxHealth,
+    /// Skip the blackout frame when output stops
+    hold_on_stop: bool,
+}
+
+/// Enttec Open DMX driver
+///
+/// The Open DMX is a bare FTDI UART with no processor, so the host has to
+/// generate every frame: break, mark-after-break, start code and 512 slots.
+/// A background thread does this continuously and reconnects on unplug;
+/// `send_dmx()` only hands it the latest universe.
+pub struct EnttecOpenDmx {
+    universe: DmxUniverse,
+    shared: Arc<Mutex<OpenDmxShared>>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl EnttecOpenDmx {
+    /// Start output on `port_name`, or the first FTDI interface when it is empty
+    pub fn new(port_name: &str, timing: OpenDmxTiming) -> Self {
+        info!("Starting Enttec Open DMX output...");
+        Self::with_connector(Box::new(OpenDmxConnector::new(port_name)), timing)
+    }
+
+    /// Start output through the given connector
+    pub fn with_connector(mut connector: Box<dyn PortConnector<dyn BreakPort>>, timing: OpenDmxTiming) -> Self {
+        let shared = Arc::new(Mutex::new(OpenDmxShared {
+            frame: [0; DMX_UNIVERSE_SIZE],
+            health: DmxHealth::default(),
+            hold_on_stop: false,
+        }));
+        let running = Arc::new(AtomicBool::new(true));
+
+        let thread_shared = Arc::clone(&shared);
+        let thread_running = Arc::clone(&running);
+
+        let thread = std::thread::spawn(move || {
+            let mut port: Option<Box<dyn BreakPort>> = None;
+            let mut last_connect_attempt: Option<Instant> = None;
+            let mut ever_connected = false;
+            let mut fps_window_start = Instant::now();
+            let mut fps_window_frames = 0u32;
+
+            while thread_running.load(Ordering::SeqCst) {
+                let frame_start = Instant::now();
+
+                if port.is_none() && last_connect_attempt.is_none_or(|t| t.elapsed() >= RECONNECT_INTERVAL) {
+                    last_connect_attempt = Some(Instant::now());
+                    match connector.connect() {
+                        Ok((port_name, new_port)) => {
+                            info!("Enttec Open DMX connected on {}", port_name);
+                            port = Some(new_port);
+                            let mut shared = thread_shared.lock().unwrap();
+                            shared.health.connected = true;
+                            shared.health.port_name = Some(port_name);
+                            if ever_connected {
+                                shared.health.reconnects += 1;
+                            }
+                            ever_connected = true;
+                        }
+                        Err(e) => debug!("Open DMX connect failed: {}", e),
+                    }
+                }
+
+                if let Some(active_port) = port.as_mut() {
+                    let frame = thread_shared.lock().unwrap().frame;
+
+                    match write_frame(active_port.as_mut(), &frame, timing) {
+                        Ok(_) => {
+                            fps_window_frames += 1;
+                            let mut shared = thread_shared.lock().unwrap();
+                            shared.health.frames_sent += 1;
+                            let elapsed = fps_window_start.elapsed();
+                            if elapsed >= Duration::from_secs(1) {
+                                shared.health.frames_per_second = fps_window_frames as f32 / elapsed.as_secs_f32();
+                                fps_window_frames = 0;
+                                fps_window_start = Instant::now();
+                            }
+                        }
+                        Err(e) => {
+                            warn!("Open DMX write failed, will reconnect: {}", e);
+                            port = None;
+                            fps_window_frames = 0;
+                            fps_window_start = Instant::now();
+                            let mut shared = thread_shared.lock().unwrap();
+                            shared.health.connected = false;
+                            shared.health.errors += 1;
+                            shared.health.last_error = Some(e.to_string());
+                            shared.health.frames_per_second = 0.0;
+                        }
+                    }
+                }
+
+                std::thread::sleep(FRAME_INTERVAL.saturating_sub(frame_start.elapsed()));
+            }
+
+            // Leave the rig dark when output stops, unless a failsafe look is latched
+            let hold_on_stop = thread_shared.lock().map(|s| s.hold_on_stop).unwrap_or(false);
+            if let (false, Some(active_port)) = (hold_on_stop, port.as_mut()) {
+                let _ = write_frame(active_port.as_mut(), &[0; DMX_UNIVERSE_SIZE], timing);
+            }
+            info!("Enttec Open DMX output stopped");
+        });
+
+        Self {
+            universe: DmxUniverse::new(),
+            shared,
+            running,
+            thread: Some(thread),
+        }
+    }
+
+    /// Skip the blackout frame when output stops
+    ///
+    /// Nothing refreshes the line once the thread ends, so fixtures that
+    /// need a steady signal only keep the look while this driver is alive.
+    pub fn hold_on_stop(&mut self) {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.hold_on_stop = true;
+        }
+    }
+
+    /// Check if the interface is currently open
+    pub fn is_connected(&self) -> bool {
+        self.shared.lock().map(|s| s.health.connected).unwrap_or(false)
+    }
+
+    /// Snapshot of output health
+    pub fn health(&self) -> DmxHealth {
+        self.shared.lock().map(|s| s.health.clone()).unwrap_or_default()
+    }
+
+    /// Set a single DMX channel value (1-512)
+    pub fn set_channel(&mut self, channel: usize, value: u8) -> Result<(), DmxError> {
+        self.universe.set_channel(channel, value)
+    }
+
+    /// Set multiple DMX channels at once
+    pub fn set_channels(&mut self, start_channel: usize, values: &[u8]) -> Result<(), DmxError> {
+        self.universe.set_channels(start_channel, values)
+    }
+
+    /// Get current value of a DMX channel
+    pub fn get_channel(&self, channel: usize) -> Result<u8, DmxError> {
+        self.universe.get_channel(channel)
+    }
+
+    /// Clear all DMX channels to 0
+    pub fn clear(&mut self) {
+        self.universe.clear();
+        debug!("DMX universe cleared");
+    }
+
+    /// Clear all DMX channels to 0 except those in the ignore list (1-indexed)
+    pub fn clear_except(&mut self, ignore_channels: &[u16]) {
+        self.universe.clear_except(ignore_channels);
+        debug!("DMX universe cleared (ignoring {} channels)", ignore_channels.len());
+    }
+
+    /// Hand the current universe to the output thread
+    pub fn send_dmx(&mut self) -> Result<(), DmxError> {
+        let mut shared = self.shared.lock()
+            .map_err(|_| DmxError::CommError("Open DMX output thread panicked".to_string()))?;
+        shared.frame.copy_from_slice(self.universe.as_slice());
+
+        if shared.health.connected {
+            Ok(())
+        } else {
+            Err(DmxError::DeviceNotFound)
+        }
+    }
+}
+
+impl Drop for EnttecOpenDmx {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Write one DMX512 frame: break, mark-after-break, start code, 512 slots
+fn write_frame(port: &mut dyn BreakPort, data: &[u8; DMX_UNIVERSE_SIZE], timing: OpenDmxTiming) -> io::Result<()> {
+    port.set_break()?;
+    std::thread::sleep(Duration::from_micros(timing.break_us as u64));
+    port.clear_break()?;
+    std::thread::sleep(Duration::from_micros(timing.mab_us as u64));
+
+    let mut packet = Vec::with_capacity(DMX_UNIVERSE_SIZE + 1);
+    packet.push(0x00); // DMX Start Code
+    packet.extend_from_slice(data);
+    port.write_all(&packet)?;
+
+    // Wait for the UART to drain so the next break doesn't cut the frame short
+    port.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum LineEvent {
+        BreakOn,
+        BreakOff,
+        Data(Vec<u8>),
+    }
+
+    struct FakeLine {
+        events: Arc<Mutex<Vec<LineEvent>>>,
+    }
+
+    impl Write for FakeLine {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.events.lock().unwrap().push(LineEvent::Data(buf.to_vec()));
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl BreakPort for FakeLine {
+        fn set_break(&mut self) -> io::Result<()> {
+            self.events.lock().unwrap().push(LineEvent::BreakOn);
+            Ok(())
+        }
+
+        fn clear_break(&mut self) -> io::Result<()> {
+            self.events.lock().unwrap().push(LineEvent::BreakOff);
+            Ok(())
+        }
+    }
+
+    struct FakeConnector {
+        events: Arc<Mutex<Vec<LineEvent>>>,
+    }
+
+    impl PortConnector<dyn BreakPort> for FakeConnector {
+        fn connect(&mut self) -> Result<(String, Box<dyn BreakPort>), DmxError> {
+            Ok(("fake0".to_string(), Box::new(FakeLine { events: Arc::clone(&self.events) })))
+        }
+    }
+
+    #[test]
+    fn test_frame_is_break_mab_then_data() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut line = FakeLine { events: Arc::clone(&events) };
+        let mut data = [0u8; DMX_UNIVERSE_SIZE];
+        data[0] = 255;
+
+        write_frame(&mut line, &data, OpenDmxTiming::default()).unwrap();
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0], LineEvent::BreakOn);
+        assert_eq!(events[1], LineEvent::BreakOff);
+        match &events[2] {
+            LineEvent::Data(bytes) => {
+                assert_eq!(bytes.len(), 513);
+                assert_eq!(&bytes[0..2], &[0x00, 255]);
+            }
+            other => panic!("expected data, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_output_thread_sends_latest_universe() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut dmx = EnttecOpenDmx::with_connector(
+            Box::new(FakeConnector { events: Arc::clone(&events) }),
+            OpenDmxTiming::default(),
+        );
+        dmx.set_channel(3, 42).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(2);
+        let mut sent = false;
+        while Instant::now() < deadline && !sent {
+            let _ = dmx.send_dmx();
+            sent = events.lock().unwrap().iter().any(|e| matches!(e, LineEvent::Data(d) if d[3] == 42));
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        assert!(sent);
+        assert!(dmx.is_connected());
+        assert!(dmx.health().frames_sent > 0);
+
+        // Stopping writes a final blackout frame
+        drop(dmx);
+        let events = events.lock().unwrap();
+        assert!(matches!(events.last(), Some(LineEvent::Data(d)) if d.iter().all(|&b| b == 0)));
+    }
+
+    #[test]
+    fn test_hold_on_stop_skips_blackout() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut dmx = EnttecOpenDmx::with_connector(
+            Box::new(FakeConnector { events: Arc::clone(&events) }),
+            OpenDmxTiming::default(),
+        );
+        dmx.set_channel(3, 42).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while Instant::now() < deadline && dmx.health().frames_sent < 2 {
+            let _ = dmx.send_dmx();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        dmx.hold_on_stop();
+        drop(dmx);
+        let events = events.lock().unwrap();
+        assert!(matches!(events.last(), Some(LineEvent::Data(d)) if d[3] == 42));
+    }
+}
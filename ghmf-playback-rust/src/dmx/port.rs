@@ -0,0 +1,126 @@
+use super::DmxError;
+use serialport::{DataBits, FlowControl, Parity, SerialPort, SerialPortInfo, SerialPortType, StopBits};
+use std::io::{self, Read, Write};
+use std::time::Duration;
+use tracing::info;
+
+/// Byte stream to a DMX widget
+///
+/// Implemented for anything that can read, write and move between threads, so a
+/// real serial port and an in-memory fake can be used interchangeably.
+pub trait DmxPort: Read + Write + Send {}
+
+impl<T: Read + Write + Send> DmxPort for T {}
+
+/// Serial line that can hold a DMX break (used by the Open DMX bit-bang driver)
+pub trait BreakPort: Write + Send {
+    fn set_break(&mut self) -> io::Result<()>;
+    fn clear_break(&mut self) -> io::Result<()>;
+}
+
+impl BreakPort for Box<dyn SerialPort> {
+    fn set_break(&mut self) -> io::Result<()> {
+        self.as_ref().set_break().map_err(io::Error::from)
+    }
+
+    fn clear_break(&mut self) -> io::Result<()> {
+        self.as_ref().clear_break().map_err(io::Error::from)
+    }
+}
+
+/// Locates and opens the port for a DMX widget
+///
+/// Called at startup and again whenever the driver loses its port, so it must
+/// re-scan for the device each time (it may come back under a different name).
+pub trait PortConnector<P: ?Sized = dyn DmxPort>: Send {
+    fn connect(&mut self) -> Result<(String, Box<P>), DmxError>;
+}
+
+/// Check if a serial port is an Enttec device
+pub fn is_enttec_device(port_info: &SerialPortInfo) -> bool {
+    match &port_info.port_type {
+        SerialPortType::UsbPort(info) => {
+            // FTDI Vendor ID and common Enttec Product IDs
+            info.vid == 0x0403 && (info.pid == 0x6001 || info.pid == 0x6015)
+        }
+        _ => false,
+    }
+}
+
+/// Resolve the port to open: the configured name, or the first Enttec device
+fn find_enttec_port(port_name: &Option<String>) -> Result<String, DmxError> {
+    if let Some(name) = port_name {
+        return Ok(name.clone());
+    }
+
+    let ports = serialport::available_ports()?;
+
+    // Try to find Enttec device
+    let enttec_port = ports.iter()
+        .find(|p| is_enttec_device(p))
+        .ok_or(DmxError::DeviceNotFound)?;
+
+    info!("Found Enttec device: {}", enttec_port.port_name);
+    Ok(enttec_port.port_name.clone())
+}
+
+/// Opens an Enttec DMX USB Pro
+pub struct SerialConnector {
+    port_name: Option<String>,
+}
+
+impl SerialConnector {
+    /// Connect to `port_name`, or auto-detect the widget when it is empty
+    pub fn new(port_name: &str) -> Self {
+        Self {
+            port_name: (!port_name.trim().is_empty()).then(|| port_name.trim().to_string()),
+        }
+    }
+}
+
+impl PortConnector for SerialConnector {
+    fn connect(&mut self) -> Result<(String, Box<dyn DmxPort>), DmxError> {
+        let port_name = find_enttec_port(&self.port_name)?;
+
+        // Open serial port with Enttec settings
+        let port = serialport::new(&port_name, 57_600)
+            .timeout(Duration::from_millis(100))
+            .open()?;
+
+        Ok((port_name, Box::new(port)))
+    }
+}
+
+/// Opens an Enttec Open DMX as a raw 250k 8N2 serial line
+pub struct OpenDmxConnector {
+    port_name: Option<String>,
+}
+
+impl OpenDmxConnector {
+    /// Connect to `port_name`, or auto-detect the interface when it is empty
+    pub fn new(port_name: &str) -> Self {
+        Self {
+            port_name: (!port_name.trim().is_empty()).then(|| port_name.trim().to_string()),
+        }
+    }
+}
+
+impl PortConnector<dyn BreakPort> for OpenDmxConnector {
+    fn connect(&mut self) -> Result<(String, Box<dyn BreakPort>), DmxError> {
+        let port_name = find_enttec_port(&self.port_name)?;
+
+        // DMX512 line settings: 250 kbaud, 8 data bits, no parity, 2 stop bits
+        let mut port = serialport::new(&port_name, 250_000)
+            .data_bits(DataBits::Eight)
+            .parity(Parity::None)
+            .stop_bits(StopBits::Two)
+            .flow_control(FlowControl::None)
+            .timeout(Duration::from_millis(100))
+            .open()?;
+
+        // RTS drives the line driver enable on the Open DMX
+        port.write_request_to_send(false)?;
+
+        Ok((port_name, Box::new(port)))
+    }
+}
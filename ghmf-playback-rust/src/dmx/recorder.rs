@@ -0,0 +1,542 @@
+use super::{DmxError, DmxUniverse};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+const DMX_UNIVERSE_SIZE: usize = 512;
+const CAPTURE_MAGIC: &[u8; 8] = b"GHMFDMX\0";
+const CAPTURE_VERSION: u8 = 1;
+const KEYFRAME_INTERVAL_MS: u32 = 5000;
+const TAG_KEYFRAME: u8 = 0x01;
+const TAG_DELTA: u8 = 0x02;
+const TAG_MARKER: u8 = 0x03;
+
+/// Largest change list worth writing; above this a full keyframe is smaller
+const MAX_DELTA_CHANGES: usize = DMX_UNIVERSE_SIZE / 3;
+
+/// Writes output frames to a compact capture file
+///
+/// File layout: 8-byte magic, version, start time (unix ms, u64 LE), label
+/// (u16 LE length + UTF-8), then records of `[tag][time_ms u32 LE][payload]`:
+/// keyframes carry all 512 channels, deltas carry only the changed channels as
+/// `[count u16][(channel u16, value u8)...]`, markers carry a UTF-8 note such
+/// as the song that just started. A keyframe is written every few seconds so a
+/// truncated file (e.g. after a crash) still loads up to the last record.
+pub struct DmxRecorder {
+    writer: BufWriter<File>,
+    path: PathBuf,
+    started: Instant,
+    last_frame: Option<[u8; DMX_UNIVERSE_SIZE]>,
+    last_keyframe_ms: u32,
+    frames_written: u64,
+}
+
+impl DmxRecorder {
+    /// Create a capture file, overwriting any existing file
+    pub fn create(path: impl AsRef<Path>, label: &str) -> Result<Self, DmxError> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut writer = BufWriter::new(File::create(&path)?);
+
+        let started_unix_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+
+        writer.write_all(CAPTURE_MAGIC)?;
+        writer.write_all(&[CAPTURE_VERSION])?;
+        writer.write_all(&started_unix_ms.to_le_bytes())?;
+        write_string(&mut writer, label)?;
+
+        info!("Recording DMX output to {}", path.display());
+
+        Ok(Self {
+            writer,
+            path,
+            started: Instant::now(),
+            last_frame: None,
+            last_keyframe_ms: 0,
+            frames_written: 0,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn frames_written(&self) -> u64 {
+        self.frames_written
+    }
+
+    /// Milliseconds since recording started
+    pub fn elapsed_ms(&self) -> u32 {
+        self.started.elapsed().as_millis() as u32
+    }
+
+    /// Record a frame stamped with the wall-clock time since recording started
+    pub fn record(&mut self, universe: &DmxUniverse) -> Result<(), DmxError> {
+        let time_ms = self.elapsed_ms();
+        self.record_at(time_ms, universe)
+    }
+
+    /// Record a frame at an explicit time (for renders on a simulated clock)
+    pub fn record_at(&mut self, time_ms: u32, universe: &DmxUniverse) -> Result<(), DmxError> {
+        let data: [u8; DMX_UNIVERSE_SIZE] = universe.as_slice()
+            .try_into()
+            .map_err(|_| DmxError::InvalidCapture("universe is not 512 channels".to_string()))?;
+
+        let changes: Option<Vec<(u16, u8)>> = self.last_frame.as_ref().map(|last| {
+            data.iter()
+                .zip(last.iter())
+                .enumerate()
+                .filter(|(_, (new, old))| new != old)
+                .map(|(idx, (new, _))| (idx as u16, *new))
+                .collect()
+        });
+
+        let keyframe_due = time_ms.saturating_sub(self.last_keyframe_ms) >= KEYFRAME_INTERVAL_MS;
+
+        match changes {
+            Some(changes) if !keyframe_due && changes.len() <= MAX_DELTA_CHANGES => {
+                self.writer.write_all(&[TAG_DELTA])?;
+                self.writer.write_all(&time_ms.to_le_bytes())?;
+                self.writer.write_all(&(changes.len() as u16).to_le_bytes())?;
+                for (channel, value) in changes {
+                    self.writer.write_all(&channel.to_le_bytes())?;
+                    self.writer.write_all(&[value])?;
+                }
+            }
+            _ => {
+                self.writer.write_all(&[TAG_KEYFRAME])?;
+                self.writer.write_all(&time_ms.to_le_bytes())?;
+                self.writer.write_all(&data)?;
+                self.last_keyframe_ms = time_ms;
+            }
+        }
+
+        self.last_frame = Some(data);
+        self.frames_written += 1;
+        Ok(())
+    }
+
+    /// Add a note (song name, event) at the current time
+    pub fn mark(&mut self, text: &str) -> Result<(), DmxError> {
+        let time_ms = self.elapsed_ms();
+        self.mark_at(time_ms, text)
+    }
+
+    /// Add a note at an explicit time
+    pub fn mark_at(&mut self, time_ms: u32, text: &str) -> Result<(), DmxError> {
+        self.writer.write_all(&[TAG_MARKER])?;
+        self.writer.write_all(&time_ms.to_le_bytes())?;
+        write_string(&mut self.writer, text)?;
+        Ok(())
+    }
+
+    /// Flush buffered records to disk and close the file
+    pub fn finish(mut self) -> Result<(), DmxError> {
+        self.writer.flush()?;
+        info!("DMX recording saved: {} ({} frames)", self.path.display(), self.frames_written);
+        Ok(())
+    }
+}
+
+impl Drop for DmxRecorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.writer.flush() {
+            warn!("Failed to flush DMX recording {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// One recorded output frame
+#[derive(Debug, Clone)]
+pub struct RecordedFrame {
+    pub time_ms: u32,
+    pub data: [u8; DMX_UNIVERSE_SIZE],
+}
+
+/// One record as stored in memory: a full frame or the channels it changed
+#[derive(Debug, Clone)]
+struct StoredFrame {
+    time_ms: u32,
+    change: FrameChange,
+}
+
+#[derive(Debug, Clone)]
+enum FrameChange {
+    Keyframe(Box<[u8; DMX_UNIVERSE_SIZE]>),
+    Delta(Vec<(u16, u8)>),
+}
+
+impl StoredFrame {
+    fn apply(&self, data: &mut [u8; DMX_UNIVERSE_SIZE]) {
+        match &self.change {
+            FrameChange::Keyframe(frame) => data.copy_from_slice(frame.as_slice()),
+            FrameChange::Delta(changes) => {
+                for &(channel, value) in changes {
+                    data[channel as usize] = value;
+                }
+            }
+        }
+    }
+}
+
+/// A channel that differs between two recordings
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameDifference {
+    pub time_ms: u32,
+    pub channel: usize, // 1-indexed
+    pub left: u8,
+    pub right: u8,
+}
+
+/// A capture file loaded into memory for replay or inspection
+///
+/// Frames stay as recorded, keyframes plus deltas; a full frame is rebuilt
+/// from the nearest keyframe when asked for.
+#[derive(Debug, Clone)]
+pub struct DmxRecording {
+    pub label: String,
+    pub started_unix_ms: u64,
+    pub markers: Vec<(u32, String)>,
+    frames: Vec<StoredFrame>,
+    /// Indices into `frames` of the keyframes
+    keyframes: Vec<usize>,
+}
+
+impl DmxRecording {
+    /// Load a capture file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, DmxError> {
+        let file = File::open(path.as_ref())?;
+        Self::from_reader(BufReader::new(file))
+    }
+
+    /// Parse a capture from any reader; a truncated final record is ignored
+    pub fn from_reader(mut reader: impl Read) -> Result<Self, DmxError> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != CAPTURE_MAGIC {
+            return Err(DmxError::InvalidCapture("not a DMX capture file".to_string()));
+        }
+
+        let version = read_u8(&mut reader)?;
+        if version != CAPTURE_VERSION {
+            return Err(DmxError::InvalidCapture(format!("unsupported capture version {}", version)));
+        }
+
+        let mut started = [0u8; 8];
+        reader.read_exact(&mut started)?;
+        let label = read_string(&mut reader)?;
+
+        let mut recording = Self {
+            label,
+            started_unix_ms: u64::from_le_bytes(started),
+            markers: Vec::new(),
+            frames: Vec::new(),
+            keyframes: Vec::new(),
+        };
+
+        loop {
+            match recording.read_record(&mut reader) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(DmxError::Io(e)) if e.kind() == ErrorKind::UnexpectedEof => {
+                    warn!("DMX capture is truncated, loaded {} frames", recording.frame_count());
+                    break;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(recording)
+    }
+
+    /// Read one record; returns false at a clean end of file
+    fn read_record(&mut self, reader: &mut impl Read) -> Result<bool, DmxError> {
+        let mut tag = [0u8; 1];
+        if reader.read(&mut tag)? == 0 {
+            return Ok(false);
+        }
+
+        let mut time = [0u8; 4];
+        reader.read_exact(&mut time)?;
+        let time_ms = u32::from_le_bytes(time);
+
+        match tag[0] {
+            TAG_KEYFRAME => {
+                let mut frame = Box::new([0u8; DMX_UNIVERSE_SIZE]);
+                reader.read_exact(frame.as_mut_slice())?;
+                self.keyframes.push(self.frames.len());
+                self.frames.push(StoredFrame { time_ms, change: FrameChange::Keyframe(frame) });
+            }
+            TAG_DELTA => {
+                let count = read_u16(reader)?;
+                let mut changes = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    let channel = read_u16(reader)?;
+                    let value = read_u8(reader)?;
+                    if channel as usize >= DMX_UNIVERSE_SIZE {
+                        return Err(DmxError::InvalidCapture(format!("channel index {} out of range", channel)));
+                    }
+                    changes.push((channel, value));
+                }
+                self.frames.push(StoredFrame { time_ms, change: FrameChange::Delta(changes) });
+            }
+            TAG_MARKER => {
+                let text = read_string(reader)?;
+                self.markers.push((time_ms, text));
+            }
+            other => {
+                return Err(DmxError::InvalidCapture(format!("unknown record type 0x{:02X}", other)));
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Length of the recording in milliseconds
+    pub fn duration_ms(&self) -> u32 {
+        self.frames.last().map(|f| f.time_ms).unwrap_or(0)
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Every frame in order, rebuilt as it goes
+    pub fn frames(&self) -> impl Iterator<Item = RecordedFrame> + '_ {
+        let mut data = [0u8; DMX_UNIVERSE_SIZE];
+        self.frames.iter().map(move |frame| {
+            frame.apply(&mut data);
+            RecordedFrame { time_ms: frame.time_ms, data }
+        })
+    }
+
+    /// Frame that was on the outputs at `time_ms`
+    pub fn frame_at(&self, time_ms: u32) -> Option<RecordedFrame> {
+        let idx = self.frames.partition_point(|f| f.time_ms <= time_ms).checked_sub(1)?;
+        // Deltas before the first keyframe start from a blank universe
+        let start = self.keyframes[..self.keyframes.partition_point(|&k| k <= idx)]
+            .last()
+            .copied()
+            .unwrap_or(0);
+
+        let mut data = [0u8; DMX_UNIVERSE_SIZE];
+        for frame in &self.frames[start..=idx] {
+            frame.apply(&mut data);
+        }
+        Some(RecordedFrame { time_ms: self.frames[idx].time_ms, data })
+    }
+
+    /// Most recent marker at or before `time_ms`
+    pub fn marker_at(&self, time_ms: u32) -> Option<&str> {
+        self.markers.iter()
+            .rev()
+            .find(|(t, _)| *t <= time_ms)
+            .map(|(_, text)| text.as_str())
+    }
+
+    /// First channel that differs from `other`, checked at each of this recording's frame times
+    pub fn first_difference(&self, other: &DmxRecording) -> Option<FrameDifference> {
+        for frame in self.frames() {
+            let other_data = other.frame_at(frame.time_ms)
+                .map(|f| f.data)
+                .unwrap_or([0; DMX_UNIVERSE_SIZE]);
+
+            if let Some(idx) = (0..DMX_UNIVERSE_SIZE).find(|&i| frame.data[i] != other_data[i]) {
+                return Some(FrameDifference {
+                    time_ms: frame.time_ms,
+                    channel: idx + 1,
+                    left: frame.data[idx],
+                    right: other_data[idx],
+                });
+            }
+        }
+        None
+    }
+}
+
+fn write_string(writer: &mut impl Write, text: &str) -> Result<(), DmxError> {
+    let bytes = text.as_bytes();
+    let len = bytes.len().min(u16::MAX as usize);
+    writer.write_all(&(len as u16).to_le_bytes())?;
+    writer.write_all(&bytes[..len])?;
+    Ok(())
+}
+
+fn read_string(reader: &mut impl Read) -> Result<String, DmxError> {
+    let len = read_u16(reader)? as usize;
+    let mut bytes = vec![0u8; len];
+    reader.read_exact(&mut bytes)?;
+    Ok(String::from_utf8_lossy(&bytes).to_string())
+}
+
+fn read_u8(reader: &mut impl Read) -> Result<u8, DmxError> {
+    let mut buf = [0u8; 1];
+    reader.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u16(reader: &mut impl Read) -> Result<u16, DmxError> {
+    let mut buf = [0u8; 2];
+    reader.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn universe_with(channel: usize, value: u8) -> DmxUniverse {
+        let mut universe = DmxUniverse::new();
+        universe.set_channel(channel, value).unwrap();
+        universe
+    }
+
+    #[test]
+    fn test_round_trip_with_deltas_and_markers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("show.dmxrec");
+
+        let mut recorder = DmxRecorder::create(&path, "Test Show").unwrap();
+        recorder.mark_at(0, "Song 1").unwrap();
+        recorder.record_at(0, &universe_with(1, 10)).unwrap();
+        recorder.record_at(50, &universe_with(1, 20)).unwrap();
+        recorder.record_at(100, &universe_with(1, 20)).unwrap();
+        recorder.mark_at(120, "Song 2").unwrap();
+        recorder.record_at(150, &universe_with(512, 255)).unwrap();
+        recorder.finish().unwrap();
+
+        let recording = DmxRecording::load(&path).unwrap();
+        assert_eq!(recording.label, "Test Show");
+        assert_eq!(recording.frame_count(), 4);
+        assert_eq!(recording.duration_ms(), 150);
+        assert_eq!(recording.frame_at(75).unwrap().data[0], 20);
+        assert_eq!(recording.frame_at(150).unwrap().data[0], 0);
+        assert_eq!(recording.frame_at(150).unwrap().data[511], 255);
+        assert_eq!(recording.marker_at(130), Some("Song 2"));
+        assert_eq!(recording.marker_at(10), Some("Song 1"));
+    }
+
+    #[test]
+    fn test_unchanged_frames_are_small() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("static.dmxrec");
+
+        let mut recorder = DmxRecorder::create(&path, "").unwrap();
+        for i in 0..100 {
+            recorder.record_at(i * 25, &universe_with(5, 128)).unwrap();
+        }
+        recorder.finish().unwrap();
+
+        // One keyframe plus 99 empty deltas (7 bytes each)
+        let size = std::fs::metadata(&path).unwrap().len();
+        assert!(size < 512 + 100 * 8 + 64, "capture is {} bytes", size);
+    }
+
+    #[test]
+    fn test_frame_at_rebuilds_from_nearest_keyframe() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("long.dmxrec");
+
+        let mut recorder = DmxRecorder::create(&path, "").unwrap();
+        recorder.record_at(0, &universe_with(1, 10)).unwrap();
+        recorder.record_at(2500, &universe_with(2, 20)).unwrap();
+        recorder.record_at(5000, &universe_with(3, 30)).unwrap();
+        recorder.record_at(6000, &universe_with(4, 40)).unwrap();
+        recorder.finish().unwrap();
+
+        let recording = DmxRecording::load(&path).unwrap();
+        assert_eq!(recording.keyframes, vec![0, 2]);
+
+        let frame = recording.frame_at(3000).unwrap();
+        assert_eq!((frame.time_ms, &frame.data[..4]), (2500, &[0, 20, 0, 0][..]));
+        let frame = recording.frame_at(7000).unwrap();
+        assert_eq!((frame.time_ms, &frame.data[..4]), (6000, &[0, 0, 0, 40][..]));
+
+        let rebuilt: Vec<RecordedFrame> = recording.frames().collect();
+        assert_eq!(rebuilt.len(), 4);
+        assert!(rebuilt.iter().all(|f| recording.frame_at(f.time_ms).unwrap().data == f.data));
+    }
+
+    #[test]
+    fn test_truncated_file_loads_complete_frames() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("crash.dmxrec");
+
+        let mut recorder = DmxRecorder::create(&path, "").unwrap();
+        recorder.record_at(0, &universe_with(1, 1)).unwrap();
+        recorder.record_at(25, &universe_with(1, 2)).unwrap();
+        recorder.finish().unwrap();
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes.truncate(bytes.len() - 2);
+        let recording = DmxRecording::from_reader(bytes.as_slice()).unwrap();
+        assert_eq!(recording.frame_count(), 1);
+    }
+
+    #[test]
+    fn test_first_difference() {
+        let dir = tempfile::tempdir().unwrap();
+        let left_path = dir.path().join("left.dmxrec");
+        let right_path = dir.path().join("right.dmxrec");
+
+        let mut left = DmxRecorder::create(&left_path, "").unwrap();
+        let mut right = DmxRecorder::create(&right_path, "").unwrap();
+        for (time_ms, value) in [(0, 0), (25, 100), (50, 200)] {
+            left.record_at(time_ms, &universe_with(7, value)).unwrap();
+            right.record_at(time_ms, &universe_with(7, if time_ms == 50 { 199 } else { value })).unwrap();
+        }
+        left.finish().unwrap();
+        right.finish().unwrap();
+
+        let left = DmxRecording::load(&left_path).unwrap();
+        let right = DmxRecording::load(&right_path).unwrap();
+        assert_eq!(left.first_difference(&left), None);
+        assert_eq!(
+            left.first_difference(&right),
+            Some(FrameDifference { time_ms: 50, channel: 7, left: 200, right: 199 })
+        );
+    }
+
+    #[test]
+    fn test_replay_stays_under_safety_blackout() {
+        use crate::config::CsvConfig;
+        use crate::lighting::FixtureManager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("show.dmxrec");
+        let mut recorder = DmxRecorder::create(&path, "test").unwrap();
+        let mut recorded = DmxUniverse::new();
+        recorded.set_channel(1, 200).unwrap();
+        recorded.set_channel(10, 255).unwrap();
+        recorded.set_channel(300, 90).unwrap();
+        recorder.record_at(0, &recorded).unwrap();
+        recorder.finish().unwrap();
+        let recording = DmxRecording::load(&path).unwrap();
+
+        let mut fm = FixtureManager::new(CsvConfig::test_rig().with_fireworks());
+        let replay = |fm: &FixtureManager| {
+            let mut universe = DmxUniverse::new();
+            universe.as_mut_slice().copy_from_slice(&recording.frame_at(0).unwrap().data);
+            fm.apply_to_replay(&mut universe).unwrap();
+            universe
+        };
+
+        // Without the blackout the capture plays, less the fire channel
+        let universe = replay(&fm);
+        assert_eq!(universe.get_channel_raw(0), 200);
+        assert_eq!(universe.get_channel_raw(9), 0);
+        assert_eq!(universe.get_channel_raw(299), 90);
+
+        // With it, nothing gets out, unmapped channels included
+        fm.set_safety_blackout(true);
+        assert!(replay(&fm).as_slice().iter().all(|&level| level == 0));
+    }
+}
@@ -3,6 +3,7 @@ use super::DmxError;
 const DMX_UNIVERSE_SIZE: usize = 512;
 
 /// Represents a DMX512 universe (512 channels)
+#[derive(Clone)]
 pub struct DmxUniverse {
     channels: [u8; DMX_UNIVERSE_SIZE],
 }
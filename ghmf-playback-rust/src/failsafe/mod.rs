@@ -0,0 +1,203 @@
+pub mod watchdog;
+
+pub use watchdog::{Heartbeat, HeartbeatSender, HeartbeatThread, WatchdogState};
+
+use std::sync::{Arc, Mutex, TryLockError, Weak};
+use std::time::{Duration, Instant};
+use once_cell::sync::Lazy;
+use crate::config::CsvConfig;
+use crate::dmx::{DmxDevice, DmxUniverse};
+use crate::lighting::color::WhiteStrategy;
+use crate::lighting::FixtureManager;
+use crate::plc::PlcClient;
+
+/// How long the panic hook waits for the PLC to take the E-stop
+const PANIC_STOP_TIMEOUT: Duration = Duration::from_millis(1500);
+
+/// How long the failsafe look may take to reach the interface
+pub const FRAME_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// What the fixtures show after an E-stop, from the `failsafe_look` setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FailsafeLook {
+    /// Every channel at zero
+    #[default]
+    Blackout,
+    /// Freeze whatever is showing
+    Hold,
+    /// A saved scene, e.g. work lights over the basin
+    Scene(u16),
+}
+
+impl FailsafeLook {
+    /// Parse "blackout", "hold" or "scene:<id>"; anything else is blackout
+    pub fn from_setting(value: &str) -> Self {
+        let value = value.trim().to_lowercase();
+        match value.split_once(':') {
+            Some(("scene", id)) => id.trim().parse().map(FailsafeLook::Scene).unwrap_or_default(),
+            _ if value == "hold" => FailsafeLook::Hold,
+            _ => FailsafeLook::Blackout,
+        }
+    }
+
+    pub fn as_setting(&self) -> String {
+        match self {
+            FailsafeLook::Blackout => "blackout".to_string(),
+            FailsafeLook::Hold => "hold".to_string(),
+            FailsafeLook::Scene(id) => format!("scene:{}", id),
+        }
+    }
+
+    /// The frame to send, or None to leave the output as it is
+    ///
+    /// Scenes are rendered on a fresh fixture manager, so this works even
+    /// when the live one is stuck.
+    pub fn frame(&self, config: Option<&CsvConfig>, white: WhiteStrategy) -> Option<DmxUniverse> {
+        match self {
+            FailsafeLook::Blackout => Some(DmxUniverse::new()),
+            FailsafeLook::Hold => None,
+            FailsafeLook::Scene(id) => {
+                let Some(config) = config else {
+                    tracing::warn!("No config for failsafe scene {}; using blackout", id);
+                    return Some(DmxUniverse::new());
+                };
+                let mut fm = FixtureManager::new(config.clone());
+                fm.set_white_strategy(white);
+                let now = Instant::now();
+                fm.recall_scene_at(*id, 0, now);
+                fm.update_fades_at(now);
+                let mut universe = DmxUniverse::new();
+                if let Err(e) = fm.apply_to_dmx(&mut universe) {
+                    tracing::warn!("Failed to render failsafe scene {}: {}", id, e);
+                    return Some(DmxUniverse::new());
+                }
+                Some(universe)
+            }
+        }
+    }
+}
+
+/// Send a frame straight to the interface and latch it there
+///
+/// Waits for the interface to connect and for a write that started after
+/// the frame was handed over, then keeps the frame up when the device is
+/// dropped instead of blacking out. Returns false if that did not happen
+/// within `timeout`.
+pub fn send_frame(dmx: &mut DmxDevice, frame: &DmxUniverse, timeout: Duration) -> bool {
+    if let Err(e) = dmx.set_channels(1, frame.as_slice()) {
+        tracing::error!("Failed to set failsafe DMX frame: {}", e);
+        return false;
+    }
+    let deadline = Instant::now() + timeout;
+    let mut first_count = None;
+    while Instant::now() < deadline {
+        let _ = dmx.send_dmx();
+        let health = dmx.health();
+        if health.connected {
+            // The first write counted may have started before the handover
+            let first = *first_count.get_or_insert(health.frames_sent);
+            if health.frames_sent >= first + 2 {
+                dmx.hold_on_stop();
+                return true;
+            }
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    tracing::error!("DMX interface did not take the failsafe frame within {} ms", timeout.as_millis());
+    false
+}
+
+/// What the panic hook needs to leave the fountain safe
+///
+/// Only weak references, so a replaced client or interface is really
+/// dropped: its sender task stops and its port can be opened again.
+#[derive(Default)]
+struct Failsafe {
+    plc: Option<Weak<PlcClient>>,
+    dmx: Option<Weak<Mutex<DmxDevice>>>,
+    sequence: Vec<String>,
+    frame: Option<DmxUniverse>,
+}
+
+static FAILSAFE: Lazy<Mutex<Failsafe>> = Lazy::new(Default::default);
+
+/// Point the panic hook at the current PLC client and DMX interface
+///
+/// Called again whenever either is replaced or the E-stop settings change.
+pub fn arm(
+    plc: Option<&Arc<PlcClient>>,
+    dmx: Option<&Arc<Mutex<DmxDevice>>>,
+    sequence: Vec<String>,
+    frame: Option<DmxUniverse>,
+) {
+    let mut failsafe = FAILSAFE.lock().unwrap_or_else(|e| e.into_inner());
+    *failsafe = Failsafe {
+        plc: plc.map(Arc::downgrade),
+        dmx: dmx.map(Arc::downgrade),
+        sequence,
+        frame,
+    };
+}
+
+/// Send the E-stop sequence and the failsafe look, waiting for the PLC
+///
+/// For a process that is about to end: the panic hook and shutdown.
+/// Locks are only tried, so a thread that panicked holding one cannot
+/// hang it. A Pro widget keeps refreshing the look after the process
+/// exits; an Open DMX line goes quiet with it.
+pub fn stop_all(timeout: Duration) {
+    let failsafe = match FAILSAFE.try_lock() {
+        Ok(failsafe) => failsafe,
+        Err(TryLockError::Poisoned(e)) => e.into_inner(),
+        Err(TryLockError::WouldBlock) => {
+            tracing::error!("Failsafe busy; E-stop not sent");
+            return;
+        }
+    };
+
+    if let Some(plc) = failsafe.plc.as_ref().and_then(Weak::upgrade) {
+        if plc.emergency_stop_blocking(&failsafe.sequence, timeout) {
+            tracing::warn!("E-stop sent: {}", failsafe.sequence.join(" "));
+        } else {
+            tracing::error!("PLC did not take the E-stop within {} ms", timeout.as_millis());
+        }
+    }
+
+    if let (Some(dmx), Some(frame)) = (failsafe.dmx.as_ref().and_then(Weak::upgrade), &failsafe.frame) {
+        match dmx.try_lock() {
+            Ok(mut dmx) => {
+                send_frame(&mut dmx, frame, FRAME_TIMEOUT);
+            }
+            Err(TryLockError::Poisoned(e)) => {
+                send_frame(&mut e.into_inner(), frame, FRAME_TIMEOUT);
+            }
+            Err(TryLockError::WouldBlock) => tracing::error!("DMX interface busy; failsafe look not sent"),
+        }
+    }
+}
+
+/// E-stop on any panic, then carry on to the default hook
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        tracing::error!("Panic: {}; sending E-stop", info);
+        stop_all(PANIC_STOP_TIMEOUT);
+        previous(info);
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failsafe_look_setting() {
+        assert_eq!(FailsafeLook::from_setting("scene:4"), FailsafeLook::Scene(4));
+        assert_eq!(FailsafeLook::from_setting(" Hold "), FailsafeLook::Hold);
+        assert_eq!(FailsafeLook::from_setting("scene:x"), FailsafeLook::Blackout);
+        assert_eq!(FailsafeLook::Scene(12).as_setting(), "scene:12");
+        assert!(FailsafeLook::Hold.frame(None, WhiteStrategy::Off).is_none());
+        let blackout = FailsafeLook::Blackout.frame(None, WhiteStrategy::Off).unwrap();
+        assert!(blackout.as_slice().iter().all(|&level| level == 0));
+    }
+}
@@ -0,0 +1,275 @@
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use anyhow::Result;
+
+/// Heartbeats go out at least this often, and at once when the state changes
+const HEARTBEAT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// `Playing` turns to `Stale` when the player has not checked in for this long
+const STALE_AFTER: Duration = Duration::from_secs(1);
+
+/// What the player tells the watchdog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Heartbeat {
+    /// A song is in progress; silence now means water may be left running
+    Playing,
+    /// Nothing running; silence is fine
+    Idle,
+    /// Clean shutdown, E-stop already sent
+    Exit,
+    /// Mid-song, but the player has stopped running the show; counts as silence
+    Stale,
+}
+
+impl Heartbeat {
+    pub fn as_message(&self) -> &'static str {
+        match self {
+            Heartbeat::Playing => "ghmf playing",
+            Heartbeat::Idle => "ghmf idle",
+            Heartbeat::Exit => "ghmf exit",
+            Heartbeat::Stale => "ghmf stale",
+        }
+    }
+
+    pub fn parse(message: &str) -> Option<Self> {
+        match message.trim() {
+            "ghmf playing" => Some(Heartbeat::Playing),
+            "ghmf idle" => Some(Heartbeat::Idle),
+            "ghmf exit" => Some(Heartbeat::Exit),
+            "ghmf stale" => Some(Heartbeat::Stale),
+            _ => None,
+        }
+    }
+}
+
+/// The player's end: UDP datagrams to the watchdog on localhost
+pub struct HeartbeatSender {
+    socket: UdpSocket,
+    target: SocketAddr,
+    last: Option<(Heartbeat, Instant)>,
+}
+
+impl HeartbeatSender {
+    pub fn new(port: u16) -> Result<Self> {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0))?;
+        Ok(Self { socket, target: SocketAddr::from((Ipv4Addr::LOCALHOST, port)), last: None })
+    }
+
+    /// Send `beat` if it is new or the last one is getting old
+    pub fn beat(&mut self, beat: Heartbeat) {
+        let now = Instant::now();
+        if let Some((last, at)) = self.last {
+            if last == beat && now.duration_since(at) < HEARTBEAT_INTERVAL {
+                return;
+            }
+        }
+        // Nobody listening is fine; the watchdog is optional
+        let _ = self.socket.send_to(beat.as_message().as_bytes(), self.target);
+        self.last = Some((beat, now));
+    }
+}
+
+struct BeatShared {
+    beat: Heartbeat,
+    /// When the player last called `set`
+    checked_in: Instant,
+    running: bool,
+}
+
+/// Sends heartbeats from a thread of its own, on a steady interval
+///
+/// The player calls `set` every time it runs the show; the thread repeats
+/// the state every interval. `Playing` only goes out while those calls keep
+/// coming, so a player whose show loop has hung sends `Stale` instead.
+/// `Exit` is sent once and ends the thread.
+pub struct HeartbeatThread {
+    shared: Arc<(Mutex<BeatShared>, Condvar)>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl HeartbeatThread {
+    pub fn start(port: u16) -> Result<Self> {
+        let mut sender = HeartbeatSender::new(port)?;
+        let shared = Arc::new((
+            Mutex::new(BeatShared { beat: Heartbeat::Idle, checked_in: Instant::now(), running: true }),
+            Condvar::new(),
+        ));
+        let thread_shared = Arc::clone(&shared);
+        let thread = std::thread::spawn(move || {
+            let (lock, wake) = &*thread_shared;
+            let mut state = lock.lock().unwrap_or_else(|e| e.into_inner());
+            while state.running {
+                let stale = state.beat == Heartbeat::Playing && state.checked_in.elapsed() > STALE_AFTER;
+                sender.beat(if stale { Heartbeat::Stale } else { state.beat });
+                if state.beat == Heartbeat::Exit {
+                    break;
+                }
+                state = wake.wait_timeout(state, HEARTBEAT_INTERVAL)
+                    .unwrap_or_else(|e| e.into_inner())
+                    .0;
+            }
+        });
+        Ok(Self { shared, thread: Some(thread) })
+    }
+
+    /// What to report from now on, and a sign the show loop is still
+    /// running; a change goes out at once
+    pub fn set(&self, beat: Heartbeat) {
+        let (lock, wake) = &*self.shared;
+        let mut state = lock.lock().unwrap_or_else(|e| e.into_inner());
+        state.checked_in = Instant::now();
+        if state.beat != beat {
+            state.beat = beat;
+            wake.notify_one();
+        }
+    }
+
+    /// Send `Exit` and wait for it to go out
+    pub fn exit(mut self) {
+        self.set(Heartbeat::Exit);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for HeartbeatThread {
+    /// Stop without a last message, e.g. when the settings change
+    fn drop(&mut self) {
+        let (lock, wake) = &*self.shared;
+        lock.lock().unwrap_or_else(|e| e.into_inner()).running = false;
+        wake.notify_one();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// The watchdog's view of the player
+///
+/// It trips once when the player goes quiet mid-song, then waits for the
+/// next song before it can trip again.
+#[derive(Debug, Clone)]
+pub struct WatchdogState {
+    timeout: Duration,
+    armed: bool,
+    last_heard: Option<Instant>,
+}
+
+impl WatchdogState {
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout, armed: false, last_heard: None }
+    }
+
+    pub fn is_armed(&self) -> bool {
+        self.armed
+    }
+
+    /// A stale beat changes nothing, so the timeout runs from the last
+    /// `Playing`
+    pub fn heard(&mut self, beat: Heartbeat, now: Instant) {
+        if beat == Heartbeat::Stale {
+            return;
+        }
+        self.armed = beat == Heartbeat::Playing;
+        self.last_heard = Some(now);
+    }
+
+    /// True when it is time to stop the water
+    pub fn check(&mut self, now: Instant) -> bool {
+        let silent = self.last_heard.is_some_and(|at| now.duration_since(at) >= self.timeout);
+        if self.armed && silent {
+            self.armed = false;
+            return true;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trips_once_when_silent_mid_song() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let mut state = WatchdogState::new(Duration::from_secs(5));
+
+        // Idle silence is fine
+        state.heard(Heartbeat::Idle, at(0));
+        assert!(!state.check(at(60_000)));
+
+        state.heard(Heartbeat::Playing, at(60_000));
+        assert!(!state.check(at(64_000)));
+        assert!(state.check(at(65_000)));
+        assert!(!state.check(at(70_000)));
+
+        // A player that is alive but stuck trips it too
+        state.heard(Heartbeat::Playing, at(70_000));
+        state.heard(Heartbeat::Stale, at(72_000));
+        state.heard(Heartbeat::Stale, at(74_000));
+        assert!(state.check(at(75_000)));
+
+        // Shutting down disarms it
+        state.heard(Heartbeat::Playing, at(80_000));
+        state.heard(Heartbeat::Exit, at(80_100));
+        assert!(!state.check(at(90_000)));
+
+        assert_eq!(Heartbeat::parse(Heartbeat::Playing.as_message()), Some(Heartbeat::Playing));
+        assert_eq!(Heartbeat::parse("hello"), None);
+    }
+
+    #[test]
+    fn test_heartbeat_reaches_watchdog() {
+        let listener = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        listener.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+        let mut sender = HeartbeatSender::new(listener.local_addr().unwrap().port()).unwrap();
+
+        sender.beat(Heartbeat::Playing);
+        sender.beat(Heartbeat::Playing); // Too soon, not sent
+        sender.beat(Heartbeat::Idle);
+
+        let mut buf = [0u8; 64];
+        let mut received = Vec::new();
+        for _ in 0..2 {
+            let (len, _) = listener.recv_from(&mut buf).unwrap();
+            received.push(Heartbeat::parse(std::str::from_utf8(&buf[..len]).unwrap()).unwrap());
+        }
+        assert_eq!(received, vec![Heartbeat::Playing, Heartbeat::Idle]);
+    }
+
+    #[test]
+    fn test_heartbeat_thread_beats_on_its_own() {
+        let listener = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        listener.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+        let heartbeat = HeartbeatThread::start(listener.local_addr().unwrap().port()).unwrap();
+        let mut buf = [0u8; 64];
+        let mut next = || {
+            let (len, _) = listener.recv_from(&mut buf).unwrap();
+            Heartbeat::parse(std::str::from_utf8(&buf[..len]).unwrap()).unwrap()
+        };
+        assert_eq!(next(), Heartbeat::Idle);
+
+        // Checking in keeps it playing between calls
+        heartbeat.set(Heartbeat::Playing);
+        assert_eq!(next(), Heartbeat::Playing);
+        assert_eq!(next(), Heartbeat::Playing);
+
+        // Set once, as when the show loop hangs mid-song: it goes stale
+        let mut beats = Vec::new();
+        while beats.len() < 8 && beats.last() != Some(&Heartbeat::Stale) {
+            beats.push(next());
+        }
+        assert_eq!(beats.last(), Some(&Heartbeat::Stale));
+        heartbeat.set(Heartbeat::Playing);
+        assert_eq!(next(), Heartbeat::Playing);
+
+        heartbeat.exit();
+        assert_eq!(next(), Heartbeat::Exit);
+        listener.set_read_timeout(Some(HEARTBEAT_INTERVAL * 2)).unwrap();
+        assert!(listener.recv_from(&mut buf).is_err());
+    }
+}
@@ -1,10 +1,15 @@
-use super::{playback_panel, lighting_panel, status_panel, settings_dialog, command_panel, theme, sidebar, dmx_map_panel, light_group_panel, legacy_color_panel, playlist_panel, start_time_panel, procedures_panel, operator_panel, lights_layout_panel};
+use super::{playback_panel, lighting_panel, status_panel, settings_dialog, command_panel, theme, sidebar, dmx_map_panel, light_group_panel, legacy_color_panel, playlist_panel, start_time_panel, procedures_panel, operator_panel, lights_layout_panel, scene_panel};
 use crate::audio::AudioPlayer;
-use crate::dmx::{EnttecDmxPro, DmxUniverse, SacnOutput, SacnFilterMode};
-use crate::plc::{PlcClient, PlcStatus};
-use crate::config::{Settings, CsvConfig};
+use crate::dmx::{DmxDevice, DmxDeviceKind, DmxError, DmxHealth, DmxRecorder, DmxRecording, DmxUniverse, SacnOutput, SacnFilterMode, NetworkInput, InputProtocol, DmxMerger, MergePolicy, MergeRange};
+use crate::plc::{PlcClient, PlcCounts, PlcEvent, PlcEventKind, PlcProtocol, PlcStatus, ReconnectPolicy};
+use crate::config::{Settings, CsvConfig, WaterMap};
 use crate::lighting::FixtureManager;
-use crate::commands::CtlFile;
+use crate::lighting::color::WhiteStrategy;
+use crate::lighting::{pyro, FadeCurve, IdleLook};
+use crate::commands::{self, CtlFile, WaterGuard};
+use crate::weather::{self, WindAlert, WindMonitor, WindReading, WindSourceKind};
+use crate::failsafe::{self, FailsafeLook, Heartbeat, HeartbeatThread};
+use crate::maintenance::{self, CounterKind, MaintenanceCounters, ServiceStatus};
 use egui::{CentralPanel, TopBottomPanel, SidePanel, Context, Color32, Stroke, Vec2};
 use egui_notify::Toasts;
 use std::sync::{Arc, Mutex};
@@ -17,17 +22,30 @@ pub use status_panel::StatusType;
 pub struct PlaybackApp {
     // Core systems
     audio_player: Option<Arc<Mutex<AudioPlayer>>>,
-    dmx_controller: Option<Arc<Mutex<EnttecDmxPro>>>,
+    dmx_controller: Option<Arc<Mutex<DmxDevice>>>,
     sacn_output: Arc<Mutex<SacnOutput>>,
+    dmx_input: Arc<Mutex<NetworkInput>>,
+    dmx_merger: DmxMerger,
     plc_client: Option<Arc<PlcClient>>,
     fixture_manager: Option<Arc<Mutex<FixtureManager>>>,
     csv_config: Option<Arc<CsvConfig>>,
+    water_map: WaterMap, // From csv_config, or the built-in addresses until it loads
+    water_guard: WaterGuard, // Checks every water command before it is queued
+    wind_monitor: WindMonitor,
+    wind_readings: Option<crossbeam_channel::Receiver<Result<WindReading, String>>>,
+    wind_error: Option<String>, // Last wind source error, so it is reported once
+    estop_frame: Option<DmxUniverse>, // Sent instead of the show after an E-stop until the next song starts
+    heartbeat: Option<HeartbeatThread>, // To the watchdog, when enabled
+    maintenance: MaintenanceCounters, // Run hours and starts for the service reminders
+    maintenance_saved: Instant,
+    maintenance_due: usize, // Services due at the last check, so each new one is reported once
     settings: Settings,
     
     // Playback data
     current_song_path: Option<PathBuf>,
     current_ctl_file: Option<CtlFile>,
     last_command_time: u64,
+    pyro_held: bool, // Seek, step or load: fire channels stay out of live until the next straight tick
     recent_commands: Vec<(u64, String)>,  // (time_ms, command_description)
     
     // UI State
@@ -44,10 +62,15 @@ pub struct PlaybackApp {
     
     // DMX state
     dmx_connected: bool,
+    dmx_health: Option<DmxHealth>,
+    dmx_recorder: Option<DmxRecorder>,
+    dmx_replay: Option<(DmxRecording, Instant)>,
     dmx_last_update: Instant,
+    dmx_input_failed_config: Option<(InputProtocol, String, u16)>,
     
     // PLC state
     plc_status: PlcStatus,
+    plc_counts: PlcCounts,
     plc_last_status_check: Instant,
     
     // Volume
@@ -59,10 +82,8 @@ pub struct PlaybackApp {
     status_time: Instant,
     
     // Light control
-    selected_fixture: Option<usize>,
-    fixture_red: u8,
-    fixture_green: u8,
-    fixture_blue: u8,
+    lighting_panel: lighting_panel::LightingPanel,
+    scene_panel: scene_panel::ScenePanel,
     
     // DMX Mapper
     dmx_map_panel: dmx_map_panel::DmxMapPanel,
@@ -111,6 +132,8 @@ impl Default for PlaybackApp {
             audio_player: None,
             dmx_controller: None,
             sacn_output: Arc::new(Mutex::new(SacnOutput::new())),
+            dmx_input: Arc::new(Mutex::new(NetworkInput::new())),
+            dmx_merger: DmxMerger::default(),
             plc_client: None,
             settings: Settings::load(),
             sidebar: sidebar::Sidebar::default(),
@@ -122,22 +145,36 @@ impl Default for PlaybackApp {
             playback_position: Duration::from_secs(0),
             playback_duration: Duration::from_secs(0),
             dmx_connected: false,
+            dmx_health: None,
+            dmx_recorder: None,
+            dmx_replay: None,
             dmx_last_update: Instant::now(),
+            dmx_input_failed_config: None,
             plc_status: PlcStatus::Disabled,
+            plc_counts: PlcCounts::default(),
             plc_last_status_check: Instant::now(),
             master_volume: 0.8,
             status_message: "Ready".to_string(),
             status_type: StatusType::Info,
             status_time: Instant::now(),
-            selected_fixture: None,
-            fixture_red: 0,
-            fixture_green: 0,
-            fixture_blue: 0,
+            lighting_panel: lighting_panel::LightingPanel::new(),
+            scene_panel: scene_panel::ScenePanel::new(),
             csv_config: None,
+            water_map: WaterMap::builtin(),
+            water_guard: WaterGuard::new(WaterMap::builtin()),
+            wind_monitor: WindMonitor::new(Vec::new(), Duration::ZERO, Duration::MAX),
+            wind_readings: None,
+            wind_error: None,
+            estop_frame: None,
+            heartbeat: None,
+            maintenance: MaintenanceCounters::default(),
+            maintenance_saved: Instant::now(),
+            maintenance_due: 0,
             fixture_manager: None,
             current_song_path: None,
             current_ctl_file: None,
             last_command_time: 0,
+            pyro_held: false,
             recent_commands: Vec::new(),
             dmx_map_panel: dmx_map_panel::DmxMapPanel::new(),
             light_group_panel: light_group_panel::LightGroupPanel::new(),
@@ -173,9 +210,13 @@ impl PlaybackApp {
         
         // Initialize systems
         app.initialize_audio();
+        app.initialize_csv_config(); // Before the PLC, which reads its water map, and the failsafe look
         app.initialize_dmx();
         app.initialize_plc();
-        app.initialize_csv_config();
+        app.initialize_wind();
+        app.initialize_failsafe();
+        failsafe::install_panic_hook();
+        app.initialize_maintenance();
         
         // Load fortune cookies
         app.load_fortunes();
@@ -215,9 +256,16 @@ impl PlaybackApp {
                 );
                 
                 // Set RGBW mode from settings
-                fixture_manager.set_rgbw_mode(self.settings.use_rgbw);
+                fixture_manager.set_white_strategy(WhiteStrategy::from_setting(&self.settings.white_strategy));
+                fixture_manager.set_default_fade_curve(
+                    FadeCurve::from_setting(&self.settings.fade_curve).unwrap_or(FadeCurve::Linear));
+                fixture_manager.set_idle_look(
+                    IdleLook::from_setting(&self.settings.idle_look), (self.settings.idle_fade_secs * 1000.0) as u64);
                 
                 self.fixture_manager = Some(Arc::new(Mutex::new(fixture_manager)));
+                self.water_map = config_arc.water.clone();
+                self.water_guard = WaterGuard::new(config_arc.water.clone());
+                self.water_guard.set_wind_limit(self.wind_monitor.limit());
                 self.csv_config = Some(config_arc);
                 
                 info!("Loaded CSV configuration from Config/");
@@ -229,74 +277,333 @@ impl PlaybackApp {
     }
     
     fn initialize_dmx(&mut self) {
+        // Release the current interface first so its port can be reopened
+        self.dmx_controller = None;
+        self.dmx_connected = false;
+        self.dmx_health = None;
+        
         if !self.settings.dmx_enabled {
             return;
         }
         
-        match EnttecDmxPro::new() {
-            Ok(controller) => {
-                self.dmx_controller = Some(Arc::new(Mutex::new(controller)));
-                self.dmx_connected = true;
-                info!("DMX controller initialized");
-            }
-            Err(e) => {
-                warn!("DMX initialization failed: {}", e);
-            }
-        }
+        // Keep a disconnected driver if the widget is missing so it can be
+        // plugged in later - send_dmx() reconnects on its own
+        let controller = DmxDevice::from_settings(&self.settings);
+        self.dmx_connected = controller.is_connected();
+        self.dmx_health = Some(controller.health());
+        self.dmx_controller = Some(Arc::new(Mutex::new(controller)));
+        self.arm_failsafe();
     }
     
     fn initialize_plc(&mut self) {
-        let plc = PlcClient::new(
-            self.settings.plc_enabled,
-            self.settings.plc_ip_address.clone(),
-            self.settings.plc_port,
-        );
+        let plc = PlcClient::from_settings(&self.settings, &self.water_map);
         
-        if self.settings.plc_enabled {
-            let plc_arc = Arc::new(plc);
-            let plc_clone = Arc::clone(&plc_arc);
-            
-            // Start persistent PLC connection and sender thread
+        // The sender owns the link: it connects, sends each CTL timestamp's
+        // commands as one batch and reconnects until this client is replaced.
+        // When the PLC is disabled it just logs what it would have sent.
+        if let Some(sender) = plc.take_sender() {
             std::thread::spawn(move || {
-                // Create runtime that lives for the entire thread
                 let rt = tokio::runtime::Builder::new_current_thread()
                     .enable_all()
                     .build()
                     .unwrap();
-                
-                // Run forever in this runtime
-                rt.block_on(async move {
-                    // Connect once
-                    if let Err(e) = plc_clone.connect(5000).await {
-                        tracing::warn!("PLC connection failed: {}", e);
-                        return;
-                    }
-                    
-                    tracing::info!("PLC connected, starting sender loop");
-                    
-                    // Keep sending queued commands every 100ms
-                    loop {
-                        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-                        
-                        if let Err(e) = plc_clone.send_queue().await {
-                            tracing::debug!("PLC send error: {}", e);
-                            // Try to reconnect
-                            tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-                            if let Err(e) = plc_clone.connect(5000).await {
-                                tracing::warn!("PLC reconnection failed: {}", e);
-                            } else {
-                                tracing::info!("PLC reconnected successfully");
-                            }
-                        }
-                    }
-                });
+                rt.block_on(sender.run());
             });
-            
-            self.plc_client = Some(plc_arc);
-            self.plc_status = PlcStatus::Disconnected; // Will update to Connected once connection succeeds
+        }
+        
+        self.plc_status = if self.settings.plc_enabled {
+            PlcStatus::Disconnected // Will update to Connected once connection succeeds
         } else {
-            self.plc_client = Some(Arc::new(plc));
-            self.plc_status = PlcStatus::Disabled;
+            PlcStatus::Disabled
+        };
+        self.plc_counts = PlcCounts::default();
+        self.plc_client = Some(Arc::new(plc));
+        self.arm_failsafe();
+    }
+    
+    /// Hand the panic hook the current PLC, DMX interface and failsafe look
+    fn arm_failsafe(&self) {
+        let look = FailsafeLook::from_setting(&self.settings.failsafe_look);
+        let white = WhiteStrategy::from_setting(&self.settings.white_strategy);
+        failsafe::arm(
+            self.plc_client.as_ref(),
+            self.dmx_controller.as_ref(),
+            self.settings.estop_sequence.clone(),
+            look.frame(self.csv_config.as_deref(), white),
+        );
+    }
+    
+    /// Arm the failsafe and start or stop the watchdog heartbeat
+    fn initialize_failsafe(&mut self) {
+        self.arm_failsafe();
+        
+        self.heartbeat = None;
+        if self.settings.watchdog_enabled {
+            match HeartbeatThread::start(self.settings.watchdog_port) {
+                Ok(heartbeat) => self.heartbeat = Some(heartbeat),
+                Err(e) => {
+                    warn!("Failed to start watchdog heartbeat: {}", e);
+                    self.set_status(&format!("Failed to start watchdog heartbeat: {}", e), StatusType::Warning);
+                }
+            }
+        }
+    }
+    
+    /// Stop the song, send the E-stop sequence ahead of anything queued and
+    /// switch the lights to the failsafe look
+    fn emergency_stop(&mut self) {
+        if let Some(player) = &self.audio_player {
+            if let Ok(player) = player.lock() {
+                player.stop();
+            }
+        }
+        self.is_playing = false;
+        self.is_paused = false;
+        self.playback_position = Duration::from_secs(0);
+        self.last_command_time = 0;
+        
+        if let Some(plc) = &self.plc_client {
+            plc.emergency_stop(&self.settings.estop_sequence);
+        }
+        self.water_guard.reset_now();
+        if let Some(fm) = &self.fixture_manager {
+            fm.lock().unwrap().disarm_pyro("E-stop");
+        }
+        
+        // Hold freezes whatever is showing now
+        let look = FailsafeLook::from_setting(&self.settings.failsafe_look);
+        let white = WhiteStrategy::from_setting(&self.settings.white_strategy);
+        self.estop_frame = look.frame(self.csv_config.as_deref(), white)
+            .or_else(|| self.render_show_frame())
+            .or_else(|| Some(DmxUniverse::new()));
+        self.send_dmx_frame();
+        
+        if let Some(heartbeat) = &self.heartbeat {
+            heartbeat.set(Heartbeat::Idle);
+        }
+        let sequence = self.settings.estop_sequence.join(" ");
+        warn!("E-stop: sent {}, lights to {}", sequence, look.as_setting());
+        self.recent_commands.push((0, format!("E-stop {}", sequence)));
+        self.set_status(&format!("E-STOP: sent {}", sequence), StatusType::Error);
+    }
+    
+    fn initialize_maintenance(&mut self) {
+        let path = PathBuf::from(&self.settings.maintenance_file);
+        match MaintenanceCounters::load(&path) {
+            Ok(counters) => self.maintenance = counters,
+            Err(e) => {
+                // Keep the unreadable file for the crew rather than overwrite it
+                let aside = path.with_extension("json.bad");
+                warn!("{:#}; moved to {:?} and counting from zero", e, aside);
+                let _ = std::fs::rename(&path, &aside);
+                self.set_status(&format!("Maintenance counters unreadable, moved to {}", aside.display()), StatusType::Warning);
+            }
+        }
+        self.check_service_due();
+    }
+    
+    /// Count what the PLC has sent and which fixtures are lit, saving now
+    /// and then
+    fn update_maintenance(&mut self) {
+        if let Some(plc) = &self.plc_client {
+            for (at, commands) in plc.take_written() {
+                for command in &commands {
+                    self.maintenance.record_water_at(command, at);
+                }
+            }
+        }
+        
+        let now = Instant::now();
+        let lit = match &self.fixture_manager {
+            Some(fm) if self.settings.dmx_enabled || self.settings.sacn_enabled => fm.lock().unwrap().lit_fixtures(),
+            _ => Vec::new(),
+        };
+        self.maintenance.set_lit_fixtures_at(&lit, now);
+        
+        if self.maintenance_saved.elapsed() >= Duration::from_secs(60) {
+            self.save_maintenance();
+            self.check_service_due();
+        }
+    }
+    
+    fn save_maintenance(&mut self) {
+        self.maintenance.tick_at(Instant::now());
+        self.maintenance_saved = Instant::now();
+        if !self.maintenance.is_dirty() {
+            return;
+        }
+        if let Err(e) = self.maintenance.save(&self.settings.maintenance_file) {
+            warn!("Failed to save maintenance counters: {:#}", e);
+            self.set_status(&format!("Failed to save maintenance counters: {}", e), StatusType::Warning);
+        }
+    }
+    
+    /// Report services that have come due since the last check
+    fn check_service_due(&mut self) {
+        let due: Vec<_> = maintenance::reminders(&self.maintenance, &self.settings.service_intervals)
+            .into_iter()
+            .filter(|reminder| reminder.status == ServiceStatus::Due)
+            .collect();
+        if due.len() > self.maintenance_due {
+            for reminder in &due {
+                info!("Maintenance: {}", reminder);
+            }
+            let more = if due.len() > 1 { format!(" (+{} more)", due.len() - 1) } else { String::new() };
+            self.set_status(&format!("Service {}{}", due[0], more), StatusType::Warning);
+        }
+        self.maintenance_due = due.len();
+    }
+    
+    fn export_maintenance_dialog(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name(format!("maintenance_{}.csv", chrono::Local::now().format("%Y-%m-%d")))
+            .save_file()
+        else {
+            return;
+        };
+        
+        self.maintenance.tick_at(Instant::now());
+        let water_map = &self.water_map;
+        let config = self.csv_config.as_deref();
+        let result = maintenance::export_csv(&path, &self.maintenance, &self.settings.service_intervals, |kind, address| {
+            match kind {
+                CounterKind::Water => water_map.device(address).map(|device| device.name.clone()),
+                CounterKind::Fixture => config.and_then(|config| config.get_fixture(address)).map(|fixture| fixture.note.clone()),
+            }
+            .unwrap_or_default()
+        });
+        match result {
+            Ok(()) => self.set_status(&format!("Maintenance counters exported to {}", path.display()), StatusType::Success),
+            Err(e) => self.set_status(&format!("Failed to export maintenance counters: {}", e), StatusType::Warning),
+        }
+    }
+    
+    /// Tell the heartbeat thread whether a song is in progress
+    fn send_heartbeat(&mut self) {
+        let Some(heartbeat) = &self.heartbeat else {
+            return;
+        };
+        let song_running = self.is_playing && !self.is_paused;
+        let mid_song = self.is_paused && !self.playback_position.is_zero();
+        heartbeat.set(if song_running || mid_song { Heartbeat::Playing } else { Heartbeat::Idle });
+    }
+    
+    fn initialize_wind(&mut self) {
+        self.wind_monitor = WindMonitor::new(
+            self.settings.wind_bands.clone(),
+            Duration::from_secs_f32(self.settings.wind_release_secs.max(0.0)),
+            Duration::from_secs_f32(self.settings.wind_stale_secs.max(1.0)),
+        );
+        self.water_guard.set_wind_limit(None);
+        self.operator_panel.wind_status = None;
+        
+        // Dropping the old receiver stops the old poller
+        self.wind_readings = None;
+        self.wind_error = None;
+        let kind = WindSourceKind::from_setting(&self.settings.wind_source);
+        let Some(source) = kind.build(&self.settings.wind_source_address) else {
+            return;
+        };
+        
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let interval = Duration::from_secs_f32(self.settings.wind_poll_secs.max(1.0));
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            rt.block_on(weather::poll(source, interval, tx));
+        });
+        self.wind_readings = Some(rx);
+        
+        if !self.wind_monitor.is_enabled() {
+            info!("Reading wind from {} with no wind bands; heights are not limited", kind.label());
+        }
+    }
+    
+    /// Feed new wind readings to the monitor and act on band changes
+    fn update_wind_state(&mut self) {
+        let Some(rx) = &self.wind_readings else {
+            return;
+        };
+        let readings: Vec<_> = rx.try_iter().collect();
+        
+        let now = Instant::now();
+        let mut alerts = Vec::new();
+        for reading in readings {
+            match reading {
+                Ok(reading) => {
+                    self.wind_error = None;
+                    alerts.extend(self.wind_monitor.update(reading, now));
+                }
+                // Only the first of a run of identical errors
+                Err(e) if self.wind_error.as_ref() != Some(&e) => {
+                    self.wind_error = Some(e.clone());
+                    alerts.push(WindAlert::SourceError(e));
+                }
+                Err(_) => {}
+            }
+        }
+        alerts.extend(self.wind_monitor.check_stale(now));
+        
+        for alert in alerts {
+            if alert.is_warning() {
+                warn!("{}", alert);
+                self.set_status(&alert.to_string(), StatusType::Warning);
+            } else {
+                info!("{}", alert);
+                self.set_status(&alert.to_string(), StatusType::Info);
+            }
+            
+            if matches!(alert, WindAlert::Raised { .. } | WindAlert::Lowered { .. }) {
+                self.water_guard.set_wind_limit(self.wind_monitor.limit());
+                self.lower_heights_for_wind();
+            }
+        }
+        
+        self.operator_panel.wind_status = self.wind_monitor.reading().map(|reading| {
+            match self.wind_monitor.band() {
+                Some(band) => (format!("Measured {} · {} limits", reading, band.name), true),
+                None => (format!("Measured {}", reading), false),
+            }
+        });
+    }
+    
+    /// Bring jets that are already running down to the new wind limit
+    fn lower_heights_for_wind(&mut self) {
+        let time_ms = self.playback_position.as_millis() as u64;
+        for checked in self.water_guard.wind_commands_now() {
+            let (Some(command), Some(detail)) = (checked.command(), checked.reason()) else {
+                continue;
+            };
+            let kind = PlcEventKind::Unsafe { command: command.to_string(), detail: detail.to_string() };
+            warn!("PLC: {}", kind);
+            self.operator_panel.push_plc_events(vec![PlcEvent::now(kind)], self.playback_position);
+            
+            if let Some(plc) = &self.plc_client {
+                plc.queue_command_sync(command.to_string());
+            }
+            self.recent_commands.push((time_ms, command.to_string()));
+            if self.recent_commands.len() > 100 {
+                self.recent_commands.remove(0);
+            }
+        }
+    }
+    
+    /// Send switch-offs the water guard held back until their dwell was
+    /// over, and the next steps of rate-limited ramps
+    fn send_held_water(&mut self) {
+        let time_ms = self.playback_position.as_millis() as u64;
+        for command in self.water_guard.due_commands_now() {
+            info!("Water guard: sending held {}", command);
+            if let Some(plc) = &self.plc_client {
+                plc.queue_command_sync(command.clone());
+            }
+            self.recent_commands.push((time_ms, command));
+            if self.recent_commands.len() > 100 {
+                self.recent_commands.remove(0);
+            }
         }
     }
     
@@ -426,6 +733,7 @@ impl PlaybackApp {
                                 let saved_pos = self.playback_panel_state.saved_position;
                                 if saved_pos > Duration::from_secs(0) {
                                     let _ = player.seek(saved_pos);
+                                    self.pyro_held = true;
                                 }
                                 
                                 // Resume if we were paused for announcement
@@ -474,6 +782,11 @@ impl PlaybackApp {
                         self.playback_position < song_duration + Duration::from_secs(2) // Prevent infinite detection
                 };
                 
+                // Starting a song again ends the E-stop look
+                if self.is_playing && !self.is_paused {
+                    self.estop_frame = None;
+                }
+                
                 // Check if we should execute commands
                 (self.is_playing && !self.is_paused, finished)
             } else {
@@ -535,6 +848,9 @@ impl PlaybackApp {
                             player.stop();
                         }
                     }
+                    if let Some(fm) = &self.fixture_manager {
+                        fm.lock().unwrap().disarm_pyro("end of show");
+                    }
                     self.current_song = "Show Completed".to_string();
                     self.is_playing = false;
                     self.is_paused = false;
@@ -545,6 +861,14 @@ impl PlaybackApp {
             }
         }
         
+        // Fire channels only follow a song that is playing straight through
+        if std::mem::take(&mut self.playback_panel_state.seeked) {
+            self.hold_pyro();
+        }
+        if let Some(fm) = &self.fixture_manager {
+            fm.lock().unwrap().set_pyro_live(should_execute_commands && !self.pyro_held);
+        }
+        
         // Execute commands if playing (after releasing player lock)
         if should_execute_commands {
             let current_time_ms = self.playback_position.as_millis() as u64;
@@ -557,54 +881,219 @@ impl PlaybackApp {
                 self.execute_commands_at_time(current_time_ms);
                 self.last_command_time = current_time_ms;
             }
+            // Commands at the new position have run; fire goes live next tick
+            self.pyro_held = false;
+        }
+    }
+    
+    /// Take the fire channels out of live after a seek, step or song load
+    fn hold_pyro(&mut self) {
+        self.pyro_held = true;
+        if let Some(fm) = &self.fixture_manager {
+            fm.lock().unwrap().set_pyro_live(false);
         }
     }
     
     fn update_dmx_state(&mut self) {
         if self.dmx_last_update.elapsed() > Duration::from_millis(50) {
-            // Update fades first
+            // Update fades first; the idle look runs while no song is in progress
+            let song_running = self.is_playing && !self.is_paused;
+            let mid_song = self.is_paused && !self.playback_position.is_zero();
+            let mut pyro_events = Vec::new();
             if let Some(fm) = &self.fixture_manager {
-                fm.lock().unwrap().update_fades();
+                let mut fm = fm.lock().unwrap();
+                fm.set_idle_wanted_at(!song_running && !mid_song, Instant::now());
+                fm.update_fades();
+                pyro_events = fm.take_pyro_events();
+            }
+            if !pyro_events.is_empty() {
+                let position_ms = self.playback_position.as_millis() as u64;
+                if let Err(e) = pyro::append_audit(&self.settings.pyro_audit_file, &pyro_events, &self.current_song, position_ms) {
+                    warn!("Failed to write pyro audit log: {}", e);
+                    self.set_status(&format!("Failed to write pyro audit log: {}", e), StatusType::Warning);
+                }
             }
             
             // Send updated DMX with interpolated fade values
-            if let Some(dmx) = &self.dmx_controller {
-                if let Some(fm) = &self.fixture_manager {
-                    let mut universe = DmxUniverse::new();
-                    let fm_lock = fm.lock().unwrap();
-                    
-                    if let Err(e) = fm_lock.apply_to_dmx(&mut universe) {
-                        warn!("Failed to apply to DMX during update: {}", e);
-                    } else {
-                        drop(fm_lock);
-                        
-                        if let Ok(mut dmx) = dmx.lock() {
-                            // Copy universe data to DMX controller
-                            for (i, val) in universe.as_slice().iter().enumerate() {
-                                if let Err(e) = dmx.set_channel(i + 1, *val) {
-                                    warn!("Failed to set DMX channel {} during update: {}", i + 1, e);
-                                    break;
-                                }
-                            }
-                            
-                            let _ = dmx.send_dmx();
-                        }
-                        
-                        // Send via sACN if enabled
-                        if let Ok(mut sacn) = self.sacn_output.lock() {
-                            if sacn.is_active() {
-                                // For 900 codes only mode, we need fixture IDs >= 900
-                                let fixture_900_ids: Vec<usize> = Vec::new(); // Will be populated from fixture manager
-                                if let Err(e) = sacn.send_dmx(&universe, &fixture_900_ids) {
-                                    warn!("Failed to send sACN data: {}", e);
-                                }
-                            }
-                        }
+            self.send_dmx_frame();
+            
+            self.dmx_last_update = Instant::now();
+        }
+    }
+    
+    /// Render the fixture manager, merge console input and send to all DMX outputs
+    fn send_dmx_frame(&mut self) {
+        let universe = match self.estop_frame.clone().or_else(|| self.next_replay_frame()) {
+            Some(universe) => universe,
+            None => match self.render_show_frame() {
+                Some(universe) => universe,
+                None => return,
+            },
+        };
+        
+        if let Some(dmx) = &self.dmx_controller {
+            if let Ok(mut dmx) = dmx.lock() {
+                // Copy universe data to DMX controller
+                if let Err(e) = dmx.set_channels(1, universe.as_slice()) {
+                    warn!("Failed to set DMX channels: {}", e);
+                } else {
+                    match dmx.send_dmx() {
+                        // Widget unplugged - the driver keeps retrying, see health
+                        Ok(_) | Err(DmxError::DeviceNotFound) => {}
+                        Err(e) => warn!("Failed to send DMX: {}", e),
                     }
                 }
+                let health = dmx.health();
+                self.dmx_connected = health.connected;
+                self.dmx_health = Some(health);
+            }
+        }
+        
+        // Send via sACN if enabled
+        if let Ok(mut sacn) = self.sacn_output.lock() {
+            if sacn.is_active() {
+                // For 900 codes only mode, we need fixture IDs >= 900
+                let fixture_900_ids: Vec<usize> = Vec::new(); // Will be populated from fixture manager
+                if let Err(e) = sacn.send_dmx(&universe, &fixture_900_ids) {
+                    warn!("Failed to send sACN data: {}", e);
+                }
+            }
+        }
+    }
+    
+    /// Build the live output frame: fixture manager merged with console input
+    fn render_show_frame(&mut self) -> Option<DmxUniverse> {
+        let fm = self.fixture_manager.as_ref()?;
+        
+        let mut universe = DmxUniverse::new();
+        let blackout = {
+            let fm = fm.lock().unwrap();
+            if let Err(e) = fm.apply_to_dmx(&mut universe) {
+                warn!("Failed to apply to DMX: {}", e);
+                return None;
             }
             
-            self.dmx_last_update = Instant::now();
+            // Correction preview from the DMX Map panel overrides the show,
+            // but not the safety blackout
+            let blackout = fm.is_safety_blackout();
+            if self.sidebar.selected_view == sidebar::AppView::SettingsDmxMap && !blackout {
+                if let Some(([r, g, b], fixtures)) = self.dmx_map_panel.preview_request() {
+                    for fixture_num in fixtures {
+                        if let Some((channel, values)) = fm.render_test_color(fixture_num, r, g, b) {
+                            let _ = universe.set_channels(channel, &values);
+                        }
+                    }
+                }
+            }
+            blackout
+        };
+        
+        // Merge console input for the configured channel ranges; the safety
+        // blackout is above the console as well as the show
+        let console_frame = match self.dmx_input.lock() {
+            Ok(input) if input.is_active() && !blackout => {
+                input.latest_frame(Duration::from_millis(self.settings.dmx_input_timeout_ms))
+            }
+            _ => None,
+        };
+        let mut universe = self.dmx_merger.merge(&universe, console_frame.as_ref());
+        self.apply_pyro_interlock(&mut universe);
+        
+        // Capture the frame exactly as it goes to the outputs
+        if let Some(recorder) = &mut self.dmx_recorder {
+            if let Err(e) = recorder.record(&universe) {
+                warn!("DMX recording stopped: {}", e);
+                self.dmx_recorder = None;
+                self.settings.dmx_recording_enabled = false;
+                self.set_status(&format!("DMX recording stopped: {}", e), StatusType::Warning);
+            }
+        }
+        
+        Some(universe)
+    }
+    
+    /// Frame from the capture being replayed, if any
+    fn next_replay_frame(&mut self) -> Option<DmxUniverse> {
+        let (recording, started) = self.dmx_replay.as_ref()?;
+        let elapsed_ms = started.elapsed().as_millis() as u32;
+        
+        if elapsed_ms > recording.duration_ms() {
+            self.dmx_replay = None;
+            self.set_status("DMX capture replay finished", StatusType::Info);
+            return None;
+        }
+        
+        let frame = recording.frame_at(elapsed_ms)?;
+        let mut universe = DmxUniverse::new();
+        universe.as_mut_slice().copy_from_slice(&frame.data);
+        // The safety blackout and fire interlock stay above the capture
+        if let Some(fm) = &self.fixture_manager {
+            if let Err(e) = fm.lock().unwrap().apply_to_replay(&mut universe) {
+                warn!("Failed to apply output layers to replay: {}", e);
+            }
+        }
+        Some(universe)
+    }
+    
+    /// Force the fire channels back to what the interlock allows, over
+    /// whatever the console put there
+    fn apply_pyro_interlock(&self, universe: &mut DmxUniverse) {
+        if let Some(fm) = &self.fixture_manager {
+            if let Err(e) = fm.lock().unwrap().apply_pyro_to_dmx(universe) {
+                warn!("Failed to apply pyro interlock: {}", e);
+            }
+        }
+    }
+    
+    /// Start or stop the DMX capture file based on settings
+    fn update_dmx_recording_state(&mut self) {
+        let enabled = self.settings.dmx_recording_enabled;
+        
+        if enabled && self.dmx_recorder.is_none() {
+            let file_name = format!("dmx_{}.dmxrec", chrono::Local::now().format("%Y%m%d_%H%M%S"));
+            let path = PathBuf::from(&self.settings.dmx_recordings_folder).join(file_name);
+            match DmxRecorder::create(&path, "Fountain Director") {
+                Ok(mut recorder) => {
+                    if self.current_song_path.is_some() {
+                        let _ = recorder.mark(&self.current_song);
+                    }
+                    self.dmx_recorder = Some(recorder);
+                    self.set_status(&format!("Recording DMX to {}", path.display()), StatusType::Info);
+                }
+                Err(e) => {
+                    warn!("Failed to start DMX recording: {}", e);
+                    self.settings.dmx_recording_enabled = false;
+                    self.set_status(&format!("Failed to start DMX recording: {}", e), StatusType::Warning);
+                }
+            }
+        } else if !enabled {
+            if let Some(recorder) = self.dmx_recorder.take() {
+                if let Err(e) = recorder.finish() {
+                    warn!("Failed to save DMX recording: {}", e);
+                }
+            }
+        }
+    }
+    
+    fn open_replay_dialog(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("DMX Capture", &["dmxrec"])
+            .set_directory(&self.settings.dmx_recordings_folder)
+            .pick_file()
+        {
+            match DmxRecording::load(&path) {
+                Ok(recording) => {
+                    info!("Replaying DMX capture {} ({} frames)", path.display(), recording.frame_count());
+                    self.set_status(
+                        &format!("Replaying {} ({:.1}s)", path.display(), recording.duration_ms() as f32 / 1000.0),
+                        StatusType::Info
+                    );
+                    self.dmx_replay = Some((recording, Instant::now()));
+                }
+                Err(e) => {
+                    self.set_status(&format!("Failed to load capture: {}", e), StatusType::Warning);
+                }
+            }
         }
     }
     
@@ -617,29 +1106,13 @@ impl PlaybackApp {
         self.plc_last_status_check = Instant::now();
         
         if let Some(plc) = &self.plc_client {
-            let plc_clone = Arc::clone(plc);
+            self.plc_status = plc.status();
             
-            // Spawn a quick thread to get status without blocking UI
-            let (tx, rx) = std::sync::mpsc::channel();
-            std::thread::spawn(move || {
-                let rt = tokio::runtime::Builder::new_current_thread()
-                    .enable_all()
-                    .build()
-                    .unwrap();
-                
-                let status = rt.block_on(async {
-                    plc_clone.status().await
-                });
-                
-                let _ = tx.send(status);
-            });
-            
-            // Give it a tiny bit of time to respond (1ms)
-            std::thread::sleep(Duration::from_millis(1));
-            
-            // Try to receive the status
-            if let Ok(status) = rx.try_recv() {
-                self.plc_status = status;
+            // Acks, faults and link changes for the operator's PLC log
+            let events = plc.take_events();
+            self.plc_counts = plc.counters();
+            if !events.is_empty() {
+                self.operator_panel.push_plc_events(events, self.playback_position);
             }
         }
     }
@@ -697,6 +1170,48 @@ impl PlaybackApp {
         }
     }
     
+    /// Update network DMX input (console takeover) based on settings
+    fn update_dmx_input_state(&mut self) {
+        self.dmx_merger.set_ranges(self.settings.dmx_merge_ranges.clone());
+        
+        let enabled = self.settings.dmx_input_enabled;
+        let protocol = InputProtocol::from_setting(&self.settings.dmx_input_protocol);
+        let interface_ip = self.settings.dmx_input_interface_ip.clone();
+        let universe = self.settings.dmx_input_universe;
+        
+        let mut status_message: Option<(String, StatusType)> = None;
+        
+        if let Ok(mut input) = self.dmx_input.lock() {
+            let settings_changed = input.protocol() != protocol
+                || input.interface_ip() != interface_ip
+                || input.universe() != universe;
+            
+            if enabled && (!input.is_active() || settings_changed) {
+                // Only retry a failed start when the settings change
+                if input.is_active() || self.dmx_input_failed_config != Some((protocol, interface_ip.clone(), universe)) {
+                    match input.start(protocol, &interface_ip, universe) {
+                        Ok(_) => {
+                            self.dmx_input_failed_config = None;
+                            status_message = Some((format!("{} input enabled (universe {})", protocol.label(), universe), StatusType::Success));
+                        }
+                        Err(e) => {
+                            warn!("Failed to start DMX input: {}", e);
+                            self.dmx_input_failed_config = Some((protocol, interface_ip, universe));
+                            status_message = Some((format!("Failed to start DMX input: {}", e), StatusType::Warning));
+                        }
+                    }
+                }
+            } else if !enabled && input.is_active() {
+                input.stop();
+                status_message = Some(("DMX input disabled".to_string(), StatusType::Info));
+            }
+        }
+        
+        if let Some((message, status_type)) = status_message {
+            self.set_status(&message, status_type);
+        }
+    }
+    
     fn open_song_dialog(&mut self) {
         // Spawn file dialog in background to avoid blocking UI
         if let Some(path) = rfd::FileDialog::new()
@@ -713,9 +1228,7 @@ impl PlaybackApp {
         tracing::info!("Resetting lighting system: sending 099-000 and resetting all fixtures");
         
         // Send 099-000 to PLC
-        if let Some(plc) = &self.plc_client {
-            plc.queue_command_sync("099-000".to_string());
-        }
+        self.queue_water(None, "099-000");
         
         // Reset all fixtures to black
         if let Some(fixture_manager) = &self.fixture_manager {
@@ -725,9 +1238,35 @@ impl PlaybackApp {
         }
     }
     
+    /// Queue a water command after the water guard has checked it
+    ///
+    /// Returns the command as queued, which may be clamped, or None when the
+    /// guard held it back. Anything the guard changed goes to the PLC log.
+    fn queue_water(&mut self, time_ms: Option<u64>, command: &str) -> Option<String> {
+        // Anything held that is due goes ahead of the new command
+        self.send_held_water();
+        let checked = self.water_guard.check_now(command);
+        if let Some(detail) = checked.reason() {
+            let kind = PlcEventKind::Unsafe { command: command.trim().to_string(), detail: detail.to_string() };
+            warn!("PLC: {}", kind);
+            self.operator_panel.push_plc_events(vec![PlcEvent::now(kind)], self.playback_position);
+        }
+        
+        let sent = checked.command()?.to_string();
+        if let Some(plc) = &self.plc_client {
+            match time_ms {
+                Some(time_ms) => plc.queue_command_at(time_ms, sent.clone()),
+                None => plc.queue_command_sync(sent.clone()),
+            }
+        }
+        Some(sent)
+    }
+    
     fn load_song(&mut self, song_path: PathBuf) {
         // Reset lighting system when loading a new song
         self.reset_lighting_system();
+        self.hold_pyro();
+        self.estop_frame = None;
         
         // Check for corresponding .ctl file
         let ctl_path = song_path.with_extension("ctl");
@@ -744,6 +1283,18 @@ impl PlaybackApp {
             Ok(ctl_file) => {
                 info!("Loaded CTL file: {} ({} command lines)", 
                     ctl_path.display(), ctl_file.lines.len());
+                
+                // Flag water cues the guard will clamp or skip during playback
+                let warnings = commands::check_ctl(&ctl_file, &self.water_map);
+                for warning in &warnings {
+                    warn!("Water guard: {}", warning);
+                }
+                if !warnings.is_empty() {
+                    self.set_status(
+                        &format!("{} water cues break device limits; see the log", warnings.len()),
+                        StatusType::Warning
+                    );
+                }
                 self.current_ctl_file = Some(ctl_file);
             }
             Err(e) => {
@@ -759,6 +1310,10 @@ impl PlaybackApp {
             .to_string();
         self.current_song_path = Some(song_path.clone());
         
+        if let Some(recorder) = &mut self.dmx_recorder {
+            let _ = recorder.mark(&self.current_song);
+        }
+        
         // Load audio file into player (but pause immediately - don't auto-play)
         let mut audio_loaded = false;
         let mut audio_error = None;
@@ -807,142 +1362,38 @@ impl PlaybackApp {
             None => return,
         };
         
-        // Get commands for this time window
-        let commands = ctl_file.get_commands_at_time(time_ms);
-        
-        if commands.is_empty() {
-            return;
-        }
-        
-        // Collect command descriptions for logging (only lighting commands)
-        let mut cmd_descriptions = Vec::new();
-        
-        // Execute each command - handle fades specially
-        let mut i = 0;
-        while i < commands.len() {
-            let cmd = &commands[i];
-            let mut fm = fixture_manager.lock().unwrap();
-            
-            // Format as raw CTL format: "XXX-YYY" 
-            let cmd_desc = if cmd.is_hex_color {
-                format!("{:03}-{}", cmd.fcw_address, cmd.hex_color.as_ref().unwrap_or(&"???".to_string()))
-            } else {
-                format!("{:03}-{:03}", cmd.fcw_address, cmd.data)
-            };
-            
-            // Check if this is a water command (for PLC routing)
-            let is_water = Self::is_water_command(cmd.fcw_address);
-            
-            // Queue water commands to PLC (synchronous, non-blocking)
-            if is_water {
-                if let Some(plc) = &self.plc_client {
-                    plc.queue_command_sync(cmd_desc.clone());
-                }
-            }
-            
-            // Add to recent commands (keep last 100 for better history)
-            self.recent_commands.push((time_ms, cmd_desc.clone()));
-            if self.recent_commands.len() > 100 {
-                self.recent_commands.remove(0);
-            }
-            
-            // Only collect lighting commands for file logging (skip water/PLC commands)
-            if !is_water {
-                cmd_descriptions.push(cmd_desc.clone());
-            }
-            
-            // Check if this is a fade command
-            let is_fade_command = Self::is_fade_command(cmd.fcw_address);
-            
-            if is_fade_command && i + 1 < commands.len() {
-                // This is a fade command - next command contains the target color
-                let fade_duration_ms = cmd.data as u64 * 100; // data is in tenths of seconds
-                let target_cmd = &commands[i + 1];
-                
-                // Get the base address
-                // 100-199 fades regular FCW addresses (100 -> 0, 117 -> 17, etc.)
-                // 600-699 fades individual fixtures in 500-series (604 -> 504, 617 -> 517, etc.)
-                let base_address = cmd.fcw_address - 100;
-                
-                // Format target command for logging
-                let target_cmd_desc = if target_cmd.is_hex_color {
-                    format!("{:03}-{}", target_cmd.fcw_address, target_cmd.hex_color.as_ref().unwrap_or(&"???".to_string()))
-                } else {
-                    format!("{:03}-{:03}", target_cmd.fcw_address, target_cmd.data)
-                };
-                
-                // Add target command to recent commands
-                self.recent_commands.push((time_ms, target_cmd_desc.clone()));
-                if self.recent_commands.len() > 100 {
-                    self.recent_commands.remove(0);
-                }
-                
-                // Only add to file log if not a water command
-                if !is_water {
-                    cmd_descriptions.push(target_cmd_desc);
-                }
-                
-                // Execute the fade based on target command type
-                let result = if target_cmd.is_hex_color {
-                    if let Some(hex) = &target_cmd.hex_color {
-                        // Parse hex color for fade
-                        let hex_clean = hex.trim_start_matches('#');
-                        if hex_clean.len() == 6 {
-                            if let (Ok(r), Ok(g), Ok(b)) = (
-                                u8::from_str_radix(&hex_clean[0..2], 16),
-                                u8::from_str_radix(&hex_clean[2..4], 16),
-                                u8::from_str_radix(&hex_clean[4..6], 16),
-                            ) {
-                                fm.start_fade(base_address, r, g, b, fade_duration_ms)
-                            } else {
-                                Err(anyhow::anyhow!("Invalid hex color"))
-                            }
-                        } else {
-                            Err(anyhow::anyhow!("Invalid hex color length"))
-                        }
-                    } else {
-                        Err(anyhow::anyhow!("Missing hex color"))
-                    }
-                } else {
-                    // Regular color index fade
-                    // Get color from color map
-                    if let Some(color) = fm.config.get_color(target_cmd.data) {
-                        if let Ok((r, g, b)) = color.to_rgb() {
-                            fm.start_fade(base_address, r, g, b, fade_duration_ms)
-                        } else {
-                            Err(anyhow::anyhow!("Color conversion failed"))
-                        }
-                    } else {
-                        Err(anyhow::anyhow!("Color not found"))
-                    }
-                };
-                
-                if let Err(e) = result {
-                    warn!("Fade command execution error: {}", e);
+        // Get commands for this time window
+        let commands = ctl_file.get_commands_at_time(time_ms);
+        
+        if commands.is_empty() {
+            return;
+        }
+        
+        // Execute the lighting side; fades consume the following target command
+        let dispatched = commands::dispatch_commands(&mut fixture_manager.lock().unwrap(), &commands, Instant::now());
+        
+        // Collect command descriptions for logging (only lighting commands)
+        let mut cmd_descriptions = Vec::new();
+        
+        for mut cmd in dispatched {
+            // Queue water commands to PLC (synchronous, non-blocking)
+            if cmd.is_water {
+                match self.queue_water(Some(time_ms), &cmd.text) {
+                    Some(sent) => cmd.text = sent,
+                    None => continue,
                 }
-                
-                // Skip the next command since we consumed it as the fade target
-                i += 2;
-                continue;
             }
             
-            // Regular (non-fade) command execution
-            let result = if cmd.is_hex_color {
-                if let Some(hex) = &cmd.hex_color {
-                    fm.execute_hex_command(cmd.fcw_address, hex)
-                } else {
-                    i += 1;
-                    continue;
-                }
-            } else {
-                fm.execute_fcw_command(cmd.fcw_address, cmd.data)
-            };
-            
-            if let Err(e) = result {
-                warn!("Command execution error: {}", e);
+            // Add to recent commands (keep last 100 for better history)
+            self.recent_commands.push((time_ms, cmd.text.clone()));
+            if self.recent_commands.len() > 100 {
+                self.recent_commands.remove(0);
             }
             
-            i += 1;
+            // Only collect lighting commands for file logging (skip water/PLC commands)
+            if !cmd.is_water {
+                cmd_descriptions.push(cmd.text);
+            }
         }
         
         // Update active fades
@@ -969,53 +1420,12 @@ impl PlaybackApp {
                 .and_then(|mut f| std::io::Write::write_all(&mut f, log_entry.as_bytes()));
         }
         
-        // Send to DMX if controller is available
-        if let Some(dmx) = &self.dmx_controller {
-            if let Some(fm) = &self.fixture_manager {
-                let mut universe = DmxUniverse::new();
-                let fm = fm.lock().unwrap();
-                
-                if let Err(e) = fm.apply_to_dmx(&mut universe) {
-                    warn!("Failed to apply to DMX: {}", e);
-                    return;
-                }
-                drop(fm);
-                
-                let mut dmx = dmx.lock().unwrap();
-                // Copy universe data to DMX controller
-                for (i, val) in universe.as_slice().iter().enumerate() {
-                    if let Err(e) = dmx.set_channel(i + 1, *val) {
-                        warn!("Failed to set DMX channel {}: {}", i + 1, e);
-                        break;
-                    }
-                }
-                
-                if let Err(e) = dmx.send_dmx() {
-                    warn!("Failed to send DMX: {}", e);
-                }
-            }
-        }
+        // Send to DMX outputs
+        self.send_dmx_frame();
         
         // PLC sending is handled by background thread - commands are already queued
     }
     
-    /// Check if FCW address is a water command (based on light_groups.json water directives)
-    fn is_water_command(fcw_address: u16) -> bool {
-        match fcw_address {
-            1..=13 | 33..=40 | 47..=48 | 87..=91 | 99 | 217..=223 | 249..=255 | 700..=749 => true,
-            _ => false,
-        }
-    }
-    
-    fn is_fade_command(fcw_address: u16) -> bool {
-        // Fade commands are base address + 100 (e.g., 17->117, 18->118)
-        // Or hex color fades are 600 series (e.g., 507->607)
-        match fcw_address {
-            100..=199 | 600..=699 => true,
-            _ => false,
-        }
-    }
-    
     // View rendering methods
     fn show_operator_view(&mut self, ctx: &Context, ui: &mut egui::Ui) {
         // Operator mode - New comprehensive operator interface
@@ -1042,6 +1452,7 @@ impl PlaybackApp {
             &mut self.playback_panel_state,
             &self.current_song_path,
             &self.recent_commands,
+            &self.water_map,
             self.fixture_manager.as_ref(),
         );
         
@@ -1057,6 +1468,8 @@ impl PlaybackApp {
             });
             
             if let Some((next_time_ms, command_count)) = next_step {
+                self.hold_pyro();
+                
                 // Execute commands at that timestamp
                 self.execute_commands_at_time(next_time_ms);
                 
@@ -1082,8 +1495,8 @@ impl PlaybackApp {
         if let Some(song_index) = clicked_song_index {
             if let Some(song_path) = self.operator_panel.jump_to_song(song_index) {
                 // Send 099-000 to PLC when manually selecting a song
-                if let Some(ref plc) = self.plc_client {
-                    plc.queue_command_sync("099-000\r\n".to_string());
+                if self.plc_client.is_some() {
+                    self.queue_water(None, "099-000");
                     info!("Sent 099-000 to PLC for manual song selection");
                 }
                 
@@ -1171,6 +1584,7 @@ impl PlaybackApp {
                 command_panel::show(
                     ui,
                     &self.recent_commands,
+                    &self.water_map,
                     self.playback_position.as_millis() as u64,
                     self.current_ctl_file.is_some(),
                 );
@@ -1190,6 +1604,7 @@ impl PlaybackApp {
                 &mut self.playback_panel_state,
                 &self.current_song_path,
                 &self.recent_commands,
+                &self.water_map,
             );
             
             // File operations section
@@ -1225,13 +1640,10 @@ impl PlaybackApp {
             .default_width(350.0)
             .resizable(true)
             .show_inside(ui, |ui| {
-                lighting_panel::show(
+                self.lighting_panel.show(
                     ui,
-                    &mut self.selected_fixture,
-                    &mut self.fixture_red,
-                    &mut self.fixture_green,
-                    &mut self.fixture_blue,
-                    &self.dmx_controller,
+                    &self.fixture_manager,
+                    self.light_group_panel.groups(),
                 );
             });
         
@@ -1285,9 +1697,35 @@ impl PlaybackApp {
                                         .strong()
                                         .color(status_color)
                                 );
+
+                                // Widget details read from the Pro at connect
+                                let widget_info = self.dmx_controller.as_ref()
+                                    .and_then(|dmx| dmx.lock().ok().and_then(|dmx| dmx.widget_info()));
+                                if let Some(info) = widget_info {
+                                    ui.add_space(6.0);
+                                    ui.label(
+                                        egui::RichText::new(format!(
+                                            "Serial {} • Firmware {}.{}",
+                                            info.serial_number.as_deref().unwrap_or("unknown"),
+                                            info.firmware_version >> 8,
+                                            info.firmware_version & 0xFF
+                                        ))
+                                            .size(12.0)
+                                            .color(theme::AppColors::TEXT_DISABLED)
+                                    );
+                                    ui.label(
+                                        egui::RichText::new(format!(
+                                            "Break {:.0}µs • MAB {:.0}µs",
+                                            info.break_time_us,
+                                            info.mab_time_us
+                                        ))
+                                            .size(12.0)
+                                            .color(theme::AppColors::TEXT_DISABLED)
+                                    );
+                                }
                             });
                         });
-                    
+
                     // PLC Status Card
                     egui::Frame::none()
                         .fill(theme::AppColors::SURFACE)
@@ -1313,6 +1751,18 @@ impl PlaybackApp {
                                         .strong()
                                         .color(status_color)
                                 );
+                                ui.label(
+                                    egui::RichText::new(format!(
+                                        "Sent {}  ·  Dropped {}  ·  Retried {}",
+                                        self.plc_counts.sent, self.plc_counts.dropped, self.plc_counts.retried,
+                                    ))
+                                        .size(12.0)
+                                        .color(if self.plc_counts.dropped > 0 {
+                                            theme::AppColors::WARNING
+                                        } else {
+                                            theme::AppColors::TEXT_SECONDARY
+                                        })
+                                );
                             });
                         });
                     
@@ -1363,9 +1813,87 @@ impl PlaybackApp {
                     }
                 }
             });
+            
+            ui.add_space(30.0);
+            
+            // DMX capture recording and replay
+            ui.heading("DMX Capture");
+            ui.add_space(10.0);
+            
+            ui.horizontal(|ui| {
+                let record_label = if self.dmx_recorder.is_some() { "⏹ Stop Recording" } else { "⏺ Record DMX" };
+                if ui.button(egui::RichText::new(record_label).size(14.0)).clicked() {
+                    self.settings.dmx_recording_enabled = self.dmx_recorder.is_none();
+                }
+                
+                if self.dmx_replay.is_some() {
+                    if ui.button(egui::RichText::new("⏹ Stop Replay").size(14.0)).clicked() {
+                        self.dmx_replay = None;
+                        self.set_status("DMX capture replay stopped", StatusType::Info);
+                    }
+                } else if ui.button(egui::RichText::new("▶ Replay Capture...").size(14.0)).clicked() {
+                    self.open_replay_dialog();
+                }
+            });
+            
+            ui.add_space(8.0);
+            if let Some(recorder) = &self.dmx_recorder {
+                ui.label(
+                    egui::RichText::new(format!(
+                        "Recording to {} • {} frames",
+                        recorder.path().display(),
+                        recorder.frames_written()
+                    ))
+                        .size(12.0)
+                        .color(theme::AppColors::ERROR)
+                );
+            }
+            if let Some((recording, started)) = &self.dmx_replay {
+                let elapsed_ms = started.elapsed().as_millis() as u32;
+                let mut text = format!(
+                    "Replaying {:.1}s / {:.1}s - show output is paused",
+                    elapsed_ms as f32 / 1000.0,
+                    recording.duration_ms() as f32 / 1000.0
+                );
+                if let Some(marker) = recording.marker_at(elapsed_ms) {
+                    text.push_str(&format!(" • {}", marker));
+                }
+                ui.label(
+                    egui::RichText::new(text)
+                        .size(12.0)
+                        .color(theme::AppColors::WARNING)
+                );
+            }
+            
+            ui.add_space(30.0);
+            
+            // Saved looks
+            self.scene_panel.show(
+                ui,
+                self.fixture_manager.as_ref(),
+                &self.water_guard,
+                self.playback_position.as_millis() as u64,
+            );
         });
     }
     
+    /// Send water commands from recalled scenes to the PLC
+    fn queue_scene_water(&mut self) {
+        let time_ms = self.playback_position.as_millis() as u64;
+        let water = self.scene_panel.take_water_commands().into_iter()
+            .chain(self.operator_panel.scenes.take_water_commands());
+        
+        for text in water.collect::<Vec<_>>() {
+            let Some(text) = self.queue_water(Some(time_ms), &text) else {
+                continue;
+            };
+            self.recent_commands.push((time_ms, text));
+            if self.recent_commands.len() > 100 {
+                self.recent_commands.remove(0);
+            }
+        }
+    }
+    
     fn show_playlist_view(&mut self, _ctx: &Context, ui: &mut egui::Ui) {
         egui::ScrollArea::vertical().show(ui, |ui| {
             ui.add_space(20.0);
@@ -1429,41 +1957,185 @@ impl PlaybackApp {
                     );
                     ui.add_space(8.0);
                     ui.label(
-                        egui::RichText::new("Control DMX lighting via Enttec USB Pro")
+                        egui::RichText::new("Control DMX lighting via an Enttec USB interface")
                             .size(13.0)
                             .color(theme::AppColors::TEXT_SECONDARY)
                     );
                     
                     ui.add_space(20.0);
                     ui.label(
-                        egui::RichText::new("Fixture Mode:")
+                        egui::RichText::new("Interface:")
+                            .size(14.0)
+                            .color(Color32::WHITE)
+                    );
+                    ui.add_space(5.0);
+                    let device_kind = DmxDeviceKind::from_setting(&self.settings.dmx_device);
+                    egui::ComboBox::from_id_salt("dmx_device")
+                        .selected_text(device_kind.label())
+                        .width(300.0)
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.settings.dmx_device, "pro".to_string(), DmxDeviceKind::Pro.label());
+                            ui.selectable_value(&mut self.settings.dmx_device, "open".to_string(), DmxDeviceKind::Open.label());
+                        });
+                    
+                    ui.add_space(15.0);
+                    ui.label(
+                        egui::RichText::new("Serial Port:")
+                            .size(14.0)
+                            .color(Color32::WHITE)
+                    );
+                    ui.add_space(5.0);
+                    egui::ComboBox::from_id_salt("dmx_port_name")
+                        .selected_text(if self.settings.dmx_port_name.is_empty() {
+                            "Auto-detect"
+                        } else {
+                            &self.settings.dmx_port_name
+                        })
+                        .width(300.0)
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.settings.dmx_port_name, String::new(), "Auto-detect");
+                            // Only enumerate while the list is open
+                            let ports: Vec<String> = serialport::available_ports()
+                                .map(|ports| ports.into_iter().map(|p| p.port_name).collect())
+                                .unwrap_or_default();
+                            for port in ports {
+                                ui.selectable_value(&mut self.settings.dmx_port_name, port.clone(), port);
+                            }
+                        });
+                    
+                    if device_kind == DmxDeviceKind::Open {
+                        ui.add_space(15.0);
+                        ui.horizontal(|ui| {
+                            ui.label(
+                                egui::RichText::new("Break (µs):")
+                                    .size(14.0)
+                                    .color(Color32::WHITE)
+                            );
+                            ui.add(egui::DragValue::new(&mut self.settings.open_dmx_break_us).range(92..=1000));
+                            ui.add_space(20.0);
+                            ui.label(
+                                egui::RichText::new("MAB (µs):")
+                                    .size(14.0)
+                                    .color(Color32::WHITE)
+                            );
+                            ui.add(egui::DragValue::new(&mut self.settings.open_dmx_mab_us).range(12..=1000));
+                        });
+                        ui.add_space(6.0);
+                        ui.label(
+                            egui::RichText::new("Open DMX timing is generated by this computer - lengthen the break if fixtures flicker")
+                                .size(12.0)
+                                .color(theme::AppColors::TEXT_DISABLED)
+                        );
+                    }
+                    
+                    ui.add_space(15.0);
+                    ui.checkbox(&mut self.settings.dmx_recording_enabled, 
+                        egui::RichText::new("Record DMX output to capture files")
+                            .size(14.0)
+                            .color(Color32::WHITE)
+                    );
+                    if self.settings.dmx_recording_enabled {
+                        ui.add_space(5.0);
+                        ui.horizontal(|ui| {
+                            ui.label(
+                                egui::RichText::new("Capture Folder:")
+                                    .size(13.0)
+                                    .color(Color32::WHITE)
+                            );
+                            ui.add_sized([240.0, 24.0], 
+                                egui::TextEdit::singleline(&mut self.settings.dmx_recordings_folder));
+                        });
+                    }
+                    
+                    ui.add_space(20.0);
+                    ui.label(
+                        egui::RichText::new("White Channel:")
                             .size(14.0)
                             .color(Color32::WHITE)
                     );
                     ui.add_space(8.0);
+                    let mut strategy = WhiteStrategy::from_setting(&self.settings.white_strategy);
                     ui.horizontal(|ui| {
-                        ui.radio_value(&mut self.settings.use_rgbw, true, 
-                            egui::RichText::new("RGBW (4 channels)")
-                                .size(13.0)
-                                .color(Color32::WHITE)
-                        );
-                        ui.add_space(10.0);
-                        ui.radio_value(&mut self.settings.use_rgbw, false, 
-                            egui::RichText::new("RGB (3 channels)")
-                                .size(13.0)
-                                .color(Color32::WHITE)
-                        );
+                        for option in [WhiteStrategy::Calibrated, WhiteStrategy::MinExtraction, WhiteStrategy::Off] {
+                            ui.radio_value(&mut strategy, option, 
+                                egui::RichText::new(option.label())
+                                    .size(13.0)
+                                    .color(Color32::WHITE)
+                            );
+                            ui.add_space(10.0);
+                        }
                     });
+                    self.settings.white_strategy = strategy.as_setting().to_string();
                     ui.add_space(6.0);
                     ui.label(
-                        egui::RichText::new(if self.settings.use_rgbw {
-                            "Converts RGB to RGBW for better color mixing"
-                        } else {
-                            "RGB mode only, White channel set to 0"
+                        egui::RichText::new(match strategy {
+                            WhiteStrategy::Calibrated => "Uses each profile's measured white LED color to keep hue and maximize brightness",
+                            WhiteStrategy::MinExtraction => "Moves min(R, G, B) to white (legacy; shifts hue on warm-white fixtures)",
+                            WhiteStrategy::Off => "RGB mode only, White channel set to 0",
                         })
                             .size(12.0)
                             .color(theme::AppColors::TEXT_DISABLED)
                     );
+                    
+                    ui.add_space(20.0);
+                    ui.label(
+                        egui::RichText::new("Default Fade Curve:")
+                            .size(14.0)
+                            .color(Color32::WHITE)
+                    );
+                    ui.add_space(8.0);
+                    let mut curve = FadeCurve::from_setting(&self.settings.fade_curve).unwrap_or(FadeCurve::Linear);
+                    egui::ComboBox::from_id_salt("default_fade_curve")
+                        .selected_text(curve.label())
+                        .show_ui(ui, |ui| {
+                            for option in FadeCurve::ALL {
+                                ui.selectable_value(&mut curve, option, option.label());
+                            }
+                        });
+                    self.settings.fade_curve = curve.as_setting().to_string();
+                    ui.add_space(6.0);
+                    ui.label(
+                        egui::RichText::new("Used unless a light group sets its own curve or the show sends 095-NNN")
+                            .size(12.0)
+                            .color(theme::AppColors::TEXT_DISABLED)
+                    );
+                    
+                    ui.add_space(20.0);
+                    ui.label(
+                        egui::RichText::new("Idle Look:")
+                            .size(14.0)
+                            .color(Color32::WHITE)
+                    );
+                    ui.add_space(8.0);
+                    let mut idle_look = IdleLook::from_setting(&self.settings.idle_look);
+                    let mut options = vec![(IdleLook::Off, "Off (black)".to_string())];
+                    if let Some(fm) = &self.fixture_manager {
+                        if let Ok(fm) = fm.lock() {
+                            options.extend(fm.config.scenes.scenes()
+                                .map(|scene| (IdleLook::Scene(scene.id), format!("Scene {:03}: {}", scene.id, scene.name))));
+                            options.extend(fm.config.effects.effects()
+                                .map(|effect| (IdleLook::Effect(effect.id), format!("Effect {:03}: {}", effect.id, effect.name))));
+                        }
+                    }
+                    let selected_text = options.iter()
+                        .find(|(look, _)| *look == idle_look)
+                        .map(|(_, label)| label.clone())
+                        .unwrap_or_else(|| self.settings.idle_look.clone());
+                    egui::ComboBox::from_id_salt("idle_look")
+                        .selected_text(selected_text)
+                        .show_ui(ui, |ui| {
+                            for (option, label) in options {
+                                ui.selectable_value(&mut idle_look, option, label);
+                            }
+                        });
+                    self.settings.idle_look = idle_look.as_setting();
+                    ui.add(egui::Slider::new(&mut self.settings.idle_fade_secs, 0.0..=10.0).text("fade (s)"));
+                    ui.add_space(6.0);
+                    ui.label(
+                        egui::RichText::new("Fades in when nothing is playing and out when a song starts; a show ending with 099-077 keeps it out")
+                            .size(12.0)
+                            .color(theme::AppColors::TEXT_DISABLED)
+                    );
                 });
             
             ui.add_space(20.0);
@@ -1517,60 +2189,225 @@ impl PlaybackApp {
                             })
                             .width(300.0)
                             .show_ui(ui, |ui| {
-                                for (name, ip) in interfaces {
-                                    let label = format!("{} ({})", name, ip);
-                                    ui.selectable_value(&mut self.settings.sacn_interface_ip, ip, label);
-                                }
+                                for (name, ip) in interfaces {
+                                    let label = format!("{} ({})", name, ip);
+                                    ui.selectable_value(&mut self.settings.sacn_interface_ip, ip, label);
+                                }
+                            });
+                        
+                        ui.add_space(15.0);
+                        
+                        // Filter Mode Selection
+                        ui.label(
+                            egui::RichText::new("Output Mode:")
+                                .size(14.0)
+                                .color(Color32::WHITE)
+                        );
+                        ui.add_space(5.0);
+                        
+                        egui::ComboBox::from_id_salt("sacn_filter_mode")
+                            .selected_text(if self.settings.sacn_filter_mode == "900only" {
+                                "900 Codes Only"
+                            } else {
+                                "All Lights"
+                            })
+                            .width(300.0)
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut self.settings.sacn_filter_mode, "all".to_string(), "All Lights");
+                                ui.selectable_value(&mut self.settings.sacn_filter_mode, "900only".to_string(), "900 Codes Only");
+                            });
+                        
+                        ui.add_space(8.0);
+                        ui.label(
+                            egui::RichText::new(if self.settings.sacn_filter_mode == "900only" {
+                                "Sends only channels 500-512 (fixture IDs ≥900)"
+                            } else {
+                                "Sends all 512 DMX channels"
+                            })
+                                .size(12.0)
+                                .color(theme::AppColors::TEXT_DISABLED)
+                        );
+                        
+                        ui.add_space(15.0);
+                        ui.label(
+                            egui::RichText::new("Universe 1 • Priority 100")
+                                .size(12.0)
+                                .color(theme::AppColors::TEXT_DISABLED)
+                        );
+                        
+                        if self.settings.sacn_interface_ip.is_empty() {
+                            ui.add_space(8.0);
+                            ui.label(
+                                egui::RichText::new("⚠ Select a network interface to enable output")
+                                    .size(13.0)
+                                    .color(theme::AppColors::WARNING)
+                            );
+                        }
+                    }
+                });
+            
+            ui.add_space(20.0);
+            
+            // Network DMX Input Settings Card
+            egui::Frame::none()
+                .fill(theme::AppColors::SURFACE)
+                .stroke(Stroke::new(1.0, theme::AppColors::SURFACE_LIGHT))
+                .rounding(12.0)
+                .inner_margin(24.0)
+                .show(ui, |ui| {
+                    ui.label(
+                        egui::RichText::new("Network DMX Input")
+                            .size(20.0)
+                            .strong()
+                            .color(theme::AppColors::CYAN)
+                    );
+                    ui.add_space(10.0);
+                    ui.add(egui::Separator::default().spacing(0.0));
+                    ui.add_space(15.0);
+                    
+                    ui.checkbox(&mut self.settings.dmx_input_enabled, 
+                        egui::RichText::new("Accept input from a lighting console")
+                            .size(14.0)
+                            .color(Color32::WHITE)
+                    );
+                    ui.add_space(8.0);
+                    ui.label(
+                        egui::RichText::new("Merge sACN or Art-Net from a console with the show before it reaches the DMX outputs")
+                            .size(13.0)
+                            .color(theme::AppColors::TEXT_SECONDARY)
+                    );
+                    
+                    if self.settings.dmx_input_enabled {
+                        ui.add_space(20.0);
+                        
+                        // Protocol Selection
+                        ui.label(
+                            egui::RichText::new("Protocol:")
+                                .size(14.0)
+                                .color(Color32::WHITE)
+                        );
+                        ui.add_space(5.0);
+                        
+                        egui::ComboBox::from_id_salt("dmx_input_protocol")
+                            .selected_text(InputProtocol::from_setting(&self.settings.dmx_input_protocol).label())
+                            .width(300.0)
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut self.settings.dmx_input_protocol, "sacn".to_string(), "sACN (E.131)");
+                                ui.selectable_value(&mut self.settings.dmx_input_protocol, "artnet".to_string(), "Art-Net");
                             });
                         
                         ui.add_space(15.0);
                         
-                        // Filter Mode Selection
+                        // Network Interface Selection
                         ui.label(
-                            egui::RichText::new("Output Mode:")
+                            egui::RichText::new("Network Interface:")
                                 .size(14.0)
                                 .color(Color32::WHITE)
                         );
                         ui.add_space(5.0);
                         
-                        egui::ComboBox::from_id_salt("sacn_filter_mode")
-                            .selected_text(if self.settings.sacn_filter_mode == "900only" {
-                                "900 Codes Only"
+                        let interfaces = crate::dmx::get_network_interfaces();
+                        egui::ComboBox::from_id_salt("dmx_input_interface")
+                            .selected_text(if self.settings.dmx_input_interface_ip.is_empty() {
+                                "All interfaces"
                             } else {
-                                "All Lights"
+                                &self.settings.dmx_input_interface_ip
                             })
                             .width(300.0)
                             .show_ui(ui, |ui| {
-                                ui.selectable_value(&mut self.settings.sacn_filter_mode, "all".to_string(), "All Lights");
-                                ui.selectable_value(&mut self.settings.sacn_filter_mode, "900only".to_string(), "900 Codes Only");
+                                ui.selectable_value(&mut self.settings.dmx_input_interface_ip, String::new(), "All interfaces");
+                                for (name, ip) in interfaces {
+                                    let label = format!("{} ({})", name, ip);
+                                    ui.selectable_value(&mut self.settings.dmx_input_interface_ip, ip, label);
+                                }
                             });
                         
+                        ui.add_space(15.0);
+                        
+                        ui.horizontal(|ui| {
+                            ui.label(
+                                egui::RichText::new("Universe:")
+                                    .size(14.0)
+                                    .color(Color32::WHITE)
+                            );
+                            ui.add(egui::DragValue::new(&mut self.settings.dmx_input_universe).range(1..=63999));
+                            ui.add_space(20.0);
+                            ui.label(
+                                egui::RichText::new("Timeout (ms):")
+                                    .size(14.0)
+                                    .color(Color32::WHITE)
+                            );
+                            ui.add(egui::DragValue::new(&mut self.settings.dmx_input_timeout_ms).range(100..=30000).speed(50));
+                        });
                         ui.add_space(8.0);
                         ui.label(
-                            egui::RichText::new(if self.settings.sacn_filter_mode == "900only" {
-                                "Sends only channels 500-512 (fixture IDs ≥900)"
-                            } else {
-                                "Sends all 512 DMX channels"
-                            })
+                            egui::RichText::new("The show takes back all channels when no console data arrives within the timeout")
                                 .size(12.0)
                                 .color(theme::AppColors::TEXT_DISABLED)
                         );
                         
+                        if self.settings.sacn_enabled
+                            && InputProtocol::from_setting(&self.settings.dmx_input_protocol) == InputProtocol::Sacn
+                            && self.settings.dmx_input_universe == 1
+                        {
+                            ui.add_space(8.0);
+                            ui.label(
+                                egui::RichText::new("⚠ sACN output also uses universe 1 - the app would receive its own output")
+                                    .size(13.0)
+                                    .color(theme::AppColors::WARNING)
+                            );
+                        }
+                        
                         ui.add_space(15.0);
+                        
+                        // Merge ranges
                         ui.label(
-                            egui::RichText::new("Universe 1 • Priority 100")
-                                .size(12.0)
-                                .color(theme::AppColors::TEXT_DISABLED)
+                            egui::RichText::new("Console Channel Ranges:")
+                                .size(14.0)
+                                .color(Color32::WHITE)
                         );
+                        ui.add_space(5.0);
                         
-                        if self.settings.sacn_interface_ip.is_empty() {
-                            ui.add_space(8.0);
+                        let mut remove_index = None;
+                        for (i, range) in self.settings.dmx_merge_ranges.iter_mut().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.label("Ch");
+                                ui.add(egui::DragValue::new(&mut range.start_channel).range(1..=512));
+                                ui.label("to");
+                                ui.add(egui::DragValue::new(&mut range.end_channel).range(range.start_channel..=512));
+                                egui::ComboBox::from_id_salt(("dmx_merge_policy", i))
+                                    .selected_text(range.policy.label())
+                                    .width(120.0)
+                                    .show_ui(ui, |ui| {
+                                        for policy in [MergePolicy::Htp, MergePolicy::Ltp, MergePolicy::ConsoleWins] {
+                                            ui.selectable_value(&mut range.policy, policy, policy.label());
+                                        }
+                                    });
+                                if ui.button("🗑").on_hover_text("Remove range").clicked() {
+                                    remove_index = Some(i);
+                                }
+                            });
+                        }
+                        if let Some(i) = remove_index {
+                            self.settings.dmx_merge_ranges.remove(i);
+                        }
+                        
+                        if self.settings.dmx_merge_ranges.is_empty() {
                             ui.label(
-                                egui::RichText::new("⚠ Select a network interface to enable output")
-                                    .size(13.0)
-                                    .color(theme::AppColors::WARNING)
+                                egui::RichText::new("No ranges - console input is received but not used")
+                                    .size(12.0)
+                                    .color(theme::AppColors::TEXT_DISABLED)
                             );
                         }
+                        
+                        ui.add_space(5.0);
+                        if ui.button("➕ Add Range").clicked() {
+                            self.settings.dmx_merge_ranges.push(MergeRange {
+                                start_channel: 1,
+                                end_channel: 512,
+                                policy: MergePolicy::Htp,
+                            });
+                        }
                     }
                 });
             
@@ -1601,29 +2438,120 @@ impl PlaybackApp {
                     ui.add_space(18.0);
                     
                     ui.label(
-                        egui::RichText::new("PLC IP Address:")
+                        egui::RichText::new("Protocol:")
                             .size(14.0)
                             .color(Color32::WHITE)
                     );
                     ui.add_space(5.0);
-                    ui.text_edit_singleline(&mut self.settings.plc_ip_address);
+                    let mut protocol = PlcProtocol::from_setting(&self.settings.plc_protocol);
+                    egui::ComboBox::from_id_salt("plc_protocol")
+                        .selected_text(protocol.label())
+                        .show_ui(ui, |ui| {
+                            for option in [PlcProtocol::AsciiTcp, PlcProtocol::ModbusTcp, PlcProtocol::Serial] {
+                                ui.selectable_value(&mut protocol, option, option.label());
+                            }
+                        });
+                    self.settings.plc_protocol = protocol.as_setting().to_string();
+                    if protocol == PlcProtocol::ModbusTcp {
+                        ui.label(
+                            egui::RichText::new("Water addresses map to registers and coils in Config/plc_modbus_map.json")
+                                .size(12.0)
+                                .color(theme::AppColors::TEXT_SECONDARY)
+                        );
+                    }
                     ui.add_space(15.0);
                     
-                    ui.label(
-                        egui::RichText::new("PLC Port:")
-                            .size(14.0)
-                            .color(Color32::WHITE)
-                    );
-                    ui.add_space(5.0);
-                    let mut port_str = self.settings.plc_port.to_string();
-                    if ui.text_edit_singleline(&mut port_str).changed() {
-                        if let Ok(port_num) = port_str.parse::<u16>() {
-                            self.settings.plc_port = port_num;
+                    if protocol == PlcProtocol::Serial {
+                        ui.label(
+                            egui::RichText::new("Serial Port:")
+                                .size(14.0)
+                                .color(Color32::WHITE)
+                        );
+                        ui.add_space(5.0);
+                        let port_name = self.settings.plc_port_name.get_or_insert_with(String::new);
+                        egui::ComboBox::from_id_salt("plc_port_name")
+                            .selected_text(if port_name.is_empty() { "Select a port" } else { port_name.as_str() })
+                            .width(300.0)
+                            .show_ui(ui, |ui| {
+                                // Only enumerate while the list is open
+                                let ports: Vec<String> = serialport::available_ports()
+                                    .map(|ports| ports.into_iter().map(|p| p.port_name).collect())
+                                    .unwrap_or_default();
+                                for port in ports {
+                                    ui.selectable_value(port_name, port.clone(), port);
+                                }
+                            });
+                        ui.add_space(15.0);
+                        
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new("Baud:").size(14.0).color(Color32::WHITE));
+                            egui::ComboBox::from_id_salt("plc_baud_rate")
+                                .selected_text(self.settings.plc_baud_rate.to_string())
+                                .show_ui(ui, |ui| {
+                                    for baud in [1200, 2400, 4800, 9600, 19_200, 38_400, 57_600, 115_200] {
+                                        ui.selectable_value(&mut self.settings.plc_baud_rate, baud, baud.to_string());
+                                    }
+                                });
+                            ui.add_space(15.0);
+                            ui.label(egui::RichText::new("Parity:").size(14.0).color(Color32::WHITE));
+                            egui::ComboBox::from_id_salt("plc_parity")
+                                .selected_text(self.settings.plc_parity.clone())
+                                .show_ui(ui, |ui| {
+                                    for parity in ["none", "even", "odd"] {
+                                        ui.selectable_value(&mut self.settings.plc_parity, parity.to_string(), parity);
+                                    }
+                                });
+                        });
+                    } else {
+                        ui.label(
+                            egui::RichText::new("PLC IP Address:")
+                                .size(14.0)
+                                .color(Color32::WHITE)
+                        );
+                        ui.add_space(5.0);
+                        ui.text_edit_singleline(&mut self.settings.plc_ip_address);
+                        ui.add_space(15.0);
+                        
+                        ui.label(
+                            egui::RichText::new("PLC Port:")
+                                .size(14.0)
+                                .color(Color32::WHITE)
+                        );
+                        ui.add_space(5.0);
+                        let mut port_str = self.settings.plc_port.to_string();
+                        if ui.text_edit_singleline(&mut port_str).changed() {
+                            if let Ok(port_num) = port_str.parse::<u16>() {
+                                self.settings.plc_port = port_num;
+                            }
                         }
                     }
                     ui.add_space(8.0);
+                    ui.add(egui::Slider::new(&mut self.settings.plc_heartbeat_secs, 0.0..=10.0)
+                        .text("heartbeat (s)"))
+                        .on_hover_text("0 turns off the heartbeat; only set this if the PLC echoes HB. The link is dropped after three missed replies");
+                    ui.add(egui::Slider::new(&mut self.settings.plc_ack_timeout_ms, 0..=5000)
+                        .text("ack timeout (ms)"))
+                        .on_hover_text("0 sends bare batches without waiting, as PLCComms.cs does; only set this if the PLC answers ACK");
+                    ui.add(egui::Slider::new(&mut self.settings.plc_max_retries, 0..=5)
+                        .text("retries"));
+                    let mut policy = ReconnectPolicy::from_setting(&self.settings.plc_reconnect_policy);
+                    egui::ComboBox::from_id_salt("plc_reconnect_policy")
+                        .selected_text(policy.label())
+                        .show_ui(ui, |ui| {
+                            for option in [ReconnectPolicy::Replay, ReconnectPolicy::Drop] {
+                                ui.selectable_value(&mut policy, option, option.label());
+                            }
+                        })
+                        .response
+                        .on_hover_text("What happens to commands queued while the PLC link was down");
+                    self.settings.plc_reconnect_policy = policy.as_setting().to_string();
+                    ui.add_space(8.0);
                     ui.label(
-                        egui::RichText::new("Send water commands to PLC over TCP/IP")
+                        egui::RichText::new(if protocol == PlcProtocol::Serial {
+                            "Send water commands to PLC over RS-232/RS-485"
+                        } else {
+                            "Send water commands to PLC over TCP/IP"
+                        })
                             .size(13.0)
                             .color(theme::AppColors::TEXT_SECONDARY)
                     );
@@ -1642,8 +2570,8 @@ impl PlaybackApp {
                     
                     if ui.add(test_button).clicked() {
                         // 099-000 is a reset command that turns off both water and lights
-                        if let Some(plc) = &self.plc_client {
-                            plc.queue_command_sync("099-000".to_string());
+                        if self.plc_client.is_some() {
+                            self.queue_water(None, "099-000");
                             
                             // Add to recent commands so it shows up in PLC output
                             let time_ms = self.playback_position.as_millis() as u64;
@@ -1669,6 +2597,248 @@ impl PlaybackApp {
             
             ui.add_space(20.0);
             
+            // Wind Limits Card
+            egui::Frame::none()
+                .fill(theme::AppColors::SURFACE)
+                .stroke(Stroke::new(1.0, theme::AppColors::SURFACE_LIGHT))
+                .rounding(12.0)
+                .inner_margin(24.0)
+                .show(ui, |ui| {
+                    ui.label(
+                        egui::RichText::new("Wind Limits")
+                            .size(20.0)
+                            .strong()
+                            .color(theme::AppColors::CYAN)
+                    );
+                    ui.add_space(10.0);
+                    ui.add(egui::Separator::default().spacing(0.0));
+                    ui.add_space(15.0);
+                    
+                    ui.label(
+                        egui::RichText::new("Wind Source:")
+                            .size(14.0)
+                            .color(Color32::WHITE)
+                    );
+                    ui.add_space(5.0);
+                    let mut source = WindSourceKind::from_setting(&self.settings.wind_source);
+                    egui::ComboBox::from_id_salt("wind_source")
+                        .selected_text(source.label())
+                        .show_ui(ui, |ui| {
+                            for option in [WindSourceKind::Off, WindSourceKind::WeatherGov, WindSourceKind::Tcp, WindSourceKind::Serial, WindSourceKind::File] {
+                                ui.selectable_value(&mut source, option, option.label());
+                            }
+                        });
+                    self.settings.wind_source = source.as_setting().to_string();
+                    if !source.address_hint().is_empty() {
+                        ui.add_space(8.0);
+                        ui.add(egui::TextEdit::singleline(&mut self.settings.wind_source_address)
+                            .hint_text(source.address_hint())
+                            .desired_width(300.0));
+                        ui.label(
+                            egui::RichText::new("One reading per line: sustained mph, then optional gust mph, e.g. 12.5,18")
+                                .size(12.0)
+                                .color(theme::AppColors::TEXT_SECONDARY)
+                        );
+                    }
+                    ui.add_space(8.0);
+                    ui.add(egui::Slider::new(&mut self.settings.wind_poll_secs, 5.0..=600.0)
+                        .text("read every (s)"));
+                    ui.add(egui::Slider::new(&mut self.settings.wind_release_secs, 0.0..=900.0)
+                        .text("ease after (s)"))
+                        .on_hover_text("How long the wind must stay below a band before its limits are lifted");
+                    ui.add(egui::Slider::new(&mut self.settings.wind_stale_secs, 30.0..=1800.0)
+                        .text("stale after (s)"))
+                        .on_hover_text("Warn when no reading has arrived for this long; the current limits stay in effect");
+                    ui.add_space(8.0);
+                    
+                    if self.settings.wind_bands.is_empty() {
+                        ui.label(
+                            egui::RichText::new("No wind bands; add [[wind_bands]] to the settings file to limit heights")
+                                .size(13.0)
+                                .color(theme::AppColors::TEXT_SECONDARY)
+                        );
+                    }
+                    for band in &self.settings.wind_bands {
+                        let mut text = format!("{}: {:.0} mph", band.name, band.wind_mph);
+                        if let Some(gust) = band.gust_mph {
+                            text.push_str(&format!(" or gusts {:.0}", gust));
+                        }
+                        text.push_str(&format!(" → heights at {:.0}%", band.height_scale * 100.0));
+                        if let Some(max) = band.max_height {
+                            text.push_str(&format!(", at most {:03}", max));
+                        }
+                        ui.label(
+                            egui::RichText::new(text)
+                                .size(13.0)
+                                .color(Color32::WHITE)
+                        );
+                    }
+                    ui.label(
+                        egui::RichText::new("Limits apply to water_map.json entries marked \"height\"")
+                            .size(12.0)
+                            .color(theme::AppColors::TEXT_SECONDARY)
+                    );
+                });
+            
+            ui.add_space(20.0);
+            
+            // E-Stop Card
+            egui::Frame::none()
+                .fill(theme::AppColors::SURFACE)
+                .stroke(Stroke::new(1.0, theme::AppColors::SURFACE_LIGHT))
+                .rounding(12.0)
+                .inner_margin(24.0)
+                .show(ui, |ui| {
+                    ui.label(
+                        egui::RichText::new("E-Stop and Watchdog")
+                            .size(20.0)
+                            .strong()
+                            .color(theme::AppColors::CYAN)
+                    );
+                    ui.add_space(10.0);
+                    ui.add(egui::Separator::default().spacing(0.0));
+                    ui.add_space(15.0);
+                    
+                    ui.label(
+                        egui::RichText::new("E-Stop Sequence:")
+                            .size(14.0)
+                            .color(Color32::WHITE)
+                    );
+                    ui.add_space(5.0);
+                    // Split on single spaces so typing a space survives the round trip
+                    let mut sequence = self.settings.estop_sequence.join(" ");
+                    if ui.add(egui::TextEdit::singleline(&mut sequence)
+                        .hint_text("099-000")
+                        .desired_width(300.0)).changed()
+                    {
+                        self.settings.estop_sequence = sequence.split(' ').map(str::to_string).collect();
+                    }
+                    ui.label(
+                        egui::RichText::new("Sent ahead of anything queued on E-stop, shutdown, a crash or a watchdog trip")
+                            .size(12.0)
+                            .color(theme::AppColors::TEXT_SECONDARY)
+                    );
+                    ui.add_space(8.0);
+                    
+                    ui.label(
+                        egui::RichText::new("Failsafe Look:")
+                            .size(14.0)
+                            .color(Color32::WHITE)
+                    );
+                    ui.add_space(5.0);
+                    let mut look = FailsafeLook::from_setting(&self.settings.failsafe_look);
+                    ui.horizontal(|ui| {
+                        let scene = match look {
+                            FailsafeLook::Scene(id) => id,
+                            _ => 1,
+                        };
+                        egui::ComboBox::from_id_salt("failsafe_look")
+                            .selected_text(match look {
+                                FailsafeLook::Blackout => "Blackout",
+                                FailsafeLook::Hold => "Hold current look",
+                                FailsafeLook::Scene(_) => "Scene",
+                            })
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut look, FailsafeLook::Blackout, "Blackout");
+                                ui.selectable_value(&mut look, FailsafeLook::Hold, "Hold current look");
+                                ui.selectable_value(&mut look, FailsafeLook::Scene(scene), "Scene");
+                            });
+                        if let FailsafeLook::Scene(id) = &mut look {
+                            ui.add(egui::DragValue::new(id).range(1..=999).prefix("scene "));
+                        }
+                    });
+                    self.settings.failsafe_look = look.as_setting();
+                    ui.add_space(8.0);
+                    
+                    ui.checkbox(&mut self.settings.watchdog_enabled, "Send heartbeats to the watchdog");
+                    if self.settings.watchdog_enabled {
+                        ui.horizontal(|ui| {
+                            ui.label("UDP port:");
+                            ui.add(egui::DragValue::new(&mut self.settings.watchdog_port).range(1024..=65535));
+                        });
+                        ui.add(egui::Slider::new(&mut self.settings.watchdog_timeout_secs, 1.0..=30.0)
+                            .text("trip after (s)"))
+                            .on_hover_text("How long the player may go quiet mid-song before the watchdog stops the water");
+                        ui.label(
+                            egui::RichText::new("Run the watchdog program alongside the player; it reads the same settings")
+                                .size(12.0)
+                                .color(theme::AppColors::TEXT_SECONDARY)
+                        );
+                    }
+                });
+            
+            ui.add_space(20.0);
+            
+            // Maintenance Card
+            egui::Frame::none()
+                .fill(theme::AppColors::SURFACE)
+                .stroke(Stroke::new(1.0, theme::AppColors::SURFACE_LIGHT))
+                .rounding(12.0)
+                .inner_margin(24.0)
+                .show(ui, |ui| {
+                    ui.label(
+                        egui::RichText::new("Maintenance")
+                            .size(20.0)
+                            .strong()
+                            .color(theme::AppColors::CYAN)
+                    );
+                    ui.add_space(10.0);
+                    ui.add(egui::Separator::default().spacing(0.0));
+                    ui.add_space(15.0);
+                    
+                    let water_hours: f64 = self.maintenance.water.values().map(|counter| counter.run_hours()).sum();
+                    let fixture_hours: f64 = self.maintenance.fixtures.values().map(|counter| counter.run_hours()).sum();
+                    ui.label(
+                        egui::RichText::new(format!(
+                            "{} water addresses, {:.1} h total · {} fixtures, {:.1} LED h total",
+                            self.maintenance.water.len(), water_hours, self.maintenance.fixtures.len(), fixture_hours
+                        ))
+                            .size(13.0)
+                            .color(Color32::WHITE)
+                    );
+                    ui.add_space(8.0);
+                    
+                    if self.settings.service_intervals.is_empty() {
+                        ui.label(
+                            egui::RichText::new("No service intervals; add [[service_intervals]] to the settings file for reminders")
+                                .size(13.0)
+                                .color(theme::AppColors::TEXT_SECONDARY)
+                        );
+                    }
+                    let mut serviced = None;
+                    for reminder in maintenance::reminders(&self.maintenance, &self.settings.service_intervals) {
+                        ui.horizontal(|ui| {
+                            let color = if reminder.status == ServiceStatus::Due {
+                                theme::AppColors::ERROR
+                            } else {
+                                theme::AppColors::WARNING
+                            };
+                            ui.label(egui::RichText::new(reminder.to_string()).size(13.0).color(color));
+                            if ui.small_button("Mark serviced").clicked() {
+                                serviced = Some(reminder.clone());
+                            }
+                        });
+                    }
+                    if let Some(reminder) = serviced {
+                        let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+                        self.maintenance.counter_mut(reminder.kind, reminder.address).mark_serviced(&reminder.interval, date);
+                        info!("Maintenance: {} marked serviced on {} {:03}", reminder.interval, reminder.kind.label(), reminder.address);
+                        self.save_maintenance();
+                        self.check_service_due();
+                    }
+                    ui.add_space(8.0);
+                    
+                    if ui.button(
+                        egui::RichText::new("📄 Export CSV...")
+                            .size(14.0)
+                            .color(theme::AppColors::CYAN_LIGHT)
+                    ).clicked() {
+                        self.export_maintenance_dialog();
+                    }
+                });
+            
+            ui.add_space(20.0);
+            
             // Folder Paths Settings Card
             egui::Frame::none()
                 .fill(theme::AppColors::SURFACE)
@@ -1990,15 +3160,21 @@ impl PlaybackApp {
                         self.set_status(&format!("Failed to save settings: {}", e), StatusType::Warning);
                     } else {
                         self.set_status("Settings saved successfully", StatusType::Success);
-                        // Update fixture manager RGBW mode
+                        // Update fixture manager white strategy
                         if let Some(fm) = &self.fixture_manager {
                             if let Ok(mut fm) = fm.lock() {
-                                fm.set_rgbw_mode(self.settings.use_rgbw);
+                                fm.set_white_strategy(WhiteStrategy::from_setting(&self.settings.white_strategy));
+                                fm.set_default_fade_curve(
+                                    FadeCurve::from_setting(&self.settings.fade_curve).unwrap_or(FadeCurve::Linear));
+                                fm.set_idle_look(
+                                    IdleLook::from_setting(&self.settings.idle_look), (self.settings.idle_fade_secs * 1000.0) as u64);
                             }
                         }
                         // Reinitialize systems with new settings
                         self.initialize_dmx();
                         self.initialize_plc();
+                        self.initialize_wind();
+                        self.initialize_failsafe();
                     }
                 }
                 
@@ -2024,6 +3200,24 @@ impl PlaybackApp {
 }
 
 impl eframe::App for PlaybackApp {
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if let Some(player) = &self.audio_player {
+            if let Ok(player) = player.lock() {
+                player.stop();
+            }
+        }
+        info!("Shutting down: sending E-stop");
+        failsafe::stop_all(Duration::from_secs(2));
+        if let Some(heartbeat) = self.heartbeat.take() {
+            heartbeat.exit();
+        }
+        
+        // Count the E-stop, then everything is off
+        self.update_maintenance();
+        self.maintenance.set_lit_fixtures_at(&[], Instant::now());
+        self.save_maintenance();
+    }
+    
     fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
         // Apply theme
         theme::configure_theme(ctx);
@@ -2059,7 +3253,17 @@ impl eframe::App for PlaybackApp {
         self.update_playback_state();
         self.update_dmx_state();
         self.update_plc_status();
+        self.update_wind_state();
+        self.send_held_water();
+        if self.water_guard.is_holding() {
+            // Held switch-offs and ramps go out on time even with nothing else redrawing
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
         self.update_sacn_state();
+        self.update_dmx_input_state();
+        self.update_dmx_recording_state();
+        self.update_maintenance();
+        self.send_heartbeat();
         
         // Bottom status bar with dark background
         TopBottomPanel::bottom("status_bar")
@@ -2068,18 +3272,28 @@ impl eframe::App for PlaybackApp {
                 .inner_margin(8.0))
             .show(ctx, |ui| {
             let mut cookie_clicked = false;
+            let mut estop_clicked = false;
+            let dmx_input = match self.dmx_input.lock() {
+                Ok(input) if input.is_active() => Some((input.protocol(), self.dmx_merger.source())),
+                _ => None,
+            };
             status_panel::show(
                 ui,
                 &self.status_message,
                 self.status_type,
                 self.status_time,
-                self.dmx_connected,
+                self.dmx_health.as_ref(),
                 &self.plc_status,
-                self.settings.use_rgbw,
+                WhiteStrategy::from_setting(&self.settings.white_strategy),
+                dmx_input,
                 self.cookie_icon.as_ref(),
                 &mut cookie_clicked,
+                &mut estop_clicked,
             );
             
+            if estop_clicked {
+                self.emergency_stop();
+            }
             if cookie_clicked {
                 self.show_random_fortune();
             }
@@ -2139,7 +3353,23 @@ impl eframe::App for PlaybackApp {
                     });
                 }
                 sidebar::AppView::SettingsDmxMap => {
+                    let blackout = self.fixture_manager.as_ref()
+                        .is_some_and(|fm| fm.lock().unwrap().is_safety_blackout());
+                    self.dmx_map_panel.set_preview_blocked(blackout);
                     self.dmx_map_panel.show(ctx, ui);
+                    
+                    // Apply correction edits to the live show immediately
+                    let updates = self.dmx_map_panel.take_correction_updates();
+                    let profiles = self.dmx_map_panel.take_profile_updates();
+                    if let Some(fm) = &self.fixture_manager {
+                        let mut fm = fm.lock().unwrap();
+                        for (fixture_num, correction) in updates {
+                            fm.set_fixture_correction(fixture_num, correction);
+                        }
+                        for (fixture_num, profile) in profiles {
+                            fm.set_fixture_profile(fixture_num, profile);
+                        }
+                    }
                 }
                 sidebar::AppView::SettingsLightGroups => {
                     self.light_group_panel.show(ctx, ui);
@@ -2162,6 +3392,8 @@ impl eframe::App for PlaybackApp {
             }
         });
         
+        self.queue_scene_water();
+        
         // About dialog
         if self.show_about {
             let should_close = std::cell::Cell::new(false);
@@ -1,9 +1,11 @@
 use super::theme;
+use crate::config::WaterMap;
 use egui::{Ui, RichText, ScrollArea, Color32};
 
 pub fn show(
     ui: &mut Ui,
     recent_commands: &[(u64, String)],
+    water: &WaterMap,
     playback_position_ms: u64,
     ctl_loaded: bool,
 ) {
@@ -30,7 +32,7 @@ pub fn show(
     
     // Separate commands into Water and Lights categories
     let (water_commands, light_commands): (Vec<_>, Vec<_>) = recent_commands.iter()
-        .partition(|(_, cmd)| is_water_command(cmd));
+        .partition(|(_, cmd)| water.is_water_command(cmd));
     
     // Water section
     ui.group(|ui| {
@@ -110,23 +112,6 @@ fn show_command_line(ui: &mut Ui, time_ms: u64, cmd_desc: &str, playback_positio
     ui.add_space(3.0);
 }
 
-fn is_water_command(cmd_desc: &str) -> bool {
-    // Check if command is water-related based on GHMF documentation
-    // Water FCW addresses:
-    // 1-13: Module water rings (W1-W8) and WAVE water controls
-    // 217-255: Module WAVE Sols, Left/Right/Odd/Even/ALL WAVE Sols
-    // 700-896: WAVE Sols 0-196
-    // Parse "XXX-YYY" format
-    if let Some(dash_pos) = cmd_desc.find('-') {
-        if let Ok(fcw_num) = cmd_desc[..dash_pos].trim().parse::<u16>() {
-            return (fcw_num >= 1 && fcw_num <= 13) ||
-                   (fcw_num >= 217 && fcw_num <= 255) ||
-                   (fcw_num >= 700 && fcw_num <= 896);
-        }
-    }
-    false
-}
-
 fn format_time(ms: u64) -> String {
     let total_secs = ms / 1000;
     let minutes = total_secs / 60;
@@ -4,6 +4,7 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 use super::theme;
+use crate::config::{ChannelRole, FixtureCorrection, FixtureProfile, ProfileLibrary, PROFILE_RGB, PROFILE_RGBW, PROFILE_SINGLE};
 
 // Constants
 const GRID_COLS: usize = 25;
@@ -36,6 +37,22 @@ pub struct FixtureDefinition {
     pub channel_count: u8,
     #[serde(default = "default_ignore_reset")]
     pub ignore_reset: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+}
+
+impl FixtureDefinition {
+    /// Profile name to store with this fixture's mapping
+    pub fn profile_name(&self) -> String {
+        if let Some(profile) = &self.profile {
+            return profile.clone();
+        }
+        match (&self.fixture_type, &self.light_type) {
+            (FixtureType::Light, Some(LightType::RGB)) => PROFILE_RGB.to_string(),
+            (FixtureType::Light, _) => PROFILE_RGBW.to_string(),
+            (FixtureType::Other, _) => PROFILE_SINGLE.to_string(),
+        }
+    }
 }
 
 fn default_fixture_type() -> FixtureType {
@@ -59,6 +76,10 @@ pub struct FixtureMapping {
     pub fixture_id: u8,
     pub fixture_name: String,
     pub start_channel: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(default, skip_serializing_if = "FixtureCorrection::is_identity")]
+    pub correction: FixtureCorrection,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -126,13 +147,24 @@ pub struct DmxMapPanel {
     show_add_fixture_dialog: bool,
     new_fixture_name: String,
     new_fixture_type: FixtureType,
-    new_light_type: LightType,
+    new_profile: String,
     new_channel_count: String,
     new_ignore_reset: bool,
     
     // Delete confirmation dialog state
     show_delete_confirmation: bool,
     fixture_to_delete_id: Option<u8>,
+    
+    profiles: ProfileLibrary,
+    
+    // Correction and profile editing, and live preview
+    pending_corrections: Vec<(u16, FixtureCorrection)>,
+    pending_profiles: Vec<(u16, FixtureProfile)>,
+    preview_enabled: bool,
+    preview_color: [u8; 3],
+    preview_all: bool,
+    /// Safety blackout is on, so the preview is not shown
+    preview_blocked: bool,
 }
 
 impl Default for DmxMapPanel {
@@ -144,78 +176,92 @@ impl Default for DmxMapPanel {
 impl DmxMapPanel {
     pub fn new() -> Self {
         let mut fixtures = vec![
-            FixtureDefinition { id: 1, name: "Mod 1 Front Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 2, name: "Mod 1 Front Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 3, name: "Mod 1 Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 4, name: "Mod 1 Back Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 5, name: "Mod 1 Back Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 6, name: "Mod 1 Back Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
+            FixtureDefinition { id: 1, name: "Mod 1 Front Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 2, name: "Mod 1 Front Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 3, name: "Mod 1 Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 4, name: "Mod 1 Back Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 5, name: "Mod 1 Back Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 6, name: "Mod 1 Back Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
             
-            FixtureDefinition { id: 7, name: "Mod 2 Front Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 8, name: "Mod 2 Front Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 9, name: "Mod 2 Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 10, name: "Mod 2 Back Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 11, name: "Mod 2 Back Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 12, name: "Mod 2 Back Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
+            FixtureDefinition { id: 7, name: "Mod 2 Front Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 8, name: "Mod 2 Front Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 9, name: "Mod 2 Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 10, name: "Mod 2 Back Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 11, name: "Mod 2 Back Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 12, name: "Mod 2 Back Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
             
-            FixtureDefinition { id: 13, name: "Mod 3 Front Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 14, name: "Mod 3 Front Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 15, name: "Mod 3 Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 16, name: "Mod 3 Back Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 17, name: "Mod 3 Back Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 18, name: "Mod 3 Back Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
+            FixtureDefinition { id: 13, name: "Mod 3 Front Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 14, name: "Mod 3 Front Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 15, name: "Mod 3 Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 16, name: "Mod 3 Back Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 17, name: "Mod 3 Back Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 18, name: "Mod 3 Back Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
             
-            FixtureDefinition { id: 19, name: "Mod 4 Front Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 20, name: "Mod 4 Front Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 21, name: "Mod 4 Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 22, name: "Mod 4 Back Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 23, name: "Mod 4 Back Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
+            FixtureDefinition { id: 19, name: "Mod 4 Front Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 20, name: "Mod 4 Front Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 21, name: "Mod 4 Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 22, name: "Mod 4 Back Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 23, name: "Mod 4 Back Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
             
-            FixtureDefinition { id: 24, name: "Mod 5 Front Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 25, name: "Mod 5 Front Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 26, name: "Mod 5 Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 27, name: "Mod 5 Back Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 28, name: "Mod 5 Back Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 29, name: "Mod 5 Back Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
+            FixtureDefinition { id: 24, name: "Mod 5 Front Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 25, name: "Mod 5 Front Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 26, name: "Mod 5 Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 27, name: "Mod 5 Back Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 28, name: "Mod 5 Back Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 29, name: "Mod 5 Back Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
             
-            FixtureDefinition { id: 30, name: "Mod 6 Front Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 31, name: "Mod 6 Front Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 32, name: "Mod 6 Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 33, name: "Mod 6 Back Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 34, name: "Mod 6 Back Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 35, name: "Mod 6 Back Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
+            FixtureDefinition { id: 30, name: "Mod 6 Front Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 31, name: "Mod 6 Front Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 32, name: "Mod 6 Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 33, name: "Mod 6 Back Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 34, name: "Mod 6 Back Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 35, name: "Mod 6 Back Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
             
-            FixtureDefinition { id: 36, name: "Mod 7 Front Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 37, name: "Mod 7 Front Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 38, name: "Mod 7 Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 39, name: "Mod 7 Back Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 40, name: "Mod 7 Back Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 41, name: "Mod 7 Back Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
+            FixtureDefinition { id: 36, name: "Mod 7 Front Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 37, name: "Mod 7 Front Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 38, name: "Mod 7 Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 39, name: "Mod 7 Back Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 40, name: "Mod 7 Back Center".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 41, name: "Mod 7 Back Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
             
-            FixtureDefinition { id: 42, name: "Peacock 1".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 43, name: "Peacock 2".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 44, name: "Peacock 3".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 45, name: "Peacock 4".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 46, name: "Peacock 5".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 47, name: "Peacock 6".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 48, name: "Peackcok Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 49, name: "Peacock Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
+            FixtureDefinition { id: 42, name: "Peacock 1".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 43, name: "Peacock 2".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 44, name: "Peacock 3".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 45, name: "Peacock 4".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 46, name: "Peacock 5".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 47, name: "Peacock 6".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 48, name: "Peackcok Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 49, name: "Peacock Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
             
-            FixtureDefinition { id: 50, name: "Spout/Helix Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 51, name: "Spout/Helix Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
+            FixtureDefinition { id: 50, name: "Spout/Helix Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 51, name: "Spout/Helix Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
             
-            FixtureDefinition { id: 52, name: "Dove Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
-            FixtureDefinition { id: 53, name: "Dove Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false },
+            FixtureDefinition { id: 52, name: "Dove Left".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
+            FixtureDefinition { id: 53, name: "Dove Right".to_string(), fixture_type: FixtureType::Light, light_type: Some(LightType::RGBW), channel_count: 4, ignore_reset: false, profile: None },
         ];
 
         let config_path = "Config/dmx_mapping.json".to_string();
-        let config = DmxMapConfig::load(&config_path).unwrap_or_else(|_| DmxMapConfig::new());
+        let mut config = DmxMapConfig::load(&config_path).unwrap_or_else(|_| DmxMapConfig::new());
         
         let fixture_config_path = "Config/custom_fixtures.json".to_string();
         let fixture_config = FixtureConfig::load(&fixture_config_path).unwrap_or_else(|_| FixtureConfig::new());
         
         // Add custom fixtures to the list
         fixtures.extend(fixture_config.custom_fixtures.clone());
+        
+        let profiles = ProfileLibrary::load_from_dir("Config").unwrap_or_else(|e| {
+            eprintln!("Failed to load fixture profiles: {}", e);
+            ProfileLibrary::builtin()
+        });
+        
+        // Mappings saved before profiles existed take the fixture's profile
+        for mapping in &mut config.mappings {
+            if mapping.profile.is_none() {
+                mapping.profile = fixtures.iter()
+                    .find(|f| f.id == mapping.fixture_id)
+                    .map(|f| f.profile_name());
+            }
+        }
 
         Self {
             fixtures,
@@ -230,11 +276,18 @@ impl DmxMapPanel {
             show_add_fixture_dialog: false,
             new_fixture_name: String::new(),
             new_fixture_type: FixtureType::Light,
-            new_light_type: LightType::RGBW,
+            new_profile: PROFILE_RGBW.to_string(),
             new_channel_count: "4".to_string(),
             new_ignore_reset: false,
             show_delete_confirmation: false,
             fixture_to_delete_id: None,
+            profiles,
+            pending_corrections: Vec::new(),
+            pending_profiles: Vec::new(),
+            preview_enabled: false,
+            preview_color: [255, 255, 255],
+            preview_all: false,
+            preview_blocked: false,
         }
     }
 
@@ -255,6 +308,15 @@ impl DmxMapPanel {
                 self.show_fixture_list(ui);
             });
 
+        if self.selected_fixture_start.is_some() && self.selected_fixture_to_place.is_none() {
+            egui::SidePanel::right("fixture_correction_panel")
+                .resizable(false)
+                .exact_width(280.0)
+                .show_inside(ui, |ui| {
+                    self.show_correction_editor(ui);
+                });
+        }
+
         egui::CentralPanel::default().show_inside(ui, |ui| {
             self.show_dmx_grid(ctx, ui);
         });
@@ -368,10 +430,11 @@ impl DmxMapPanel {
                         }
 
                         // Show tooltip with assignment info
+                        let profile = fixture.profile_name();
                         if let Some(mapping) = self.config.mappings.iter().find(|m| m.fixture_id == fixture.id) {
-                            response.on_hover_text(format!("Assigned to channel {}\nClick to select for reassignment", mapping.start_channel));
+                            response.on_hover_text(format!("{}\nAssigned to channel {}\nClick to select for reassignment", profile, mapping.start_channel));
                         } else {
-                            response.on_hover_text("Click to select, then click a channel in the grid");
+                            response.on_hover_text(format!("{}\nClick to select, then click a channel in the grid", profile));
                         }
 
                         // Add delete button for custom fixtures
@@ -411,7 +474,7 @@ impl DmxMapPanel {
             self.show_add_fixture_dialog = true;
             self.new_fixture_name = String::new();
             self.new_fixture_type = FixtureType::Light;
-            self.new_light_type = LightType::RGBW;
+            self.new_profile = PROFILE_RGBW.to_string();
             self.new_channel_count = "4".to_string();
             self.new_ignore_reset = false;
         }
@@ -431,7 +494,7 @@ impl DmxMapPanel {
             ui.label(egui::RichText::new(format!("Selected: #{:02} {} - Click a channel to place", fixture.id, fixture.name))
                 .color(theme::AppColors::TEXT_PRIMARY));
         } else if self.selected_fixture_start.is_some() {
-            ui.label(egui::RichText::new("Press DELETE to remove selected fixture")
+            ui.label(egui::RichText::new("Adjust corrections on the right, or press DELETE to remove selected fixture")
                 .color(theme::AppColors::WARNING));
         } else {
             ui.label(egui::RichText::new("Click a fixture in the list, then click a channel to place it")
@@ -561,26 +624,15 @@ impl DmxMapPanel {
                                     if let Some(mapping) = self.config.mappings.iter().find(|m| m.fixture_id == *fixture_id) {
                                         let offset = channel - mapping.start_channel;
                                         
+                                        let role = mapping.profile.as_deref()
+                                            .and_then(|name| self.profiles.resolve(name))
+                                            .and_then(|profile| profile.channels.get(offset as usize).copied());
+                                        
                                         let color = if fixture.fixture_type == FixtureType::Other {
                                             // Yellow for "Other" type fixtures
                                             Color32::from_rgb(255, 215, 0)
-                                        } else if let Some(light_type) = &fixture.light_type {
-                                            // RGBW or RGB coloring
-                                            match light_type {
-                                                LightType::RGBW => match offset {
-                                                    0 => Color32::from_rgb(255, 0, 0),     // Red
-                                                    1 => Color32::from_rgb(0, 255, 0),     // Green
-                                                    2 => Color32::from_rgb(0, 100, 255),   // Blue
-                                                    3 => Color32::from_rgb(255, 255, 255), // White
-                                                    _ => Color32::GRAY,
-                                                },
-                                                LightType::RGB => match offset {
-                                                    0 => Color32::from_rgb(255, 0, 0),     // Red
-                                                    1 => Color32::from_rgb(0, 255, 0),     // Green
-                                                    2 => Color32::from_rgb(0, 100, 255),   // Blue
-                                                    _ => Color32::GRAY,
-                                                }
-                                            }
+                                        } else if let Some(role) = role {
+                                            role_color(role)
                                         } else {
                                             Color32::GRAY
                                         };
@@ -636,13 +688,11 @@ impl DmxMapPanel {
                             if let Some((fixture_id, fixture_name)) = channel_map.get(&channel) {
                                 if let Some(mapping) = self.config.mappings.iter().find(|m| m.fixture_id == *fixture_id) {
                                     let offset = channel - mapping.start_channel;
-                                    let channel_type = match offset {
-                                        0 => "Red",
-                                        1 => "Green",
-                                        2 => "Blue",
-                                        3 => "White",
-                                        _ => "Unknown",
-                                    };
+                                    let channel_type = mapping.profile.as_deref()
+                                        .and_then(|name| self.profiles.resolve(name))
+                                        .and_then(|profile| profile.channels.get(offset as usize))
+                                        .map(|role| role.label())
+                                        .unwrap_or_else(|| "Unknown".to_string());
                                     cell_response.on_hover_text(
                                         format!("#{:02} {}\nChannel: {} ({})", fixture_id, fixture_name, channel, channel_type)
                                     );
@@ -687,15 +737,168 @@ impl DmxMapPanel {
         true
     }
 
+    fn show_correction_editor(&mut self, ui: &mut Ui) {
+        let Some(start) = self.selected_fixture_start else { return };
+        let Some(index) = self.config.mappings.iter().position(|m| m.start_channel == start) else { return };
+        
+        Frame::none()
+            .fill(theme::AppColors::SURFACE)
+            .stroke(Stroke::new(1.0, theme::AppColors::SURFACE_LIGHT))
+            .rounding(12.0)
+            .inner_margin(16.0)
+            .show(ui, |ui| {
+                let mapping = &self.config.mappings[index];
+                ui.label(
+                    egui::RichText::new(format!("#{:02} {}", mapping.fixture_id, mapping.fixture_name))
+                        .size(16.0)
+                        .strong()
+                        .color(theme::AppColors::CYAN)
+                );
+                let footprint = self.fixtures.iter()
+                    .find(|f| f.id == mapping.fixture_id)
+                    .map(|f| f.channel_count as usize)
+                    .unwrap_or(4);
+                let current = mapping.profile.clone().unwrap_or_else(|| "No profile".to_string());
+                let mut chosen = None;
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_salt("mapping_profile")
+                        .selected_text(&current)
+                        .width(180.0)
+                        .show_ui(ui, |ui| {
+                            // Only profiles that fit the channels the fixture takes up in the grid
+                            for profile in self.profiles.profiles().filter(|p| p.channel_count() <= footprint) {
+                                if ui.selectable_label(current.eq_ignore_ascii_case(&profile.name), &profile.name).clicked() {
+                                    chosen = Some(profile.clone());
+                                }
+                            }
+                        })
+                        .response
+                        .on_hover_text(format!("Profiles up to {} channels fit this fixture", footprint));
+                    ui.label(
+                        egui::RichText::new(format!("at channel {}", mapping.start_channel))
+                            .color(theme::AppColors::TEXT_SECONDARY)
+                            .size(12.0)
+                    );
+                });
+                if let Some(profile) = chosen {
+                    let fixture_id = mapping.fixture_id as u16;
+                    self.config.mappings[index].profile = Some(profile.name.clone());
+                    self.pending_profiles.push((fixture_id, profile));
+                }
+                let mapping = &self.config.mappings[index];
+                ui.add_space(12.0);
+                
+                let mut correction = mapping.correction.clone();
+                let mut changed = false;
+                
+                ui.label(egui::RichText::new("Trim").strong().color(theme::AppColors::TEXT_PRIMARY));
+                for (i, label) in ["R", "G", "B", "W"].iter().enumerate() {
+                    changed |= ui.add(egui::Slider::new(&mut correction.trim[i], 0.0..=1.5).text(*label)).changed();
+                }
+                ui.add_space(8.0);
+                
+                ui.label(egui::RichText::new("Gamma").strong().color(theme::AppColors::TEXT_PRIMARY));
+                changed |= ui.add(egui::Slider::new(&mut correction.gamma, 0.5..=3.0)).changed();
+                ui.add_space(8.0);
+                
+                ui.label(egui::RichText::new("Max Output").strong().color(theme::AppColors::TEXT_PRIMARY));
+                for (i, label) in ["R", "G", "B", "W"].iter().enumerate() {
+                    changed |= ui.add(egui::Slider::new(&mut correction.max_output[i], 0..=255).text(*label)).changed();
+                }
+                ui.add_space(12.0);
+                
+                ui.horizontal(|ui| {
+                    if ui.button("Reset").clicked() {
+                        correction = FixtureCorrection::default();
+                        changed = true;
+                    }
+                    if ui.button("💾 Save").clicked() {
+                        self.save_config();
+                    }
+                });
+                
+                if changed {
+                    let fixture_id = self.config.mappings[index].fixture_id as u16;
+                    self.config.mappings[index].correction = correction.clone();
+                    self.pending_corrections.push((fixture_id, correction));
+                }
+                
+                ui.add_space(12.0);
+                ui.separator();
+                ui.add_space(8.0);
+                
+                ui.checkbox(&mut self.preview_enabled, 
+                    egui::RichText::new("Preview test color").color(theme::AppColors::TEXT_PRIMARY));
+                ui.add_enabled_ui(self.preview_enabled, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.color_edit_button_srgb(&mut self.preview_color);
+                        ui.radio_value(&mut self.preview_all, false, "This fixture");
+                        ui.radio_value(&mut self.preview_all, true, "All");
+                    });
+                });
+                if self.preview_blocked {
+                    ui.label(
+                        egui::RichText::new("Safety blackout is on; the preview is held off until it is released")
+                            .size(11.0)
+                            .color(theme::AppColors::WARNING)
+                    );
+                } else {
+                    ui.label(
+                        egui::RichText::new("Preview overrides the live output so batches can be matched side by side")
+                            .size(11.0)
+                            .color(theme::AppColors::TEXT_SECONDARY)
+                    );
+                }
+            });
+    }
+    
+    /// Correction edits since the last call, as (fixture number, correction)
+    pub fn take_correction_updates(&mut self) -> Vec<(u16, FixtureCorrection)> {
+        std::mem::take(&mut self.pending_corrections)
+    }
+    
+    /// Profile changes since the last call, as (fixture number, profile)
+    pub fn take_profile_updates(&mut self) -> Vec<(u16, FixtureProfile)> {
+        std::mem::take(&mut self.pending_profiles)
+    }
+    
+    /// Tell the panel whether the safety blackout is holding the preview off
+    pub fn set_preview_blocked(&mut self, blocked: bool) {
+        self.preview_blocked = blocked;
+    }
+    
+    /// Test color and the fixtures it should be shown on, while preview is enabled
+    pub fn preview_request(&self) -> Option<([u8; 3], Vec<u16>)> {
+        if !self.preview_enabled {
+            return None;
+        }
+        
+        let fixtures = if self.preview_all {
+            self.config.mappings.iter().map(|m| m.fixture_id as u16).collect()
+        } else {
+            let start = self.selected_fixture_start?;
+            let mapping = self.config.mappings.iter().find(|m| m.start_channel == start)?;
+            vec![mapping.fixture_id as u16]
+        };
+        Some((self.preview_color, fixtures))
+    }
+
     fn place_fixture(&mut self, fixture: FixtureDefinition, start_channel: u16) {
+        // Keep the calibration and profile when a fixture is moved
+        let existing = self.config.mappings.iter().find(|m| m.fixture_id == fixture.id);
+        let correction = existing.map(|m| m.correction.clone()).unwrap_or_default();
+        let profile = existing.and_then(|m| m.profile.clone()).unwrap_or_else(|| fixture.profile_name());
+        
         // Remove existing mapping for this fixture if any
         self.config.mappings.retain(|m| m.fixture_id != fixture.id);
 
         // Add new mapping
         self.config.mappings.push(FixtureMapping {
             fixture_id: fixture.id,
-            fixture_name: fixture.name,
+            fixture_name: fixture.name.clone(),
             start_channel,
+            profile: Some(profile),
+            correction,
         });
 
         // Sort by start channel
@@ -782,14 +985,25 @@ impl DmxMapPanel {
                 // Light-specific options
                 if self.new_fixture_type == FixtureType::Light {
                     ui.label(egui::RichText::new("Light Type:").color(theme::AppColors::TEXT_PRIMARY));
-                    ui.horizontal(|ui| {
-                        if ui.radio_value(&mut self.new_light_type, LightType::RGBW, "RGBW").clicked() {
-                            self.new_channel_count = "4".to_string();
-                        }
-                        if ui.radio_value(&mut self.new_light_type, LightType::RGB, "RGB").clicked() {
-                            self.new_channel_count = "3".to_string();
-                        }
-                    });
+                    egui::ComboBox::from_id_salt("new_fixture_profile")
+                        .selected_text(&self.new_profile)
+                        .width(250.0)
+                        .show_ui(ui, |ui| {
+                            for profile in self.profiles.profiles().filter(|p| p.is_color()) {
+                                if ui.selectable_label(self.new_profile == profile.name, &profile.name).clicked() {
+                                    self.new_profile = profile.name.clone();
+                                    self.new_channel_count = profile.channel_count().to_string();
+                                }
+                            }
+                        });
+                    if let Some(profile) = self.profiles.get(&self.new_profile) {
+                        let roles: Vec<String> = profile.channels.iter().map(|r| r.label()).collect();
+                        ui.label(
+                            egui::RichText::new(format!("{} channels: {}", profile.channel_count(), roles.join(" ")))
+                                .size(11.0)
+                                .color(theme::AppColors::TEXT_SECONDARY)
+                        );
+                    }
                 } else {
                     // Other type - manual channel count
                     ui.label(egui::RichText::new("Number of Channels:").color(theme::AppColors::TEXT_PRIMARY));
@@ -893,8 +1107,9 @@ impl DmxMapPanel {
         let next_id = self.fixtures.iter().map(|f| f.id).max().unwrap_or(54) + 1;
         
         // Parse channel count
+        let profile = self.profiles.get(&self.new_profile).cloned();
         let channel_count = if self.new_fixture_type == FixtureType::Light {
-            if self.new_light_type == LightType::RGBW { 4 } else { 3 }
+            profile.as_ref().map(|p| p.channel_count() as u8).unwrap_or(4)
         } else {
             self.new_channel_count.parse::<u8>().unwrap_or(1).max(1).min(32)
         };
@@ -903,10 +1118,10 @@ impl DmxMapPanel {
             id: next_id,
             name: self.new_fixture_name.clone(),
             fixture_type: self.new_fixture_type.clone(),
-            light_type: if self.new_fixture_type == FixtureType::Light {
-                Some(self.new_light_type.clone())
-            } else {
-                None
+            light_type: match (&self.new_fixture_type, &profile) {
+                (FixtureType::Light, Some(p)) if !p.channels.contains(&ChannelRole::White) => Some(LightType::RGB),
+                (FixtureType::Light, _) => Some(LightType::RGBW),
+                (FixtureType::Other, _) => None,
             },
             channel_count,
             ignore_reset: if self.new_fixture_type == FixtureType::Other {
@@ -914,6 +1129,10 @@ impl DmxMapPanel {
             } else {
                 false
             },
+            profile: match (&self.new_fixture_type, profile) {
+                (FixtureType::Light, Some(p)) => Some(p.name),
+                _ => None,
+            },
         };
         
         // Add to fixtures list
@@ -947,3 +1166,16 @@ impl DmxMapPanel {
     }
 }
 
+/// Grid marker color for a profile channel
+fn role_color(role: ChannelRole) -> Color32 {
+    match role {
+        ChannelRole::Red | ChannelRole::RedFine => Color32::from_rgb(255, 0, 0),
+        ChannelRole::Green | ChannelRole::GreenFine => Color32::from_rgb(0, 255, 0),
+        ChannelRole::Blue | ChannelRole::BlueFine => Color32::from_rgb(0, 100, 255),
+        ChannelRole::White | ChannelRole::WhiteFine => Color32::from_rgb(255, 255, 255),
+        ChannelRole::Amber | ChannelRole::AmberFine => Color32::from_rgb(255, 170, 0),
+        ChannelRole::Uv => Color32::from_rgb(140, 60, 255),
+        ChannelRole::Intensity | ChannelRole::IntensityFine => Color32::from_rgb(255, 240, 180),
+        ChannelRole::Dimmer | ChannelRole::DimmerFine | ChannelRole::Strobe | ChannelRole::Fixed(_) => Color32::GRAY,
+    }
+}
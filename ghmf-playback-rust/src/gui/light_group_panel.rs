@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
 use super::theme;
+use crate::lighting::FadeCurve;
 
 // Structure from DMX mapping file
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,6 +44,8 @@ pub struct LightGroup {
     pub fcw_code: String,
     pub fcw_fade_code: String,
     pub fixture_ids: Vec<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fade_curve: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,6 +75,7 @@ pub struct LightGroupPanel {
     group_name: String,
     fcw_code: String,
     fcw_fade_code: String,
+    fade_curve: Option<FadeCurve>, // None = use the global default
     
     // Available fixtures (from DMX map)
     available_fixtures: Vec<FixtureDefinition>,
@@ -107,6 +111,7 @@ impl LightGroupPanel {
             group_name: String::new(),
             fcw_code: String::new(),
             fcw_fade_code: String::new(),
+            fade_curve: None,
             available_fixtures,
             selected_fixtures: Vec::new(),
             editing_group_index: None,
@@ -153,6 +158,11 @@ impl LightGroupPanel {
         fixtures
     }
     
+    /// Groups as last loaded or saved
+    pub fn groups(&self) -> &[LightGroup] {
+        &self.config.groups
+    }
+    
     fn load_config(path: &str) -> LightGroupConfig {
         if Path::new(path).exists() {
             if let Ok(content) = fs::read_to_string(path) {
@@ -238,6 +248,18 @@ impl LightGroupPanel {
                         ui.label(egui::RichText::new("Fade Code").color(theme::AppColors::TEXT_PRIMARY));
                         ui.add(egui::TextEdit::singleline(&mut self.fcw_fade_code).desired_width(100.0));
                     });
+                    ui.add_space(10.0);
+                    ui.vertical(|ui| {
+                        ui.label(egui::RichText::new("Fade Curve").color(theme::AppColors::TEXT_PRIMARY));
+                        egui::ComboBox::from_id_salt("group_fade_curve")
+                            .selected_text(self.fade_curve.map(|c| c.label()).unwrap_or("Default"))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut self.fade_curve, None, "Default");
+                                for curve in FadeCurve::ALL {
+                                    ui.selectable_value(&mut self.fade_curve, Some(curve), curve.label());
+                                }
+                            });
+                    });
                 });
             });
         
@@ -517,6 +539,7 @@ impl LightGroupPanel {
                     fcw_code: self.fcw_code.clone(),
                     fcw_fade_code: self.fcw_fade_code.clone(),
                     fixture_ids: self.selected_fixtures.clone(),
+                    fade_curve: self.fade_curve.map(|c| c.as_setting().to_string()),
                 };
                 self.success_message = Some("Group updated successfully".to_string());
             }
@@ -533,6 +556,7 @@ impl LightGroupPanel {
                 fcw_code: self.fcw_code.clone(),
                 fcw_fade_code: self.fcw_fade_code.clone(),
                 fixture_ids: self.selected_fixtures.clone(),
+                fade_curve: self.fade_curve.map(|c| c.as_setting().to_string()),
             };
             
             self.config.groups.push(new_group);
@@ -555,6 +579,7 @@ impl LightGroupPanel {
             self.group_name = group.name.clone();
             self.fcw_code = group.fcw_code.clone();
             self.fcw_fade_code = group.fcw_fade_code.clone();
+            self.fade_curve = group.fade_curve.as_deref().and_then(FadeCurve::from_setting);
             self.selected_fixtures = group.fixture_ids.clone();
             self.editing_group_index = Some(index);
         }
@@ -564,6 +589,7 @@ impl LightGroupPanel {
         self.group_name.clear();
         self.fcw_code.clear();
         self.fcw_fade_code.clear();
+        self.fade_curve = None;
         self.selected_fixtures.clear();
         self.editing_group_index = None;
     }
@@ -1,231 +1,244 @@
 use super::theme;
-use crate::dmx::EnttecDmxPro;
+use super::light_group_panel::LightGroup;
+use crate::lighting::{FixtureManager, Layer, MergeRule};
 use egui::{Ui, RichText, Slider, Color32};
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
-pub fn show(
-    ui: &mut Ui,
-    selected_fixture: &mut Option<usize>,
-    fixture_red: &mut u8,
-    fixture_green: &mut u8,
-    fixture_blue: &mut u8,
-    dmx_controller: &Option<Arc<Mutex<EnttecDmxPro>>>,
-) {
-    ui.vertical(|ui| {
-        ui.heading(RichText::new("Lighting Control").size(18.0));
-        ui.add_space(10.0);
-        ui.separator();
-        ui.add_space(10.0);
-        
-        // Fixture selector
-        ui.group(|ui| {
-            ui.label(RichText::new("Fixture").size(14.0).strong());
-            ui.add_space(5.0);
-            
-            ui.horizontal(|ui| {
-                if ui.button("1").clicked() {
-                    *selected_fixture = Some(1);
-                }
-                if ui.button("2").clicked() {
-                    *selected_fixture = Some(2);
-                }
-                if ui.button("3").clicked() {
-                    *selected_fixture = Some(3);
-                }
-                if ui.button("4").clicked() {
-                    *selected_fixture = Some(4);
-                }
-                if ui.button("5").clicked() {
-                    *selected_fixture = Some(5);
-                }
-            });
-            
-            ui.horizontal(|ui| {
-                if ui.button("6").clicked() {
-                    *selected_fixture = Some(6);
-                }
-                if ui.button("7").clicked() {
-                    *selected_fixture = Some(7);
-                }
-                if ui.button("8").clicked() {
-                    *selected_fixture = Some(8);
-                }
-                if ui.button("9").clicked() {
-                    *selected_fixture = Some(9);
-                }
-                if ui.button("10").clicked() {
-                    *selected_fixture = Some(10);
-                }
-            });
-            
-            if let Some(fixture) = selected_fixture {
-                ui.label(RichText::new(format!("Selected: Fixture {}", fixture))
-                    .color(theme::AppColors::PRIMARY_LIGHT));
-            } else {
-                ui.label(RichText::new("No fixture selected")
-                    .color(theme::AppColors::TEXT_DISABLED));
-            }
-        });
-        
-        ui.add_space(15.0);
-        
-        // RGB controls
-        if selected_fixture.is_some() {
-            ui.group(|ui| {
-                ui.label(RichText::new("RGB Color").size(14.0).strong());
-                ui.add_space(10.0);
-                
-                // Red slider
-                ui.horizontal(|ui| {
-                    ui.label("R:");
-                    let mut red_f = *fixture_red as f32;
-                    if ui.add(Slider::new(&mut red_f, 0.0..=255.0)).changed() {
-                        *fixture_red = red_f as u8;
-                        update_dmx(selected_fixture, *fixture_red, *fixture_green, *fixture_blue, dmx_controller);
-                    }
-                });
-                
-                // Green slider
-                ui.horizontal(|ui| {
-                    ui.label("G:");
-                    let mut green_f = *fixture_green as f32;
-                    if ui.add(Slider::new(&mut green_f, 0.0..=255.0)).changed() {
-                        *fixture_green = green_f as u8;
-                        update_dmx(selected_fixture, *fixture_red, *fixture_green, *fixture_blue, dmx_controller);
-                    }
+/// A labelled quick color button
+type QuickColor = (&'static str, (u8, u8, u8));
+
+/// Quick color buttons, one row per line
+const QUICK_COLORS: [&[QuickColor]; 3] = [
+    &[("Off", (0, 0, 0)), ("White", (255, 255, 255))],
+    &[("Red", (255, 0, 0)), ("Green", (0, 255, 0)), ("Blue", (0, 0, 255))],
+    &[("Yellow", (255, 255, 0)), ("Cyan", (0, 255, 255)), ("Magenta", (255, 0, 255))],
+];
+
+/// What the manual controls act on
+#[derive(Debug, Clone, PartialEq)]
+enum Target {
+    Fixture(u16),
+    Group { name: String, address: u16 },
+}
+
+/// Manual light control for the Testing view
+///
+/// Colors are held on the override layer above the running show, so an
+/// operator can take a group during a live show and release it back to the
+/// choreography with a fade.
+pub struct LightingPanel {
+    target: Option<Target>,
+    red: u8,
+    green: u8,
+    blue: u8,
+    rule: MergeRule,
+    release_secs: f32,
+}
+
+impl Default for LightingPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LightingPanel {
+    pub fn new() -> Self {
+        Self {
+            target: None,
+            red: 0,
+            green: 0,
+            blue: 0,
+            rule: MergeRule::Ltp,
+            release_secs: 2.0,
+        }
+    }
+
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        fixture_manager: &Option<Arc<Mutex<FixtureManager>>>,
+        groups: &[LightGroup],
+    ) {
+        let Some(fm) = fixture_manager else {
+            ui.label(RichText::new("Lighting configuration not loaded")
+                .color(theme::AppColors::TEXT_DISABLED));
+            return;
+        };
+        let Ok(mut fm) = fm.lock() else { return };
+
+        ui.vertical(|ui| {
+            ui.heading(RichText::new("Lighting Control").size(18.0));
+            ui.add_space(10.0);
+            ui.separator();
+            ui.add_space(10.0);
+
+            self.show_target_selector(ui, &fm, groups);
+
+            ui.add_space(15.0);
+
+            if let Some(target) = self.target.clone() {
+                let fixtures = match &target {
+                    Target::Fixture(fixture_num) => vec![*fixture_num],
+                    Target::Group { address, .. } => fm.group_fixtures(*address),
+                };
+                let is_held = fixtures.iter().any(|f| fm.held_fixtures(Layer::Override).contains(f));
+
+                let changed = self.show_color_controls(ui);
+
+                ui.add_space(15.0);
+
+                ui.group(|ui| {
+                    ui.label(RichText::new("Override").size(14.0).strong());
+                    ui.add_space(5.0);
+
+                    ui.horizontal(|ui| {
+                        ui.label("Merge:");
+                        for rule in [MergeRule::Ltp, MergeRule::Htp] {
+                            ui.radio_value(&mut self.rule, rule, rule.label());
+                        }
+                    });
+                    ui.label(RichText::new(match self.rule {
+                        MergeRule::Ltp => "Hold replaces the show",
+                        MergeRule::Htp => "Brightest of show and hold, per channel",
+                    }).size(12.0).color(theme::AppColors::TEXT_DISABLED));
+
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        // Color changes follow live once the target is held
+                        if ui.button("✋ Hold").clicked() || (changed && is_held) {
+                            fm.hold_fixtures(Layer::Override, &fixtures, self.red, self.green, self.blue, self.rule);
+                        }
+                        if ui.add_enabled(is_held, egui::Button::new("↩ Release")).clicked() {
+                            let fade_ms = (self.release_secs * 1000.0) as u64;
+                            fm.release_fixtures(Layer::Override, &fixtures, fade_ms, Instant::now());
+                        }
+                    });
+                    ui.add(Slider::new(&mut self.release_secs, 0.0..=10.0).text("release fade (s)"));
                 });
-                
-                // Blue slider
-                ui.horizontal(|ui| {
-                    ui.label("B:");
-                    let mut blue_f = *fixture_blue as f32;
-                    if ui.add(Slider::new(&mut blue_f, 0.0..=255.0)).changed() {
-                        *fixture_blue = blue_f as u8;
-                        update_dmx(selected_fixture, *fixture_red, *fixture_green, *fixture_blue, dmx_controller);
-                    }
+            } else {
+                ui.vertical_centered(|ui| {
+                    ui.add_space(40.0);
+                    ui.label(RichText::new("Select a fixture or group to control")
+                        .size(14.0)
+                        .color(theme::AppColors::TEXT_DISABLED));
                 });
-                
-                ui.add_space(10.0);
-                
-                // Color preview
-                let color = Color32::from_rgb(*fixture_red, *fixture_green, *fixture_blue);
-                let (rect, _) = ui.allocate_exact_size(
-                    egui::vec2(ui.available_width(), 60.0),
-                    egui::Sense::hover()
-                );
-                ui.painter().rect_filled(rect, 4.0, color);
-                
-                ui.add_space(5.0);
-                ui.label(format!("#{:02X}{:02X}{:02X}", fixture_red, fixture_green, fixture_blue));
-            });
-            
+            }
+
             ui.add_space(15.0);
-            
-            // Quick colors
-            ui.group(|ui| {
-                ui.label(RichText::new("Quick Colors").size(14.0).strong());
-                ui.add_space(5.0);
-                
-                ui.horizontal(|ui| {
-                    if ui.button("Off").clicked() {
-                        *fixture_red = 0;
-                        *fixture_green = 0;
-                        *fixture_blue = 0;
-                        update_dmx(selected_fixture, 0, 0, 0, dmx_controller);
+
+            let held = fm.held_fixtures(Layer::Override);
+            if !held.is_empty() {
+                let list: Vec<String> = held.iter().map(|f| f.to_string()).collect();
+                ui.label(RichText::new(format!("Held: {}", list.join(", ")))
+                    .color(theme::AppColors::WARNING));
+                if ui.button("Release All").clicked() {
+                    let fade_ms = (self.release_secs * 1000.0) as u64;
+                    fm.release_layer(Layer::Override, fade_ms, Instant::now());
+                }
+                ui.add_space(10.0);
+            }
+
+            ui.add_space(10.0);
+
+            // Safety layer sits above the show and every hold
+            let blackout = fm.is_safety_blackout();
+            let label = if blackout { "🔴 Blackout ON - click to restore" } else { "🔴 Blackout" };
+            let button = egui::Button::new(RichText::new(label).color(Color32::WHITE))
+                .fill(if blackout { theme::AppColors::ERROR } else { theme::AppColors::SURFACE_LIGHT });
+            if ui.add(button).clicked() {
+                fm.set_safety_blackout(!blackout);
+            }
+        });
+    }
+
+    fn show_target_selector(&mut self, ui: &mut Ui, fm: &FixtureManager, groups: &[LightGroup]) {
+        ui.group(|ui| {
+            ui.label(RichText::new("Target").size(14.0).strong());
+            ui.add_space(5.0);
+
+            let mut fixtures = fm.get_all_fixture_numbers();
+            fixtures.sort_unstable();
+
+            let selected_text = match &self.target {
+                Some(Target::Fixture(fixture_num)) => format!("Fixture {}", fixture_num),
+                Some(Target::Group { name, .. }) => name.clone(),
+                None => "None".to_string(),
+            };
+
+            egui::ComboBox::from_id_salt("manual_light_target")
+                .selected_text(selected_text)
+                .width(ui.available_width() - 10.0)
+                .show_ui(ui, |ui| {
+                    for group in groups {
+                        if let Ok(address) = group.fcw_code.parse::<u16>() {
+                            let target = Target::Group { name: group.name.clone(), address };
+                            let label = format!("{} ({})", group.name, group.fcw_code);
+                            ui.selectable_value(&mut self.target, Some(target), label);
+                        }
                     }
-                    if ui.button("White").clicked() {
-                        *fixture_red = 255;
-                        *fixture_green = 255;
-                        *fixture_blue = 255;
-                        update_dmx(selected_fixture, 255, 255, 255, dmx_controller);
+                    ui.separator();
+                    for fixture_num in fixtures {
+                        ui.selectable_value(&mut self.target, Some(Target::Fixture(fixture_num)), format!("Fixture {}", fixture_num));
                     }
                 });
-                
+
+            if let Some(Target::Group { address, .. }) = &self.target {
+                let fixtures: Vec<String> = fm.group_fixtures(*address).iter().map(|f| f.to_string()).collect();
+                ui.label(RichText::new(format!("Fixtures: {}", fixtures.join(", ")))
+                    .color(theme::AppColors::PRIMARY_LIGHT));
+            }
+        });
+    }
+
+    /// RGB sliders and quick colors; returns true when the color changed
+    fn show_color_controls(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+
+        ui.group(|ui| {
+            ui.label(RichText::new("RGB Color").size(14.0).strong());
+            ui.add_space(10.0);
+
+            for (label, value) in [("R:", &mut self.red), ("G:", &mut self.green), ("B:", &mut self.blue)] {
                 ui.horizontal(|ui| {
-                    if ui.button("Red").clicked() {
-                        *fixture_red = 255;
-                        *fixture_green = 0;
-                        *fixture_blue = 0;
-                        update_dmx(selected_fixture, 255, 0, 0, dmx_controller);
-                    }
-                    if ui.button("Green").clicked() {
-                        *fixture_red = 0;
-                        *fixture_green = 255;
-                        *fixture_blue = 0;
-                        update_dmx(selected_fixture, 0, 255, 0, dmx_controller);
-                    }
-                    if ui.button("Blue").clicked() {
-                        *fixture_red = 0;
-                        *fixture_green = 0;
-                        *fixture_blue = 255;
-                        update_dmx(selected_fixture, 0, 0, 255, dmx_controller);
-                    }
+                    ui.label(label);
+                    changed |= ui.add(Slider::new(value, 0..=255)).changed();
                 });
-                
+            }
+
+            ui.add_space(10.0);
+
+            // Color preview
+            let color = Color32::from_rgb(self.red, self.green, self.blue);
+            let (rect, _) = ui.allocate_exact_size(
+                egui::vec2(ui.available_width(), 60.0),
+                egui::Sense::hover()
+            );
+            ui.painter().rect_filled(rect, 4.0, color);
+
+            ui.add_space(5.0);
+            ui.label(format!("#{:02X}{:02X}{:02X}", self.red, self.green, self.blue));
+        });
+
+        ui.add_space(15.0);
+
+        // Quick colors
+        ui.group(|ui| {
+            ui.label(RichText::new("Quick Colors").size(14.0).strong());
+            ui.add_space(5.0);
+
+            for row in QUICK_COLORS {
                 ui.horizontal(|ui| {
-                    if ui.button("Yellow").clicked() {
-                        *fixture_red = 255;
-                        *fixture_green = 255;
-                        *fixture_blue = 0;
-                        update_dmx(selected_fixture, 255, 255, 0, dmx_controller);
-                    }
-                    if ui.button("Cyan").clicked() {
-                        *fixture_red = 0;
-                        *fixture_green = 255;
-                        *fixture_blue = 255;
-                        update_dmx(selected_fixture, 0, 255, 255, dmx_controller);
-                    }
-                    if ui.button("Magenta").clicked() {
-                        *fixture_red = 255;
-                        *fixture_green = 0;
-                        *fixture_blue = 255;
-                        update_dmx(selected_fixture, 255, 0, 255, dmx_controller);
+                    for (label, (r, g, b)) in row {
+                        if ui.button(*label).clicked() {
+                            (self.red, self.green, self.blue) = (*r, *g, *b);
+                            changed = true;
+                        }
                     }
                 });
-            });
-        } else {
-            ui.vertical_centered(|ui| {
-                ui.add_space(40.0);
-                ui.label(RichText::new("Select a fixture to control")
-                    .size(14.0)
-                    .color(theme::AppColors::TEXT_DISABLED));
-            });
-        }
-        
-        ui.add_space(20.0);
-        
-        // All off button
-        if ui.button("🔴 All Lights Off").clicked() {
-            if let Some(dmx) = dmx_controller {
-                if let Ok(mut dmx) = dmx.lock() {
-                    dmx.clear();
-                    let _ = dmx.send_dmx();
-                }
             }
-            *fixture_red = 0;
-            *fixture_green = 0;
-            *fixture_blue = 0;
-        }
-    });
-}
+        });
 
-fn update_dmx(
-    selected_fixture: &Option<usize>,
-    red: u8,
-    green: u8,
-    blue: u8,
-    dmx_controller: &Option<Arc<Mutex<EnttecDmxPro>>>,
-) {
-    if let Some(fixture) = selected_fixture {
-        if let Some(dmx) = dmx_controller {
-            if let Ok(mut dmx) = dmx.lock() {
-                // Assuming RGB fixtures with 3 consecutive channels
-                let base_channel = (*fixture - 1) * 3 + 1;
-                let _ = dmx.set_channel(base_channel, red);
-                let _ = dmx.set_channel(base_channel + 1, green);
-                let _ = dmx.set_channel(base_channel + 2, blue);
-                let _ = dmx.send_dmx();
-            }
-        }
+        changed
     }
 }
@@ -14,6 +14,8 @@ mod start_time_panel;
 mod procedures_panel;
 mod operator_panel;
 mod lights_layout_panel;
+mod scene_panel;
+mod pyro_panel;
 
 pub use app::PlaybackApp;
 pub use sidebar::{AppView, Sidebar};
@@ -4,8 +4,13 @@ use egui_plot::{Plot, Line, PlotPoints, Legend};
 use crate::gui::theme;
 use crate::gui::playback_panel::{self, PlaybackPanelState};
 use crate::gui::procedures_panel::ProcedureEntry;
+use crate::gui::scene_panel::ScenePanel;
+use crate::gui::pyro_panel::PyroPanel;
 use crate::audio::AudioPlayer;
-use crate::lighting::FixtureManager;
+use crate::config::WaterMap;
+use crate::lighting::{FixtureManager, IdleLook};
+use crate::plc::PlcEvent;
+use crate::weather::weather_gov;
 use std::time::Duration;
 use chrono::{Local, Timelike, NaiveTime, Datelike};
 use serde::{Deserialize, Serialize};
@@ -57,11 +62,12 @@ pub struct AnnouncementFile {
     pub path: String,
 }
 
-/// PLC output log entry
+/// PLC log entry: a fault, rejected batch or link change
 #[derive(Clone, Debug)]
 pub struct PlcLogEntry {
+    /// Song position when the app picked the event up
     pub timestamp: Duration,
-    pub ctl_codes: Vec<String>,
+    pub event: PlcEvent,
 }
 
 /// DMX fixture state
@@ -149,6 +155,9 @@ pub struct OperatorPanel {
     pub weather_rx: Receiver<WeatherInfo>,
     pub weather_tx: Sender<WeatherInfo>,
     pub weather_fetch_pending: bool,
+    /// Wind from the wind source and the limits in effect, set by the app;
+    /// true while heights are limited
+    pub wind_status: Option<(String, bool)>,
     
     // Playlist
     pub current_playlist: Vec<PlaylistSong>,
@@ -174,6 +183,12 @@ pub struct OperatorPanel {
     pub lights_layout: LightsLayout,
     pub dmx_expanded: bool, // Toggle state for DMX fixture layout visibility
     
+    // Scene recall
+    pub scenes: ScenePanel,
+    
+    // Firework arm/disarm
+    pub pyro: PyroPanel,
+    
     // Start Show With selector
     pub available_playlists: Vec<String>,
     pub selected_playlist_index: usize,
@@ -214,6 +229,7 @@ impl Default for OperatorPanel {
             weather_rx,
             weather_tx,
             weather_fetch_pending: false,
+            wind_status: None,
             current_playlist: Vec::new(),
             current_playlist_date: None,
             current_playlist_theme: None,
@@ -230,6 +246,8 @@ impl Default for OperatorPanel {
             available_fixtures: Vec::new(),
             lights_layout: LightsLayout::load(),
             dmx_expanded: false, // Start collapsed
+            scenes: ScenePanel::new(),
+            pyro: PyroPanel::new(),
             available_playlists: vec![
                 "Pre-Show".to_string(),
                 "Playlist".to_string(),
@@ -840,6 +858,7 @@ impl OperatorPanel {
         playback_panel_state: &mut PlaybackPanelState,
         current_song_path: &Option<PathBuf>,
         recent_commands: &[(u64, String)],
+        water: &WaterMap,
         fixture_manager: Option<&Arc<Mutex<FixtureManager>>>,
     ) -> (Option<String>, Option<usize>, bool) { // Added bool for step button
         // Load icons if not already loaded
@@ -882,7 +901,7 @@ impl OperatorPanel {
         
         egui::CentralPanel::default().show_inside(ui, |ui| {
             if self.show_main_content(ui, is_playing, is_paused, playback_position, playback_duration, 
-                current_song, current_playlist, audio_player, playback_panel_state, current_song_path, recent_commands, fixture_manager) {
+                current_song, current_playlist, audio_player, playback_panel_state, current_song_path, recent_commands, water, fixture_manager) {
                 step_clicked = true;
             }
         });
@@ -974,6 +993,7 @@ impl OperatorPanel {
         playback_panel_state: &mut PlaybackPanelState,
         current_song_path: &Option<PathBuf>,
         recent_commands: &[(u64, String)],
+        water: &WaterMap,
         fixture_manager: Option<&Arc<Mutex<FixtureManager>>>,
     ) -> bool {
         let mut step_clicked = false;
@@ -992,6 +1012,7 @@ impl OperatorPanel {
                 playback_panel_state,
                 current_song_path,
                 recent_commands,
+                water,
             ) {
                 step_clicked = true;
             }
@@ -999,7 +1020,19 @@ impl OperatorPanel {
             ui.add_space(8.0);
             
             // 4. DMX Output Section
-            self.show_dmx_output(ui, fixture_manager, recent_commands);
+            self.show_dmx_output(ui, fixture_manager, recent_commands, water);
+            ui.add_space(8.0);
+            
+            // 5. Scene recall and idle look
+            self.scenes.show_recall(ui, fixture_manager);
+            self.show_idle_toggle(ui, fixture_manager);
+            
+            // 6. Pyro interlock
+            ui.add_space(8.0);
+            self.pyro.show(ui, fixture_manager);
+            
+            // 7. PLC replies and faults
+            self.show_plc_log(ui);
             ui.add_space(20.0);
         });
         
@@ -1100,6 +1133,15 @@ impl OperatorPanel {
                             .size(13.0)
                             .color(Color32::WHITE));
                     });
+                    
+                    // Wind at the fountain, when a wind source is set up
+                    if let Some((status, limited)) = &self.wind_status {
+                        ui.add_space(4.0);
+                        let color = if *limited { theme::AppColors::WARNING } else { theme::AppColors::TEXT_SECONDARY };
+                        ui.label(RichText::new(status)
+                            .size(13.0)
+                            .color(color));
+                    }
                 });
             });
     }
@@ -1328,7 +1370,7 @@ impl OperatorPanel {
         playlist_to_load
     }
     
-    fn show_dmx_output(&mut self, ui: &mut Ui, fixture_manager: Option<&Arc<Mutex<FixtureManager>>>, recent_commands: &[(u64, String)]) {
+    fn show_dmx_output(&mut self, ui: &mut Ui, fixture_manager: Option<&Arc<Mutex<FixtureManager>>>, recent_commands: &[(u64, String)], water: &WaterMap) {
         // Load fixtures if not already loaded
         if self.available_fixtures.is_empty() {
             self.load_fixtures();
@@ -1365,12 +1407,81 @@ impl OperatorPanel {
                     // Only show CTL command log if expanded
                     if self.dmx_expanded {
                         ui.add_space(12.0);
-                        self.render_ctl_command_log(ui, recent_commands);
+                        self.render_ctl_command_log(ui, recent_commands, water);
                     }
                 });
             });
     }
     
+    /// Add PLC link events to the log, dropping the oldest past the limit
+    pub fn push_plc_events(&mut self, events: Vec<PlcEvent>, position: Duration) {
+        self.plc_log.extend(events.into_iter().map(|event| PlcLogEntry { timestamp: position, event }));
+        let excess = self.plc_log.len().saturating_sub(self.max_plc_log_entries);
+        self.plc_log.drain(..excess);
+    }
+    
+    /// Log of PLC faults, rejected batches and link changes, newest first
+    fn show_plc_log(&mut self, ui: &mut Ui) {
+        if self.plc_log.is_empty() {
+            return;
+        }
+        
+        ui.add_space(8.0);
+        egui::Frame::none()
+            .fill(theme::AppColors::SURFACE)
+            .stroke(Stroke::new(1.0, theme::AppColors::SURFACE_LIGHT))
+            .rounding(8.0)
+            .inner_margin(12.0)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("PLC Log").size(14.0).color(theme::AppColors::TEXT_SECONDARY));
+                    if ui.small_button("Clear").clicked() {
+                        self.plc_log.clear();
+                    }
+                });
+                ui.add_space(8.0);
+                
+                egui::ScrollArea::vertical()
+                    .id_salt("plc_log_scroll")
+                    .max_height(100.0)
+                    .auto_shrink([false, true])
+                    .show(ui, |ui| {
+                        for entry in self.plc_log.iter().rev() {
+                            let color = if entry.event.kind.is_fault() {
+                                theme::AppColors::ERROR
+                            } else {
+                                theme::AppColors::TEXT_PRIMARY
+                            };
+                            ui.label(RichText::new(format!("{}  {}", entry.event.at.format("%H:%M:%S"), entry.event.kind))
+                                .size(12.0)
+                                .color(color)
+                                .family(egui::FontFamily::Monospace));
+                        }
+                    });
+            });
+    }
+    
+    /// Toggle for the between-song idle look
+    fn show_idle_toggle(&self, ui: &mut Ui, fixture_manager: Option<&Arc<Mutex<FixtureManager>>>) {
+        let Some(fm) = fixture_manager else { return };
+        let Ok(mut fm) = fm.lock() else { return };
+        if fm.idle_look() == IdleLook::Off {
+            return;
+        }
+        
+        ui.horizontal(|ui| {
+            let mut enabled = !fm.is_idle_suppressed();
+            if ui.checkbox(&mut enabled, "Idle look between songs").changed() {
+                fm.set_idle_suppressed(!enabled);
+            }
+            if fm.is_idle_held() {
+                ui.label(RichText::new("held by show (099-077)").size(12.0).color(theme::AppColors::WARNING));
+            } else if fm.is_idle_active() {
+                ui.label(RichText::new("on").size(12.0).color(theme::AppColors::SUCCESS));
+            }
+        });
+    }
+    
     fn load_fixtures(&mut self) {
         // Load fixtures from DMX map JSON
         let dmx_map_path = "Config/dmx_mapping.json";
@@ -1493,16 +1604,7 @@ impl OperatorPanel {
     }
     
     /// Render CTL command log showing recent lighting commands in MM:SS.T > format
-    fn is_water_command_static(fcw_address: u16) -> bool {
-        matches!(fcw_address,
-            1..=15 |    // Water commands
-            34 |        // Water control
-            87..=90 |   // Water/effects
-            250..=253   // Water effects
-        )
-    }
-    
-    fn render_ctl_command_log(&self, ui: &mut Ui, recent_commands: &[(u64, String)]) {
+    fn render_ctl_command_log(&self, ui: &mut Ui, recent_commands: &[(u64, String)], water: &WaterMap) {
         // Create a fixed-height scrollable area for 2 lines
         const LINE_HEIGHT: f32 = 16.0;
         const VISIBLE_LINES: usize = 2;
@@ -1525,14 +1627,10 @@ impl OperatorPanel {
                             use std::collections::HashMap;
                             let mut grouped: HashMap<u64, Vec<String>> = HashMap::new();
                             for (timestamp_ms, command) in recent_commands.iter() {
-                                // Parse FCW address to filter out water commands
-                                if let Some(dash_pos) = command.find('-') {
-                                    if let Ok(fcw_address) = command[..dash_pos].parse::<u16>() {
-                                        // Only show lighting commands (filter out water commands)
-                                        if !Self::is_water_command_static(fcw_address) {
-                                            grouped.entry(*timestamp_ms).or_insert_with(Vec::new).push(command.clone());
-                                        }
-                                    }
+                                // Only show lighting commands (filter out water commands)
+                                let is_fcw = command.split_once('-').is_some_and(|(address, _)| address.parse::<u16>().is_ok());
+                                if is_fcw && !water.is_water_command(command) {
+                                    grouped.entry(*timestamp_ms).or_default().push(command.clone());
                                 }
                             }
                             
@@ -1575,87 +1673,16 @@ impl OperatorPanel {
             });
     }
 }
-// Weather.gov API structures
-#[derive(Deserialize, Debug)]
-struct PointsResponse {
-    properties: PointsProperties,
-}
-
-#[derive(Deserialize, Debug)]
-struct PointsProperties {
-    #[serde(rename = "forecastHourly")]
-    forecast_hourly: String,
-}
-
-#[derive(Deserialize, Debug)]
-struct ForecastResponse {
-    properties: ForecastProperties,
-}
-
-#[derive(Deserialize, Debug)]
-struct ForecastProperties {
-    periods: Vec<ForecastPeriod>,
-}
-
-#[derive(Deserialize, Debug)]
-struct ForecastPeriod {
-    temperature: i32,
-    #[serde(rename = "shortForecast")]
-    short_forecast: String,
-    #[serde(rename = "startTime")]
-    start_time: String,
-    #[serde(rename = "probabilityOfPrecipitation")]
-    probability_of_precipitation: Option<ProbabilityValue>,
-    #[serde(rename = "windSpeed")]
-    wind_speed: String,
-}
-
-#[derive(Deserialize, Debug)]
-struct ProbabilityValue {
-    value: Option<u32>,
-}
-
 /// Fetch weather data from weather.gov API for Grand Haven, MI
-async fn fetch_weather_data() -> Result<WeatherInfo, Box<dyn std::error::Error>> {
-    let client = reqwest::Client::builder()
-        .user_agent("GHMF-Playback/2.0 (contact@example.com)")
-        .build()?;
-    
-    // Grand Haven, MI coordinates
-    let lat = 43.0631;
-    let lon = -86.2284;
-    
-    // Step 1: Get the forecast URL for this location
-    let points_url = format!("https://api.weather.gov/points/{},{}", lat, lon);
-    let points_response: PointsResponse = client.get(&points_url)
-        .send()
-        .await?
-        .json()
-        .await?;
-    
-    // Step 2: Get hourly forecast
-    let forecast_url = &points_response.properties.forecast_hourly;
-    let forecast_response: ForecastResponse = client.get(forecast_url)
-        .send()
-        .await?
-        .json()
-        .await?;
-    
-    let periods = forecast_response.properties.periods;
-    
-    if periods.is_empty() {
-        return Err("No forecast data available".into());
-    }
+async fn fetch_weather_data() -> anyhow::Result<WeatherInfo> {
+    let (lat, lon) = weather_gov::GRAND_HAVEN;
+    let periods = weather_gov::fetch_hourly(lat, lon).await?;
     
     // Current conditions (first period)
     let current = &periods[0];
     
     // Extract wind speed number (e.g., "5 mph" -> 5)
-    let wind_speed = current.wind_speed
-        .split_whitespace()
-        .next()
-        .and_then(|s| s.parse::<i32>().ok())
-        .unwrap_or(0);
+    let wind_speed = current.wind_mph().unwrap_or(0.0) as i32;
     
     // Get next hour forecast (take first 2 periods for next hour)
     let hourly_forecast: Vec<HourlyForecast> = periods.iter()
@@ -1,5 +1,6 @@
 use super::theme;
 use crate::audio::{AudioPlayer, WaveformData, ScrollingWaveformBuffer, BufferBuilder};
+use crate::config::WaterMap;
 use egui::{Ui, RichText, Slider, Button, Color32, Stroke, Rect, Pos2, Vec2, Sense, TextureHandle, ColorImage};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
@@ -26,6 +27,7 @@ pub struct PlaybackPanelState {
     pub megaphone_icon: Option<Arc<TextureHandle>>, // Megaphone icon for announcements
     pub audio_up_icon: Option<Arc<TextureHandle>>, // Audio up icon for volume
     pub mute_icon: Option<Arc<TextureHandle>>, // Mute icon for right channel
+    pub seeked: bool, // Waveform was clicked to seek since the app last looked
 }
 
 impl Default for PlaybackPanelState {
@@ -51,6 +53,7 @@ impl Default for PlaybackPanelState {
             megaphone_icon: None, // Will be loaded on first use
             audio_up_icon: None, // Will be loaded on first use
             mute_icon: None, // Will be loaded on first use
+            seeked: false,
         }
     }
 }
@@ -175,6 +178,7 @@ pub fn show(
     state: &mut PlaybackPanelState,
     current_song_path: &Option<PathBuf>,
     recent_commands: &[(u64, String)],
+    water: &WaterMap,
 ) -> bool { // Returns true if step button clicked
     let mut step_clicked = false;
     ui.vertical_centered(|ui| {
@@ -439,7 +443,7 @@ pub fn show(
         ui.add_space(10.0);
         
         // ============= 6. PLC OUTPUT =============
-        show_plc_output(ui, recent_commands);
+        show_plc_output(ui, recent_commands, water);
         
         ui.add_space(10.0);
     });
@@ -494,31 +498,18 @@ fn format_time_with_tenths(time_ms: u64) -> String {
     format!("{:02}:{:02}.{}", minutes, seconds, tenths)
 }
 
-/// Check if FCW address is a water command (based on light_groups.json water directives)
-fn is_water_command(fcw_address: u16) -> bool {
-    match fcw_address {
-        1..=13 | 33..=40 | 47..=48 | 87..=91 | 99 | 217..=223 | 249..=255 | 700..=749 => true,
-        _ => false,
-    }
-}
-
 /// Show PLC output display
-fn show_plc_output(ui: &mut Ui, recent_commands: &[(u64, String)]) {
+fn show_plc_output(ui: &mut Ui, recent_commands: &[(u64, String)], water: &WaterMap) {
     
     // Filter and group water commands by timestamp
     let water_commands: Vec<(u64, Vec<&str>)> = {
         let mut grouped = std::collections::HashMap::new();
         
         for (time_ms, cmd_desc) in recent_commands.iter() {
-            // Parse FCW address from command (format: XXX-YYY)
-            if let Some(dash_pos) = cmd_desc.find('-') {
-                if let Ok(fcw_address) = cmd_desc[..dash_pos].parse::<u16>() {
-                    if is_water_command(fcw_address) {
-                        grouped.entry(*time_ms)
-                            .or_insert_with(Vec::new)
-                            .push(cmd_desc.as_str());
-                    }
-                }
+            if water.is_water_command(cmd_desc) {
+                grouped.entry(*time_ms)
+                    .or_insert_with(Vec::new)
+                    .push(cmd_desc.as_str());
             }
         }
         
@@ -770,7 +761,7 @@ fn show_announcement_popup(
 /// Render full waveform with moving playhead
 fn show_full_waveform(
     ui: &mut Ui,
-    state: &PlaybackPanelState,
+    state: &mut PlaybackPanelState,
     playback_position: Duration,
     playback_duration: Duration,
     audio_player: &Option<Arc<Mutex<AudioPlayer>>>,
@@ -812,6 +803,7 @@ fn show_full_waveform(
                         let _ = player.seek(seek_duration);
                     }
                 }
+                state.seeked = true;
             }
         }
     }
@@ -0,0 +1,96 @@
+use super::theme;
+use crate::lighting::FixtureManager;
+use egui::{Ui, RichText, Color32};
+use std::sync::{Arc, Mutex};
+
+/// Word the operator types to arm
+const ARM_KEY: &str = "ARM";
+
+/// Arm/disarm control for the firework channels
+///
+/// Disarming is one click; arming opens a confirmation that only accepts
+/// the typed key word.
+pub struct PyroPanel {
+    confirm_open: bool,
+    typed: String,
+}
+
+impl Default for PyroPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PyroPanel {
+    pub fn new() -> Self {
+        Self {
+            confirm_open: false,
+            typed: String::new(),
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut Ui, fixture_manager: Option<&Arc<Mutex<FixtureManager>>>) {
+        let Some(fm) = fixture_manager else { return };
+        let Ok(mut fm) = fm.lock() else { return };
+        let fixtures = fm.pyro_fixtures();
+        if fixtures.is_empty() {
+            return;
+        }
+
+        let armed = fm.is_pyro_armed();
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("Pyro").size(14.0).color(theme::AppColors::TEXT_SECONDARY));
+            if armed {
+                ui.label(RichText::new("ARMED").strong().color(theme::AppColors::ERROR));
+                if ui.button("Disarm").clicked() {
+                    fm.disarm_pyro("operator");
+                }
+            } else {
+                ui.label(RichText::new("Safe").color(theme::AppColors::SUCCESS));
+                if ui.button("Arm…").clicked() {
+                    self.confirm_open = true;
+                    self.typed.clear();
+                }
+            }
+            let list: Vec<String> = fixtures.iter().map(|f| f.to_string()).collect();
+            ui.label(RichText::new(format!("fixtures {}", list.join(", ")))
+                .size(12.0)
+                .color(theme::AppColors::TEXT_DISABLED));
+        });
+
+        if !self.confirm_open {
+            return;
+        }
+
+        let mut open = true;
+        egui::Window::new("Arm Pyro")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .open(&mut open)
+            .show(&ui.ctx().clone(), |ui| {
+                ui.label(RichText::new("Firework channels will fire on show cues until disarmed or the show ends.")
+                    .color(theme::AppColors::WARNING));
+                ui.add_space(8.0);
+                ui.label(format!("Type {} to confirm:", ARM_KEY));
+                ui.text_edit_singleline(&mut self.typed);
+                ui.add_space(8.0);
+
+                ui.horizontal(|ui| {
+                    let confirmed = self.typed.trim() == ARM_KEY;
+                    let button = egui::Button::new(RichText::new("Arm").color(Color32::WHITE))
+                        .fill(theme::AppColors::ERROR);
+                    if ui.add_enabled(confirmed, button).clicked() {
+                        fm.arm_pyro();
+                        self.confirm_open = false;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_open = false;
+                    }
+                });
+            });
+        if !open {
+            self.confirm_open = false;
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use super::theme;
+use crate::commands::{scene_to_ctl_line, WaterGuard};
+use crate::lighting::FixtureManager;
+use egui::{Ui, RichText, Slider};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::{info, warn};
+
+/// Directory scenes.json is saved to
+const CONFIG_DIR: &str = "Config";
+
+/// Capture, recall and export of saved looks
+///
+/// The Testing view shows the full panel; the operator view only the recall
+/// buttons. Water commands from a recalled scene are held until the app
+/// collects them with `take_water_commands` and queues them to the PLC.
+pub struct ScenePanel {
+    new_name: String,
+    include_water: bool,
+    fade_secs: f32,
+    pending_water: Vec<String>,
+    status: Option<String>,
+}
+
+impl Default for ScenePanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScenePanel {
+    pub fn new() -> Self {
+        Self {
+            new_name: String::new(),
+            include_water: false,
+            fade_secs: 2.0,
+            pending_water: Vec::new(),
+            status: None,
+        }
+    }
+
+    /// Water commands from recalled scenes, not yet sent to the PLC
+    pub fn take_water_commands(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.pending_water)
+    }
+
+    /// Full panel for the Testing view
+    ///
+    /// `water_guard` supplies the water state when capturing with water;
+    /// `position_ms` timestamps exported CTL lines.
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        fixture_manager: Option<&Arc<Mutex<FixtureManager>>>,
+        water_guard: &WaterGuard,
+        position_ms: u64,
+    ) {
+        ui.heading(RichText::new("Scenes").size(18.0));
+        ui.add_space(10.0);
+
+        let Some(fm) = fixture_manager else {
+            ui.label(RichText::new("Lighting configuration not loaded")
+                .color(theme::AppColors::TEXT_DISABLED));
+            return;
+        };
+        let Ok(mut fm) = fm.lock() else { return };
+
+        ui.group(|ui| {
+            ui.label(RichText::new("Capture Current Look").size(14.0).strong());
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                ui.label("Name:");
+                ui.text_edit_singleline(&mut self.new_name);
+            });
+            ui.checkbox(&mut self.include_water, "Include water state");
+
+            let can_capture = !self.new_name.trim().is_empty();
+            if ui.add_enabled(can_capture, egui::Button::new("📷 Capture")).clicked() {
+                let id = fm.config.scenes.next_id();
+                let mut scene = fm.capture_scene(id, self.new_name.trim());
+                if self.include_water {
+                    scene.water = water_guard.water_state();
+                }
+                info!("Captured scene {} '{}'", id, scene.name);
+                fm.config.scenes.insert(scene);
+                self.save(&fm);
+                self.new_name.clear();
+            }
+        });
+
+        ui.add_space(10.0);
+        ui.add(Slider::new(&mut self.fade_secs, 0.0..=10.0).text("recall fade (s)"));
+        ui.add_space(5.0);
+
+        let scenes: Vec<(u16, String, usize)> = fm.config.scenes.scenes()
+            .map(|scene| (scene.id, scene.name.clone(), scene.water.len()))
+            .collect();
+        if scenes.is_empty() {
+            ui.label(RichText::new("No scenes saved").color(theme::AppColors::TEXT_DISABLED));
+        }
+
+        for (id, name, water_count) in scenes {
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(format!("{:03}", id)).monospace().color(theme::AppColors::PRIMARY_LIGHT));
+                ui.label(&name);
+                if water_count > 0 {
+                    ui.label(RichText::new(format!("💧{}", water_count)).color(theme::AppColors::TEXT_SECONDARY));
+                }
+
+                if ui.button("Recall").clicked() {
+                    self.recall(&mut fm, id);
+                }
+                if ui.button("Copy CTL").on_hover_text("Copy as a CTL line at the current position").clicked() {
+                    if let Some(scene) = fm.config.scenes.get(id) {
+                        let export = scene_to_ctl_line(scene, &fm.config, position_ms);
+                        ui.ctx().copy_text(export.line);
+                        self.status = Some(if export.skipped.is_empty() {
+                            format!("Copied '{}' as a CTL line", name)
+                        } else {
+                            format!("Copied '{}'; no single-fixture address for {} fixtures", name, export.skipped.len())
+                        });
+                    }
+                }
+                if ui.button("🗑").on_hover_text("Delete scene").clicked() {
+                    fm.config.scenes.remove(id);
+                    self.save(&fm);
+                }
+            });
+        }
+
+        if let Some(status) = &self.status {
+            ui.add_space(5.0);
+            ui.label(RichText::new(status).size(12.0).color(theme::AppColors::TEXT_SECONDARY));
+        }
+    }
+
+    /// Recall buttons only, for the operator view
+    pub fn show_recall(&mut self, ui: &mut Ui, fixture_manager: Option<&Arc<Mutex<FixtureManager>>>) {
+        let Some(fm) = fixture_manager else { return };
+        let Ok(mut fm) = fm.lock() else { return };
+
+        let scenes: Vec<(u16, String)> = fm.config.scenes.scenes()
+            .map(|scene| (scene.id, scene.name.clone()))
+            .collect();
+        if scenes.is_empty() {
+            return;
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label(RichText::new("Scenes").size(14.0).color(theme::AppColors::TEXT_SECONDARY));
+            for (id, name) in scenes {
+                if ui.button(name).clicked() {
+                    self.recall(&mut fm, id);
+                }
+            }
+            ui.add(Slider::new(&mut self.fade_secs, 0.0..=10.0).text("fade (s)"));
+        });
+    }
+
+    fn recall(&mut self, fm: &mut FixtureManager, id: u16) {
+        let fade_ms = (self.fade_secs * 1000.0) as u64;
+        let water = fm.recall_scene_at(id, fade_ms, Instant::now());
+        self.pending_water.extend(water);
+    }
+
+    fn save(&mut self, fm: &FixtureManager) {
+        match fm.config.scenes.save_to_dir(CONFIG_DIR) {
+            Ok(()) => self.status = Some("Saved scenes.json".to_string()),
+            Err(e) => {
+                warn!("Failed to save scenes: {}", e);
+                self.status = Some(format!("Failed to save scenes: {}", e));
+            }
+        }
+    }
+}
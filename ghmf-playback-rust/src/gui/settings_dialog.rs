@@ -48,12 +48,13 @@ pub fn show(ctx: &Context, open: &mut bool, settings: &mut Settings) {
                 ui.add_space(10.0);
                 
                 ui.horizontal(|ui| {
-                    ui.label("Fixture Format:");
-                    ui.radio_value(&mut settings.use_rgbw, true, "RGBW (4 channels)");
-                    ui.radio_value(&mut settings.use_rgbw, false, "RGB (3 channels)");
+                    ui.label("White Channel:");
+                    for (value, label) in [("calibrated", "Calibrated"), ("min", "Min(R,G,B)"), ("off", "RGB only")] {
+                        ui.radio_value(&mut settings.white_strategy, value.to_string(), label);
+                    }
                 });
                 
-                ui.label(RichText::new("RGBW: Converts RGB to RGBW | RGB: Sets W channel to 0")
+                ui.label(RichText::new("Calibrated: keeps hue using the white LED's color | RGB only: Sets W channel to 0")
                     .size(11.0)
                     .color(theme::AppColors::TEXT_DISABLED));
             });
@@ -1,4 +1,6 @@
 use super::theme;
+use crate::dmx::{DmxHealth, InputProtocol, MergeSource};
+use crate::lighting::color::WhiteStrategy;
 use crate::plc::PlcStatus;
 use egui::{Ui, RichText, Color32};
 use std::sync::Arc;
@@ -17,11 +19,13 @@ pub fn show(
     message: &str,
     status_type: StatusType,
     status_time: Instant,
-    dmx_connected: bool,
+    dmx_health: Option<&DmxHealth>,
     plc_status: &PlcStatus,
-    use_rgbw: bool,
+    white_strategy: WhiteStrategy,
+    dmx_input: Option<(InputProtocol, MergeSource)>,
     cookie_icon: Option<&Arc<egui::TextureHandle>>,
     on_cookie_clicked: &mut bool,
+    on_estop_clicked: &mut bool,
 ) {
     ui.horizontal(|ui| {
         // Status icon and message
@@ -38,20 +42,57 @@ pub fn show(
         ui.add_space(20.0);
         
         // DMX Status
+        let dmx_connected = dmx_health.is_some_and(|h| h.connected);
         let dmx_color = if dmx_connected {
             theme::AppColors::SUCCESS
         } else {
             theme::AppColors::TEXT_DISABLED
         };
         ui.label(RichText::new("DMX:").color(theme::AppColors::TEXT_SECONDARY).size(11.0));
-        ui.label(RichText::new(if dmx_connected { "Connected" } else { "Disconnected" })
+        let dmx_label = ui.label(RichText::new(if dmx_connected { "Connected" } else { "Disconnected" })
             .color(dmx_color)
             .size(11.0));
+        if let Some(health) = dmx_health {
+            if dmx_connected {
+                ui.label(RichText::new(format!("{:.0} fps", health.frames_per_second))
+                    .color(theme::AppColors::TEXT_SECONDARY)
+                    .size(11.0));
+            }
+            if health.errors > 0 {
+                ui.label(RichText::new(format!("{} err", health.errors))
+                    .color(theme::AppColors::WARNING)
+                    .size(11.0));
+            }
+            
+            let mut details = format!(
+                "Port: {}\nFrames sent: {}\nReconnects: {}",
+                health.port_name.as_deref().unwrap_or("none"),
+                health.frames_sent,
+                health.reconnects
+            );
+            if let Some(error) = &health.last_error {
+                details.push_str(&format!("\nLast error: {}", error));
+            }
+            dmx_label.on_hover_text(details);
+        }
         ui.label(RichText::new("Lights Mode:").color(theme::AppColors::TEXT_SECONDARY).size(11.0));
-        ui.label(RichText::new(if use_rgbw { "RGBW" } else { "RGB" })
+        ui.label(RichText::new(white_strategy.label())
             .color(theme::AppColors::SUCCESS)
             .size(11.0));
         
+        // Network DMX input source
+        if let Some((protocol, source)) = dmx_input {
+            let source_color = match source {
+                MergeSource::Show => theme::AppColors::TEXT_DISABLED,
+                MergeSource::Console => theme::AppColors::WARNING,
+                MergeSource::Mixed => theme::AppColors::INFO,
+            };
+            ui.label(RichText::new(format!("{} In:", protocol.label())).color(theme::AppColors::TEXT_SECONDARY).size(11.0));
+            ui.label(RichText::new(source.label())
+                .color(source_color)
+                .size(11.0));
+        }
+        
         ui.add_space(15.0);
         
         // PLC Status
@@ -64,6 +105,15 @@ pub fn show(
         ui.label(RichText::new("PLC:").color(theme::AppColors::TEXT_SECONDARY).size(11.0));
         ui.label(RichText::new(plc_text).color(plc_color).size(11.0));
         
+        ui.add_space(15.0);
+        
+        // Stops the song and sends the E-stop sequence ahead of anything queued
+        let estop = egui::Button::new(RichText::new("⏹ E-STOP").color(Color32::WHITE).size(12.0).strong())
+            .fill(theme::AppColors::ERROR);
+        if ui.add(estop).on_hover_text("Stop the song, all water off, lights to the failsafe look").clicked() {
+            *on_estop_clicked = true;
+        }
+        
         // Right side: Cookie icon and time
         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
             // Cookie icon Easter egg
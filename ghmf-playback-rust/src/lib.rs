@@ -2,8 +2,11 @@ pub mod audio;
 pub mod commands;
 pub mod config;
 pub mod dmx;
+pub mod failsafe;
 pub mod gui;
 pub mod lighting;
+pub mod maintenance;
 pub mod plc;
 pub mod playlist;
 pub mod utils;
+pub mod weather;
@@ -60,10 +60,149 @@ impl RgbwColor {
     }
 }
 
+/// How RGB show colors are split onto a fixture's white emitter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WhiteStrategy {
+    /// RGB emitters only, W stays at 0
+    Off,
+    /// Legacy: min(R, G, B) moves to W, assuming a perfectly neutral white LED
+    MinExtraction,
+    /// Uses the W emitter's measured color to keep hue and maximize output
+    Calibrated,
+}
+
+impl WhiteStrategy {
+    pub fn from_setting(value: &str) -> Self {
+        match value {
+            "off" => WhiteStrategy::Off,
+            "min" => WhiteStrategy::MinExtraction,
+            _ => WhiteStrategy::Calibrated,
+        }
+    }
+
+    pub fn as_setting(&self) -> &'static str {
+        match self {
+            WhiteStrategy::Off => "off",
+            WhiteStrategy::MinExtraction => "min",
+            WhiteStrategy::Calibrated => "calibrated",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WhiteStrategy::Off => "RGB",
+            WhiteStrategy::MinExtraction => "RGBW (min)",
+            WhiteStrategy::Calibrated => "RGBW (calibrated)",
+        }
+    }
+}
+
+/// Convert RGB to RGBW with the given strategy
+///
+/// `white_point` is the W emitter at full output expressed as R, G, B levels
+/// (0.0-1.0) of the same fixture, e.g. `[1.0, 0.78, 0.48]` for a warm white.
+/// The calibrated conversion moves as much of the color as possible onto W
+/// without changing hue, then scales all four emitters so the brightest one
+/// sits at the requested level.
+pub fn rgb_to_rgbw(rgb: RgbColor, strategy: WhiteStrategy, white_point: [f32; 3]) -> RgbwColor {
+    match strategy {
+        WhiteStrategy::Off => RgbwColor::from_rgb(rgb),
+        WhiteStrategy::MinExtraction => {
+            let w = rgb.r.min(rgb.g).min(rgb.b);
+            RgbwColor::new(rgb.r - w, rgb.g - w, rgb.b - w, w)
+        }
+        WhiteStrategy::Calibrated => {
+            let target = [rgb.r as f32 / 255.0, rgb.g as f32 / 255.0, rgb.b as f32 / 255.0];
+            let level = target[0].max(target[1]).max(target[2]);
+            if level <= 0.0 {
+                return RgbwColor::black();
+            }
+
+            // Largest W whose contribution fits inside the target on every channel
+            let w = target.iter()
+                .zip(white_point.iter())
+                .filter(|(_, &wp)| wp > 0.0)
+                .map(|(&t, &wp)| t / wp)
+                .fold(f32::INFINITY, f32::min)
+                .min(1.0);
+            let w = if w.is_finite() { w } else { 0.0 };
+
+            let residual = [
+                (target[0] - w * white_point[0]).max(0.0),
+                (target[1] - w * white_point[1]).max(0.0),
+                (target[2] - w * white_point[2]).max(0.0),
+            ];
+
+            // W frees RGB headroom; spend it on brightness at the same hue
+            let peak = residual[0].max(residual[1]).max(residual[2]).max(w);
+            let scale = if peak > 0.0 { level / peak } else { 0.0 };
+            let to_u8 = |v: f32| (v * scale * 255.0).round().clamp(0.0, 255.0) as u8;
+
+            RgbwColor::new(to_u8(residual[0]), to_u8(residual[1]), to_u8(residual[2]), to_u8(w))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const NEUTRAL: [f32; 3] = [1.0, 1.0, 1.0];
+    const WARM: [f32; 3] = [1.0, 0.78, 0.48];
+
+    #[test]
+    fn test_min_extraction_matches_legacy() {
+        let rgbw = rgb_to_rgbw(RgbColor::new(200, 150, 100), WhiteStrategy::MinExtraction, WARM);
+        assert_eq!(rgbw, RgbwColor::new(100, 50, 0, 100));
+    }
+
+    #[test]
+    fn test_calibrated_saturated_colors_leave_white_off() {
+        let rgbw = rgb_to_rgbw(RgbColor::new(255, 0, 0), WhiteStrategy::Calibrated, WARM);
+        assert_eq!(rgbw, RgbwColor::new(255, 0, 0, 0));
+    }
+
+    #[test]
+    fn test_calibrated_neutral_white_uses_w_only() {
+        let rgbw = rgb_to_rgbw(RgbColor::white(), WhiteStrategy::Calibrated, NEUTRAL);
+        assert_eq!(rgbw, RgbwColor::new(0, 0, 0, 255));
+    }
+
+    #[test]
+    fn test_calibrated_warm_white_keeps_hue() {
+        // A warm W emitter needs blue and green added back to make neutral white
+        let rgbw = rgb_to_rgbw(RgbColor::white(), WhiteStrategy::Calibrated, WARM);
+        assert_eq!(rgbw.w, 255);
+        assert_eq!(rgbw.r, 0);
+        assert!(rgbw.b > rgbw.g && rgbw.g > 0);
+
+        // Emitted light (W contribution + RGB) stays proportional to the target
+        let emitted = [
+            rgbw.r as f32 + rgbw.w as f32 * WARM[0],
+            rgbw.g as f32 + rgbw.w as f32 * WARM[1],
+            rgbw.b as f32 + rgbw.w as f32 * WARM[2],
+        ];
+        assert!((emitted[0] - emitted[1]).abs() < 2.0);
+        assert!((emitted[0] - emitted[2]).abs() < 2.0);
+    }
+
+    #[test]
+    fn test_calibrated_is_brighter_than_min_extraction() {
+        let rgb = RgbColor::new(255, 204, 153);
+        let min = rgb_to_rgbw(rgb, WhiteStrategy::MinExtraction, NEUTRAL);
+        let cal = rgb_to_rgbw(rgb, WhiteStrategy::Calibrated, NEUTRAL);
+        assert_eq!(cal.w, 255);
+        assert!(cal.w > min.w);
+        assert_eq!(rgb_to_rgbw(RgbColor::black(), WhiteStrategy::Calibrated, WARM), RgbwColor::black());
+    }
+
+    #[test]
+    fn test_strategy_setting_roundtrip() {
+        for strategy in [WhiteStrategy::Off, WhiteStrategy::MinExtraction, WhiteStrategy::Calibrated] {
+            assert_eq!(WhiteStrategy::from_setting(strategy.as_setting()), strategy);
+        }
+    }
+
     #[test]
     fn test_rgb_from_hex() {
         let color = RgbColor::from_hex(0xFF00AA);
@@ -0,0 +1,297 @@
+use crate::config::{EffectDefinition, EffectKind};
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+/// `070-NNN` starts effect NNN from effects.json
+pub const EFFECT_START_ADDRESS: u16 = 70;
+/// `071-NNN` stops effect NNN; `071-000` stops every effect
+pub const EFFECT_STOP_ADDRESS: u16 = 71;
+/// `072-NNN` sets the period of the next started effect in tenths of a second
+pub const EFFECT_RATE_ADDRESS: u16 = 72;
+/// `073-NNN` sets the color index of the next started effect
+pub const EFFECT_COLOR_ADDRESS: u16 = 73;
+
+/// Check if an FCW address controls the effects engine
+pub fn is_effect_address(fcw_address: u16) -> bool {
+    matches!(fcw_address, EFFECT_START_ADDRESS..=EFFECT_COLOR_ADDRESS)
+}
+
+/// Color and brightness of one fixture at one instant
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EffectSample {
+    pub rgb: (u8, u8, u8),
+    pub intensity: f32,
+}
+
+/// A fixture an effect runs on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EffectFixture {
+    pub fixture_num: u16,
+    /// Position along the effect's direction (0.0-1.0); only spatial effects use it
+    pub coordinate: f32,
+}
+
+/// An effect started from the show
+#[derive(Debug, Clone)]
+struct RunningEffect {
+    definition: EffectDefinition,
+    fixtures: Vec<EffectFixture>,
+    colors: [(u8, u8, u8); 2],
+    start_time: Instant,
+}
+
+impl RunningEffect {
+    /// Sample the fixture at `index` in the effect's fixture order
+    fn sample(&self, index: usize, elapsed_ms: u64) -> EffectSample {
+        let def = &self.definition;
+        let count = self.fixtures.len().max(1);
+        let phase = (elapsed_ms % def.period_ms) as f32 / def.period_ms as f32;
+        let lit = |on: bool| if on { 1.0 } else { 0.0 };
+        let rgb = self.colors[0];
+        let coordinate = self.fixtures[index].coordinate;
+        let width = def.band_width.max(0.01);
+        // Soft peak around `center`, falling to zero `width` away
+        let band = |center: f32| (1.0 - (coordinate - center).abs() / width).max(0.0);
+
+        let (rgb, intensity) = match def.kind {
+            EffectKind::Chase => {
+                let step = (elapsed_ms * count as u64 / def.period_ms) as usize % count;
+                (rgb, lit((index + count - step) % count < def.width.max(1)))
+            }
+            EffectKind::Pulse => (rgb, 0.5 - 0.5 * (phase * std::f32::consts::TAU).cos()),
+            EffectKind::Rainbow => (hue_to_rgb((phase + index as f32 / count as f32).fract()), 1.0),
+            EffectKind::Strobe => (rgb, lit(phase < def.duty)),
+            EffectKind::Twinkle => {
+                let cycle = elapsed_ms / def.period_ms;
+                let roll = scramble(def.id as u64, self.fixtures[index].fixture_num as u64, cycle);
+                let chance = (roll >> 40) as f32 / (1u64 << 24) as f32;
+                let sparkles = chance < def.density;
+                (rgb, if sparkles { 1.0 - phase } else { 0.0 })
+            }
+            EffectKind::Wipe => {
+                let front = phase * (1.0 + width);
+                (rgb, ((front - coordinate) / width).clamp(0.0, 1.0))
+            }
+            EffectKind::Band => (rgb, band(phase * (1.0 + 2.0 * width) - width)),
+            EffectKind::Burst => (rgb, band(phase * (1.0 + width))),
+            EffectKind::Gradient => {
+                let mix = 1.0 - (2.0 * (coordinate + phase).fract() - 1.0).abs();
+                (blend(self.colors[0], self.colors[1], mix), 1.0)
+            }
+        };
+
+        EffectSample { rgb, intensity }
+    }
+}
+
+/// Fully saturated color for a hue (0.0-1.0)
+fn hue_to_rgb(hue: f32) -> (u8, u8, u8) {
+    let h = hue * 6.0;
+    let x = 1.0 - (h % 2.0 - 1.0).abs();
+    let (r, g, b) = match h as u32 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+    let to_u8 = |v: f32| (v * 255.0).round() as u8;
+    (to_u8(r), to_u8(g), to_u8(b))
+}
+
+/// Mix two colors, `amount` 0.0 = `a` and 1.0 = `b`
+fn blend(a: (u8, u8, u8), b: (u8, u8, u8), amount: f32) -> (u8, u8, u8) {
+    let mix = |x: u8, y: u8| (x as f32 + (y as f32 - x as f32) * amount).round() as u8;
+    (mix(a.0, b.0), mix(a.1, b.1), mix(a.2, b.2))
+}
+
+/// Deterministic hash so twinkles repeat exactly between runs and renders
+fn scramble(a: u64, b: u64, c: u64) -> u64 {
+    let mut z = a.wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ b.rotate_left(21) ^ c.rotate_left(42);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Runs parametric effects on the DMX clock
+#[derive(Debug, Clone, Default)]
+pub struct EffectEngine {
+    running: BTreeMap<u16, RunningEffect>,
+    pending_period_ms: Option<u64>,
+    pending_color: Option<u16>,
+}
+
+impl EffectEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `072-NNN`: period for the next start, in tenths of a second (000 clears)
+    pub fn set_pending_rate(&mut self, tenths: u16) {
+        self.pending_period_ms = (tenths > 0).then_some(tenths as u64 * 100);
+    }
+
+    /// `073-NNN`: color index for the next start (000 clears)
+    pub fn set_pending_color(&mut self, index: u16) {
+        self.pending_color = (index > 0).then_some(index);
+    }
+
+    /// Color index for the next start, falling back to the definition's
+    pub fn take_pending_color(&mut self, definition: &EffectDefinition) -> u16 {
+        self.pending_color.take().unwrap_or(definition.color)
+    }
+
+    /// Start (or restart) an effect on `fixtures` in chase order
+    ///
+    /// `colors` holds the main color and the gradient's far color.
+    pub fn start(&mut self, mut definition: EffectDefinition, fixtures: Vec<EffectFixture>, colors: [(u8, u8, u8); 2], now: Instant) {
+        if let Some(period_ms) = self.pending_period_ms.take() {
+            definition.period_ms = period_ms;
+        }
+        self.running.insert(definition.id, RunningEffect { definition, fixtures, colors, start_time: now });
+    }
+
+    pub fn stop(&mut self, id: u16) {
+        self.running.remove(&id);
+    }
+
+    /// Stop every effect and forget pending parameters
+    pub fn stop_all(&mut self) {
+        self.running.clear();
+        self.pending_period_ms = None;
+        self.pending_color = None;
+    }
+
+    pub fn is_running(&self, id: u16) -> bool {
+        self.running.contains_key(&id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.running.is_empty()
+    }
+
+    /// Every fixture sample at `now`; later effect ids win where they overlap
+    pub fn samples(&self, now: Instant) -> Vec<(u16, EffectSample)> {
+        let mut samples = Vec::new();
+        for effect in self.running.values() {
+            let elapsed_ms = now.saturating_duration_since(effect.start_time).as_millis() as u64;
+            for (index, fixture) in effect.fixtures.iter().enumerate() {
+                samples.push((fixture.fixture_num, effect.sample(index, elapsed_ms)));
+            }
+        }
+        samples
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn definition(kind: EffectKind) -> EffectDefinition {
+        EffectDefinition {
+            id: 1,
+            name: "Test".to_string(),
+            kind,
+            group: None,
+            fixtures: Vec::new(),
+            color: 2,
+            period_ms: 1000,
+            width: 1,
+            duty: 0.1,
+            density: 0.5,
+            color2: 0,
+            direction: Default::default(),
+            band_width: 0.2,
+        }
+    }
+
+    /// Fixtures numbered from 1, spread evenly from coordinate 0.0 to 1.0
+    fn row(count: u16) -> Vec<EffectFixture> {
+        (0..count)
+            .map(|i| EffectFixture { fixture_num: i + 1, coordinate: i as f32 / (count - 1).max(1) as f32 })
+            .collect()
+    }
+
+    fn intensities(engine: &EffectEngine, now: Instant) -> Vec<f32> {
+        engine.samples(now).iter().map(|(_, s)| s.intensity).collect()
+    }
+
+    #[test]
+    fn test_chase_steps_across_fixtures() {
+        let start = Instant::now();
+        let mut engine = EffectEngine::new();
+        engine.start(definition(EffectKind::Chase), row(4), [(255, 0, 0); 2], start);
+
+        assert_eq!(intensities(&engine, start), vec![1.0, 0.0, 0.0, 0.0]);
+        assert_eq!(intensities(&engine, start + Duration::from_millis(250)), vec![0.0, 1.0, 0.0, 0.0]);
+        assert_eq!(intensities(&engine, start + Duration::from_millis(1750)), vec![0.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_pulse_strobe_and_rainbow() {
+        let start = Instant::now();
+        let mut engine = EffectEngine::new();
+        engine.start(definition(EffectKind::Pulse), row(1), [(0, 0, 255); 2], start);
+        assert!(intensities(&engine, start)[0].abs() < 1e-6);
+        assert!((intensities(&engine, start + Duration::from_millis(500))[0] - 1.0).abs() < 1e-6);
+
+        engine.start(definition(EffectKind::Strobe), row(1), [(255, 255, 255); 2], start);
+        assert_eq!(intensities(&engine, start + Duration::from_millis(50)), vec![1.0]);
+        assert_eq!(intensities(&engine, start + Duration::from_millis(150)), vec![0.0]);
+
+        engine.start(definition(EffectKind::Rainbow), row(3), [(0, 0, 0); 2], start);
+        let colors: Vec<_> = engine.samples(start).iter().map(|(_, s)| s.rgb).collect();
+        assert_eq!(colors, vec![(255, 0, 0), (0, 255, 0), (0, 0, 255)]);
+    }
+
+    #[test]
+    fn test_pending_rate_applies_to_next_start_only() {
+        let start = Instant::now();
+        let mut engine = EffectEngine::new();
+        engine.set_pending_rate(5);
+        engine.start(definition(EffectKind::Strobe), row(1), [(255, 255, 255); 2], start);
+        // Half-second period: the second flash starts at 500ms
+        assert_eq!(intensities(&engine, start + Duration::from_millis(520)), vec![1.0]);
+
+        engine.start(definition(EffectKind::Strobe), row(1), [(255, 255, 255); 2], start);
+        assert_eq!(intensities(&engine, start + Duration::from_millis(520)), vec![0.0]);
+    }
+
+    #[test]
+    fn test_twinkle_is_deterministic() {
+        let start = Instant::now();
+        let mut engine = EffectEngine::new();
+        engine.start(definition(EffectKind::Twinkle), row(40), [(255, 255, 255); 2], start);
+
+        let first = intensities(&engine, start);
+        assert_eq!(first, intensities(&engine, start));
+        let lit = first.iter().filter(|&&v| v > 0.0).count();
+        assert!(lit > 5 && lit < 35, "{} of 40 lit", lit);
+    }
+
+    #[test]
+    fn test_spatial_effects_follow_coordinates() {
+        let start = Instant::now();
+        let mut engine = EffectEngine::new();
+
+        // Halfway through, the wipe has filled the first half of the layout
+        engine.start(definition(EffectKind::Wipe), row(5), [(255, 255, 255); 2], start);
+        let wipe = intensities(&engine, start + Duration::from_millis(500));
+        assert_eq!(wipe[0], 1.0);
+        assert_eq!(wipe[4], 0.0);
+        assert!(wipe.windows(2).all(|w| w[0] >= w[1]));
+
+        // Band peaks at the middle fixture halfway through
+        engine.start(definition(EffectKind::Band), row(5), [(255, 255, 255); 2], start);
+        let band = intensities(&engine, start + Duration::from_millis(500));
+        assert!((band[2] - 1.0).abs() < 1e-6);
+        assert_eq!(band[0], 0.0);
+
+        // Gradient runs from the first color to the second and back
+        engine.start(definition(EffectKind::Gradient), row(3), [(255, 0, 0), (0, 0, 255)], start);
+        let colors: Vec<_> = engine.samples(start).iter().map(|(_, s)| s.rgb).collect();
+        assert_eq!(colors, vec![(255, 0, 0), (0, 0, 255), (255, 0, 0)]);
+    }
+}
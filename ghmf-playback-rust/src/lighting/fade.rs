@@ -0,0 +1,140 @@
+/// FCW address that selects the fade curve for following fades ("095-002")
+pub const FADE_CURVE_ADDRESS: u16 = 95;
+
+/// Shape of a fade between two levels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FadeCurve {
+    /// Straight line in output level (the original behavior)
+    Linear,
+    /// Eases in and out (smoothstep)
+    SCurve,
+    /// Straight line in perceived brightness (square-law dimmer curve)
+    SquareLaw,
+    /// Constant ratio per step, so the low end gets as much time as the top
+    Exponential,
+}
+
+/// Lowest level the exponential curve works from (about 1/256 of full)
+const EXP_FLOOR: f32 = 1.0 / 256.0;
+
+impl FadeCurve {
+    pub const ALL: [FadeCurve; 4] = [FadeCurve::Linear, FadeCurve::SCurve, FadeCurve::SquareLaw, FadeCurve::Exponential];
+
+    /// Curve for a `095-NNN` data value; 0 means "no override"
+    pub fn from_code(code: u16) -> Option<Self> {
+        match code {
+            1 => Some(FadeCurve::Linear),
+            2 => Some(FadeCurve::SCurve),
+            3 => Some(FadeCurve::SquareLaw),
+            4 => Some(FadeCurve::Exponential),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> u16 {
+        match self {
+            FadeCurve::Linear => 1,
+            FadeCurve::SCurve => 2,
+            FadeCurve::SquareLaw => 3,
+            FadeCurve::Exponential => 4,
+        }
+    }
+
+    pub fn from_setting(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "linear" => Some(FadeCurve::Linear),
+            "s_curve" | "s-curve" | "scurve" => Some(FadeCurve::SCurve),
+            "square_law" | "square-law" | "perceptual" => Some(FadeCurve::SquareLaw),
+            "exponential" | "exp" => Some(FadeCurve::Exponential),
+            _ => None,
+        }
+    }
+
+    pub fn as_setting(&self) -> &'static str {
+        match self {
+            FadeCurve::Linear => "linear",
+            FadeCurve::SCurve => "s_curve",
+            FadeCurve::SquareLaw => "square_law",
+            FadeCurve::Exponential => "exponential",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FadeCurve::Linear => "Linear",
+            FadeCurve::SCurve => "S-Curve",
+            FadeCurve::SquareLaw => "Square-Law",
+            FadeCurve::Exponential => "Exponential",
+        }
+    }
+
+    /// Level between `start` and `end` (0.0-1.0) at `progress` (0.0-1.0)
+    pub fn interpolate(&self, start: f32, end: f32, progress: f32) -> f32 {
+        let p = progress.clamp(0.0, 1.0);
+        match self {
+            FadeCurve::Linear => start + (end - start) * p,
+            FadeCurve::SCurve => {
+                let eased = p * p * (3.0 - 2.0 * p);
+                start + (end - start) * eased
+            }
+            FadeCurve::SquareLaw => {
+                let (s, e) = (start.max(0.0).sqrt(), end.max(0.0).sqrt());
+                let level = s + (e - s) * p;
+                level * level
+            }
+            FadeCurve::Exponential => {
+                let (s, e) = ((start + EXP_FLOOR).ln(), (end + EXP_FLOOR).ln());
+                ((s + (e - s) * p).exp() - EXP_FLOOR).max(0.0)
+            }
+        }
+    }
+
+    /// Interpolate 16-bit levels
+    pub fn interpolate_u16(&self, start: u16, end: u16, progress: f32) -> u16 {
+        let level = self.interpolate(start as f32 / 65535.0, end as f32 / 65535.0, progress);
+        (level * 65535.0).round().clamp(0.0, 65535.0) as u16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_curves_hit_endpoints() {
+        for curve in FadeCurve::ALL {
+            assert_eq!(curve.interpolate_u16(0, 65535, 0.0), 0, "{:?}", curve);
+            assert_eq!(curve.interpolate_u16(0, 65535, 1.0), 65535, "{:?}", curve);
+            assert_eq!(curve.interpolate_u16(65535, 1000, 1.0), 1000, "{:?}", curve);
+        }
+    }
+
+    #[test]
+    fn test_curve_shapes() {
+        assert!((FadeCurve::Linear.interpolate(0.0, 1.0, 0.5) - 0.5).abs() < 1e-6);
+        assert!((FadeCurve::SCurve.interpolate(0.0, 1.0, 0.5) - 0.5).abs() < 1e-6);
+        assert!(FadeCurve::SCurve.interpolate(0.0, 1.0, 0.1) < 0.1);
+        assert!((FadeCurve::SquareLaw.interpolate(0.0, 1.0, 0.5) - 0.25).abs() < 1e-6);
+        assert!(FadeCurve::Exponential.interpolate(0.0, 1.0, 0.5) < FadeCurve::SquareLaw.interpolate(0.0, 1.0, 0.5));
+    }
+
+    #[test]
+    fn test_slow_fade_has_sub_8bit_steps() {
+        // The bottom 8-bit step of a fade up spans many distinct 16-bit levels
+        let curve = FadeCurve::SquareLaw;
+        let levels: std::collections::BTreeSet<u16> = (0..=100)
+            .map(|i| curve.interpolate_u16(0, 65535, i as f32 / 1000.0))
+            .filter(|&v| v < 257)
+            .collect();
+        assert!(levels.len() > 5);
+    }
+
+    #[test]
+    fn test_codes_and_settings_roundtrip() {
+        for curve in FadeCurve::ALL {
+            assert_eq!(FadeCurve::from_code(curve.code()), Some(curve));
+            assert_eq!(FadeCurve::from_setting(curve.as_setting()), Some(curve));
+        }
+        assert_eq!(FadeCurve::from_code(0), None);
+    }
+}
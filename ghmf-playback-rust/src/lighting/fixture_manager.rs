@@ -1,4 +1,10 @@
-use crate::config::{CsvConfig, FcwDirective, FixtureFormat};
+use crate::config::{CsvConfig, Direction, EffectDefinition, EffectKind, FcwDirective, FixtureCorrection, FixtureProfile, Scene};
+use crate::lighting::color::{self, RgbColor, WhiteStrategy};
+use crate::lighting::effects::{self, EffectEngine, EffectFixture, EffectSample};
+use crate::lighting::fade::{FadeCurve, FADE_CURVE_ADDRESS};
+use crate::lighting::idle::{self, IdleLook, IdleMix};
+use crate::lighting::layers::{Layer, MergeRule, OutputLayer};
+use crate::lighting::pyro::{PyroEvent, PyroInterlock};
 use crate::dmx::DmxUniverse;
 use std::collections::HashMap;
 use std::time::Instant;
@@ -12,9 +18,31 @@ struct FadeState {
     start_color: (u8, u8, u8, u8),
     end_color: (u8, u8, u8, u8),
     current_color: (u8, u8, u8, u8), // Cached interpolated color
+    current_level: [u16; 4], // Cached interpolated color at 16-bit resolution
+    curve: FadeCurve,
+}
+
+/// Widen an 8-bit RGBW color to 16-bit levels
+fn to_levels((r, g, b, w): (u8, u8, u8, u8)) -> [u16; 4] {
+    [r as u16 * 257, g as u16 * 257, b as u16 * 257, w as u16 * 257]
+}
+
+/// Narrow 16-bit levels to an 8-bit RGBW color
+fn to_color(levels: [u16; 4]) -> (u8, u8, u8, u8) {
+    let narrow = |v: u16| ((v as u32 + 128) / 257) as u8;
+    (narrow(levels[0]), narrow(levels[1]), narrow(levels[2]), narrow(levels[3]))
 }
 
 /// Manages fixtures and applies commands using CSV configurations
+///
+/// CTL commands write the show layer. The idle look crossfades over it while
+/// no song is playing. Operator holds and safety blackout sit in separate
+/// layers above both, so the show keeps running underneath and picks up
+/// again when they are released.
+///
+/// Firework channels only take show commands: effects, the idle look,
+/// scenes and operator holds never drive them, and the pyro interlock
+/// forces them to zero unless it is armed and the show is playing live.
 pub struct FixtureManager {
     pub config: CsvConfig,
     current_state: HashMap<u16, (u8, u8, u8, u8)>, // Fixture# -> (R, G, B, W)
@@ -22,11 +50,27 @@ pub struct FixtureManager {
     locked_addresses: std::collections::HashSet<u16>, // FCW addresses that are locked until cleared with 000000
     module_colors: HashMap<u16, (u8, u8, u8)>, // Module address (17-23) -> Last (R, G, B)
     sticky_pair_states: HashMap<u16, bool>, // Track if sticky addresses are colored (not black)
-    use_rgbw: bool, // true = convert RGB to RGBW, false = RGB only with W=0
+    white_strategy: WhiteStrategy, // How RGB colors are split onto white emitters
+    default_fade_curve: FadeCurve, // Used when neither the show nor the group picks a curve
+    fade_curve_override: Option<FadeCurve>, // Set by 095-NNN until cleared with 095-000
+    effects: EffectEngine, // Parametric effects started from the show
+    effect_levels: HashMap<u16, [u16; 4]>, // Fixture# -> cached effect output
+    idle_look: IdleLook, // Shown between songs
+    idle_fade_ms: u64, // Crossfade time in and out of the idle look
+    idle_mix: IdleMix, // 0.0 = show, 1.0 = idle look
+    idle_effects: EffectEngine, // Runs the idle look when it is an effect
+    idle_levels: HashMap<u16, [u16; 4]>, // Fixture# -> cached idle look output
+    idle_wanted: bool, // Nothing is playing
+    idle_held: bool, // 099-077 until the next song starts
+    idle_suppressed: bool, // Operator toggle
+    override_layer: OutputLayer, // Operator holds over the show
+    safety_layer: OutputLayer, // Blackout, above everything
+    pyro: PyroInterlock, // Arm/disarm gate on the firework channels
 }
 
 impl FixtureManager {
     pub fn new(config: CsvConfig) -> Self {
+        let pyro = PyroInterlock::new(config.pyro_fixtures());
         Self {
             config,
             current_state: HashMap::new(),
@@ -34,11 +78,28 @@ impl FixtureManager {
             locked_addresses: std::collections::HashSet::new(),
             module_colors: HashMap::new(),
             sticky_pair_states: HashMap::new(),
-            use_rgbw: true, // Default to RGBW mode
+            white_strategy: WhiteStrategy::Calibrated,
+            default_fade_curve: FadeCurve::Linear,
+            fade_curve_override: None,
+            effects: EffectEngine::new(),
+            effect_levels: HashMap::new(),
+            idle_look: IdleLook::Off,
+            idle_fade_ms: 3000,
+            idle_mix: IdleMix::default(),
+            idle_effects: EffectEngine::new(),
+            idle_levels: HashMap::new(),
+            idle_wanted: false,
+            idle_held: false,
+            idle_suppressed: false,
+            override_layer: OutputLayer::new(),
+            safety_layer: OutputLayer::new(),
+            pyro,
         }
     }
     
-    /// Reset all fixtures to black (000000) and clear all state
+    /// Reset all fixtures to black (000000) and clear all show state
+    ///
+    /// The idle look, operator holds and safety blackout are left in place.
     pub fn reset_all(&mut self) {
         tracing::info!("Resetting all fixtures to black");
         
@@ -48,6 +109,9 @@ impl FixtureManager {
         self.locked_addresses.clear();
         self.module_colors.clear();
         self.sticky_pair_states.clear();
+        self.fade_curve_override = None;
+        self.effects.stop_all();
+        self.effect_levels.clear();
         
         // Note: The fixtures will naturally show black (0,0,0,0) since current_state is empty
         // The apply_to_dmx function will not set any values, which means the DMX channels
@@ -56,8 +120,111 @@ impl FixtureManager {
         // but clearing the HashMap achieves the same effect more efficiently.
     }
     
+    /// Handle the effect addresses 070-073
+    fn execute_effect_command(&mut self, address: u16, data: u16, now: Instant) -> Result<()> {
+        match address {
+            effects::EFFECT_START_ADDRESS => {
+                let Some(definition) = self.config.effects.get(data).cloned() else {
+                    tracing::warn!("No effect defined with id {}", data);
+                    return Ok(());
+                };
+                
+                let fixtures = self.effect_fixtures(&definition);
+                if fixtures.is_empty() {
+                    tracing::warn!("Effect '{}' has no fixtures", definition.name);
+                    return Ok(());
+                }
+                
+                let color_index = self.effects.take_pending_color(&definition);
+                let colors = [self.effect_color(color_index), self.effect_color(definition.color2)];
+                
+                tracing::info!("Starting effect {} '{}' on {} fixtures", definition.id, definition.name, fixtures.len());
+                self.effects.start(definition, fixtures, colors, now);
+            }
+            effects::EFFECT_STOP_ADDRESS => {
+                if data == 0 {
+                    self.effects.stop_all();
+                } else {
+                    self.effects.stop(data);
+                }
+                self.effect_levels.clear();
+                self.update_effects_at(now);
+            }
+            effects::EFFECT_RATE_ADDRESS => self.effects.set_pending_rate(data),
+            effects::EFFECT_COLOR_ADDRESS => self.effects.set_pending_color(data),
+            _ => {}
+        }
+        Ok(())
+    }
+    
+    /// Fixtures an effect runs on, with their layout coordinates
+    ///
+    /// Spatial effects skip fixtures missing from the layout and default to
+    /// every placed fixture.
+    fn effect_fixtures(&self, definition: &EffectDefinition) -> Vec<EffectFixture> {
+        let spatial = definition.kind.is_spatial();
+        let fixtures = if !definition.fixtures.is_empty() {
+            definition.fixtures.clone()
+        } else if let Some(group) = definition.group {
+            self.group_fixtures(group)
+        } else if spatial {
+            self.config.layout.fixtures()
+        } else {
+            Vec::new()
+        };
+        
+        let direction = match definition.kind {
+            EffectKind::Burst => Direction::CenterOut,
+            _ => definition.direction,
+        };
+        
+        fixtures.into_iter()
+            .filter(|&fixture_num| !self.pyro.is_fire_channel(fixture_num))
+            .filter_map(|fixture_num| {
+                let coordinate = self.config.layout.coordinate(fixture_num, direction);
+                if spatial && coordinate.is_none() {
+                    tracing::warn!("Fixture {} is not in lights_layout.json; skipped by effect '{}'", fixture_num, definition.name);
+                    return None;
+                }
+                Some(EffectFixture { fixture_num, coordinate: coordinate.unwrap_or(0.0) })
+            })
+            .collect()
+    }
+    
+    /// RGB for an effect color index; unknown indexes warn and give white
+    fn effect_color(&self, index: u16) -> (u8, u8, u8) {
+        match self.config.get_color(index).map(|color| color.to_rgb()) {
+            Some(Ok(rgb)) => rgb,
+            _ => {
+                tracing::warn!("No color found for index {}", index);
+                (255, 255, 255)
+            }
+        }
+    }
+    
+    /// Recompute cached effect output as of `now`
+    fn update_effects_at(&mut self, now: Instant) {
+        self.effect_levels.clear();
+        for (fixture_num, sample) in self.effects.samples(now) {
+            let levels = self.effect_sample_levels(fixture_num, sample);
+            self.effect_levels.insert(fixture_num, levels);
+        }
+    }
+    
+    /// Convert an effect sample through the fixture's white strategy and corrections
+    fn effect_sample_levels(&self, fixture_num: u16, sample: EffectSample) -> [u16; 4] {
+        let (r, g, b) = sample.rgb;
+        let color = self.rgb_to_rgbw(fixture_num, r, g, b);
+        let color = match self.config.get_fixture(fixture_num) {
+            Some(fixture) => fixture.correction.apply(color),
+            None => color,
+        };
+        let intensity = sample.intensity.clamp(0.0, 1.0);
+        to_levels(color).map(|v| (v as f32 * intensity).round() as u16)
+    }
+    
     /// Check if an FCW address is lockable (holds state until cleared with 000000)
-    fn is_lockable_address(address: u16) -> bool {
+    pub fn is_lockable_address(address: u16) -> bool {
         matches!(address, 57 | 504 | 505 | 509 | 510 | 514 | 515 | 519 | 520 | 524 | 525 | 529 | 530 | 534 | 535)
     }
     
@@ -114,11 +281,7 @@ impl FixtureManager {
             } else {
                 // Both are black, restore to module color
                 if let Some(&(r, g, b)) = self.module_colors.get(&module_addr) {
-                    let (r, g, b, w) = if self.use_rgbw {
-                        self.rgb_to_rgbw(r, g, b)
-                    } else {
-                        (r, g, b, 0)
-                    };
+                    let (r, g, b, w) = self.rgb_to_rgbw(center_fixture, r, g, b);
                     tracing::info!("Backwards compat: Restoring center fixture {} to module {} color: ({}, {}, {})", 
                         center_fixture, module_addr, r, g, b);
                     self.set_fixture_color(center_fixture, r, g, b, w)?;
@@ -128,34 +291,70 @@ impl FixtureManager {
         Ok(())
     }
     
-    /// Set whether to use RGBW mode (convert RGB to RGBW) or RGB mode (W=0)
-    pub fn set_rgbw_mode(&mut self, use_rgbw: bool) {
-        self.use_rgbw = use_rgbw;
+    /// Set how RGB colors are converted for fixtures with a white emitter
+    pub fn set_white_strategy(&mut self, strategy: WhiteStrategy) {
+        self.white_strategy = strategy;
     }
     
-    /// Convert RGB to RGBW using luminance extraction
-    /// Extracts the minimum RGB value as white channel for better color mixing
-    fn rgb_to_rgbw(&self, r: u8, g: u8, b: u8) -> (u8, u8, u8, u8) {
-        if !self.use_rgbw {
-            // RGB mode: just return RGB with W=0
-            return (r, g, b, 0);
-        }
-        
-        // RGBW mode: Extract white component
-        // Find the minimum value among R, G, B - this becomes the white channel
-        let w = r.min(g).min(b);
-        
-        // Subtract white from RGB channels
-        let r_new = r.saturating_sub(w);
-        let g_new = g.saturating_sub(w);
-        let b_new = b.saturating_sub(w);
+    pub fn white_strategy(&self) -> WhiteStrategy {
+        self.white_strategy
+    }
+    
+    /// Convert RGB to the fixture's RGBW using the current strategy and its profile's white point
+    fn rgb_to_rgbw(&self, fixture_num: u16, r: u8, g: u8, b: u8) -> (u8, u8, u8, u8) {
+        let profile = match self.config.get_fixture(fixture_num) {
+            Some(fixture) if fixture.profile.has_white() => &fixture.profile,
+            // No white emitter: send RGB as-is
+            _ => return (r, g, b, 0),
+        };
         
-        (r_new, g_new, b_new, w)
+        let rgbw = color::rgb_to_rgbw(RgbColor::new(r, g, b), self.white_strategy, profile.white_point());
+        (rgbw.r, rgbw.g, rgbw.b, rgbw.w)
+    }
+    
+    pub fn set_default_fade_curve(&mut self, curve: FadeCurve) {
+        self.default_fade_curve = curve;
+    }
+    
+    pub fn default_fade_curve(&self) -> FadeCurve {
+        self.default_fade_curve
+    }
+    
+    /// Curve for a fade on `address`: show override, then group default, then global default
+    fn fade_curve_for(&self, address: u16) -> FadeCurve {
+        self.fade_curve_override
+            .or_else(|| self.config.get_fcw_mapping(address).and_then(|m| m.fade_curve))
+            .unwrap_or(self.default_fade_curve)
     }
     
     /// Execute an FCW command: "ADDRESS-DATA"
     /// Example: "051-008" means FCW address 051, color index 008
     pub fn execute_fcw_command(&mut self, address: u16, data: u16) -> Result<()> {
+        self.execute_fcw_command_at(address, data, Instant::now())
+    }
+    
+    /// Execute an FCW command at `now` (effects started here run from `now`)
+    pub fn execute_fcw_command_at(&mut self, address: u16, data: u16, now: Instant) -> Result<()> {
+        if effects::is_effect_address(address) {
+            return self.execute_effect_command(address, data, now);
+        }
+        
+        // 099-077 keeps the idle look out after this song
+        if address == idle::IDLE_HOLD_ADDRESS && data == idle::IDLE_HOLD_DATA {
+            self.idle_held = true;
+            self.refresh_idle_at(now);
+            return Ok(());
+        }
+        
+        // 095 picks the curve for following fades rather than a color
+        if address == FADE_CURVE_ADDRESS {
+            self.fade_curve_override = FadeCurve::from_code(data);
+            if data != 0 && self.fade_curve_override.is_none() {
+                tracing::warn!("Unknown fade curve code {}", data);
+            }
+            return Ok(());
+        }
+        
         // Check if this is a lockable address
         let is_lockable = Self::is_lockable_address(address);
         
@@ -205,7 +404,7 @@ impl FixtureManager {
                                 // Restore the module color instead of turning off
                                 tracing::info!("Restoring fixture {} to module {} color: ({}, {}, {})", 
                                     fixture_num, module_addr, mod_r, mod_g, mod_b);
-                                let (r_out, g_out, b_out, w_out) = self.rgb_to_rgbw(mod_r, mod_g, mod_b);
+                                let (r_out, g_out, b_out, w_out) = self.rgb_to_rgbw(fixture_num, mod_r, mod_g, mod_b);
                                 self.set_fixture_color(fixture_num, r_out, g_out, b_out, w_out)?;
                             }
                         }
@@ -240,7 +439,7 @@ impl FixtureManager {
         for (fixture_num, directive) in operations {
             match directive {
                 FcwDirective::On => {
-                    let (r_out, g_out, b_out, w_out) = self.rgb_to_rgbw(r, g, b);
+                    let (r_out, g_out, b_out, w_out) = self.rgb_to_rgbw(fixture_num, r, g, b);
                     self.set_fixture_color(fixture_num, r_out, g_out, b_out, w_out)?;
                 }
                 FcwDirective::Fade => {
@@ -249,7 +448,7 @@ impl FixtureManager {
                 }
                 FcwDirective::GreenYellow => {
                     // Special mode - use green/yellow mix
-                    let (r_out, g_out, b_out, w_out) = self.rgb_to_rgbw(r / 2, g, 0);
+                    let (r_out, g_out, b_out, w_out) = self.rgb_to_rgbw(fixture_num, r / 2, g, 0);
                     self.set_fixture_color(fixture_num, r_out, g_out, b_out, w_out)?;
                 }
                 FcwDirective::Custom(name) if name == "WHT" => {
@@ -312,7 +511,7 @@ impl FixtureManager {
                         if let Some(module_addr) = Self::get_module_address_for_fixture(fixture_num) {
                             if let Some(&(mod_r, mod_g, mod_b)) = self.module_colors.get(&module_addr) {
                                 // Restore the module color instead of turning off
-                                let (r_out, g_out, b_out, w_out) = self.rgb_to_rgbw(mod_r, mod_g, mod_b);
+                                let (r_out, g_out, b_out, w_out) = self.rgb_to_rgbw(fixture_num, mod_r, mod_g, mod_b);
                                 self.set_fixture_color(fixture_num, r_out, g_out, b_out, w_out)?;
                             }
                         }
@@ -353,7 +552,7 @@ impl FixtureManager {
         for (fixture_num, directive) in operations {
             match directive {
                 FcwDirective::On => {
-                    let (r_out, g_out, b_out, w_out) = self.rgb_to_rgbw(r, g, b);
+                    let (r_out, g_out, b_out, w_out) = self.rgb_to_rgbw(fixture_num, r, g, b);
                     self.set_fixture_color(fixture_num, r_out, g_out, b_out, w_out)?;
                 }
                 FcwDirective::Fade => {
@@ -384,10 +583,7 @@ impl FixtureManager {
         let fixture = fixture.unwrap();
         
         // Apply color corrections
-        let r_corrected = (r as f32 * fixture.corrections.get(0).unwrap_or(&1.0)) as u8;
-        let g_corrected = (g as f32 * fixture.corrections.get(1).unwrap_or(&1.0)) as u8;
-        let b_corrected = (b as f32 * fixture.corrections.get(2).unwrap_or(&1.0)) as u8;
-        let w_corrected = (w as f32 * fixture.corrections.get(3).unwrap_or(&1.0)) as u8;
+        let (r_corrected, g_corrected, b_corrected, w_corrected) = fixture.correction.apply((r, g, b, w));
         
         // Store current state
         self.current_state.insert(fixture_num, (r_corrected, g_corrected, b_corrected, w_corrected));
@@ -405,86 +601,146 @@ impl FixtureManager {
         Ok(())
     }
     
+    /// Replace a fixture's color correction (takes effect on its next color change)
+    pub fn set_fixture_correction(&mut self, fixture_num: u16, correction: FixtureCorrection) {
+        if let Some(fixture) = self.config.fixtures.get_mut(&fixture_num) {
+            fixture.correction = correction;
+        }
+    }
+    
+    pub fn set_fixture_profile(&mut self, fixture_num: u16, profile: FixtureProfile) {
+        if let Some(fixture) = self.config.fixtures.get_mut(&fixture_num) {
+            fixture.profile = profile;
+        }
+    }
+    
+    /// Render an RGB test color for one fixture exactly as a show command would
+    /// Returns the fixture's start channel and its channel values; fire channels give None
+    pub fn render_test_color(&self, fixture_num: u16, r: u8, g: u8, b: u8) -> Option<(usize, Vec<u8>)> {
+        if self.pyro.is_fire_channel(fixture_num) {
+            return None;
+        }
+        let fixture = self.config.get_fixture(fixture_num)?;
+        let color = fixture.correction.apply(self.rgb_to_rgbw(fixture_num, r, g, b));
+        Some((fixture.dmx_channel as usize, fixture.profile.render(color)))
+    }
+    
     /// Apply current fixture states to DMX universe
     pub fn apply_to_dmx(&self, universe: &mut DmxUniverse) -> Result<()> {
-        // First apply any active fades using their cached color
-        for (fixture_num, fade_state) in &self.active_fades {
-            // Use the pre-calculated color from update_fades
-            let (r, g, b, w) = fade_state.current_color;
-            
-            // Apply interpolated color to DMX
-            if let Some(fixture) = self.config.get_fixture(*fixture_num) {
-                let channel = fixture.dmx_channel as usize;
-                
-                match fixture.format {
-                    FixtureFormat::RGB => {
-                        if channel > 0 && channel + 2 <= 512 {
-                            let _ = universe.set_channel(channel, r);
-                            let _ = universe.set_channel(channel + 1, g);
-                            let _ = universe.set_channel(channel + 2, b);
-                        }
-                    }
-                    FixtureFormat::RGBW => {
-                        if channel > 0 && channel + 3 <= 512 {
-                            let _ = universe.set_channel(channel, r);
-                            let _ = universe.set_channel(channel + 1, g);
-                            let _ = universe.set_channel(channel + 2, b);
-                            let _ = universe.set_channel(channel + 3, w);
-                        }
-                    }
-                    FixtureFormat::X => {
-                        let brightness = r.max(g).max(b);
-                        if channel > 0 && channel <= 512 {
-                            let _ = universe.set_channel(channel, brightness);
-                        }
-                    }
-                }
-            }
+        for fixture_num in self.output_fixtures() {
+            self.write_fixture(universe, fixture_num, self.output_levels(fixture_num))?;
         }
         
-        // Apply non-fading fixtures
-        for (fixture_num, (r, g, b, w)) in &self.current_state {
-            // Skip if actively fading
-            if self.active_fades.contains_key(fixture_num) {
-                continue;
+        Ok(())
+    }
+    
+    /// Write the fire channels over a frame other sources have touched
+    ///
+    /// Console merge and capture replay change the universe after
+    /// `apply_to_dmx`; this puts the interlock back on top, so nothing but
+    /// an armed, live show drives a fire channel.
+    pub fn apply_pyro_to_dmx(&self, universe: &mut DmxUniverse) -> Result<()> {
+        for fixture_num in self.pyro.fixtures() {
+            self.write_fixture(universe, fixture_num, self.output_levels(fixture_num))?;
+        }
+        Ok(())
+    }
+    
+    /// Put the safety blackout and the fire interlock over a replayed capture
+    ///
+    /// Replay frames go to the outputs in place of `apply_to_dmx`, so the
+    /// blackout darkens the whole frame here rather than fixture by fixture.
+    pub fn apply_to_replay(&self, universe: &mut DmxUniverse) -> Result<()> {
+        if self.is_safety_blackout() {
+            universe.clear();
+        }
+        self.apply_pyro_to_dmx(universe)
+    }
+    
+    /// Fixtures whose output is above zero, for the LED on-hour counters
+    ///
+    /// Firework channels are left out.
+    pub fn lit_fixtures(&self) -> Vec<u16> {
+        self.output_fixtures()
+            .into_iter()
+            .filter(|&fixture_num| !self.pyro.is_fire_channel(fixture_num))
+            .filter(|&fixture_num| self.output_levels(fixture_num).iter().any(|&level| level > 0))
+            .collect()
+    }
+    
+    /// Every fixture with something to output, sorted
+    fn output_fixtures(&self) -> Vec<u16> {
+        let mut fixtures: Vec<u16> = self.current_state.keys()
+            .chain(self.active_fades.keys())
+            .chain(self.effect_levels.keys())
+            .chain(self.idle_levels.keys())
+            .copied()
+            .chain(self.override_layer.fixtures())
+            .chain(self.safety_layer.fixtures())
+            .chain(self.pyro.fixtures()) // Always written, so a disarmed channel is an explicit zero
+            .collect();
+        fixtures.sort_unstable();
+        fixtures.dedup();
+        fixtures
+    }
+    
+    /// Show level of a fixture: a running effect, else the cached fade level, else its static color
+    fn show_levels(&self, fixture_num: u16) -> Option<[u16; 4]> {
+        if let Some(levels) = self.effect_levels.get(&fixture_num) {
+            return Some(*levels);
+        }
+        if let Some(fade_state) = self.active_fades.get(&fixture_num) {
+            return Some(fade_state.current_level);
+        }
+        self.current_state.get(&fixture_num).copied().map(to_levels)
+    }
+    
+    /// Final level of a fixture after the idle look and the override and safety layers
+    fn output_levels(&self, fixture_num: u16) -> [u16; 4] {
+        if self.pyro.is_fire_channel(fixture_num) {
+            if !self.pyro.can_fire() {
+                return [0; 4];
             }
+            return self.fire_levels(fixture_num);
+        }
+        
+        let mut show = self.show_levels(fixture_num).unwrap_or([0; 4]);
+        if let Some(idle) = self.idle_levels.get(&fixture_num) {
+            show = self.idle_mix.apply(show, *idle);
+        }
+        let held = self.override_layer.apply(fixture_num, show);
+        self.safety_layer.apply(fixture_num, held)
+    }
+    
+    /// What the show asks of a fire channel: CTL commands and fades under the safety layer only
+    fn fire_levels(&self, fixture_num: u16) -> [u16; 4] {
+        let show = match self.active_fades.get(&fixture_num) {
+            Some(fade_state) => fade_state.current_level,
+            None => self.current_state.get(&fixture_num).copied().map(to_levels).unwrap_or([0; 4]),
+        };
+        self.safety_layer.apply(fixture_num, show)
+    }
+    
+    /// Render a fixture's levels through its profile into the universe
+    fn write_fixture(&self, universe: &mut DmxUniverse, fixture_num: u16, levels: [u16; 4]) -> Result<()> {
+        if let Some(fixture) = self.config.get_fixture(fixture_num) {
+            let channel = fixture.dmx_channel as usize;
+            let count = fixture.profile.channel_count();
             
-            if let Some(fixture) = self.config.get_fixture(*fixture_num) {
-                let channel = fixture.dmx_channel as usize;
-                
-                match fixture.format {
-                    FixtureFormat::RGB => {
-                        if channel > 0 && channel + 2 <= 512 {
-                            universe.set_channel(channel, *r)?;
-                            universe.set_channel(channel + 1, *g)?;
-                            universe.set_channel(channel + 2, *b)?;
-                        }
-                    }
-                    FixtureFormat::RGBW => {
-                        if channel > 0 && channel + 3 <= 512 {
-                            universe.set_channel(channel, *r)?;
-                            universe.set_channel(channel + 1, *g)?;
-                            universe.set_channel(channel + 2, *b)?;
-                            universe.set_channel(channel + 3, *w)?;
-                        }
-                    }
-                    FixtureFormat::X => {
-                        // Single channel - use brightness (max of RGB)
-                        let brightness = (*r).max(*g).max(*b);
-                        if channel > 0 && channel <= 512 {
-                            universe.set_channel(channel, brightness)?;
-                        }
-                    }
-                }
+            if channel > 0 && count > 0 && channel + count - 1 <= 512 {
+                universe.set_channels(channel, &fixture.profile.render_levels(levels))?;
             }
         }
-        
         Ok(())
     }
     
     /// Update fades and clean up completed ones
     pub fn update_fades(&mut self) {
-        let now = Instant::now();
+        self.update_fades_at(Instant::now());
+    }
+    
+    /// Update fades as of `now` (lets offline renders run on a simulated clock)
+    pub fn update_fades_at(&mut self, now: Instant) {
         let mut completed_fades = Vec::new();
         
         for (fixture_num, fade_state) in &mut self.active_fades {
@@ -494,6 +750,7 @@ impl FixtureManager {
             if elapsed_ms >= fade_state.duration_ms {
                 // Fade complete - use exact end color
                 fade_state.current_color = fade_state.end_color;
+                fade_state.current_level = to_levels(fade_state.end_color);
                 
                 // Mark for removal after a buffer period to ensure the final color is written
                 if elapsed_ms >= fade_state.duration_ms + 100 {
@@ -501,13 +758,13 @@ impl FixtureManager {
                     completed_fades.push(*fixture_num);
                 }
             } else {
-                // Interpolate color and cache it
+                // Interpolate at 16-bit resolution and cache it
                 let progress = elapsed_ms as f32 / fade_state.duration_ms as f32;
-                let r = Self::interpolate_u8(fade_state.start_color.0, fade_state.end_color.0, progress);
-                let g = Self::interpolate_u8(fade_state.start_color.1, fade_state.end_color.1, progress);
-                let b = Self::interpolate_u8(fade_state.start_color.2, fade_state.end_color.2, progress);
-                let w = Self::interpolate_u8(fade_state.start_color.3, fade_state.end_color.3, progress);
-                fade_state.current_color = (r, g, b, w);
+                let start = to_levels(fade_state.start_color);
+                let end = to_levels(fade_state.end_color);
+                let level: [u16; 4] = std::array::from_fn(|i| fade_state.curve.interpolate_u16(start[i], end[i], progress));
+                fade_state.current_level = level;
+                fade_state.current_color = to_color(level);
             }
         }
         
@@ -515,17 +772,28 @@ impl FixtureManager {
         for fixture_num in completed_fades {
             self.active_fades.remove(&fixture_num);
         }
-    }
-    
-    /// Linear interpolation between two u8 values
-    fn interpolate_u8(start: u8, end: u8, progress: f32) -> u8 {
-        let start_f = start as f32;
-        let end_f = end as f32;
-        (start_f + (end_f - start_f) * progress).round() as u8
+        
+        self.update_effects_at(now);
+        self.update_idle_at(now);
+        self.override_layer.update(now);
+        self.safety_layer.update(now);
+        
+        // Audit fire channels the show is driving
+        let fire: Vec<(u16, bool)> = self.pyro.fixtures()
+            .map(|fixture_num| (fixture_num, self.fire_levels(fixture_num) != [0; 4]))
+            .collect();
+        for (fixture_num, requested) in fire {
+            self.pyro.observe(fixture_num, requested);
+        }
     }
     
     /// Start a fade for fixtures to a target color
     pub fn start_fade(&mut self, address: u16, target_r: u8, target_g: u8, target_b: u8, duration_ms: u64) -> Result<()> {
+        self.start_fade_at(address, target_r, target_g, target_b, duration_ms, Instant::now())
+    }
+    
+    /// Start a fade that begins at `now`
+    pub fn start_fade_at(&mut self, address: u16, target_r: u8, target_g: u8, target_b: u8, duration_ms: u64, now: Instant) -> Result<()> {
         // Get the FCW mapping to find affected fixtures
         let mapping = self.config.get_fcw_mapping(address);
         
@@ -534,7 +802,7 @@ impl FixtureManager {
             return Ok(());
         }
         
-        let (target_r_out, target_g_out, target_b_out, target_w_out) = self.rgb_to_rgbw(target_r, target_g, target_b);
+        let curve = self.fade_curve_for(address);
         
         // Start fade for all affected fixtures
         let operations: Vec<(u16, FcwDirective)> = mapping.unwrap()
@@ -543,8 +811,6 @@ impl FixtureManager {
             .map(|(num, dir)| (*num, dir.clone()))
             .collect();
         
-        let now = Instant::now();
-        
         for (fixture_num, directive) in operations {
             match directive {
                 FcwDirective::On | FcwDirective::Fade => {
@@ -552,12 +818,10 @@ impl FixtureManager {
                     let start_color = self.current_state.get(&fixture_num).copied().unwrap_or((0, 0, 0, 0));
                     
                     // Apply color corrections to target
+                    let target = self.rgb_to_rgbw(fixture_num, target_r, target_g, target_b);
                     let fixture = self.config.get_fixture(fixture_num);
                     if let Some(fixture) = fixture {
-                        let end_r = (target_r_out as f32 * fixture.corrections.get(0).unwrap_or(&1.0)) as u8;
-                        let end_g = (target_g_out as f32 * fixture.corrections.get(1).unwrap_or(&1.0)) as u8;
-                        let end_b = (target_b_out as f32 * fixture.corrections.get(2).unwrap_or(&1.0)) as u8;
-                        let end_w = (target_w_out as f32 * fixture.corrections.get(3).unwrap_or(&1.0)) as u8;
+                        let (end_r, end_g, end_b, end_w) = fixture.correction.apply(target);
                         
                         // Start the fade
                         self.active_fades.insert(fixture_num, FadeState {
@@ -566,10 +830,13 @@ impl FixtureManager {
                             start_color,
                             end_color: (end_r, end_g, end_b, end_w),
                             current_color: start_color, // Initialize with start color
+                            current_level: to_levels(start_color),
+                            curve,
                         });
                         
                         tracing::debug!(
-                            "Starting fade for fixture {}: {:?} -> {:?} over {}ms",
+                            "Starting {:?} fade for fixture {}: {:?} -> {:?} over {}ms",
+                            curve,
                             fixture_num,
                             start_color,
                             (end_r, end_g, end_b, end_w),
@@ -584,15 +851,94 @@ impl FixtureManager {
         Ok(())
     }
     
-    /// Get current color of a fixture (including fade interpolation if active)
+    /// Get current output color of a fixture (including fades and held layers)
     pub fn get_fixture_color(&self, fixture_num: u16) -> Option<(u8, u8, u8, u8)> {
-        // If actively fading, return cached color
-        if let Some(fade_state) = self.active_fades.get(&fixture_num) {
-            return Some(fade_state.current_color);
+        let is_held = self.override_layer.is_held(fixture_num) || self.safety_layer.is_held(fixture_num);
+        let is_idle = self.idle_levels.contains_key(&fixture_num);
+        if self.show_levels(fixture_num).is_none() && !is_held && !is_idle {
+            return None;
         }
-        
-        // Not fading or fade complete - return static color
-        self.current_state.get(&fixture_num).copied()
+        Some(to_color(self.output_levels(fixture_num)))
+    }
+    
+    /// Check if any fade (or idle crossfade or layer release) is still running
+    pub fn is_fading(&self) -> bool {
+        !self.active_fades.is_empty()
+            || self.idle_mix.is_fading()
+            || self.override_layer.is_releasing()
+            || self.safety_layer.is_releasing()
+    }
+    
+    fn layer_mut(&mut self, layer: Layer) -> &mut OutputLayer {
+        match layer {
+            Layer::Override => &mut self.override_layer,
+            Layer::Safety => &mut self.safety_layer,
+        }
+    }
+    
+    fn layer(&self, layer: Layer) -> &OutputLayer {
+        match layer {
+            Layer::Override => &self.override_layer,
+            Layer::Safety => &self.safety_layer,
+        }
+    }
+    
+    /// Hold fixtures at an RGB color on a layer above the show
+    ///
+    /// The color goes through the same white conversion and corrections as
+    /// show colors.
+    pub fn hold_fixtures(&mut self, layer: Layer, fixtures: &[u16], r: u8, g: u8, b: u8, rule: MergeRule) {
+        for &fixture_num in fixtures {
+            let Some(fixture) = self.config.get_fixture(fixture_num) else {
+                tracing::warn!("No fixture definition found for fixture {}", fixture_num);
+                continue;
+            };
+            let color = fixture.correction.apply(self.rgb_to_rgbw(fixture_num, r, g, b));
+            self.layer_mut(layer).hold(fixture_num, to_levels(color), rule);
+        }
+    }
+    
+    /// Fixtures driven by an FCW address (a light group)
+    pub fn group_fixtures(&self, address: u16) -> Vec<u16> {
+        let mut fixtures: Vec<u16> = self.config.get_fcw_mapping(address)
+            .map(|m| m.fixture_directives.keys().copied().collect())
+            .unwrap_or_default();
+        fixtures.sort_unstable();
+        fixtures
+    }
+    
+    /// Hand fixtures back to the layers below over `duration_ms`
+    pub fn release_fixtures(&mut self, layer: Layer, fixtures: &[u16], duration_ms: u64, now: Instant) {
+        for &fixture_num in fixtures {
+            self.layer_mut(layer).release(fixture_num, duration_ms, now);
+        }
+    }
+    
+    /// Release everything held on a layer
+    pub fn release_layer(&mut self, layer: Layer, duration_ms: u64, now: Instant) {
+        self.layer_mut(layer).release_all(duration_ms, now);
+    }
+    
+    /// Fixtures currently held on a layer, including ones releasing
+    pub fn held_fixtures(&self, layer: Layer) -> Vec<u16> {
+        let mut fixtures: Vec<u16> = self.layer(layer).fixtures().collect();
+        fixtures.sort_unstable();
+        fixtures
+    }
+    
+    /// Force every fixture to black on the safety layer, or release it instantly
+    pub fn set_safety_blackout(&mut self, active: bool) {
+        if active {
+            for fixture_num in self.get_all_fixture_numbers() {
+                self.safety_layer.hold(fixture_num, [0; 4], MergeRule::Ltp);
+            }
+        } else {
+            self.safety_layer.clear();
+        }
+    }
+    
+    pub fn is_safety_blackout(&self) -> bool {
+        !self.safety_layer.is_empty()
     }
     
     /// Clear all fixtures (blackout)
@@ -606,11 +952,383 @@ impl FixtureManager {
     pub fn get_all_fixture_numbers(&self) -> Vec<u16> {
         self.config.fixtures.keys().copied().collect()
     }
+    
+    /// Pick the idle look and how long it takes to fade in and out
+    pub fn set_idle_look(&mut self, look: IdleLook, fade_ms: u64) {
+        self.idle_fade_ms = fade_ms;
+        if look != self.idle_look {
+            self.idle_look = look;
+            self.idle_effects.stop_all();
+            self.idle_levels.clear();
+            self.refresh_idle_at(Instant::now());
+        }
+    }
+    
+    pub fn idle_look(&self) -> IdleLook {
+        self.idle_look
+    }
+    
+    /// Tell the manager whether a song is playing
+    ///
+    /// The idle look fades in while nothing plays. Starting a song fades it
+    /// out and clears a `099-077` hold.
+    pub fn set_idle_wanted_at(&mut self, wanted: bool, now: Instant) {
+        if wanted == self.idle_wanted {
+            return;
+        }
+        self.idle_wanted = wanted;
+        if !wanted {
+            self.idle_held = false;
+        }
+        self.refresh_idle_at(now);
+    }
+    
+    /// Operator toggle: keep the idle look out regardless of playback
+    pub fn set_idle_suppressed(&mut self, suppressed: bool) {
+        self.idle_suppressed = suppressed;
+        self.refresh_idle_at(Instant::now());
+    }
+    
+    pub fn is_idle_suppressed(&self) -> bool {
+        self.idle_suppressed
+    }
+    
+    /// True while the show has held the idle look out with `099-077`
+    pub fn is_idle_held(&self) -> bool {
+        self.idle_held
+    }
+    
+    /// True while the idle look is (at least partly) on
+    pub fn is_idle_active(&self) -> bool {
+        self.idle_mix.level() > 0.0 || self.idle_mix.is_fading()
+    }
+    
+    /// Start fading the idle look in or out to match the current state
+    fn refresh_idle_at(&mut self, now: Instant) {
+        let active = self.idle_wanted
+            && !self.idle_held
+            && !self.idle_suppressed
+            && self.idle_look != IdleLook::Off;
+        
+        if active {
+            self.start_idle_look(now);
+        }
+        self.idle_mix.fade_to(if active { 1.0 } else { 0.0 }, self.idle_fade_ms, now);
+        self.update_idle_at(now);
+    }
+    
+    /// Fill the idle levels from a scene, or start the idle effect
+    fn start_idle_look(&mut self, now: Instant) {
+        match self.idle_look {
+            IdleLook::Off => {}
+            IdleLook::Scene(id) => {
+                let Some(scene) = self.config.scenes.get(id) else {
+                    tracing::warn!("Idle look: no scene defined with id {}", id);
+                    return;
+                };
+                self.idle_levels = scene.fixtures.iter()
+                    .filter(|(fixture_num, _)| !self.pyro.is_fire_channel(**fixture_num))
+                    .map(|(&fixture_num, &[r, g, b, w])| (fixture_num, to_levels((r, g, b, w))))
+                    .collect();
+            }
+            IdleLook::Effect(id) => {
+                if self.idle_effects.is_running(id) {
+                    return;
+                }
+                let Some(definition) = self.config.effects.get(id).cloned() else {
+                    tracing::warn!("Idle look: no effect defined with id {}", id);
+                    return;
+                };
+                let fixtures = self.effect_fixtures(&definition);
+                let colors = [self.effect_color(definition.color), self.effect_color(definition.color2)];
+                self.idle_effects.start(definition, fixtures, colors, now);
+            }
+        }
+    }
+    
+    /// Advance the idle crossfade and refresh an idle effect as of `now`
+    fn update_idle_at(&mut self, now: Instant) {
+        self.idle_mix.update(now);
+        
+        if self.idle_mix.level() == 0.0 && !self.idle_mix.is_fading() {
+            self.idle_effects.stop_all();
+            self.idle_levels.clear();
+            return;
+        }
+        
+        if !self.idle_effects.is_empty() {
+            self.idle_levels = self.idle_effects.samples(now).into_iter()
+                .map(|(fixture_num, sample)| (fixture_num, self.effect_sample_levels(fixture_num, sample)))
+                .collect();
+        }
+    }
+    
+    /// Arm the firework channels (the UI asks for confirmation first)
+    ///
+    /// Fire channels the show set while disarmed are cleared first, so
+    /// arming mid-song never fires from them; only the next fire command
+    /// does, and it is audited.
+    pub fn arm_pyro(&mut self) {
+        if !self.pyro.is_armed() {
+            self.cut_pyro();
+        }
+        self.pyro.arm();
+    }
+    
+    pub fn disarm_pyro(&mut self, reason: &str) {
+        self.pyro.disarm(reason);
+    }
+    
+    pub fn is_pyro_armed(&self) -> bool {
+        self.pyro.is_armed()
+    }
+    
+    /// Tell the interlock whether a song is playing live
+    ///
+    /// Fire channels are cleared whenever the show goes live, so resuming,
+    /// stepping or seeking never fires from state built up before.
+    pub fn set_pyro_live(&mut self, live: bool) {
+        if self.pyro.set_live(live) {
+            self.cut_pyro();
+        }
+    }
+    
+    /// Drop every fire channel from the show state
+    pub fn cut_pyro(&mut self) {
+        for fixture_num in self.pyro.fixtures().collect::<Vec<_>>() {
+            self.current_state.remove(&fixture_num);
+            self.active_fades.remove(&fixture_num);
+            self.pyro.observe(fixture_num, false);
+        }
+    }
+    
+    /// Firework fixture numbers, sorted
+    pub fn pyro_fixtures(&self) -> Vec<u16> {
+        self.pyro.fixtures().collect()
+    }
+    
+    /// Arm, disarm, fire and blocked events since the last call, for the audit log
+    pub fn take_pyro_events(&mut self) -> Vec<PyroEvent> {
+        self.pyro.take_events()
+    }
+    
+    /// Snapshot the show look of every fixture as a scene
+    ///
+    /// Takes the show and effect levels only, so an idle look, override or
+    /// safety blackout on the rig is not saved into the scene.
+    pub fn capture_scene(&self, id: u16, name: &str) -> Scene {
+        let mut fixtures = std::collections::BTreeMap::new();
+        for fixture_num in self.get_all_fixture_numbers() {
+            if self.pyro.is_fire_channel(fixture_num) {
+                continue;
+            }
+            let (r, g, b, w) = self.show_levels(fixture_num).map(to_color).unwrap_or((0, 0, 0, 0));
+            fixtures.insert(fixture_num, [r, g, b, w]);
+        }
+        
+        Scene { id, name: name.to_string(), fixtures, water: Vec::new() }
+    }
+    
+    /// Recall a scene onto the show layer, crossfading over `duration_ms`
+    ///
+    /// Running effects stop. Fixtures not in the scene keep their color.
+    /// Returns the scene's water commands for the caller to send to the PLC.
+    pub fn recall_scene_at(&mut self, id: u16, duration_ms: u64, now: Instant) -> Vec<String> {
+        let Some(scene) = self.config.scenes.get(id).cloned() else {
+            tracing::warn!("No scene defined with id {}", id);
+            return Vec::new();
+        };
+        
+        tracing::info!("Recalling scene {} '{}' over {}ms", id, scene.name, duration_ms);
+        
+        // Freeze effect output where it is so the crossfade starts from what is visible
+        let visible: HashMap<u16, (u8, u8, u8, u8)> = scene.fixtures.keys()
+            .map(|&fixture_num| (fixture_num, self.show_levels(fixture_num).map(to_color).unwrap_or((0, 0, 0, 0))))
+            .collect();
+        self.effects.stop_all();
+        self.effect_levels.clear();
+        
+        let curve = self.fade_curve_override.unwrap_or(self.default_fade_curve);
+        for (&fixture_num, &[r, g, b, w]) in &scene.fixtures {
+            if self.pyro.is_fire_channel(fixture_num) {
+                tracing::warn!("Scene '{}' includes fire channel {}; skipped", scene.name, fixture_num);
+                continue;
+            }
+            // Scene colors were captured after correction, so they go out as-is
+            let end_color = (r, g, b, w);
+            if duration_ms == 0 {
+                self.active_fades.remove(&fixture_num);
+                self.current_state.insert(fixture_num, end_color);
+                continue;
+            }
+            
+            let start_color = visible[&fixture_num];
+            self.active_fades.insert(fixture_num, FadeState {
+                start_time: now,
+                duration_ms,
+                start_color,
+                end_color,
+                current_color: start_color,
+                current_level: to_levels(start_color),
+                curve,
+            });
+        }
+        
+        scene.water
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    
-    // Tests would require loading actual CSV files or mocking
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    fn manager(config: CsvConfig) -> FixtureManager {
+        let mut fm = FixtureManager::new(config);
+        fm.set_white_strategy(WhiteStrategy::Off);
+        fm
+    }
+
+    /// The universe as of `now`
+    fn dmx_at(fm: &mut FixtureManager, now: Instant) -> DmxUniverse {
+        fm.update_fades_at(now);
+        let mut universe = DmxUniverse::new();
+        fm.apply_to_dmx(&mut universe).unwrap();
+        universe
+    }
+
+    #[test]
+    fn test_fade_curve_override_and_fine_channels() {
+        let mut config = CsvConfig::test_rig();
+        let wide = config.profiles.get("RGBW 16-bit").unwrap().clone();
+        config.fixtures.get_mut(&1).unwrap().profile = wide;
+
+        let base = Instant::now();
+        let at = |ms: u64| base + Duration::from_millis(ms);
+        let fade_up = |curve: u16| {
+            let mut fm = manager(config.clone());
+            fm.execute_fcw_command_at(FADE_CURVE_ADDRESS, curve, at(0)).unwrap();
+            fm.start_fade_at(17, 255, 0, 0, 1000, at(0)).unwrap();
+            let half = dmx_at(&mut fm, at(500));
+            let end = dmx_at(&mut fm, at(1000));
+            ((half.get_channel_raw(0), half.get_channel_raw(1)), (end.get_channel_raw(0), end.get_channel_raw(1)))
+        };
+
+        // Halfway through a linear fade the coarse/fine pair holds 0x7FFF or 0x8000
+        let (half, _) = fade_up(0);
+        assert!(half == (127, 255) || half == (128, 0));
+
+        // Square-law is at a quarter level halfway through
+        let (half, end) = fade_up(3);
+        assert!(half == (64, 0) || half == (63, 255));
+        assert_eq!(end, (255, 255));
+    }
+
+    #[test]
+    fn test_effects_start_and_stop() {
+        let mut config = CsvConfig::test_rig();
+        config.effects.insert(EffectDefinition {
+            id: 1,
+            name: "Breathe".to_string(),
+            kind: EffectKind::Pulse,
+            group: Some(17),
+            fixtures: Vec::new(),
+            color: 2,
+            period_ms: 1000,
+            width: 1,
+            duty: 0.1,
+            density: 0.3,
+            color2: 0,
+            direction: Default::default(),
+            band_width: 0.2,
+        });
+
+        let base = Instant::now();
+        let at = |ms: u64| base + Duration::from_millis(ms);
+        let mut fm = manager(config);
+        // Red instead of the defined white, stopped after one breath
+        fm.execute_fcw_command_at(73, 1, at(0)).unwrap();
+        fm.execute_fcw_command_at(70, 1, at(0)).unwrap();
+
+        let peak = dmx_at(&mut fm, at(500));
+        assert_eq!(peak.get_channel_raw(0), 255);
+        assert_eq!(peak.get_channel_raw(3), 0);
+
+        fm.execute_fcw_command_at(71, 1, at(1000)).unwrap();
+        assert_eq!(dmx_at(&mut fm, at(1000)).get_channel_raw(0), 0);
+    }
+
+    #[test]
+    fn test_scene_recall_crossfade() {
+        let mut config = CsvConfig::test_rig();
+        config.scenes.insert(Scene {
+            id: 4,
+            name: "Blue".to_string(),
+            fixtures: BTreeMap::from([(1, [0, 0, 255, 0])]),
+            water: vec!["001-005".to_string()],
+        });
+
+        let base = Instant::now();
+        let at = |ms: u64| base + Duration::from_millis(ms);
+        let mut fm = manager(config);
+        fm.execute_fcw_command_at(17, 1, at(0)).unwrap();
+        dmx_at(&mut fm, at(0));
+
+        // Crossfade from red to the scene over one second
+        assert_eq!(fm.recall_scene_at(4, 1000, at(1000)), vec!["001-005".to_string()]);
+        let half = dmx_at(&mut fm, at(1500));
+        assert_eq!((half.get_channel_raw(0), half.get_channel_raw(2)), (128, 128));
+        let end = dmx_at(&mut fm, at(2000));
+        assert_eq!((end.get_channel_raw(0), end.get_channel_raw(2)), (0, 255));
+    }
+
+    #[test]
+    fn test_capture_scene_skips_safety_layer() {
+        let mut fm = manager(CsvConfig::test_rig());
+        fm.execute_fcw_command_at(17, 1, Instant::now()).unwrap();
+        fm.update_fades_at(Instant::now());
+        fm.set_safety_blackout(true);
+
+        assert_eq!(fm.get_fixture_color(1), Some((0, 0, 0, 0)));
+        assert_eq!(fm.capture_scene(1, "Red").fixtures.get(&1), Some(&[255, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_idle_look_crossfade_and_hold() {
+        let mut config = CsvConfig::test_rig();
+        config.scenes.insert(Scene {
+            id: 1,
+            name: "Standby".to_string(),
+            fixtures: BTreeMap::from([(1, [0, 0, 200, 0])]),
+            water: Vec::new(),
+        });
+
+        let base = Instant::now();
+        let at = |ms: u64| base + Duration::from_millis(ms);
+        let mut fm = FixtureManager::new(config);
+        fm.set_idle_look(IdleLook::Scene(1), 1000);
+
+        fm.set_idle_wanted_at(true, at(0));
+        fm.update_fades_at(at(500));
+        assert_eq!(fm.get_fixture_color(1), Some((0, 0, 100, 0)));
+        fm.update_fades_at(at(1000));
+        assert_eq!(fm.get_fixture_color(1), Some((0, 0, 200, 0)));
+
+        // A song starts: the idle look fades out over the show
+        fm.set_idle_wanted_at(false, at(2000));
+        fm.update_fades_at(at(3000));
+        assert_eq!(fm.get_fixture_color(1), None);
+
+        // The song ends with 099-077, so the gap after it stays dark
+        fm.execute_fcw_command_at(99, 77, at(3000)).unwrap();
+        fm.set_idle_wanted_at(true, at(3000));
+        fm.update_fades_at(at(4000));
+        assert!(fm.is_idle_held());
+        assert_eq!(fm.get_fixture_color(1), None);
+
+        // The next song clears the hold
+        fm.set_idle_wanted_at(false, at(5000));
+        assert!(!fm.is_idle_held());
+    }
 }
@@ -0,0 +1,130 @@
+use std::time::Instant;
+
+/// `099-077` keeps the idle look out until the next song starts
+pub const IDLE_HOLD_ADDRESS: u16 = 99;
+pub const IDLE_HOLD_DATA: u16 = 77;
+
+/// What the fountain shows while no song is playing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IdleLook {
+    /// Leave the fixtures black (the original behavior)
+    #[default]
+    Off,
+    /// A scene from scenes.json
+    Scene(u16),
+    /// An effect from effects.json
+    Effect(u16),
+}
+
+impl IdleLook {
+    /// Parse "off", "scene:<id>" or "effect:<id>"; anything else is off
+    pub fn from_setting(value: &str) -> Self {
+        let value = value.trim().to_lowercase();
+        let id = |rest: &str| rest.trim().parse::<u16>().ok();
+        match value.split_once(':') {
+            Some(("scene", rest)) => id(rest).map(IdleLook::Scene).unwrap_or_default(),
+            Some(("effect", rest)) => id(rest).map(IdleLook::Effect).unwrap_or_default(),
+            _ => IdleLook::Off,
+        }
+    }
+
+    pub fn as_setting(&self) -> String {
+        match self {
+            IdleLook::Off => "off".to_string(),
+            IdleLook::Scene(id) => format!("scene:{}", id),
+            IdleLook::Effect(id) => format!("effect:{}", id),
+        }
+    }
+}
+
+/// Crossfade level between the show (0.0) and the idle look (1.0)
+#[derive(Debug, Clone, Default)]
+pub struct IdleMix {
+    level: f32,
+    from: f32,
+    target: f32,
+    start_time: Option<Instant>,
+    duration_ms: u64,
+}
+
+impl IdleMix {
+    /// Head towards `target` over `duration_ms`, starting from the current level
+    pub fn fade_to(&mut self, target: f32, duration_ms: u64, now: Instant) {
+        if target == self.target {
+            return;
+        }
+
+        self.from = self.level;
+        self.target = target;
+        self.duration_ms = duration_ms;
+        if duration_ms == 0 {
+            self.level = target;
+            self.start_time = None;
+        } else {
+            self.start_time = Some(now);
+        }
+    }
+
+    /// Advance the crossfade as of `now`
+    pub fn update(&mut self, now: Instant) {
+        let Some(start_time) = self.start_time else { return };
+
+        let elapsed_ms = now.duration_since(start_time).as_millis() as u64;
+        let progress = (elapsed_ms as f32 / self.duration_ms as f32).min(1.0);
+        self.level = self.from + (self.target - self.from) * progress;
+        if progress >= 1.0 {
+            self.start_time = None;
+        }
+    }
+
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    pub fn is_fading(&self) -> bool {
+        self.start_time.is_some()
+    }
+
+    /// Blend show levels with idle levels at the current mix
+    pub fn apply(&self, show: [u16; 4], idle: [u16; 4]) -> [u16; 4] {
+        std::array::from_fn(|i| {
+            let (from, to) = (show[i] as f32, idle[i] as f32);
+            (from + (to - from) * self.level).round() as u16
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_idle_look_settings() {
+        assert_eq!(IdleLook::from_setting("scene:3"), IdleLook::Scene(3));
+        assert_eq!(IdleLook::from_setting(" Effect:12 "), IdleLook::Effect(12));
+        assert_eq!(IdleLook::from_setting("scene:"), IdleLook::Off);
+        assert_eq!(IdleLook::from_setting("off"), IdleLook::Off);
+        assert_eq!(IdleLook::Scene(3).as_setting(), "scene:3");
+    }
+
+    #[test]
+    fn test_mix_fades_in_and_reverses() {
+        let start = Instant::now();
+        let mut mix = IdleMix::default();
+        mix.fade_to(1.0, 1000, start);
+
+        mix.update(start + Duration::from_millis(500));
+        assert_eq!(mix.level(), 0.5);
+        assert_eq!(mix.apply([0, 0, 1000, 0], [20000, 0, 0, 0]), [10000, 0, 500, 0]);
+
+        // Reversing mid-fade starts from where it is
+        mix.fade_to(0.0, 1000, start + Duration::from_millis(500));
+        mix.update(start + Duration::from_millis(1000));
+        assert_eq!(mix.level(), 0.25);
+
+        mix.update(start + Duration::from_millis(1500));
+        assert_eq!(mix.level(), 0.0);
+        assert!(!mix.is_fading());
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Layers that sit above the show, lowest priority first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Layer {
+    /// Operator holds from the Testing view
+    Override,
+    /// Blackout and interlocks; always wins
+    Safety,
+}
+
+/// How a held fixture combines with the layers below it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergeRule {
+    /// Highest takes precedence, per channel
+    Htp,
+    /// Latest takes precedence: the hold replaces what is below
+    #[default]
+    Ltp,
+}
+
+impl MergeRule {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MergeRule::Htp => "HTP",
+            MergeRule::Ltp => "LTP",
+        }
+    }
+
+    fn merge(&self, below: [u16; 4], held: [u16; 4]) -> [u16; 4] {
+        match self {
+            MergeRule::Htp => std::array::from_fn(|i| below[i].max(held[i])),
+            MergeRule::Ltp => held,
+        }
+    }
+}
+
+/// A fixture held by a layer
+#[derive(Debug, Clone)]
+struct Hold {
+    levels: [u16; 4],
+    rule: MergeRule,
+    release: Option<Release>,
+}
+
+/// A hold fading back to the layers below
+#[derive(Debug, Clone)]
+struct Release {
+    start_time: Instant,
+    duration_ms: u64,
+    progress: f32, // Cached by update(), 0.0 = fully held
+}
+
+/// Per-fixture holds stacked over the show output
+#[derive(Debug, Clone, Default)]
+pub struct OutputLayer {
+    holds: HashMap<u16, Hold>,
+}
+
+impl OutputLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hold a fixture at 16-bit RGBW levels, cancelling any release in progress
+    pub fn hold(&mut self, fixture_num: u16, levels: [u16; 4], rule: MergeRule) {
+        self.holds.insert(fixture_num, Hold { levels, rule, release: None });
+    }
+
+    /// Hand a fixture back to the layers below over `duration_ms`
+    pub fn release(&mut self, fixture_num: u16, duration_ms: u64, now: Instant) {
+        if duration_ms == 0 {
+            self.holds.remove(&fixture_num);
+            return;
+        }
+
+        if let Some(hold) = self.holds.get_mut(&fixture_num) {
+            if hold.release.is_none() {
+                hold.release = Some(Release { start_time: now, duration_ms, progress: 0.0 });
+            }
+        }
+    }
+
+    /// Release every held fixture
+    pub fn release_all(&mut self, duration_ms: u64, now: Instant) {
+        let fixtures: Vec<u16> = self.holds.keys().copied().collect();
+        for fixture_num in fixtures {
+            self.release(fixture_num, duration_ms, now);
+        }
+    }
+
+    /// Advance releases as of `now` and drop the finished ones
+    pub fn update(&mut self, now: Instant) {
+        self.holds.retain(|_, hold| match &mut hold.release {
+            Some(release) => {
+                let elapsed_ms = now.duration_since(release.start_time).as_millis() as u64;
+                release.progress = (elapsed_ms as f32 / release.duration_ms as f32).min(1.0);
+                elapsed_ms < release.duration_ms
+            }
+            None => true,
+        });
+    }
+
+    /// Combine this layer with the output of the layers below
+    pub fn apply(&self, fixture_num: u16, below: [u16; 4]) -> [u16; 4] {
+        let Some(hold) = self.holds.get(&fixture_num) else {
+            return below;
+        };
+
+        let held = hold.rule.merge(below, hold.levels);
+        match &hold.release {
+            Some(release) => std::array::from_fn(|i| {
+                let (from, to) = (held[i] as f32, below[i] as f32);
+                (from + (to - from) * release.progress).round() as u16
+            }),
+            None => held,
+        }
+    }
+
+    pub fn is_held(&self, fixture_num: u16) -> bool {
+        self.holds.contains_key(&fixture_num)
+    }
+
+    /// Held fixtures, including ones still releasing
+    pub fn fixtures(&self) -> impl Iterator<Item = u16> + '_ {
+        self.holds.keys().copied()
+    }
+
+    pub fn is_releasing(&self) -> bool {
+        self.holds.values().any(|hold| hold.release.is_some())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.holds.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.holds.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const SHOW: [u16; 4] = [60000, 0, 1000, 0];
+    const HELD: [u16; 4] = [0, 30000, 2000, 0];
+
+    #[test]
+    fn test_merge_rules() {
+        let mut layer = OutputLayer::new();
+        assert_eq!(layer.apply(1, SHOW), SHOW);
+
+        layer.hold(1, HELD, MergeRule::Ltp);
+        assert_eq!(layer.apply(1, SHOW), HELD);
+
+        layer.hold(1, HELD, MergeRule::Htp);
+        assert_eq!(layer.apply(1, SHOW), [60000, 30000, 2000, 0]);
+
+        // Other fixtures pass straight through
+        assert_eq!(layer.apply(2, SHOW), SHOW);
+    }
+
+    #[test]
+    fn test_release_fades_back_to_show() {
+        let start = Instant::now();
+        let mut layer = OutputLayer::new();
+        layer.hold(1, [0; 4], MergeRule::Ltp);
+        layer.release(1, 1000, start);
+
+        layer.update(start + Duration::from_millis(500));
+        assert_eq!(layer.apply(1, SHOW), [30000, 0, 500, 0]);
+        assert!(layer.is_releasing());
+
+        layer.update(start + Duration::from_millis(1000));
+        assert!(layer.is_empty());
+        assert_eq!(layer.apply(1, SHOW), SHOW);
+    }
+
+    #[test]
+    fn test_hold_cancels_release() {
+        let start = Instant::now();
+        let mut layer = OutputLayer::new();
+        layer.hold(1, HELD, MergeRule::Ltp);
+        layer.release(1, 1000, start);
+        layer.hold(1, HELD, MergeRule::Ltp);
+
+        layer.update(start + Duration::from_millis(2000));
+        assert_eq!(layer.apply(1, SHOW), HELD);
+
+        layer.release(1, 0, start);
+        assert!(!layer.is_held(1));
+    }
+}
@@ -1,8 +1,16 @@
 pub mod color;
+pub mod effects;
 pub mod channel;
+pub mod fade;
 pub mod fixture_manager;
+pub mod idle;
+pub mod layers;
+pub mod pyro;
 
+pub use fade::FadeCurve;
 pub use fixture_manager::FixtureManager;
+pub use idle::IdleLook;
+pub use layers::{Layer, MergeRule};
 
 use thiserror::Error;
 
@@ -0,0 +1,273 @@
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::Path;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+
+/// Something the interlock did, for the audit log
+#[derive(Debug, Clone, PartialEq)]
+pub enum PyroEventKind {
+    Armed,
+    Disarmed { reason: String },
+    /// A fire channel went live
+    Fired { fixture_num: u16 },
+    /// The show asked for a fire channel while it was not allowed to fire
+    Blocked { fixture_num: u16, reason: &'static str },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PyroEvent {
+    pub at: DateTime<Local>,
+    pub kind: PyroEventKind,
+}
+
+/// Arm/disarm state for the firework channels
+///
+/// Fire channels only reach DMX while the interlock is armed and a song is
+/// playing live. Every fire, and every fire that was blocked, is queued as
+/// an event for the audit log.
+#[derive(Debug, Clone, Default)]
+pub struct PyroInterlock {
+    fixtures: BTreeSet<u16>,
+    armed: bool,
+    live: bool,
+    requested: BTreeSet<u16>, // Fire channels the show is driving, for edge detection
+    events: Vec<PyroEvent>,
+}
+
+impl PyroInterlock {
+    pub fn new(fixtures: impl IntoIterator<Item = u16>) -> Self {
+        Self { fixtures: fixtures.into_iter().collect(), ..Self::default() }
+    }
+
+    pub fn is_fire_channel(&self, fixture_num: u16) -> bool {
+        self.fixtures.contains(&fixture_num)
+    }
+
+    /// Fire fixtures, sorted
+    pub fn fixtures(&self) -> impl Iterator<Item = u16> + '_ {
+        self.fixtures.iter().copied()
+    }
+
+    pub fn arm(&mut self) {
+        if !self.armed {
+            self.armed = true;
+            self.record(PyroEventKind::Armed);
+        }
+    }
+
+    pub fn disarm(&mut self, reason: &str) {
+        if self.armed {
+            self.armed = false;
+            self.record(PyroEventKind::Disarmed { reason: reason.to_string() });
+        }
+    }
+
+    pub fn is_armed(&self) -> bool {
+        self.armed
+    }
+
+    /// Set whether a song is playing live (not paused, stepped or just seeked)
+    ///
+    /// Returns true when the show went live, so the caller can drop fire
+    /// state carried over from before.
+    pub fn set_live(&mut self, live: bool) -> bool {
+        let went_live = live && !self.live;
+        self.live = live;
+        went_live
+    }
+
+    pub fn is_live(&self) -> bool {
+        self.live
+    }
+
+    /// True when fire channels may reach DMX
+    pub fn can_fire(&self) -> bool {
+        self.armed && self.live
+    }
+
+    /// Track which fire channels the show is driving and log the rising edges
+    pub fn observe(&mut self, fixture_num: u16, requested: bool) {
+        if !requested {
+            self.requested.remove(&fixture_num);
+            return;
+        }
+        if !self.requested.insert(fixture_num) {
+            return;
+        }
+
+        let kind = if self.can_fire() {
+            PyroEventKind::Fired { fixture_num }
+        } else {
+            let reason = if self.armed { "not playing live" } else { "disarmed" };
+            PyroEventKind::Blocked { fixture_num, reason }
+        };
+        self.record(kind);
+    }
+
+    /// Events since the last call, oldest first
+    pub fn take_events(&mut self) -> Vec<PyroEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn record(&mut self, kind: PyroEventKind) {
+        match &kind {
+            PyroEventKind::Fired { fixture_num } => tracing::warn!("PYRO: fixture {} fired", fixture_num),
+            PyroEventKind::Blocked { fixture_num, reason } => tracing::warn!("PYRO: fixture {} blocked ({})", fixture_num, reason),
+            PyroEventKind::Armed => tracing::warn!("PYRO: armed"),
+            PyroEventKind::Disarmed { reason } => tracing::warn!("PYRO: disarmed ({})", reason),
+        }
+        self.events.push(PyroEvent { at: Local::now(), kind });
+    }
+}
+
+/// Append events to the CSV audit log, creating it with a header if needed
+///
+/// `song` and `position_ms` say where in the show the events happened.
+pub fn append_audit<P: AsRef<Path>>(path: P, events: &[PyroEvent], song: &str, position_ms: u64) -> Result<()> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let is_new = !path.exists();
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .context(format!("Failed to open pyro audit log at {:?}", path))?;
+
+    if is_new {
+        writeln!(file, "time,event,fixture,detail,song,position_ms")?;
+    }
+    for event in events {
+        let (name, fixture, detail) = match &event.kind {
+            PyroEventKind::Armed => ("armed", String::new(), String::new()),
+            PyroEventKind::Disarmed { reason } => ("disarmed", String::new(), reason.clone()),
+            PyroEventKind::Fired { fixture_num } => ("fired", fixture_num.to_string(), String::new()),
+            PyroEventKind::Blocked { fixture_num, reason } => ("blocked", fixture_num.to_string(), reason.to_string()),
+        };
+        writeln!(
+            file,
+            "{},{},{},{},\"{}\",{}",
+            event.at.format("%Y-%m-%d %H:%M:%S%.3f"),
+            name,
+            fixture,
+            detail,
+            song.replace('"', "\"\""),
+            position_ms
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(interlock: &mut PyroInterlock) -> Vec<PyroEventKind> {
+        interlock.take_events().into_iter().map(|e| e.kind).collect()
+    }
+
+    #[test]
+    fn test_fires_only_when_armed_and_live() {
+        let mut interlock = PyroInterlock::new([54]);
+        assert!(interlock.is_fire_channel(54));
+
+        interlock.observe(54, true);
+        interlock.observe(54, true);
+        assert_eq!(kinds(&mut interlock), vec![PyroEventKind::Blocked { fixture_num: 54, reason: "disarmed" }]);
+
+        interlock.observe(54, false);
+        interlock.arm();
+        assert!(interlock.set_live(true));
+        assert!(interlock.can_fire());
+        interlock.observe(54, true);
+        interlock.disarm("end of show");
+        assert!(!interlock.can_fire());
+
+        assert_eq!(kinds(&mut interlock), vec![
+            PyroEventKind::Armed,
+            PyroEventKind::Fired { fixture_num: 54 },
+            PyroEventKind::Disarmed { reason: "end of show".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn test_audit_log_appends() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs").join("pyro_audit.csv");
+        let mut interlock = PyroInterlock::new([54]);
+        interlock.arm();
+        interlock.set_live(true);
+        interlock.observe(54, true);
+
+        append_audit(&path, &interlock.take_events(), "Finale", 61_200).unwrap();
+        append_audit(&path, &[], "Finale", 0).unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("time,event"));
+        assert!(lines[2].ends_with(",fired,54,,\"Finale\",61200"));
+    }
+
+    #[test]
+    fn test_console_merge_cannot_fire() {
+        use crate::config::CsvConfig;
+        use crate::dmx::{DmxMerger, DmxUniverse, MergePolicy, MergeRange};
+        use crate::lighting::FixtureManager;
+
+        let mut fm = FixtureManager::new(CsvConfig::test_rig().with_fireworks());
+        let mut merger = DmxMerger::new(vec![MergeRange { start_channel: 1, end_channel: 20, policy: MergePolicy::ConsoleWins }]);
+        let mut console = [0u8; 512];
+        console[9] = 255;
+        let output = |fm: &FixtureManager, merger: &mut DmxMerger, console: &[u8; 512]| {
+            let mut show = DmxUniverse::new();
+            fm.apply_to_dmx(&mut show).unwrap();
+            let mut universe = merger.merge(&show, Some(console));
+            fm.apply_pyro_to_dmx(&mut universe).unwrap();
+            universe.get_channel_raw(9)
+        };
+
+        // Disarmed: the console's 255 on the fire channel never gets out
+        assert_eq!(output(&fm, &mut merger, &console), 0);
+
+        // Armed and live, the show fires and the console cannot cut it
+        fm.arm_pyro();
+        fm.set_pyro_live(true);
+        assert_eq!(output(&fm, &mut merger, &console), 0);
+        fm.execute_fcw_command_at(900, 2, std::time::Instant::now()).unwrap();
+        console[9] = 0;
+        assert_eq!(output(&fm, &mut merger, &console), 255);
+    }
+
+    #[test]
+    fn test_arming_mid_song_does_not_fire_held_state() {
+        use crate::config::CsvConfig;
+        use crate::dmx::DmxUniverse;
+        use crate::lighting::FixtureManager;
+
+        let now = std::time::Instant::now();
+        let mut fm = FixtureManager::new(CsvConfig::test_rig().with_fireworks());
+        fm.set_pyro_live(true);
+        fm.execute_fcw_command_at(900, 2, now).unwrap();
+        fm.update_fades_at(now);
+        fm.take_pyro_events();
+
+        fm.arm_pyro();
+        fm.update_fades_at(now);
+        let mut universe = DmxUniverse::new();
+        fm.apply_to_dmx(&mut universe).unwrap();
+        assert_eq!(universe.get_channel_raw(9), 0);
+        let kinds: Vec<PyroEventKind> = fm.take_pyro_events().into_iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, vec![PyroEventKind::Armed]);
+
+        // The next fire command after arming goes out and is audited
+        fm.execute_fcw_command_at(900, 2, now).unwrap();
+        fm.update_fades_at(now);
+        fm.apply_to_dmx(&mut universe).unwrap();
+        assert_eq!(universe.get_channel_raw(9), 255);
+        let kinds: Vec<PyroEventKind> = fm.take_pyro_events().into_iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, vec![PyroEventKind::Fired { fixture_num: 54 }]);
+    }
+}
@@ -2,10 +2,13 @@ mod audio;
 mod dmx;
 mod plc;
 mod lighting;
+mod maintenance;
 mod commands;
 mod playlist;
 mod config;
 mod utils;
+mod failsafe;
+mod weather;
 mod gui;
 
 use anyhow::Result;
@@ -0,0 +1,265 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Instant;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use crate::commands::{parse_command, RESET_ADDRESS};
+
+/// Totals at the last service for one interval
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceMark {
+    pub run_secs: f64,
+    pub starts: u64,
+    /// YYYY-MM-DD
+    pub date: String,
+}
+
+/// Running time and start count for one water address or fixture
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Counter {
+    pub run_secs: f64,
+    pub starts: u64,
+    /// Interval name -> totals when that service was last done
+    #[serde(default)]
+    pub serviced: BTreeMap<String, ServiceMark>,
+}
+
+impl Counter {
+    pub fn run_hours(&self) -> f64 {
+        self.run_secs / 3600.0
+    }
+
+    /// Hours and starts since `interval` was last done, or since new
+    pub fn since_service(&self, interval: &str) -> (f64, u64) {
+        match self.serviced.get(interval) {
+            Some(mark) => ((self.run_secs - mark.run_secs) / 3600.0, self.starts - mark.starts),
+            None => (self.run_hours(), self.starts),
+        }
+    }
+
+    pub fn mark_serviced(&mut self, interval: &str, date: String) {
+        let mark = ServiceMark { run_secs: self.run_secs, starts: self.starts, date };
+        self.serviced.insert(interval.to_string(), mark);
+    }
+}
+
+/// Which counters a reading or interval applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CounterKind {
+    /// Pumps and valves, by FCW address
+    #[default]
+    Water,
+    /// LED fixtures, by fixture number
+    Fixture,
+}
+
+impl CounterKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            CounterKind::Water => "water",
+            CounterKind::Fixture => "fixture",
+        }
+    }
+}
+
+/// Run hours and starts for every pump, valve and fixture, kept in a JSON
+/// file between runs
+///
+/// Water addresses count from the commands the PLC has taken: a nonzero
+/// value on an address that was off is a start, and it runs until it is
+/// sent `000` or `099-000`. Fixtures count while their output is lit.
+/// Running time is folded into the totals by `tick_at`, so a crash loses at
+/// most the time since the last save.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MaintenanceCounters {
+    #[serde(default)]
+    pub water: BTreeMap<u16, Counter>,
+    #[serde(default)]
+    pub fixtures: BTreeMap<u16, Counter>,
+    /// Running now, and when their time was last counted
+    #[serde(skip)]
+    water_on: BTreeMap<u16, Instant>,
+    #[serde(skip)]
+    fixtures_on: BTreeMap<u16, Instant>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl MaintenanceCounters {
+    /// Load the counters file; a missing file starts from zero
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let json_data = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read maintenance counters at {:?}", path))?;
+        serde_json::from_str(&json_data)
+            .with_context(|| format!("Failed to parse maintenance counters at {:?}", path))
+    }
+
+    /// Write the counters, via a temporary file so a crash mid-write keeps
+    /// the old ones
+    pub fn save<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let temp = path.with_extension("json.tmp");
+        std::fs::write(&temp, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write maintenance counters at {:?}", temp))?;
+        std::fs::rename(&temp, path)
+            .with_context(|| format!("Failed to replace maintenance counters at {:?}", path))?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// True when something has changed since the last save
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn counters(&self, kind: CounterKind) -> &BTreeMap<u16, Counter> {
+        match kind {
+            CounterKind::Water => &self.water,
+            CounterKind::Fixture => &self.fixtures,
+        }
+    }
+
+    pub fn counter_mut(&mut self, kind: CounterKind, address: u16) -> &mut Counter {
+        self.dirty = true;
+        match kind {
+            CounterKind::Water => self.water.entry(address).or_default(),
+            CounterKind::Fixture => self.fixtures.entry(address).or_default(),
+        }
+    }
+
+    /// Count a water command the PLC has taken
+    pub fn record_water_at(&mut self, command: &str, now: Instant) {
+        let Some((address, data)) = parse_command(command) else {
+            return;
+        };
+        if address == RESET_ADDRESS {
+            if data == 0 {
+                let running = std::mem::take(&mut self.water_on);
+                for (address, since) in running {
+                    add_run(&mut self.water, address, since, now);
+                }
+                self.dirty = true;
+            }
+            return;
+        }
+
+        match (self.water_on.contains_key(&address), data != 0) {
+            (false, true) => {
+                self.water.entry(address).or_default().starts += 1;
+                self.water_on.insert(address, now);
+                self.dirty = true;
+            }
+            (true, false) => {
+                if let Some(since) = self.water_on.remove(&address) {
+                    add_run(&mut self.water, address, since, now);
+                }
+                self.dirty = true;
+            }
+            _ => {}
+        }
+    }
+
+    /// The fixtures lit right now; any not in `lit` have gone dark
+    pub fn set_lit_fixtures_at(&mut self, lit: &[u16], now: Instant) {
+        let dark: Vec<u16> = self.fixtures_on.keys()
+            .filter(|fixture| !lit.contains(fixture))
+            .copied()
+            .collect();
+        for fixture in dark {
+            if let Some(since) = self.fixtures_on.remove(&fixture) {
+                add_run(&mut self.fixtures, fixture, since, now);
+                self.dirty = true;
+            }
+        }
+        for &fixture in lit {
+            if !self.fixtures_on.contains_key(&fixture) {
+                self.fixtures.entry(fixture).or_default().starts += 1;
+                self.fixtures_on.insert(fixture, now);
+                self.dirty = true;
+            }
+        }
+    }
+
+    /// Fold the time of everything still running into the totals
+    pub fn tick_at(&mut self, now: Instant) {
+        for (&address, since) in self.water_on.iter_mut() {
+            add_run(&mut self.water, address, *since, now);
+            *since = now;
+        }
+        for (&fixture, since) in self.fixtures_on.iter_mut() {
+            add_run(&mut self.fixtures, fixture, *since, now);
+            *since = now;
+        }
+        if !self.water_on.is_empty() || !self.fixtures_on.is_empty() {
+            self.dirty = true;
+        }
+    }
+}
+
+fn add_run(counters: &mut BTreeMap<u16, Counter>, address: u16, since: Instant, now: Instant) {
+    counters.entry(address).or_default().run_secs += now.saturating_duration_since(since).as_secs_f64();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_counts_runs_and_starts() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut counters = MaintenanceCounters::default();
+
+        counters.record_water_at("001-005", at(0));
+        counters.record_water_at("001-009", at(10)); // Still running, not a start
+        counters.record_water_at("002-005", at(20));
+        counters.record_water_at("001-000", at(60));
+        counters.tick_at(at(80));
+        counters.record_water_at("001-003", at(100));
+        counters.record_water_at("099-000", at(130));
+        counters.record_water_at("500-FF0000", at(140));
+
+        assert_eq!(counters.water[&1].starts, 2);
+        assert_eq!(counters.water[&1].run_secs, 90.0);
+        assert_eq!(counters.water[&2].starts, 1);
+        assert_eq!(counters.water[&2].run_secs, 110.0);
+        assert!(!counters.water.contains_key(&500));
+
+        counters.set_lit_fixtures_at(&[4, 5], at(0));
+        counters.set_lit_fixtures_at(&[5], at(30));
+        counters.tick_at(at(45));
+        assert_eq!(counters.fixtures[&4].run_secs, 30.0);
+        assert_eq!(counters.fixtures[&5].run_secs, 45.0);
+        assert_eq!(counters.fixtures[&5].starts, 1);
+
+        counters.water.get_mut(&1).unwrap().mark_serviced("Bearings", "2026-05-01".to_string());
+        counters.record_water_at("001-005", at(200));
+        counters.record_water_at("001-000", at(560));
+        assert_eq!(counters.water[&1].since_service("Bearings"), (0.1, 1));
+        assert_eq!(counters.water[&1].since_service("Seals"), (450.0 / 3600.0, 3));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Logs/maintenance.json");
+        let mut counters = MaintenanceCounters::default();
+        counters.record_water_at("033-001", Instant::now());
+        assert!(counters.is_dirty());
+        counters.save(&path).unwrap();
+        assert!(!counters.is_dirty());
+
+        let loaded = MaintenanceCounters::load(&path).unwrap();
+        assert_eq!(loaded.water[&33].starts, 1);
+        assert!(MaintenanceCounters::load(dir.path().join("missing.json")).unwrap().water.is_empty());
+    }
+}
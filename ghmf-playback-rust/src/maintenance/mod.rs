@@ -0,0 +1,5 @@
+pub mod counters;
+pub mod service;
+
+pub use counters::{Counter, CounterKind, MaintenanceCounters, ServiceMark};
+pub use service::{export_csv, reminders, ServiceInterval, ServiceReminder, ServiceStatus};
@@ -0,0 +1,228 @@
+use std::fmt;
+use std::io::Write;
+use std::path::Path;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use crate::config::parse_range;
+use super::counters::{Counter, CounterKind, MaintenanceCounters};
+
+/// Share of an interval after which a reminder says it is coming up
+const SOON_FRACTION: f64 = 0.9;
+
+/// A service the maintenance crew does every so many hours or starts, from
+/// `[[service_intervals]]` in the settings file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceInterval {
+    pub name: String,
+    #[serde(default)]
+    pub kind: CounterKind,
+    /// "1-13,33" style list; empty covers everything of its kind
+    #[serde(default)]
+    pub addresses: String,
+    #[serde(default)]
+    pub run_hours: Option<f64>,
+    #[serde(default)]
+    pub starts: Option<u64>,
+}
+
+impl ServiceInterval {
+    pub fn covers(&self, kind: CounterKind, address: u16) -> bool {
+        if kind != self.kind {
+            return false;
+        }
+        if self.addresses.trim().is_empty() {
+            return true;
+        }
+        self.addresses.split(',')
+            .filter_map(parse_range)
+            .any(|(first, last)| (first..=last).contains(&address))
+    }
+
+    /// How close `counter` is to needing this service
+    pub fn status(&self, counter: &Counter) -> ServiceStatus {
+        let (hours, starts) = counter.since_service(&self.name);
+        let used = [
+            self.run_hours.filter(|limit| *limit > 0.0).map(|limit| hours / limit),
+            self.starts.filter(|limit| *limit > 0).map(|limit| starts as f64 / limit as f64),
+        ]
+        .into_iter()
+        .flatten()
+        .fold(0.0, f64::max);
+
+        if used >= 1.0 {
+            ServiceStatus::Due
+        } else if used >= SOON_FRACTION {
+            ServiceStatus::Soon
+        } else {
+            ServiceStatus::Ok
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ServiceStatus {
+    Ok,
+    /// Past 90% of the interval
+    Soon,
+    Due,
+}
+
+impl ServiceStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ServiceStatus::Ok => "ok",
+            ServiceStatus::Soon => "soon",
+            ServiceStatus::Due => "due",
+        }
+    }
+}
+
+/// A service that is due or coming up on one counter
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceReminder {
+    pub interval: String,
+    pub kind: CounterKind,
+    pub address: u16,
+    pub hours: f64,
+    pub starts: u64,
+    pub status: ServiceStatus,
+}
+
+impl fmt::Display for ServiceReminder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let when = if self.status == ServiceStatus::Due { "due" } else { "coming up" };
+        write!(
+            f,
+            "{} {} on {} {:03}: {:.1} h, {} starts since service",
+            self.interval, when, self.kind.label(), self.address, self.hours, self.starts
+        )
+    }
+}
+
+/// Every service that is due or coming up, most urgent first
+pub fn reminders(counters: &MaintenanceCounters, intervals: &[ServiceInterval]) -> Vec<ServiceReminder> {
+    let mut reminders = Vec::new();
+    for interval in intervals {
+        for (&address, counter) in counters.counters(interval.kind) {
+            if !interval.covers(interval.kind, address) {
+                continue;
+            }
+            let status = interval.status(counter);
+            if status != ServiceStatus::Ok {
+                let (hours, starts) = counter.since_service(&interval.name);
+                reminders.push(ServiceReminder {
+                    interval: interval.name.clone(),
+                    kind: interval.kind,
+                    address,
+                    hours,
+                    starts,
+                    status,
+                });
+            }
+        }
+    }
+    reminders.sort_by(|a, b| b.status.cmp(&a.status).then(a.kind.cmp(&b.kind)).then(a.address.cmp(&b.address)));
+    reminders
+}
+
+/// Write every counter to a CSV for the maintenance crew
+///
+/// One row per counter and service interval that covers it, or a single
+/// row with no interval. `name` labels an address, e.g. from the water map.
+pub fn export_csv<P: AsRef<Path>>(
+    path: P,
+    counters: &MaintenanceCounters,
+    intervals: &[ServiceInterval],
+    name: impl Fn(CounterKind, u16) -> String,
+) -> Result<()> {
+    let path = path.as_ref();
+    let mut file = std::fs::File::create(path)
+        .with_context(|| format!("Failed to create maintenance export at {:?}", path))?;
+
+    writeln!(file, "kind,address,name,run_hours,starts,service,hours_since_service,starts_since_service,last_serviced,status")?;
+    for kind in [CounterKind::Water, CounterKind::Fixture] {
+        for (&address, counter) in counters.counters(kind) {
+            let prefix = format!(
+                "{},{:03},\"{}\",{:.2},{}",
+                kind.label(),
+                address,
+                name(kind, address).replace('"', "\"\""),
+                counter.run_hours(),
+                counter.starts
+            );
+            let covering: Vec<&ServiceInterval> = intervals.iter()
+                .filter(|interval| interval.covers(kind, address))
+                .collect();
+            if covering.is_empty() {
+                writeln!(file, "{},,,,,", prefix)?;
+            }
+            for interval in covering {
+                let (hours, starts) = counter.since_service(&interval.name);
+                let last = counter.serviced.get(&interval.name).map_or("", |mark| mark.date.as_str());
+                writeln!(
+                    file,
+                    "{},\"{}\",{:.2},{},{},{}",
+                    prefix,
+                    interval.name.replace('"', "\"\""),
+                    hours,
+                    starts,
+                    last,
+                    interval.status(counter).label()
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn interval(name: &str, kind: CounterKind, addresses: &str, run_hours: Option<f64>, starts: Option<u64>) -> ServiceInterval {
+        ServiceInterval { name: name.to_string(), kind, addresses: addresses.to_string(), run_hours, starts }
+    }
+
+    #[test]
+    fn test_reminders_and_export() {
+        let start = Instant::now();
+        let mut counters = MaintenanceCounters::default();
+        for cycle in 0..10 {
+            let on = start + Duration::from_secs(cycle * 400);
+            counters.record_water_at("003-005", on);
+            counters.record_water_at("003-000", on + Duration::from_secs(360));
+        }
+        counters.record_water_at("040-001", start);
+        counters.record_water_at("040-000", start + Duration::from_secs(3600));
+
+        let intervals = vec![
+            interval("Pump bearings", CounterKind::Water, "1-13", Some(1.0), None),
+            interval("Valve seals", CounterKind::Water, "33-40", None, Some(1)),
+            interval("Cycle check", CounterKind::Water, "1-13", None, Some(11)),
+            interval("Lamp check", CounterKind::Fixture, "", Some(100.0), None),
+        ];
+        let due = reminders(&counters, &intervals);
+        let text: Vec<String> = due.iter().map(ToString::to_string).collect();
+        assert_eq!(text, vec![
+            "Pump bearings due on water 003: 1.0 h, 10 starts since service",
+            "Valve seals due on water 040: 1.0 h, 1 starts since service",
+            "Cycle check coming up on water 003: 1.0 h, 10 starts since service",
+        ]);
+
+        counters.counter_mut(CounterKind::Water, 3).mark_serviced("Pump bearings", "2026-06-01".to_string());
+        assert_eq!(reminders(&counters, &intervals).len(), 2);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("maintenance.csv");
+        export_csv(&path, &counters, &intervals, |kind, address| match (kind, address) {
+            (CounterKind::Water, 3) => "Module 1 pump".to_string(),
+            _ => String::new(),
+        }).unwrap();
+        let csv = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1], "water,003,\"Module 1 pump\",1.00,10,\"Pump bearings\",0.00,0,2026-06-01,ok");
+        assert_eq!(lines[3], "water,040,\"\",1.00,1,\"Valve seals\",1.00,1,,due");
+    }
+}
@@ -0,0 +1,339 @@
+use tokio::net::TcpStream;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::task::JoinHandle;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+use tokio::sync::Mutex;
+use anyhow::{Context, Result};
+use super::protocol::{push_event, format_batch, AckTracker, LinkOptions, PlcEvent, PlcEventKind, PlcReply, HEARTBEAT};
+use super::transport::{BoxFuture, PlcTransport};
+
+/// Ack, heartbeat and reply bookkeeping for the line protocol, shared by
+/// the TCP and serial transports and their reader tasks
+pub(crate) struct LineLink {
+    next_seq: u32,
+    acks: AckTracker,
+    last_heard: Instant,
+    last_heartbeat: Instant,
+    last_status: Option<String>,
+    events: Vec<PlcEvent>,
+    /// Batches still unacknowledged when the link was reopened
+    unconfirmed: Vec<Vec<String>>,
+}
+
+impl LineLink {
+    pub(crate) fn new() -> Self {
+        Self {
+            next_seq: 1,
+            acks: AckTracker::default(),
+            last_heard: Instant::now(),
+            last_heartbeat: Instant::now(),
+            last_status: None,
+            events: Vec::new(),
+            unconfirmed: Vec::new(),
+        }
+    }
+
+    pub(crate) fn record(&mut self, kind: PlcEventKind) {
+        push_event(&mut self.events, kind);
+    }
+
+    /// A fresh connection counts as hearing from the PLC
+    ///
+    /// Batches unacknowledged from before are not resent on the new link;
+    /// they wait in `take_unconfirmed` for the sender's reconnect policy.
+    pub(crate) fn connected(&mut self) {
+        self.unconfirmed.extend(self.acks.take_all());
+        self.last_heard = Instant::now();
+        self.last_heartbeat = Instant::now();
+        self.record(PlcEventKind::Connected);
+    }
+
+    pub(crate) fn take_events(&mut self) -> Vec<PlcEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub(crate) fn unacknowledged(&self) -> usize {
+        self.acks.pending_count()
+    }
+
+    pub(crate) fn take_unconfirmed(&mut self) -> Vec<Vec<String>> {
+        std::mem::take(&mut self.unconfirmed)
+    }
+
+    /// Handle one line from the PLC
+    pub(crate) fn handle_line(&mut self, line: &str) {
+        self.last_heard = Instant::now();
+        let Some(reply) = PlcReply::parse(line) else { return };
+
+        match reply {
+            PlcReply::Ack(seq) => {
+                if !self.acks.ack(seq) {
+                    tracing::debug!("PLC acknowledged unknown batch {}", seq);
+                }
+            }
+            PlcReply::Nak { seq, reason } => {
+                let commands = self.acks.nak(seq).unwrap_or_default();
+                self.record(PlcEventKind::Rejected { seq, reason, commands });
+            }
+            PlcReply::Heartbeat => {}
+            PlcReply::Fault { code, text } => self.record(PlcEventKind::Fault { code, text }),
+            PlcReply::Status(text) => {
+                // PLCs repeat their status; only log changes
+                if self.last_status.as_deref() != Some(text.as_str()) {
+                    self.last_status = Some(text.clone());
+                    self.record(PlcEventKind::Status(text));
+                }
+            }
+            PlcReply::Other(text) => tracing::debug!("Unrecognised PLC reply: {}", text),
+        }
+    }
+
+    /// Wire text for a batch, tagged with a sequence number when acks are on
+    pub(crate) fn batch_message(&mut self, options: &LinkOptions, commands: &[String]) -> String {
+        if !options.acks_enabled() {
+            return format_batch(None, commands);
+        }
+        let seq = self.next_seq;
+        self.next_seq = self.next_seq.wrapping_add(1).max(1);
+        self.acks.sent(seq, commands.to_vec(), Instant::now());
+        format_batch(Some(seq), commands)
+    }
+
+    /// The last batch never reached the PLC; stop waiting for its ack so it
+    /// is only resent by the sender
+    pub(crate) fn unsent(&mut self) {
+        let seq = if self.next_seq == 1 { u32::MAX } else { self.next_seq - 1 };
+        self.acks.nak(seq);
+    }
+
+    /// Lines to send for retries and the heartbeat as of `now`
+    ///
+    /// Errs with the reason when the PLC has been silent too long; a
+    /// half-open link still accepts writes, so silence is the only sign.
+    pub(crate) fn service(&mut self, options: &LinkOptions, now: Instant) -> Result<Vec<String>, String> {
+        let silent_for = now.duration_since(self.last_heard);
+        if options.heartbeat_enabled() && silent_for > options.heartbeat_timeout {
+            let reason = format!("no reply for {:.1}s", silent_for.as_secs_f32());
+            self.record(PlcEventKind::LinkLost { reason: reason.clone() });
+            return Err(reason);
+        }
+
+        let due = self.acks.due(now, options);
+        for (seq, commands) in due.dropped {
+            self.record(PlcEventKind::Dropped { seq, commands });
+        }
+        let mut messages = Vec::new();
+        for (seq, commands, attempt) in due.retry {
+            self.record(PlcEventKind::Retried { seq, attempt });
+            messages.push(format_batch(Some(seq), &commands));
+        }
+
+        if options.heartbeat_enabled() && now.duration_since(self.last_heartbeat) >= options.heartbeat_interval {
+            self.last_heartbeat = now;
+            messages.push(format!("{}\r\n", HEARTBEAT));
+        }
+        Ok(messages)
+    }
+}
+
+/// The line protocol inherited from PLCComms.cs, over a TCP socket
+///
+/// Batches go out as one line each. The PLC acknowledges tagged batches,
+/// echoes heartbeats and reports faults on the same socket.
+pub struct AsciiTcpTransport {
+    ip_address: String,
+    port: u16,
+    options: LinkOptions,
+    stream: Arc<Mutex<Option<OwnedWriteHalf>>>,
+    connected: Arc<AtomicBool>,
+    reader: std::sync::Mutex<Option<JoinHandle<()>>>,
+    link: Arc<std::sync::Mutex<LineLink>>,
+}
+
+impl AsciiTcpTransport {
+    pub fn new(ip_address: String, port: u16, options: LinkOptions) -> Self {
+        Self {
+            ip_address,
+            port,
+            options,
+            stream: Arc::new(Mutex::new(None)),
+            connected: Arc::new(AtomicBool::new(false)),
+            reader: std::sync::Mutex::new(None),
+            link: Arc::new(std::sync::Mutex::new(LineLink::new())),
+        }
+    }
+
+    async fn open(&self, timeout_ms: u64) -> Result<()> {
+        // Disconnect if already connected
+        self.close().await;
+
+        tracing::info!("Connecting to PLC at {}:{}", self.ip_address, self.port);
+
+        let addr = format!("{}:{}", self.ip_address, self.port);
+
+        // Connect with timeout
+        let connect_future = TcpStream::connect(&addr);
+        let stream = tokio::time::timeout(
+            tokio::time::Duration::from_millis(timeout_ms),
+            connect_future
+        )
+        .await
+        .context("PLC connection timed out")?
+        .context("Failed to connect to PLC")?;
+
+        let (read_half, write_half) = stream.into_split();
+        *self.stream.lock().await = Some(write_half);
+        self.connected.store(true, Ordering::SeqCst);
+
+        if let Ok(mut link) = self.link.lock() {
+            link.connected();
+        }
+        let task = tokio::spawn(Self::read_loop(
+            read_half,
+            Arc::clone(&self.link),
+            Arc::clone(&self.stream),
+            Arc::clone(&self.connected),
+        ));
+        if let Ok(mut reader) = self.reader.lock() {
+            *reader = Some(task);
+        }
+
+        tracing::info!("Successfully connected to PLC");
+        Ok(())
+    }
+
+    /// Read replies until the PLC closes the connection
+    async fn read_loop(
+        read_half: OwnedReadHalf,
+        link: Arc<std::sync::Mutex<LineLink>>,
+        stream: Arc<Mutex<Option<OwnedWriteHalf>>>,
+        connected: Arc<AtomicBool>,
+    ) {
+        let mut lines = BufReader::new(read_half).lines();
+        let reason = loop {
+            match lines.next_line().await {
+                Ok(Some(line)) => {
+                    if let Ok(mut link) = link.lock() {
+                        link.handle_line(&line);
+                    }
+                }
+                Ok(None) => break "PLC closed the connection".to_string(),
+                Err(e) => break format!("read failed: {}", e),
+            }
+        };
+
+        *stream.lock().await = None;
+        connected.store(false, Ordering::SeqCst);
+        if let Ok(mut link) = link.lock() {
+            link.record(PlcEventKind::LinkLost { reason });
+        }
+    }
+
+    async fn close(&self) {
+        if let Some(task) = self.reader.lock().ok().and_then(|mut reader| reader.take()) {
+            task.abort();
+        }
+        let mut stream = self.stream.lock().await;
+        if let Some(mut s) = stream.take() {
+            let _ = s.shutdown().await;
+            tracing::debug!("Disconnected from PLC");
+        }
+        self.connected.store(false, Ordering::SeqCst);
+    }
+
+    async fn send_commands(&self, commands: &[String]) -> Result<()> {
+        // Tagged batches are resent by `service` until acknowledged
+        let message = self.link.lock()
+            .map_err(|_| anyhow::anyhow!("PLC link state poisoned"))?
+            .batch_message(&self.options, commands);
+        let result = self.send(&message).await;
+        if result.is_err() {
+            if let Ok(mut link) = self.link.lock() {
+                link.unsent();
+            }
+        }
+        result
+    }
+
+    async fn run_service(&self) -> Result<()> {
+        let messages = self.link.lock()
+            .map_err(|_| anyhow::anyhow!("PLC link state poisoned"))?
+            .service(&self.options, Instant::now());
+        let messages = match messages {
+            Ok(messages) => messages,
+            Err(reason) => {
+                self.close().await;
+                return Err(anyhow::anyhow!("PLC {}", reason));
+            }
+        };
+
+        for message in messages {
+            self.send(&message).await?;
+        }
+        Ok(())
+    }
+
+    /// Send a string to the PLC
+    async fn send(&self, message: &str) -> Result<()> {
+        let mut stream_guard = self.stream.lock().await;
+        let Some(stream) = stream_guard.as_mut() else {
+            return Err(anyhow::anyhow!("PLC not connected"));
+        };
+
+        tracing::debug!("Sending to PLC: {}", message.trim());
+        match stream.write_all(message.as_bytes()).await {
+            Ok(_) => {
+                // Ensure data is flushed
+                stream.flush().await.context("Failed to flush PLC stream")?;
+                Ok(())
+            }
+            Err(e) => {
+                tracing::warn!("PLC disconnected during send: {}. Will attempt reconnect.", e);
+                *stream_guard = None;
+                self.connected.store(false, Ordering::SeqCst);
+                Err(anyhow::anyhow!("PLC send failed: {}", e))
+            }
+        }
+    }
+}
+
+impl PlcTransport for AsciiTcpTransport {
+    fn describe(&self) -> String {
+        format!("ASCII TCP {}:{}", self.ip_address, self.port)
+    }
+
+    fn connect(&self, timeout_ms: u64) -> BoxFuture<'_, Result<()>> {
+        Box::pin(self.open(timeout_ms))
+    }
+
+    fn disconnect(&self) -> BoxFuture<'_, ()> {
+        Box::pin(self.close())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::SeqCst)
+    }
+
+    fn send_batch<'a>(&'a self, commands: &'a [String]) -> BoxFuture<'a, Result<()>> {
+        Box::pin(self.send_commands(commands))
+    }
+
+    fn service(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(self.run_service())
+    }
+
+    fn take_events(&self) -> Vec<PlcEvent> {
+        self.link.lock().map(|mut link| link.take_events()).unwrap_or_default()
+    }
+
+    fn unacknowledged(&self) -> usize {
+        self.link.lock().map(|link| link.unacknowledged()).unwrap_or(0)
+    }
+
+    fn take_unconfirmed(&self) -> Vec<Vec<String>> {
+        self.link.lock().map(|mut link| link.take_unconfirmed()).unwrap_or_default()
+    }
+}
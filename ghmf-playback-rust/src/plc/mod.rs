@@ -1,3 +1,16 @@
 pub mod plc_client;
+pub mod protocol;
+pub mod sender;
+pub mod simulator;
+pub mod transport;
+pub mod ascii_tcp;
+pub mod modbus;
+pub mod serial;
 
 pub use plc_client::{PlcClient, PlcStatus};
+pub use protocol::{LinkOptions, PlcEvent, PlcEventKind};
+pub use sender::{PlcCounts, PlcSender, ReconnectPolicy};
+pub use transport::{PlcProtocol, PlcTransport};
+pub use ascii_tcp::AsciiTcpTransport;
+pub use modbus::{ModbusMap, ModbusTcpTransport};
+pub use serial::{SerialLine, SerialTransport};
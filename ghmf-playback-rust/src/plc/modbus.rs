@@ -0,0 +1,575 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use crate::config::WaterMap;
+use super::protocol::{push_event, LinkOptions, PlcEvent, PlcEventKind};
+use super::transport::{BoxFuture, PlcTransport};
+
+const READ_HOLDING_REGISTERS: u8 = 0x03;
+const WRITE_SINGLE_COIL: u8 = 0x05;
+const WRITE_SINGLE_REGISTER: u8 = 0x06;
+
+/// How long to wait for a response when acks are turned off in Settings;
+/// Modbus always answers, so there is no fire-and-forget mode
+const DEFAULT_RESPONSE_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// Where one water FCW address lands on the PLC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModbusTarget {
+    /// Holding register, written with the command's data value
+    Register(u16),
+    /// Coil, on for any nonzero data value
+    Coil(u16),
+}
+
+impl ModbusTarget {
+    /// The register or coil `offset` further on, if it still fits in 16 bits
+    fn offset(&self, offset: u16) -> Option<Self> {
+        match self {
+            ModbusTarget::Register(register) => register.checked_add(offset).map(ModbusTarget::Register),
+            ModbusTarget::Coil(coil) => coil.checked_add(offset).map(ModbusTarget::Coil),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct MapFile {
+    #[serde(default = "default_unit_id")]
+    unit_id: u8,
+    #[serde(default)]
+    fault_register: Option<u16>,
+    mappings: Vec<MapEntry>,
+}
+
+#[derive(Deserialize)]
+struct MapEntry {
+    fcw: u16,
+    /// Consecutive FCW addresses mapped onto consecutive registers/coils
+    #[serde(default = "default_count")]
+    count: u16,
+    register: Option<u16>,
+    coil: Option<u16>,
+}
+
+fn default_unit_id() -> u8 {
+    1
+}
+
+fn default_count() -> u16 {
+    1
+}
+
+/// FCW address -> register/coil map from plc_modbus_map.json
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModbusMap {
+    pub unit_id: u8,
+    /// Holding register the PLC keeps its fault code in; 0 means no fault
+    pub fault_register: Option<u16>,
+    targets: BTreeMap<u16, ModbusTarget>,
+}
+
+impl Default for ModbusMap {
+    fn default() -> Self {
+        Self { unit_id: default_unit_id(), fault_register: None, targets: BTreeMap::new() }
+    }
+}
+
+impl ModbusMap {
+    /// Load plc_modbus_map.json from `dir`; a missing file gives an empty map
+    pub fn load_from_dir<P: AsRef<Path>>(dir: P, water: &WaterMap) -> Result<Self> {
+        let path = dir.as_ref().join("plc_modbus_map.json");
+        if !path.exists() {
+            tracing::warn!("No plc_modbus_map.json; Modbus PLC will receive no water commands");
+            return Ok(Self::default());
+        }
+
+        let json_data = std::fs::read_to_string(&path)
+            .context(format!("Failed to read plc_modbus_map.json at {:?}", path))?;
+        let map = Self::parse(&json_data, water).context("Failed to parse plc_modbus_map.json")?;
+        tracing::info!("Loaded {} Modbus mappings from plc_modbus_map.json", map.targets.len());
+        Ok(map)
+    }
+
+    /// Parse the map; entries for addresses `water` doesn't send to the PLC are skipped
+    pub fn parse(json_data: &str, water: &WaterMap) -> Result<Self> {
+        let file: MapFile = serde_json::from_str(json_data)?;
+        let mut map = Self {
+            unit_id: file.unit_id,
+            fault_register: file.fault_register,
+            targets: BTreeMap::new(),
+        };
+
+        for entry in file.mappings {
+            let start = match (entry.register, entry.coil) {
+                (Some(register), None) => ModbusTarget::Register(register),
+                (None, Some(coil)) => ModbusTarget::Coil(coil),
+                _ => {
+                    tracing::warn!("Skipping Modbus mapping for FCW {}: give either a register or a coil", entry.fcw);
+                    continue;
+                }
+            };
+            // The whole run has to fit in 16-bit addresses on both ends
+            let last = entry.count.saturating_sub(1);
+            if entry.fcw.checked_add(last).is_none() || start.offset(last).is_none() {
+                tracing::warn!(
+                    "Skipping Modbus mapping for FCW {}: {} addresses run past the end of the address range",
+                    entry.fcw, entry.count
+                );
+                continue;
+            }
+
+            for offset in 0..entry.count {
+                let fcw = entry.fcw + offset;
+                if !water.is_water(fcw) {
+                    tracing::warn!("Skipping Modbus mapping for FCW {}: not a water address", fcw);
+                    continue;
+                }
+                if let Some(target) = start.offset(offset) {
+                    map.targets.insert(fcw, target);
+                }
+            }
+        }
+        Ok(map)
+    }
+
+    pub fn target(&self, fcw_address: u16) -> Option<ModbusTarget> {
+        self.targets.get(&fcw_address).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+}
+
+/// The requests this transport makes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModbusRequest {
+    ReadRegister(u16),
+    WriteRegister { address: u16, value: u16 },
+    WriteCoil { address: u16, on: bool },
+}
+
+impl ModbusRequest {
+    /// Write a water command's data value to its target
+    pub fn write(target: ModbusTarget, data: u16) -> Self {
+        match target {
+            ModbusTarget::Register(address) => ModbusRequest::WriteRegister { address, value: data },
+            ModbusTarget::Coil(address) => ModbusRequest::WriteCoil { address, on: data != 0 },
+        }
+    }
+
+    fn function(&self) -> u8 {
+        match self {
+            ModbusRequest::ReadRegister(_) => READ_HOLDING_REGISTERS,
+            ModbusRequest::WriteRegister { .. } => WRITE_SINGLE_REGISTER,
+            ModbusRequest::WriteCoil { .. } => WRITE_SINGLE_COIL,
+        }
+    }
+
+    /// Modbus TCP frame: MBAP header, then the PDU
+    pub fn encode(&self, transaction: u16, unit_id: u8) -> Vec<u8> {
+        let (address, value) = match *self {
+            ModbusRequest::ReadRegister(address) => (address, 1),
+            ModbusRequest::WriteRegister { address, value } => (address, value),
+            ModbusRequest::WriteCoil { address, on } => (address, if on { 0xFF00 } else { 0x0000 }),
+        };
+
+        let mut frame = Vec::with_capacity(12);
+        frame.extend_from_slice(&transaction.to_be_bytes());
+        frame.extend_from_slice(&[0, 0]); // Protocol id
+        frame.extend_from_slice(&6u16.to_be_bytes()); // Unit id + 5-byte PDU
+        frame.push(unit_id);
+        frame.push(self.function());
+        frame.extend_from_slice(&address.to_be_bytes());
+        frame.extend_from_slice(&value.to_be_bytes());
+        frame
+    }
+}
+
+/// A response PDU, checked against its request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModbusReply {
+    Done,
+    Value(u16),
+    Exception(u8),
+}
+
+impl ModbusReply {
+    pub fn parse(request: &ModbusRequest, pdu: &[u8]) -> Result<Self> {
+        let Some(&function) = pdu.first() else { bail!("Empty Modbus response") };
+        if function == request.function() | 0x80 {
+            return Ok(ModbusReply::Exception(pdu.get(1).copied().unwrap_or(0)));
+        }
+        if function != request.function() {
+            bail!("Modbus response for function {:#04x}, expected {:#04x}", function, request.function());
+        }
+
+        match request {
+            ModbusRequest::ReadRegister(_) => match pdu {
+                [_, 2, high, low] => Ok(ModbusReply::Value(u16::from_be_bytes([*high, *low]))),
+                _ => bail!("Malformed Modbus read response"),
+            },
+            // Writes echo the request
+            _ if pdu.len() == 5 => Ok(ModbusReply::Done),
+            _ => bail!("Malformed Modbus write response"),
+        }
+    }
+}
+
+/// Readable name for a Modbus exception code
+pub fn exception_name(code: u8) -> &'static str {
+    match code {
+        1 => "illegal function",
+        2 => "illegal data address",
+        3 => "illegal data value",
+        4 => "server device failure",
+        6 => "server busy",
+        _ => "exception",
+    }
+}
+
+struct ModbusState {
+    transaction: u16,
+    last_heartbeat: Instant,
+    last_fault: u16,
+    events: Vec<PlcEvent>,
+}
+
+/// Water commands written to holding registers and coils over Modbus TCP
+///
+/// Each command is one write; the PLC's response is the acknowledgement,
+/// and a request with no response is retried before the link is dropped.
+/// The heartbeat reads the fault register (or register 0) and reports
+/// changes in the fault code.
+pub struct ModbusTcpTransport {
+    ip_address: String,
+    port: u16,
+    options: LinkOptions,
+    map: ModbusMap,
+    stream: Mutex<Option<TcpStream>>,
+    connected: AtomicBool,
+    state: std::sync::Mutex<ModbusState>,
+}
+
+impl ModbusTcpTransport {
+    pub fn new(ip_address: String, port: u16, options: LinkOptions, map: ModbusMap) -> Self {
+        Self {
+            ip_address,
+            port,
+            options,
+            map,
+            stream: Mutex::new(None),
+            connected: AtomicBool::new(false),
+            state: std::sync::Mutex::new(ModbusState {
+                transaction: 0,
+                last_heartbeat: Instant::now(),
+                last_fault: 0,
+                events: Vec::new(),
+            }),
+        }
+    }
+
+    fn record(&self, kind: PlcEventKind) {
+        if let Ok(mut state) = self.state.lock() {
+            push_event(&mut state.events, kind);
+        }
+    }
+
+    async fn open(&self, timeout_ms: u64) -> Result<()> {
+        self.close().await;
+
+        let addr = format!("{}:{}", self.ip_address, self.port);
+        let stream = tokio::time::timeout(Duration::from_millis(timeout_ms), TcpStream::connect(&addr))
+            .await
+            .context("PLC connection timed out")?
+            .context("Failed to connect to PLC")?;
+        stream.set_nodelay(true)?;
+
+        *self.stream.lock().await = Some(stream);
+        self.connected.store(true, Ordering::SeqCst);
+        if let Ok(mut state) = self.state.lock() {
+            state.last_heartbeat = Instant::now();
+        }
+        self.record(PlcEventKind::Connected);
+        Ok(())
+    }
+
+    async fn close(&self) {
+        if let Some(mut stream) = self.stream.lock().await.take() {
+            let _ = stream.shutdown().await;
+            tracing::debug!("Disconnected from Modbus PLC");
+        }
+        self.connected.store(false, Ordering::SeqCst);
+    }
+
+    /// Send one request and wait for its response, retrying on silence
+    ///
+    /// Returns the transaction id with the reply. Drops the link when the
+    /// socket fails or every attempt goes unanswered.
+    async fn request(&self, request: ModbusRequest) -> Result<(u16, ModbusReply)> {
+        let transaction = {
+            let mut state = self.state.lock().map_err(|_| anyhow::anyhow!("Modbus state poisoned"))?;
+            state.transaction = state.transaction.wrapping_add(1);
+            state.transaction
+        };
+        let frame = request.encode(transaction, self.map.unit_id);
+        let timeout = if self.options.acks_enabled() { self.options.ack_timeout } else { DEFAULT_RESPONSE_TIMEOUT };
+
+        let mut guard = self.stream.lock().await;
+        let Some(stream) = guard.as_mut() else {
+            bail!("PLC not connected");
+        };
+
+        let mut reason = format!("no response after {} attempts", self.options.max_retries + 1);
+        for attempt in 0..=self.options.max_retries {
+            if attempt > 0 {
+                self.record(PlcEventKind::Retried { seq: transaction as u32, attempt });
+            }
+            if let Err(e) = stream.write_all(&frame).await {
+                reason = format!("write failed: {}", e);
+                break;
+            }
+            match tokio::time::timeout(timeout, read_response(stream, transaction)).await {
+                Ok(Ok(pdu)) => return Ok((transaction, ModbusReply::parse(&request, &pdu)?)),
+                Ok(Err(e)) => {
+                    reason = format!("read failed: {}", e);
+                    break;
+                }
+                Err(_) => continue,
+            }
+        }
+
+        *guard = None;
+        self.connected.store(false, Ordering::SeqCst);
+        self.record(PlcEventKind::LinkLost { reason: reason.clone() });
+        bail!("Modbus PLC {}", reason)
+    }
+
+    async fn write_commands(&self, commands: &[String]) -> Result<()> {
+        let mut unmapped = Vec::new();
+        for text in commands {
+            let parsed = text.split_once('-')
+                .and_then(|(address, data)| Some((address.parse::<u16>().ok()?, data.trim().parse::<u16>().ok()?)));
+            let Some((target, data)) = parsed.and_then(|(address, data)| Some((self.map.target(address)?, data))) else {
+                unmapped.push(text.as_str());
+                continue;
+            };
+
+            let (transaction, reply) = self.request(ModbusRequest::write(target, data)).await?;
+            if let ModbusReply::Exception(code) = reply {
+                self.record(PlcEventKind::Rejected {
+                    seq: transaction as u32,
+                    reason: exception_name(code).to_string(),
+                    commands: vec![text.clone()],
+                });
+            }
+        }
+
+        if !unmapped.is_empty() {
+            tracing::warn!("No Modbus mapping for {}", unmapped.join(" "));
+        }
+        Ok(())
+    }
+
+    async fn run_service(&self) -> Result<()> {
+        if !self.options.heartbeat_enabled() {
+            return Ok(());
+        }
+        {
+            let mut state = self.state.lock().map_err(|_| anyhow::anyhow!("Modbus state poisoned"))?;
+            if state.last_heartbeat.elapsed() < self.options.heartbeat_interval {
+                return Ok(());
+            }
+            state.last_heartbeat = Instant::now();
+        }
+
+        let register = self.map.fault_register.unwrap_or(0);
+        let (_, reply) = self.request(ModbusRequest::ReadRegister(register)).await?;
+        let (ModbusReply::Value(code), Some(_)) = (reply, self.map.fault_register) else {
+            return Ok(());
+        };
+
+        let changed = self.state.lock()
+            .map(|mut state| std::mem::replace(&mut state.last_fault, code) != code)
+            .unwrap_or(false);
+        if changed {
+            self.record(if code == 0 {
+                PlcEventKind::Status("faults cleared".to_string())
+            } else {
+                PlcEventKind::Fault { code: code.to_string(), text: format!("fault register {}", register) }
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Read frames until the one for `transaction`, skipping late replies to
+/// earlier attempts; returns its PDU
+async fn read_response(stream: &mut TcpStream, transaction: u16) -> std::io::Result<Vec<u8>> {
+    loop {
+        let mut header = [0u8; 7];
+        stream.read_exact(&mut header).await?;
+        let length = u16::from_be_bytes([header[4], header[5]]) as usize;
+        if !(2..=254).contains(&length) {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "bad Modbus frame length"));
+        }
+
+        let mut pdu = vec![0u8; length - 1];
+        stream.read_exact(&mut pdu).await?;
+        if u16::from_be_bytes([header[0], header[1]]) == transaction {
+            return Ok(pdu);
+        }
+    }
+}
+
+impl PlcTransport for ModbusTcpTransport {
+    fn describe(&self) -> String {
+        format!("Modbus TCP {}:{} unit {}", self.ip_address, self.port, self.map.unit_id)
+    }
+
+    fn connect(&self, timeout_ms: u64) -> BoxFuture<'_, Result<()>> {
+        Box::pin(self.open(timeout_ms))
+    }
+
+    fn disconnect(&self) -> BoxFuture<'_, ()> {
+        Box::pin(self.close())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::SeqCst)
+    }
+
+    fn send_batch<'a>(&'a self, commands: &'a [String]) -> BoxFuture<'a, Result<()>> {
+        Box::pin(self.write_commands(commands))
+    }
+
+    fn service(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(self.run_service())
+    }
+
+    fn take_events(&self) -> Vec<PlcEvent> {
+        self.state.lock().map(|mut state| std::mem::take(&mut state.events)).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_map_from_json() {
+        let map = ModbusMap::parse(r#"{
+            "unit_id": 3,
+            "fault_register": 200,
+            "mappings": [
+                { "fcw": 1, "count": 3, "register": 10 },
+                { "fcw": 33, "coil": 0 },
+                { "fcw": 500, "register": 50 },
+                { "fcw": 34 }
+            ]
+        }"#, &WaterMap::builtin()).unwrap();
+
+        assert_eq!(map.unit_id, 3);
+        assert_eq!(map.fault_register, Some(200));
+        assert_eq!(map.target(3), Some(ModbusTarget::Register(12)));
+        assert_eq!(map.target(33), Some(ModbusTarget::Coil(0)));
+        assert_eq!(map.target(500), None); // Lighting, not water
+        assert_eq!(map.target(34), None);
+        assert_eq!(map.len(), 4);
+    }
+
+    #[test]
+    fn test_map_rejects_runs_past_the_last_address() {
+        let map = ModbusMap::parse(r#"{
+            "mappings": [
+                { "fcw": 1, "count": 13, "register": 65530 },
+                { "fcw": 33, "count": 8, "coil": 65528 },
+                { "fcw": 700, "count": 50, "register": 100 }
+            ]
+        }"#, &WaterMap::builtin()).unwrap();
+
+        assert_eq!(map.target(1), None);
+        assert_eq!(map.target(33), Some(ModbusTarget::Coil(65528)));
+        assert_eq!(map.target(40), Some(ModbusTarget::Coil(65535)));
+        assert_eq!(map.target(749), Some(ModbusTarget::Register(149)));
+        assert_eq!(map.len(), 58);
+    }
+
+    #[test]
+    fn test_sample_map_loads() {
+        let map = ModbusMap::load_from_dir(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/Config"),
+            &WaterMap::builtin(),
+        ).unwrap();
+        assert_eq!(map.target(1), Some(ModbusTarget::Register(0)));
+        assert_eq!(map.target(33), Some(ModbusTarget::Coil(0)));
+        assert_eq!(map.target(700), Some(ModbusTarget::Register(100)));
+    }
+
+    #[test]
+    fn test_frames() {
+        let request = ModbusRequest::write(ModbusTarget::Register(0x0102), 300);
+        assert_eq!(request.encode(7, 1), vec![0, 7, 0, 0, 0, 6, 1, 0x06, 0x01, 0x02, 0x01, 0x2C]);
+        let coil = ModbusRequest::write(ModbusTarget::Coil(5), 1);
+        assert_eq!(&coil.encode(8, 1)[7..], &[0x05, 0, 5, 0xFF, 0x00]);
+
+        assert_eq!(ModbusReply::parse(&request, &[0x06, 0x01, 0x02, 0x01, 0x2C]).unwrap(), ModbusReply::Done);
+        assert_eq!(ModbusReply::parse(&request, &[0x86, 0x02]).unwrap(), ModbusReply::Exception(2));
+        assert!(ModbusReply::parse(&request, &[0x05, 0, 5, 0xFF, 0]).is_err());
+        let read = ModbusRequest::ReadRegister(200);
+        assert_eq!(ModbusReply::parse(&read, &[0x03, 2, 0, 21]).unwrap(), ModbusReply::Value(21));
+    }
+
+    #[tokio::test]
+    async fn test_writes_and_reports_exceptions() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        // Echo writes to register 10; refuse anything else
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut frames = Vec::new();
+            for _ in 0..2 {
+                let mut frame = [0u8; 12];
+                socket.read_exact(&mut frame).await.unwrap();
+                frames.push(frame);
+                if frame[8..10] == [0, 10] {
+                    socket.write_all(&frame).await.unwrap();
+                } else {
+                    let mut reply = frame[..9].to_vec();
+                    reply[5] = 3;
+                    reply[7] |= 0x80;
+                    reply[8] = 2;
+                    socket.write_all(&reply).await.unwrap();
+                }
+            }
+            frames
+        });
+
+        let map = ModbusMap::parse(r#"{ "mappings": [ { "fcw": 1, "count": 2, "register": 10 } ] }"#, &WaterMap::builtin()).unwrap();
+        let transport = ModbusTcpTransport::new("127.0.0.1".to_string(), port, LinkOptions::default(), map);
+        transport.open(1000).await.unwrap();
+        transport.write_commands(&["001-005".to_string(), "700-001".to_string(), "002-000".to_string()]).await.unwrap();
+
+        let frames = server.await.unwrap();
+        assert_eq!(&frames[0][7..], &[0x06, 0, 10, 0, 5]);
+        assert_eq!(&frames[1][7..], &[0x06, 0, 11, 0, 0]);
+
+        let kinds: Vec<_> = transport.take_events().into_iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, vec![
+            PlcEventKind::Connected,
+            PlcEventKind::Rejected { seq: 2, reason: "illegal data address".to_string(), commands: vec!["002-000".to_string()] },
+        ]);
+    }
+}
@@ -1,8 +1,14 @@
-use tokio::net::TcpStream;
-use tokio::io::AsyncWriteExt;
-use std::sync::Arc;
-use tokio::sync::Mutex;
-use anyhow::{Context, Result};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use crossbeam_channel::{Receiver, Sender};
+use crate::config::{Settings, WaterMap};
+use super::ascii_tcp::AsciiTcpTransport;
+use super::modbus::{ModbusMap, ModbusTcpTransport};
+use super::protocol::{LinkOptions, PlcEvent, PlcEventKind};
+use super::sender::{PlcCounters, PlcCounts, PlcSender, QueuedCommand, ReconnectPolicy};
+use super::serial::{SerialLine, SerialTransport};
+use super::transport::{PlcProtocol, PlcTransport};
 
 /// PLC connection status
 #[derive(Debug, Clone, PartialEq)]
@@ -14,231 +20,284 @@ pub enum PlcStatus {
 }
 
 /// PLC Client for sending water commands to a Programmable Logic Controller
-/// Matches the behavior of the C# PLCComms.cs class
+/// Matches the behavior of the C# PLCComms.cs class: commands are queued and
+/// sent in batches. The transport decides what a batch looks like on the wire.
+///
+/// Queued commands go over a channel to a `PlcSender`, which must be taken
+/// with `take_sender` and run for anything to reach the PLC.
 pub struct PlcClient {
     enabled: bool,
-    ip_address: String,
-    port: u16,
-    stream: Arc<Mutex<Option<TcpStream>>>,
-    command_queue: Arc<Mutex<Vec<String>>>,
-    status: Arc<Mutex<PlcStatus>>,
-    reconnecting: Arc<Mutex<bool>>,
+    transport: Arc<dyn PlcTransport>,
+    commands: Sender<QueuedCommand>,
+    stops: Sender<Vec<String>>,
+    written: Receiver<(Instant, Vec<String>)>,
+    sender: Mutex<Option<PlcSender>>,
+    counters: Arc<PlcCounters>,
+    reconnecting: Arc<AtomicBool>,
 }
 
 impl PlcClient {
-    /// Create a new PLC client
-    pub fn new(enabled: bool, ip_address: String, port: u16) -> Self {
-        let status = if enabled {
-            PlcStatus::Disconnected
-        } else {
-            PlcStatus::Disabled
-        };
+    /// Create a new PLC client speaking the ASCII line protocol
+    pub fn new(enabled: bool, ip_address: String, port: u16, options: LinkOptions) -> Self {
+        Self::with_transport(enabled, Arc::new(AsciiTcpTransport::new(ip_address, port, options)))
+    }
 
+    /// Create a new PLC client over any transport
+    pub fn with_transport(enabled: bool, transport: Arc<dyn PlcTransport>) -> Self {
+        let (commands, receiver) = crossbeam_channel::unbounded();
+        let (stops, stop_receiver) = crossbeam_channel::unbounded();
+        let (written_sender, written) = crossbeam_channel::unbounded();
+        let counters = Arc::new(PlcCounters::default());
+        let reconnecting = Arc::new(AtomicBool::new(false));
+        let sender = PlcSender::new(
+            enabled,
+            Arc::clone(&transport),
+            receiver,
+            stop_receiver,
+            written_sender,
+            Arc::clone(&counters),
+            Arc::clone(&reconnecting),
+        );
         Self {
             enabled,
-            ip_address,
-            port,
-            stream: Arc::new(Mutex::new(None)),
-            command_queue: Arc::new(Mutex::new(Vec::new())),
-            status: Arc::new(Mutex::new(status)),
-            reconnecting: Arc::new(Mutex::new(false)),
+            transport,
+            commands,
+            stops,
+            written,
+            sender: Mutex::new(Some(sender)),
+            counters,
+            reconnecting,
         }
     }
 
-    /// Get the current connection status
-    pub async fn status(&self) -> PlcStatus {
-        self.status.lock().await.clone()
+    /// The client the settings describe: protocol, address, link timing and
+    /// reconnect policy
+    pub fn from_settings(settings: &Settings, water: &WaterMap) -> Self {
+        let options = LinkOptions::from_settings(
+            settings.plc_heartbeat_secs,
+            settings.plc_ack_timeout_ms,
+            settings.plc_max_retries,
+        );
+        let plc = match PlcProtocol::from_setting(&settings.plc_protocol) {
+            PlcProtocol::AsciiTcp => PlcClient::new(
+                settings.plc_enabled,
+                settings.plc_ip_address.clone(),
+                settings.plc_port,
+                options,
+            ),
+            PlcProtocol::ModbusTcp => {
+                let map = ModbusMap::load_from_dir("Config", water).unwrap_or_else(|e| {
+                    tracing::warn!("Failed to load Modbus map: {}", e);
+                    ModbusMap::default()
+                });
+                let transport = ModbusTcpTransport::new(
+                    settings.plc_ip_address.clone(),
+                    settings.plc_port,
+                    options,
+                    map,
+                );
+                PlcClient::with_transport(settings.plc_enabled, Arc::new(transport))
+            }
+            PlcProtocol::Serial => {
+                let line = SerialLine::from_settings(
+                    settings.plc_port_name.as_deref().unwrap_or(""),
+                    settings.plc_baud_rate,
+                    &settings.plc_parity,
+                );
+                PlcClient::with_transport(settings.plc_enabled, Arc::new(SerialTransport::new(line, options)))
+            }
+        };
+        plc.with_reconnect_policy(ReconnectPolicy::from_setting(&settings.plc_reconnect_policy))
     }
 
-    /// Check if connected (or disabled, which counts as "ok")
-    pub async fn is_connected(&self) -> bool {
-        if !self.enabled {
-            return true; // Disabled counts as "connected" for logic purposes
+    /// What the sender does with commands queued while the link was down
+    pub fn with_reconnect_policy(self, policy: ReconnectPolicy) -> Self {
+        if let Ok(mut sender) = self.sender.lock() {
+            if let Some(sender) = sender.as_mut() {
+                sender.set_policy(policy);
+            }
         }
-        
-        let status = self.status.lock().await;
-        *status == PlcStatus::Connected
+        self
+    }
+
+    /// The send loop for this client; None once it has been taken
+    ///
+    /// `PlcSender::run` connects, sends and reconnects until the client is
+    /// dropped, then disconnects.
+    pub fn take_sender(&self) -> Option<PlcSender> {
+        self.sender.lock().ok().and_then(|mut sender| sender.take())
     }
 
-    /// Connect to the PLC with a timeout
-    pub async fn connect(&self, timeout_ms: u64) -> Result<()> {
+    /// Get the current connection status
+    pub fn status(&self) -> PlcStatus {
         if !self.enabled {
-            tracing::debug!("PLC is disabled, skipping connection");
-            return Ok(());
+            PlcStatus::Disabled
+        } else if self.transport.is_connected() {
+            PlcStatus::Connected
+        } else if self.reconnecting.load(Ordering::SeqCst) {
+            PlcStatus::Reconnecting
+        } else {
+            PlcStatus::Disconnected
         }
+    }
 
-        // Disconnect if already connected
-        self.disconnect().await;
-
-        tracing::info!("Connecting to PLC at {}:{}", self.ip_address, self.port);
-        
-        let addr = format!("{}:{}", self.ip_address, self.port);
-        
-        // Connect with timeout
-        let connect_future = TcpStream::connect(&addr);
-        let stream = tokio::time::timeout(
-            tokio::time::Duration::from_millis(timeout_ms),
-            connect_future
-        )
-        .await
-        .context("PLC connection timed out")?
-        .context("Failed to connect to PLC")?;
-
-        *self.stream.lock().await = Some(stream);
-        *self.status.lock().await = PlcStatus::Connected;
-        *self.reconnecting.lock().await = false;
-
-        tracing::info!("Successfully connected to PLC");
-        Ok(())
-    }
-
-    /// Disconnect from the PLC
-    pub async fn disconnect(&self) {
-        let mut stream = self.stream.lock().await;
-        if let Some(mut s) = stream.take() {
-            let _ = s.shutdown().await;
-            tracing::debug!("Disconnected from PLC");
+    /// Check if connected (or disabled, which counts as "ok")
+    pub fn is_connected(&self) -> bool {
+        if !self.enabled {
+            return true; // Disabled counts as "connected" for logic purposes
         }
-        *self.status.lock().await = PlcStatus::Disconnected;
+
+        self.transport.is_connected()
+    }
+
+    /// Queue a command from the CTL file; commands with the same timestamp
+    /// go to the PLC as one batch
+    pub fn queue_command_at(&self, time_ms: u64, command: String) {
+        self.queue(Some(time_ms), command);
     }
 
-    /// Add a command to the queue (will be sent in batch)
-    /// Synchronous version that doesn't block
+    /// Queue a manual command (reset, test, scene recall) as its own batch
+    ///
+    /// Never blocks and never loses the command.
     pub fn queue_command_sync(&self, command: String) {
-        if command.trim().is_empty() {
+        self.queue(None, command);
+    }
+
+    fn queue(&self, time_ms: Option<u64>, command: String) {
+        let text = command.trim().to_string();
+        if text.is_empty() {
             return;
         }
 
-        // Try to lock without blocking
-        if let Ok(mut queue) = self.command_queue.try_lock() {
-            queue.push(command);
-            tracing::trace!("Added command to PLC queue, queue size: {}", queue.len());
-        } else {
-            tracing::warn!("Failed to queue PLC command - queue locked");
+        // Only fails once the sender has stopped, when the client is going away
+        if self.commands.send(QueuedCommand { time_ms, text }).is_err() {
+            tracing::debug!("PLC sender stopped, command not queued");
         }
     }
 
-    /// Add a command to the queue (will be sent in batch)
-    pub async fn add_to_queue(&self, command: String) {
-        if command.trim().is_empty() {
+    /// Send an E-stop sequence ahead of everything queued
+    ///
+    /// The sender throws the backlog away and sends `commands` next, even
+    /// after a reconnect with the drop policy.
+    pub fn emergency_stop(&self, commands: &[String]) {
+        let commands: Vec<String> = commands.iter()
+            .map(|command| command.trim().to_string())
+            .filter(|command| !command.is_empty())
+            .collect();
+        if commands.is_empty() {
             return;
         }
-
-        let mut queue = self.command_queue.lock().await;
-        queue.push(command);
-        tracing::trace!("Added command to PLC queue, queue size: {}", queue.len());
-    }
-
-    /// Send all queued commands in a single batch
-    /// Returns the number of commands sent
-    pub async fn send_queue(&self) -> Result<usize> {
-        let mut queue = self.command_queue.lock().await;
-        let count = queue.len();
-
-        if count == 0 {
-            return Ok(0);
+        if self.stops.send(commands).is_err() {
+            tracing::error!("PLC sender stopped, E-stop not sent");
         }
-
-        // Join all commands with spaces and add line terminator
-        let message = format!("{}\r\n", queue.join(" "));
-        
-        // Clear queue before sending (so we don't resend on failure)
-        queue.clear();
-        drop(queue); // Release lock before sending
-
-        // Send the batch
-        self.send(&message).await?;
-
-        Ok(count)
     }
 
-    /// Send a string to the PLC
-    async fn send(&self, message: &str) -> Result<()> {
-        if !self.enabled {
-            tracing::debug!("Would have sent to PLC: {}", message.trim());
-            return Ok(());
-        }
-
-        let mut stream_guard = self.stream.lock().await;
-        
-        if let Some(stream) = stream_guard.as_mut() {
-            tracing::debug!("Sending to PLC: {}", message.trim());
-            
-            match stream.write_all(message.as_bytes()).await {
-                Ok(_) => {
-                    // Ensure data is flushed
-                    stream.flush().await.context("Failed to flush PLC stream")?;
-                    Ok(())
-                }
-                Err(e) => {
-                    tracing::warn!("PLC disconnected during send: {}. Will attempt reconnect.", e);
-                    *stream_guard = None;
-                    *self.status.lock().await = PlcStatus::Disconnected;
-                    
-                    // Trigger reconnect attempt
-                    drop(stream_guard); // Release lock
-                    self.attempt_reconnect().await;
-                    
-                    Err(anyhow::anyhow!("PLC send failed: {}", e))
-                }
+    /// `emergency_stop`, then wait up to `timeout` for the transport to take
+    /// it, for when the process is about to exit
+    ///
+    /// Returns true once it has gone.
+    pub fn emergency_stop_blocking(&self, commands: &[String], timeout: Duration) -> bool {
+        let before = self.counters.snapshot().stops;
+        self.emergency_stop(commands);
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            if self.counters.snapshot().stops > before {
+                return true;
             }
-        } else {
-            // Not connected, try to reconnect
-            drop(stream_guard); // Release lock
-            self.attempt_reconnect().await;
-            Err(anyhow::anyhow!("PLC not connected"))
+            std::thread::sleep(Duration::from_millis(10));
         }
+        false
     }
 
-    /// Attempt to reconnect to the PLC (non-blocking)
-    async fn attempt_reconnect(&self) {
-        let mut reconnecting = self.reconnecting.lock().await;
-        if *reconnecting {
-            return; // Already reconnecting
-        }
-        *reconnecting = true;
-        *self.status.lock().await = PlcStatus::Reconnecting;
-        drop(reconnecting);
-
-        tracing::info!("Attempting to reconnect to PLC");
-
-        // Spawn reconnect attempt (non-blocking)
-        let self_clone = Self {
-            enabled: self.enabled,
-            ip_address: self.ip_address.clone(),
-            port: self.port,
-            stream: Arc::clone(&self.stream),
-            command_queue: Arc::clone(&self.command_queue),
-            status: Arc::clone(&self.status),
-            reconnecting: Arc::clone(&self.reconnecting),
-        };
+    /// Commands queued and not yet picked up by the sender
+    pub fn queue_size(&self) -> usize {
+        self.commands.len()
+    }
 
-        tokio::spawn(async move {
-            match self_clone.connect(1000).await {
-                Ok(_) => tracing::info!("PLC reconnection successful"),
-                Err(e) => {
-                    tracing::warn!("PLC reconnection failed: {}", e);
-                    *self_clone.reconnecting.lock().await = false;
-                }
-            }
-        });
+    /// Sent, dropped and retried command totals
+    pub fn counters(&self) -> PlcCounts {
+        self.counters.snapshot()
     }
 
-    /// Get the current queue size
-    pub async fn queue_size(&self) -> usize {
-        self.command_queue.lock().await.len()
+    /// Batches the transport has written since the last call, with when
+    ///
+    /// Nothing is reported while the PLC is disabled.
+    pub fn take_written(&self) -> Vec<(Instant, Vec<String>)> {
+        self.written.try_iter().collect()
     }
 
-    /// Clear the command queue (useful for stopping/resetting)
-    pub async fn clear_queue(&self) {
-        self.command_queue.lock().await.clear();
-        tracing::debug!("PLC command queue cleared");
+    /// Link events since the last call, oldest first
+    ///
+    /// Batches the PLC refused or never acknowledged count as dropped.
+    pub fn take_events(&self) -> Vec<PlcEvent> {
+        let events = self.transport.take_events();
+        for event in &events {
+            if let PlcEventKind::Dropped { commands, .. } | PlcEventKind::Rejected { commands, .. } = &event.kind {
+                self.counters.add_dropped(commands.len());
+            }
+        }
+        events
+    }
+
+    /// Batches sent and still waiting for an acknowledgement
+    pub fn unacknowledged(&self) -> usize {
+        self.transport.unacknowledged()
     }
 }
 
 impl Drop for PlcClient {
     fn drop(&mut self) {
-        // Note: Can't call async disconnect in Drop, but the TcpStream will close automatically
+        // The sender notices the channel closing, sends what it already has
+        // and disconnects. Dropping does not stop the water; shutdown and
+        // the panic hook call emergency_stop first.
         tracing::debug!("PLC client dropped");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_acks_and_faults_from_plc() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let plc = PlcClient::new(true, "127.0.0.1".to_string(), port, LinkOptions::from_settings(2.0, 1000, 2));
+
+        // Queued before the link is up; sent in order once it is
+        plc.queue_command_at(100, "001-005".to_string());
+        plc.queue_command_at(100, "033-000".to_string());
+        plc.queue_command_sync("099-000\r\n".to_string());
+        tokio::spawn(plc.take_sender().unwrap().run());
+
+        let (socket, _) = listener.accept().await.unwrap();
+        let (read_half, mut write_half) = socket.into_split();
+        let mut lines = BufReader::new(read_half).lines();
+
+        assert_eq!(lines.next_line().await.unwrap().unwrap(), "@1 001-005 033-000");
+        assert_eq!(lines.next_line().await.unwrap().unwrap(), "@2 099-000");
+        assert_eq!(plc.unacknowledged(), 2);
+
+        write_half.write_all(b"ACK 1\r\nACK 2\r\nFAULT E21 pump 3 overload\r\n").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(plc.unacknowledged(), 0);
+        assert_eq!(plc.counters(), PlcCounts { sent: 3, dropped: 0, retried: 0, stops: 0 });
+        let written: Vec<_> = plc.take_written().into_iter().map(|(_, commands)| commands.join(" ")).collect();
+        assert_eq!(written, vec!["001-005 033-000", "099-000"]);
+
+        let kinds: Vec<_> = plc.take_events().into_iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, vec![
+            PlcEventKind::Connected,
+            PlcEventKind::Fault { code: "E21".to_string(), text: "pump 3 overload".to_string() },
+        ]);
+
+        // Closing the socket is noticed without a write
+        drop(write_half);
+        drop(lines);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(plc.status(), PlcStatus::Disconnected);
+    }
+}
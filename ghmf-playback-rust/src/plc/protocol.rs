@@ -0,0 +1,351 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Local};
+use crate::commands::RESET_ADDRESS;
+
+/// Line sent to check the PLC is still there; the PLC echoes it back
+pub const HEARTBEAT: &str = "HB";
+
+/// Timing for acknowledgements and heartbeats
+///
+/// A zero `ack_timeout` sends batches untagged and fire-and-forget, the way
+/// PLCComms.cs always has. A zero `heartbeat_interval` turns off both the
+/// heartbeat and the silence timeout. Both are off by default, since
+/// existing PLC programs never reply.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkOptions {
+    pub heartbeat_interval: Duration,
+    /// Silence after which the link is treated as dead
+    pub heartbeat_timeout: Duration,
+    pub ack_timeout: Duration,
+    /// Resends of an unacknowledged batch before it is dropped
+    pub max_retries: u32,
+}
+
+impl LinkOptions {
+    /// Options from the settings file; the link is declared dead after
+    /// three missed heartbeats
+    pub fn from_settings(heartbeat_secs: f32, ack_timeout_ms: u64, max_retries: u32) -> Self {
+        let heartbeat_interval = Duration::from_secs_f32(heartbeat_secs.max(0.0));
+        Self {
+            heartbeat_interval,
+            heartbeat_timeout: heartbeat_interval * 3,
+            ack_timeout: Duration::from_millis(ack_timeout_ms),
+            max_retries,
+        }
+    }
+
+    pub fn acks_enabled(&self) -> bool {
+        !self.ack_timeout.is_zero()
+    }
+
+    pub fn heartbeat_enabled(&self) -> bool {
+        !self.heartbeat_interval.is_zero()
+    }
+}
+
+impl Default for LinkOptions {
+    fn default() -> Self {
+        Self::from_settings(0.0, 0, 2)
+    }
+}
+
+/// One line received from the PLC
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlcReply {
+    /// `ACK <seq>`: batch accepted
+    Ack(u32),
+    /// `NAK <seq> [reason]`: batch rejected
+    Nak { seq: u32, reason: String },
+    /// `HB`: heartbeat echo
+    Heartbeat,
+    /// `FAULT <code> [text]`: something is wrong on the PLC side
+    Fault { code: String, text: String },
+    /// `STATUS <text>`: free-form state report
+    Status(String),
+    /// Anything this version doesn't understand
+    Other(String),
+}
+
+impl PlcReply {
+    /// Parse one reply line; None for blank lines
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        if line.is_empty() {
+            return None;
+        }
+
+        let (word, rest) = match line.split_once(char::is_whitespace) {
+            Some((word, rest)) => (word, rest.trim()),
+            None => (line, ""),
+        };
+        let (first, tail) = match rest.split_once(char::is_whitespace) {
+            Some((first, tail)) => (first, tail.trim()),
+            None => (rest, ""),
+        };
+        let seq = first.trim_start_matches('@').parse::<u32>().ok();
+
+        let reply = match (word.to_uppercase().as_str(), seq) {
+            ("ACK", Some(seq)) => PlcReply::Ack(seq),
+            ("NAK", Some(seq)) => PlcReply::Nak { seq, reason: tail.to_string() },
+            ("HB", _) => PlcReply::Heartbeat,
+            ("FAULT", _) if !first.is_empty() => PlcReply::Fault { code: first.to_string(), text: tail.to_string() },
+            ("STATUS", _) => PlcReply::Status(rest.to_string()),
+            _ => PlcReply::Other(line.to_string()),
+        };
+        Some(reply)
+    }
+}
+
+/// Format a batch for the wire
+///
+/// With a sequence number the line is `@<seq> cmd cmd...` and the PLC
+/// answers `ACK <seq>` or `NAK <seq>`; without one it is the bare command
+/// list.
+pub fn format_batch(seq: Option<u32>, commands: &[String]) -> String {
+    match seq {
+        Some(seq) => format!("@{} {}\r\n", seq, commands.join(" ")),
+        None => format!("{}\r\n", commands.join(" ")),
+    }
+}
+
+/// Something that happened on the PLC link, for the operator's PLC log
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlcEventKind {
+    Connected,
+    LinkLost { reason: String },
+    /// A batch went unacknowledged and was sent again
+    Retried { seq: u32, attempt: u32 },
+    /// A batch was given up on after its retries
+    Dropped { seq: u32, commands: Vec<String> },
+    /// The PLC refused a batch
+    Rejected { seq: u32, reason: String, commands: Vec<String> },
+    Fault { code: String, text: String },
+    Status(String),
+    /// The water guard changed or held back a command before it was queued
+    Unsafe { command: String, detail: String },
+}
+
+impl PlcEventKind {
+    /// True for events the operator needs to act on
+    pub fn is_fault(&self) -> bool {
+        matches!(self, PlcEventKind::LinkLost { .. }
+            | PlcEventKind::Dropped { .. }
+            | PlcEventKind::Rejected { .. }
+            | PlcEventKind::Fault { .. }
+            | PlcEventKind::Unsafe { .. })
+    }
+}
+
+impl fmt::Display for PlcEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlcEventKind::Connected => write!(f, "Connected"),
+            PlcEventKind::LinkLost { reason } => write!(f, "Link lost: {}", reason),
+            PlcEventKind::Retried { seq, attempt } => write!(f, "Batch {} not acknowledged, retry {}", seq, attempt),
+            PlcEventKind::Dropped { seq, commands } => write!(f, "Batch {} dropped: {}", seq, commands.join(" ")),
+            PlcEventKind::Rejected { seq, reason, commands } => {
+                write!(f, "Batch {} rejected", seq)?;
+                if !reason.is_empty() {
+                    write!(f, " ({})", reason)?;
+                }
+                write!(f, ": {}", commands.join(" "))
+            }
+            PlcEventKind::Fault { code, text } if text.is_empty() => write!(f, "Fault {}", code),
+            PlcEventKind::Fault { code, text } => write!(f, "Fault {}: {}", code, text),
+            PlcEventKind::Status(text) => write!(f, "Status: {}", text),
+            PlcEventKind::Unsafe { command, detail } => write!(f, "Unsafe {}: {}", command, detail),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlcEvent {
+    pub at: DateTime<Local>,
+    pub kind: PlcEventKind,
+}
+
+impl PlcEvent {
+    pub fn now(kind: PlcEventKind) -> Self {
+        Self { at: Local::now(), kind }
+    }
+}
+
+/// Log an event and add it to `events` for the operator's PLC log
+pub fn push_event(events: &mut Vec<PlcEvent>, kind: PlcEventKind) {
+    if kind.is_fault() {
+        tracing::warn!("PLC: {}", kind);
+    } else {
+        tracing::info!("PLC: {}", kind);
+    }
+    events.push(PlcEvent::now(kind));
+}
+
+/// A batch waiting for its acknowledgement
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingBatch {
+    pub commands: Vec<String>,
+    /// Commands no later batch has overwritten; only these are resent
+    pub resend: Vec<String>,
+    pub sent_at: Instant,
+    /// Sends so far, counting the first
+    pub attempts: u32,
+}
+
+/// What to do with overdue batches
+#[derive(Debug, Default, PartialEq)]
+pub struct DueBatches {
+    /// (seq, commands, retry number) to send again
+    pub retry: Vec<(u32, Vec<String>, u32)>,
+    /// Batches out of retries
+    pub dropped: Vec<(u32, Vec<String>)>,
+}
+
+/// Batches sent with a sequence number and not yet acknowledged
+#[derive(Debug, Default)]
+pub struct AckTracker {
+    pending: BTreeMap<u32, PendingBatch>,
+}
+
+impl AckTracker {
+    /// A retry goes out after every batch sent since, so anything a newer
+    /// batch sets is taken out of the older ones here; resending it would
+    /// put the stale value back.
+    pub fn sent(&mut self, seq: u32, commands: Vec<String>, now: Instant) {
+        let addresses: Vec<Option<u16>> = commands.iter().map(|c| command_address(c)).collect();
+        let resets = addresses.contains(&Some(RESET_ADDRESS));
+        for batch in self.pending.values_mut() {
+            batch.resend.retain(|command| match command_address(command) {
+                // A reset turns everything off, so it is stale once anything follows
+                Some(RESET_ADDRESS) => false,
+                Some(address) => !resets && !addresses.contains(&Some(address)),
+                None => true,
+            });
+        }
+        let resend = commands.clone();
+        self.pending.insert(seq, PendingBatch { commands, resend, sent_at: now, attempts: 1 });
+    }
+
+    /// Returns false for an unknown or already acknowledged batch
+    pub fn ack(&mut self, seq: u32) -> bool {
+        self.pending.remove(&seq).is_some()
+    }
+
+    /// A refused batch is not retried; its commands are returned for the log
+    pub fn nak(&mut self, seq: u32) -> Option<Vec<String>> {
+        self.pending.remove(&seq).map(|batch| batch.commands)
+    }
+
+    /// Batches whose acknowledgement is overdue as of `now`
+    ///
+    /// Batches to retry are marked as resent at `now`; dropped ones are
+    /// forgotten. A batch whose commands have all been overwritten by newer
+    /// batches is forgotten too, with nothing left to resend.
+    pub fn due(&mut self, now: Instant, options: &LinkOptions) -> DueBatches {
+        let mut due = DueBatches::default();
+        self.pending.retain(|&seq, batch| {
+            if now.duration_since(batch.sent_at) < options.ack_timeout {
+                return true;
+            }
+            if batch.resend.is_empty() {
+                tracing::debug!("Batch {} not acknowledged; newer batches replaced all of it", seq);
+                return false;
+            }
+            if batch.attempts > options.max_retries {
+                due.dropped.push((seq, batch.commands.clone()));
+                return false;
+            }
+            due.retry.push((seq, batch.resend.clone(), batch.attempts));
+            batch.attempts += 1;
+            batch.sent_at = now;
+            true
+        });
+        due
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Stop waiting for every batch, returning what is still worth
+    /// resending from each, oldest first
+    pub fn take_all(&mut self) -> Vec<Vec<String>> {
+        std::mem::take(&mut self.pending)
+            .into_values()
+            .map(|batch| batch.resend)
+            .filter(|resend| !resend.is_empty())
+            .collect()
+    }
+}
+
+/// The FCW address a command sets, e.g. 87 for `087-100`
+fn command_address(command: &str) -> Option<u16> {
+    command.split_once('-')?.0.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_replies() {
+        assert_eq!(PlcReply::parse("ACK 12\r\n"), Some(PlcReply::Ack(12)));
+        assert_eq!(PlcReply::parse("nak @7 bad address 999"), Some(PlcReply::Nak { seq: 7, reason: "bad address 999".to_string() }));
+        assert_eq!(PlcReply::parse("HB"), Some(PlcReply::Heartbeat));
+        assert_eq!(PlcReply::parse("FAULT E21 pump 3 overload"), Some(PlcReply::Fault { code: "E21".to_string(), text: "pump 3 overload".to_string() }));
+        assert_eq!(PlcReply::parse("STATUS RUN"), Some(PlcReply::Status("RUN".to_string())));
+        assert_eq!(PlcReply::parse("ACK"), Some(PlcReply::Other("ACK".to_string())));
+        assert_eq!(PlcReply::parse("  "), None);
+
+        let commands = vec!["001-005".to_string(), "033-000".to_string()];
+        assert_eq!(format_batch(Some(4), &commands), "@4 001-005 033-000\r\n");
+        assert_eq!(format_batch(None, &commands), "001-005 033-000\r\n");
+    }
+
+    #[test]
+    fn test_retries_then_drops() {
+        let options = LinkOptions::from_settings(2.0, 1000, 1);
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut tracker = AckTracker::default();
+        tracker.sent(1, vec!["001-005".to_string()], start);
+        tracker.sent(2, vec!["002-005".to_string()], start);
+        tracker.sent(3, vec!["003-005".to_string()], start);
+
+        assert!(tracker.ack(1));
+        assert!(!tracker.ack(1));
+        assert_eq!(tracker.nak(3), Some(vec!["003-005".to_string()]));
+        assert_eq!(tracker.due(at(500), &options), DueBatches::default());
+
+        let due = tracker.due(at(1000), &options);
+        assert_eq!(due.retry, vec![(2, vec!["002-005".to_string()], 1)]);
+        assert!(due.dropped.is_empty());
+
+        assert_eq!(tracker.due(at(1500), &options), DueBatches::default());
+        let due = tracker.due(at(2000), &options);
+        assert!(due.retry.is_empty());
+        assert_eq!(due.dropped, vec![(2, vec!["002-005".to_string()])]);
+        assert_eq!(tracker.pending_count(), 0);
+    }
+
+    #[test]
+    fn test_retry_skips_overwritten_commands() {
+        let options = LinkOptions::from_settings(2.0, 1000, 2);
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut tracker = AckTracker::default();
+        tracker.sent(1, vec!["087-100".to_string(), "001-005".to_string()], start);
+        tracker.sent(2, vec!["087-000".to_string()], at(100));
+        assert!(tracker.ack(2));
+
+        // Only the untouched address goes out again, after 087-000
+        let due = tracker.due(at(1000), &options);
+        assert_eq!(due.retry, vec![(1, vec!["001-005".to_string()], 1)]);
+
+        tracker.sent(3, vec!["099-000".to_string()], at(1200));
+        assert!(tracker.ack(3));
+        assert_eq!(tracker.due(at(2000), &options), DueBatches::default());
+        assert_eq!(tracker.pending_count(), 0);
+    }
+}
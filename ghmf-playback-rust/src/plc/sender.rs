@@ -0,0 +1,519 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use anyhow::Result;
+use crossbeam_channel::{Receiver, Sender, TryRecvError};
+use super::transport::PlcTransport;
+
+/// How often the sender wakes to send, heartbeat and retry
+const SEND_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Pause after a failure before reconnecting
+const RECONNECT_DELAY: Duration = Duration::from_millis(1000);
+
+const CONNECT_TIMEOUT_MS: u64 = 5000;
+
+/// What to do with batches that queued up while the PLC link was down
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReconnectPolicy {
+    /// Send them, in order, once the link is back
+    #[default]
+    Replay,
+    /// Throw them away and carry on from the next CTL command
+    Drop,
+}
+
+impl ReconnectPolicy {
+    /// Parse "replay" or "drop"; anything else is replay
+    pub fn from_setting(value: &str) -> Self {
+        match value.trim().to_lowercase().as_str() {
+            "drop" => ReconnectPolicy::Drop,
+            _ => ReconnectPolicy::Replay,
+        }
+    }
+
+    pub fn as_setting(&self) -> &'static str {
+        match self {
+            ReconnectPolicy::Replay => "replay",
+            ReconnectPolicy::Drop => "drop",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReconnectPolicy::Replay => "Replay missed commands",
+            ReconnectPolicy::Drop => "Drop missed commands",
+        }
+    }
+}
+
+/// A water command on its way to the sender
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueuedCommand {
+    /// CTL time the command belongs to; manual commands have none and go
+    /// out in a batch of their own
+    pub time_ms: Option<u64>,
+    pub text: String,
+}
+
+/// Commands that go to the PLC together
+#[derive(Debug, Clone, PartialEq)]
+pub struct Batch {
+    pub time_ms: Option<u64>,
+    pub commands: Vec<String>,
+    /// Failed sends so far
+    pub failures: u32,
+    /// An E-stop sequence, kept whatever the reconnect policy
+    pub urgent: bool,
+}
+
+/// Add a command to the backlog, joining the batch for its CTL timestamp
+///
+/// Only the newest batch is joined, so commands never overtake each other.
+pub fn push_command(backlog: &mut VecDeque<Batch>, command: QueuedCommand) {
+    if let (Some(time_ms), Some(last)) = (command.time_ms, backlog.back_mut()) {
+        if last.time_ms == Some(time_ms) && last.failures == 0 {
+            last.commands.push(command.text);
+            return;
+        }
+    }
+    backlog.push_back(Batch { time_ms: command.time_ms, commands: vec![command.text], failures: 0, urgent: false });
+}
+
+/// Replace the backlog with an E-stop sequence
+///
+/// Returns the number of commands thrown away to make room for it.
+pub fn push_stop(backlog: &mut VecDeque<Batch>, commands: Vec<String>) -> usize {
+    let dropped = backlog.iter().filter(|batch| !batch.urgent).map(|batch| batch.commands.len()).sum();
+    backlog.retain(|batch| batch.urgent);
+    backlog.push_back(Batch { time_ms: None, commands, failures: 0, urgent: true });
+    dropped
+}
+
+/// Running totals of water commands, shared between the sender and the UI
+#[derive(Debug, Default)]
+pub struct PlcCounters {
+    sent: AtomicU64,
+    dropped: AtomicU64,
+    retried: AtomicU64,
+    stops: AtomicU64,
+}
+
+/// A snapshot of `PlcCounters`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlcCounts {
+    /// Commands handed to the transport
+    pub sent: u64,
+    /// Commands given up on, by the reconnect policy or the PLC
+    pub dropped: u64,
+    /// Commands sent again after a failed send
+    pub retried: u64,
+    /// E-stop sequences handed to the transport
+    pub stops: u64,
+}
+
+impl PlcCounters {
+    pub fn add_sent(&self, count: usize) {
+        self.sent.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    pub fn add_dropped(&self, count: usize) {
+        self.dropped.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    pub fn add_retried(&self, count: usize) {
+        self.retried.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    pub fn add_stop(&self) {
+        self.stops.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn snapshot(&self) -> PlcCounts {
+        PlcCounts {
+            sent: self.sent.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            retried: self.retried.load(Ordering::Relaxed),
+            stops: self.stops.load(Ordering::SeqCst),
+        }
+    }
+}
+
+/// The task that owns the PLC link
+///
+/// Commands arrive over a channel, so queueing from the UI thread never
+/// blocks or fails. A batch leaves the backlog only once the transport has
+/// taken it; a failed send keeps it at the front for after the reconnect.
+/// An E-stop sequence arrives on its own channel and replaces the backlog.
+/// The sender stops when its `PlcClient` is dropped.
+pub struct PlcSender {
+    enabled: bool,
+    transport: Arc<dyn PlcTransport>,
+    commands: Receiver<QueuedCommand>,
+    stops: Receiver<Vec<String>>,
+    /// Every batch the transport has taken, for the maintenance counters
+    written: Sender<(Instant, Vec<String>)>,
+    backlog: VecDeque<Batch>,
+    policy: ReconnectPolicy,
+    counters: Arc<PlcCounters>,
+    reconnecting: Arc<AtomicBool>,
+    /// Link state after the last pass, to notice it coming back
+    was_connected: bool,
+    /// The last E-stop sequence sent, so it stays urgent if it comes back
+    /// unconfirmed
+    last_stop: Option<Vec<String>>,
+}
+
+impl PlcSender {
+    pub(crate) fn new(
+        enabled: bool,
+        transport: Arc<dyn PlcTransport>,
+        commands: Receiver<QueuedCommand>,
+        stops: Receiver<Vec<String>>,
+        written: Sender<(Instant, Vec<String>)>,
+        counters: Arc<PlcCounters>,
+        reconnecting: Arc<AtomicBool>,
+    ) -> Self {
+        Self {
+            enabled,
+            transport,
+            commands,
+            stops,
+            written,
+            backlog: VecDeque::new(),
+            policy: ReconnectPolicy::default(),
+            counters,
+            reconnecting,
+            was_connected: false,
+            last_stop: None,
+        }
+    }
+
+    pub(crate) fn set_policy(&mut self, policy: ReconnectPolicy) {
+        self.policy = policy;
+    }
+
+    /// Batches waiting to be sent
+    pub fn backlog_len(&self) -> usize {
+        self.backlog.len()
+    }
+
+    /// Move everything queued so far into the backlog
+    ///
+    /// Returns false once the client has gone and nothing more can arrive.
+    pub fn collect(&mut self) -> bool {
+        // Anything queued after an E-stop was queued after the operator
+        // hit it, so it follows the sequence
+        while let Ok(sequence) = self.stops.try_recv() {
+            let dropped = push_stop(&mut self.backlog, sequence);
+            if dropped > 0 {
+                tracing::warn!("E-stop: dropped {} queued commands", dropped);
+                self.counters.add_dropped(dropped);
+            }
+        }
+        loop {
+            match self.commands.try_recv() {
+                Ok(command) => push_command(&mut self.backlog, command),
+                Err(TryRecvError::Empty) => return true,
+                Err(TryRecvError::Disconnected) => return false,
+            }
+        }
+    }
+
+    /// Send the backlog, oldest first, stopping at the first failure
+    ///
+    /// Returns the number of commands sent.
+    pub async fn flush(&mut self) -> Result<usize> {
+        let mut count = 0;
+        while let Some(mut batch) = self.backlog.pop_front() {
+            if !self.enabled {
+                tracing::debug!("Would have sent to PLC: {}", batch.commands.join(" "));
+            } else if !self.transport.is_connected() {
+                self.backlog.push_front(batch);
+                anyhow::bail!("PLC not connected");
+            } else if let Err(e) = self.transport.send_batch(&batch.commands).await {
+                batch.failures += 1;
+                self.backlog.push_front(batch);
+                return Err(e);
+            }
+
+            if self.enabled {
+                self.counters.add_sent(batch.commands.len());
+                let _ = self.written.send((Instant::now(), batch.commands.clone()));
+            }
+            // Counted even while disabled, so `emergency_stop_blocking` returns
+            if batch.urgent {
+                tracing::warn!("E-stop sent to PLC: {}", batch.commands.join(" "));
+                self.counters.add_stop();
+                self.last_stop = Some(batch.commands.clone());
+            }
+            if batch.failures > 0 {
+                self.counters.add_retried(batch.commands.len());
+            }
+            count += batch.commands.len();
+        }
+        Ok(count)
+    }
+
+    /// Apply the reconnect policy if the link has just come back
+    ///
+    /// Batches the PLC never acknowledged before the outage go back at the
+    /// front of the backlog first, so the policy covers them too.
+    pub fn check_link(&mut self) {
+        let connected = self.transport.is_connected();
+        if connected && !self.was_connected {
+            for commands in self.transport.take_unconfirmed().into_iter().rev() {
+                let urgent = self.last_stop.as_ref() == Some(&commands);
+                self.backlog.push_front(Batch { time_ms: None, commands, failures: 1, urgent });
+            }
+        }
+        if connected && !self.was_connected && !self.backlog.is_empty() {
+            let commands: usize = self.backlog.iter().map(|batch| batch.commands.len()).sum();
+            match self.policy {
+                ReconnectPolicy::Replay => {
+                    tracing::info!("PLC link up, replaying {} queued commands", commands);
+                }
+                ReconnectPolicy::Drop => {
+                    let urgent: usize = self.backlog.iter().filter(|batch| batch.urgent).map(|batch| batch.commands.len()).sum();
+                    tracing::warn!("PLC link up, dropping {} commands queued while it was down", commands - urgent);
+                    self.counters.add_dropped(commands - urgent);
+                    self.backlog.retain(|batch| batch.urgent);
+                }
+            }
+        }
+        self.was_connected = connected;
+    }
+
+    async fn connect(&mut self) {
+        // Connecting drops any open link, so whatever comes up is new
+        self.was_connected = false;
+        self.reconnecting.store(true, Ordering::SeqCst);
+        match self.transport.connect(CONNECT_TIMEOUT_MS).await {
+            Ok(()) => tracing::info!("PLC connected over {}", self.transport.describe()),
+            Err(e) => tracing::warn!("PLC connection failed: {}", e),
+        }
+        self.reconnecting.store(false, Ordering::SeqCst);
+        self.check_link();
+    }
+
+    /// Send, heartbeat and reconnect until the client is dropped
+    pub async fn run(mut self) {
+        if self.enabled {
+            self.connect().await;
+        }
+
+        loop {
+            tokio::time::sleep(SEND_INTERVAL).await;
+
+            let open = self.collect();
+            if !self.enabled {
+                let _ = self.flush().await;
+            } else {
+                self.check_link();
+                let result = match self.flush().await {
+                    Ok(_) => self.transport.service().await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = result {
+                    tracing::debug!("PLC send error: {}", e);
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                    self.connect().await;
+                }
+            }
+
+            if !open {
+                break;
+            }
+        }
+
+        if !self.backlog.is_empty() {
+            tracing::debug!("PLC sender stopping with {} batches unsent", self.backlog.len());
+        }
+        self.transport.disconnect().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use super::super::ascii_tcp::LineLink;
+    use super::super::protocol::{LinkOptions, PlcEvent};
+    use super::super::transport::BoxFuture;
+
+    /// The line protocol's bookkeeping with the wire taken out
+    struct LineStub {
+        options: LinkOptions,
+        link: Mutex<LineLink>,
+        connected: AtomicBool,
+        wire: Mutex<Vec<String>>,
+    }
+
+    impl PlcTransport for LineStub {
+        fn describe(&self) -> String {
+            "stub".to_string()
+        }
+
+        fn connect(&self, _timeout_ms: u64) -> BoxFuture<'_, Result<()>> {
+            self.link.lock().unwrap().connected();
+            self.connected.store(true, Ordering::SeqCst);
+            Box::pin(async { Ok(()) })
+        }
+
+        fn disconnect(&self) -> BoxFuture<'_, ()> {
+            self.connected.store(false, Ordering::SeqCst);
+            Box::pin(async {})
+        }
+
+        fn is_connected(&self) -> bool {
+            self.connected.load(Ordering::SeqCst)
+        }
+
+        fn send_batch<'a>(&'a self, commands: &'a [String]) -> BoxFuture<'a, Result<()>> {
+            let message = self.link.lock().unwrap().batch_message(&self.options, commands);
+            self.wire.lock().unwrap().push(message.trim().to_string());
+            Box::pin(async { Ok(()) })
+        }
+
+        fn service(&self) -> BoxFuture<'_, Result<()>> {
+            let messages = self.link.lock().unwrap().service(&self.options, Instant::now() + Duration::from_secs(5));
+            self.wire.lock().unwrap().extend(messages.unwrap().iter().map(|m| m.trim().to_string()));
+            Box::pin(async { Ok(()) })
+        }
+
+        fn take_events(&self) -> Vec<PlcEvent> {
+            Vec::new()
+        }
+
+        fn take_unconfirmed(&self) -> Vec<Vec<String>> {
+            self.link.lock().unwrap().take_unconfirmed()
+        }
+    }
+
+    fn at(time_ms: u64, text: &str) -> QueuedCommand {
+        QueuedCommand { time_ms: Some(time_ms), text: text.to_string() }
+    }
+
+    #[test]
+    fn test_batches_per_timestamp_in_order() {
+        let mut backlog = VecDeque::new();
+        push_command(&mut backlog, at(100, "001-005"));
+        push_command(&mut backlog, at(100, "002-005"));
+        push_command(&mut backlog, QueuedCommand { time_ms: None, text: "099-000".to_string() });
+        push_command(&mut backlog, at(100, "003-005"));
+        push_command(&mut backlog, at(250, "001-000"));
+        // A batch that already failed once is resent as it was
+        if let Some(last) = backlog.back_mut() {
+            last.failures = 1;
+        }
+        push_command(&mut backlog, at(250, "002-000"));
+
+        let batches: Vec<_> = backlog.iter().map(|batch| batch.commands.join(" ")).collect();
+        assert_eq!(batches, vec!["001-005 002-005", "099-000", "003-005", "001-000", "002-000"]);
+
+        // An E-stop clears everything but an earlier E-stop
+        assert_eq!(push_stop(&mut backlog, vec!["099-000".to_string()]), 6);
+        assert_eq!(push_stop(&mut backlog, vec!["001-000".to_string(), "099-000".to_string()]), 0);
+        push_command(&mut backlog, at(300, "001-005"));
+        let batches: Vec<_> = backlog.iter().map(|batch| (batch.commands.join(" "), batch.urgent)).collect();
+        assert_eq!(batches, vec![
+            ("099-000".to_string(), true),
+            ("001-000 099-000".to_string(), true),
+            ("001-005".to_string(), false),
+        ]);
+
+        assert_eq!(ReconnectPolicy::from_setting("Drop"), ReconnectPolicy::Drop);
+        assert_eq!(ReconnectPolicy::from_setting("whatever"), ReconnectPolicy::Replay);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_sender_counts_nothing_sent() {
+        let stub = Arc::new(LineStub {
+            options: LinkOptions::from_settings(0.0, 1000, 2),
+            link: Mutex::new(LineLink::new()),
+            connected: AtomicBool::new(false),
+            wire: Mutex::new(Vec::new()),
+        });
+        let (commands_tx, commands) = crossbeam_channel::unbounded();
+        let (stops_tx, stops) = crossbeam_channel::unbounded();
+        let (written, written_rx) = crossbeam_channel::unbounded();
+        let counters = Arc::new(PlcCounters::default());
+        let mut sender = PlcSender::new(
+            false,
+            Arc::clone(&stub) as Arc<dyn PlcTransport>,
+            commands,
+            stops,
+            written,
+            Arc::clone(&counters),
+            Arc::new(AtomicBool::new(false)),
+        );
+
+        commands_tx.send(at(100, "001-005")).unwrap();
+        stops_tx.send(vec!["099-000".to_string()]).unwrap();
+        sender.collect();
+        sender.flush().await.unwrap();
+
+        assert!(stub.wire.lock().unwrap().is_empty());
+        assert!(written_rx.try_recv().is_err());
+        assert_eq!(counters.snapshot(), PlcCounts { sent: 0, dropped: 0, retried: 0, stops: 1 });
+    }
+
+    /// Send one batch that goes unacknowledged, lose the link, queue
+    /// another and reconnect under `policy`; returns what reached the wire
+    async fn reconnect_with(policy: ReconnectPolicy) -> (Vec<String>, PlcCounts) {
+        let stub = Arc::new(LineStub {
+            options: LinkOptions::from_settings(0.0, 1000, 2),
+            link: Mutex::new(LineLink::new()),
+            connected: AtomicBool::new(false),
+            wire: Mutex::new(Vec::new()),
+        });
+        let (commands_tx, commands) = crossbeam_channel::unbounded();
+        let (_stops_tx, stops) = crossbeam_channel::unbounded();
+        let (written, _written_rx) = crossbeam_channel::unbounded();
+        let counters = Arc::new(PlcCounters::default());
+        let mut sender = PlcSender::new(
+            true,
+            Arc::clone(&stub) as Arc<dyn PlcTransport>,
+            commands,
+            stops,
+            written,
+            Arc::clone(&counters),
+            Arc::new(AtomicBool::new(false)),
+        );
+        sender.set_policy(policy);
+
+        sender.connect().await;
+        commands_tx.send(at(100, "001-005")).unwrap();
+        sender.collect();
+        sender.flush().await.unwrap();
+
+        stub.disconnect().await;
+        sender.check_link();
+        commands_tx.send(at(200, "002-005")).unwrap();
+        sender.collect();
+        assert!(sender.flush().await.is_err());
+
+        sender.connect().await;
+        sender.flush().await.unwrap();
+        // The PLC takes everything sent since; nothing is left for the
+        // link itself to resend
+        for seq in 2..=3 {
+            stub.link.lock().unwrap().handle_line(&format!("ACK {}", seq));
+        }
+        stub.service().await.unwrap();
+        let wire = stub.wire.lock().unwrap().clone();
+        (wire, counters.snapshot())
+    }
+
+    #[tokio::test]
+    async fn test_unconfirmed_batches_follow_reconnect_policy() {
+        let (wire, counts) = reconnect_with(ReconnectPolicy::Replay).await;
+        assert_eq!(wire, vec!["@1 001-005", "@2 001-005", "@3 002-005"]);
+        assert_eq!(counts.retried, 1);
+
+        let (wire, counts) = reconnect_with(ReconnectPolicy::Drop).await;
+        assert_eq!(wire, vec!["@1 001-005"]);
+        assert_eq!(counts.dropped, 2);
+    }
+}
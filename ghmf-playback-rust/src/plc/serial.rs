@@ -0,0 +1,287 @@
+use std::io::{Read, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use anyhow::{Context, Result};
+use serialport::{DataBits, FlowControl, Parity, SerialPort, StopBits};
+use super::ascii_tcp::LineLink;
+use super::protocol::{LinkOptions, PlcEvent, PlcEventKind};
+use super::transport::{BoxFuture, PlcTransport};
+
+/// How often the reader thread wakes to check it is still wanted
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Baud rate and parity for the PLC's serial line (always 8 data bits,
+/// 1 stop bit, no flow control)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SerialLine {
+    pub port_name: String,
+    pub baud_rate: u32,
+    pub parity: Parity,
+}
+
+impl SerialLine {
+    /// Line settings from the settings file; parity is "none", "even" or "odd"
+    pub fn from_settings(port_name: &str, baud_rate: u32, parity: &str) -> Self {
+        Self {
+            port_name: port_name.trim().to_string(),
+            baud_rate,
+            parity: parity_from_setting(parity),
+        }
+    }
+}
+
+/// Parse "none", "even" or "odd"; anything else is none
+pub fn parity_from_setting(value: &str) -> Parity {
+    match value.trim().to_lowercase().as_str() {
+        "even" => Parity::Even,
+        "odd" => Parity::Odd,
+        _ => Parity::None,
+    }
+}
+
+pub fn parity_as_setting(parity: Parity) -> &'static str {
+    match parity {
+        Parity::None => "none",
+        Parity::Even => "even",
+        Parity::Odd => "odd",
+    }
+}
+
+/// The ASCII line protocol over RS-232/RS-485, for older installs and the
+/// backup control cabinet
+///
+/// Batches, acknowledgements and heartbeats work exactly as over TCP. A
+/// reader thread collects replies; a USB adapter that disappears drops the
+/// link and is reopened by the client's reconnect.
+pub struct SerialTransport {
+    line: SerialLine,
+    options: LinkOptions,
+    port: Arc<Mutex<Option<Box<dyn SerialPort>>>>,
+    connected: Arc<AtomicBool>,
+    /// Bumped on every open and close, so a stale reader thread stops
+    generation: Arc<AtomicU64>,
+    link: Arc<Mutex<LineLink>>,
+}
+
+impl SerialTransport {
+    pub fn new(line: SerialLine, options: LinkOptions) -> Self {
+        Self {
+            line,
+            options,
+            port: Arc::new(Mutex::new(None)),
+            connected: Arc::new(AtomicBool::new(false)),
+            generation: Arc::new(AtomicU64::new(0)),
+            link: Arc::new(Mutex::new(LineLink::new())),
+        }
+    }
+
+    async fn open(&self) -> Result<()> {
+        self.close().await;
+        if self.line.port_name.is_empty() {
+            anyhow::bail!("No PLC serial port selected");
+        }
+
+        tracing::info!("Opening PLC serial port {} at {} baud", self.line.port_name, self.line.baud_rate);
+        let port = serialport::new(&self.line.port_name, self.line.baud_rate)
+            .data_bits(DataBits::Eight)
+            .parity(self.line.parity)
+            .stop_bits(StopBits::One)
+            .flow_control(FlowControl::None)
+            .timeout(READ_TIMEOUT)
+            .open()
+            .with_context(|| format!("Failed to open PLC serial port {}", self.line.port_name))?;
+        let reader = port.try_clone().context("Failed to clone PLC serial port")?;
+
+        *self.port.lock().map_err(|_| anyhow::anyhow!("PLC serial port poisoned"))? = Some(port);
+        self.connected.store(true, Ordering::SeqCst);
+        if let Ok(mut link) = self.link.lock() {
+            link.connected();
+        }
+
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let link = Arc::clone(&self.link);
+        let port = Arc::clone(&self.port);
+        let connected = Arc::clone(&self.connected);
+        let current = Arc::clone(&self.generation);
+        std::thread::spawn(move || {
+            Self::read_loop(reader, link, generation, &current);
+            // Only the current reader may mark the link down
+            if current.load(Ordering::SeqCst) == generation {
+                if let Ok(mut port) = port.lock() {
+                    *port = None;
+                }
+                connected.store(false, Ordering::SeqCst);
+            }
+        });
+        Ok(())
+    }
+
+    /// Read replies until the port fails or the transport moves on
+    fn read_loop(mut reader: Box<dyn SerialPort>, link: Arc<Mutex<LineLink>>, generation: u64, current: &AtomicU64) {
+        let mut pending = Vec::new();
+        let mut buf = [0u8; 256];
+        while current.load(Ordering::SeqCst) == generation {
+            match reader.read(&mut buf) {
+                Ok(0) => continue,
+                Ok(n) => {
+                    pending.extend_from_slice(&buf[..n]);
+                    if let Ok(mut link) = link.lock() {
+                        for line in split_lines(&mut pending) {
+                            link.handle_line(&line);
+                        }
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::TimedOut => continue,
+                Err(e) => {
+                    if current.load(Ordering::SeqCst) == generation {
+                        if let Ok(mut link) = link.lock() {
+                            link.record(PlcEventKind::LinkLost { reason: format!("serial read failed: {}", e) });
+                        }
+                    }
+                    return;
+                }
+            }
+        }
+    }
+
+    async fn close(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        if let Ok(mut port) = self.port.lock() {
+            if port.take().is_some() {
+                tracing::debug!("Closed PLC serial port");
+            }
+        }
+        self.connected.store(false, Ordering::SeqCst);
+    }
+
+    async fn send_commands(&self, commands: &[String]) -> Result<()> {
+        // Tagged batches are resent by `service` until acknowledged
+        let message = self.link.lock()
+            .map_err(|_| anyhow::anyhow!("PLC link state poisoned"))?
+            .batch_message(&self.options, commands);
+        let result = self.send(message).await;
+        if result.is_err() {
+            if let Ok(mut link) = self.link.lock() {
+                link.unsent();
+            }
+        }
+        result
+    }
+
+    async fn run_service(&self) -> Result<()> {
+        let messages = self.link.lock()
+            .map_err(|_| anyhow::anyhow!("PLC link state poisoned"))?
+            .service(&self.options, Instant::now());
+        let messages = match messages {
+            Ok(messages) => messages,
+            Err(reason) => {
+                self.close().await;
+                return Err(anyhow::anyhow!("PLC {}", reason));
+            }
+        };
+
+        for message in messages {
+            self.send(message).await?;
+        }
+        Ok(())
+    }
+
+    /// Write off the async thread; at 9600 baud a batch takes tens of ms
+    async fn send(&self, message: String) -> Result<()> {
+        let port = Arc::clone(&self.port);
+        let connected = Arc::clone(&self.connected);
+        tokio::task::spawn_blocking(move || {
+            let mut port = port.lock().map_err(|_| anyhow::anyhow!("PLC serial port poisoned"))?;
+            let Some(writer) = port.as_mut() else {
+                anyhow::bail!("PLC not connected");
+            };
+
+            tracing::debug!("Sending to PLC: {}", message.trim());
+            if let Err(e) = writer.write_all(message.as_bytes()).and_then(|_| writer.flush()) {
+                tracing::warn!("PLC serial write failed: {}. Will attempt reconnect.", e);
+                *port = None;
+                connected.store(false, Ordering::SeqCst);
+                anyhow::bail!("PLC send failed: {}", e);
+            }
+            Ok(())
+        })
+        .await
+        .context("PLC serial writer panicked")?
+    }
+}
+
+/// Take complete lines out of `pending`, leaving any partial line behind
+fn split_lines(pending: &mut Vec<u8>) -> Vec<String> {
+    let mut lines = Vec::new();
+    while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+        let line: Vec<u8> = pending.drain(..=end).collect();
+        lines.push(String::from_utf8_lossy(&line).trim().to_string());
+    }
+    lines
+}
+
+impl PlcTransport for SerialTransport {
+    fn describe(&self) -> String {
+        format!(
+            "serial {} {} baud, parity {}",
+            self.line.port_name,
+            self.line.baud_rate,
+            parity_as_setting(self.line.parity),
+        )
+    }
+
+    fn connect(&self, _timeout_ms: u64) -> BoxFuture<'_, Result<()>> {
+        Box::pin(self.open())
+    }
+
+    fn disconnect(&self) -> BoxFuture<'_, ()> {
+        Box::pin(self.close())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::SeqCst)
+    }
+
+    fn send_batch<'a>(&'a self, commands: &'a [String]) -> BoxFuture<'a, Result<()>> {
+        Box::pin(self.send_commands(commands))
+    }
+
+    fn service(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(self.run_service())
+    }
+
+    fn take_events(&self) -> Vec<PlcEvent> {
+        self.link.lock().map(|mut link| link.take_events()).unwrap_or_default()
+    }
+
+    fn unacknowledged(&self) -> usize {
+        self.link.lock().map(|link| link.unacknowledged()).unwrap_or(0)
+    }
+
+    fn take_unconfirmed(&self) -> Vec<Vec<String>> {
+        self.link.lock().map(|mut link| link.take_unconfirmed()).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_settings_and_splitting() {
+        let line = SerialLine::from_settings(" /dev/ttyUSB0 ", 19_200, "Even");
+        assert_eq!(line.port_name, "/dev/ttyUSB0");
+        assert_eq!(line.parity, Parity::Even);
+        assert_eq!(parity_from_setting("mark"), Parity::None);
+        assert_eq!(parity_as_setting(Parity::Odd), "odd");
+
+        let mut pending = b"ACK 1\r\nHB\r\nFAULT E".to_vec();
+        assert_eq!(split_lines(&mut pending), vec!["ACK 1", "HB"]);
+        assert_eq!(pending, b"FAULT E");
+        pending.extend_from_slice(b"21\n");
+        assert_eq!(split_lines(&mut pending), vec!["FAULT E21"]);
+        assert!(pending.is_empty());
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+use crate::config::WaterMap;
+use super::protocol::HEARTBEAT;
+
+/// Last command seen on one water address
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaterOutput {
+    pub data: u16,
+    pub changed_at: Instant,
+}
+
+impl WaterOutput {
+    /// Valves and pumps are open/running for any nonzero value
+    pub fn is_on(&self) -> bool {
+        self.data != 0
+    }
+}
+
+/// Faults to inject for resilience testing
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SimOptions {
+    /// Wait this long before every reply
+    pub reply_delay: Duration,
+    /// Never reply, like an older PLC program or a half-open socket
+    pub silent: bool,
+    /// Leave every Nth tagged batch unacknowledged
+    pub stall_every: Option<u32>,
+    /// Close the connection after every Nth batch
+    pub drop_every: Option<u32>,
+}
+
+/// What to do after a line
+#[derive(Debug, Default, PartialEq)]
+pub struct SimResponse {
+    pub replies: Vec<String>,
+    pub disconnect: bool,
+}
+
+/// Stand-in for the fountain PLC
+///
+/// Accepts the lines `PlcClient` sends, keeps the last value written to
+/// each water FCW address and answers with `ACK`/`NAK`/`HB` as the real
+/// PLC does.
+#[derive(Debug, Default)]
+pub struct PlcSimulator {
+    options: SimOptions,
+    water: WaterMap,
+    outputs: BTreeMap<u16, WaterOutput>,
+    batches: u32,
+    last_line: String,
+}
+
+impl PlcSimulator {
+    pub fn new(options: SimOptions) -> Self {
+        Self { options, ..Self::default() }
+    }
+
+    /// Accept the addresses in `water` rather than the built-in ones
+    pub fn with_water_map(self, water: WaterMap) -> Self {
+        Self { water, ..self }
+    }
+
+    pub fn options(&self) -> &SimOptions {
+        &self.options
+    }
+
+    /// Water outputs that have been written, by address
+    pub fn outputs(&self) -> &BTreeMap<u16, WaterOutput> {
+        &self.outputs
+    }
+
+    pub fn batches(&self) -> u32 {
+        self.batches
+    }
+
+    pub fn last_line(&self) -> &str {
+        &self.last_line
+    }
+
+    /// Handle one line from the client
+    pub fn handle_line(&mut self, line: &str, now: Instant) -> SimResponse {
+        let line = line.trim();
+        if line.is_empty() {
+            return SimResponse::default();
+        }
+        if line.eq_ignore_ascii_case(HEARTBEAT) {
+            return self.reply(vec![HEARTBEAT.to_string()], false);
+        }
+
+        self.last_line = line.to_string();
+        self.batches += 1;
+        let disconnect = self.options.drop_every.is_some_and(|n| self.batches.is_multiple_of(n));
+
+        let (seq, body) = match line.strip_prefix('@').and_then(|tagged| tagged.split_once(' ')) {
+            Some((seq, body)) => (seq.parse::<u32>().ok(), body),
+            None => (None, line),
+        };
+
+        let mut commands = Vec::new();
+        let mut invalid = Vec::new();
+        for text in body.split_whitespace() {
+            match self.parse_water_command(text) {
+                Some(command) => commands.push(command),
+                None => invalid.push(text),
+            }
+        }
+
+        // A tagged batch is all or nothing; an untagged one has nobody to
+        // tell, so the good commands still apply
+        let Some(seq) = seq else {
+            for text in &invalid {
+                tracing::warn!("Ignoring invalid water command '{}'", text);
+            }
+            self.apply(&commands, now);
+            return self.reply(Vec::new(), disconnect);
+        };
+        if !invalid.is_empty() {
+            let reply = format!("NAK {} invalid {}", seq, invalid.join(" "));
+            return self.reply(vec![reply], disconnect);
+        }
+
+        self.apply(&commands, now);
+        let stalled = self.options.stall_every.is_some_and(|n| self.batches.is_multiple_of(n));
+        let replies = if stalled { Vec::new() } else { vec![format!("ACK {}", seq)] };
+        self.reply(replies, disconnect)
+    }
+
+    fn apply(&mut self, commands: &[(u16, u16)], now: Instant) {
+        for &(address, data) in commands {
+            // 099-000 resets everything
+            if address == 99 && data == 0 {
+                self.outputs.clear();
+                continue;
+            }
+            let changed = self.outputs.get(&address).is_none_or(|output| output.data != data);
+            if changed {
+                self.outputs.insert(address, WaterOutput { data, changed_at: now });
+            }
+        }
+    }
+
+    /// "AAA-DDD" on a water address
+    fn parse_water_command(&self, text: &str) -> Option<(u16, u16)> {
+        let (address, data) = text.split_once('-')?;
+        let address = address.parse::<u16>().ok()?;
+        let data = data.parse::<u16>().ok()?;
+        self.water.is_water(address).then_some((address, data))
+    }
+
+    fn reply(&self, replies: Vec<String>, disconnect: bool) -> SimResponse {
+        let replies = if self.options.silent { Vec::new() } else { replies };
+        SimResponse { replies, disconnect }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracks_water_state_and_acks() {
+        let now = Instant::now();
+        let mut sim = PlcSimulator::new(SimOptions::default());
+
+        let response = sim.handle_line("@1 001-005 033-000 255-016\r\n", now);
+        assert_eq!(response.replies, vec!["ACK 1"]);
+        assert!(sim.outputs()[&1].is_on());
+        assert!(!sim.outputs()[&33].is_on());
+        assert_eq!(sim.outputs()[&255].data, 16);
+
+        // Lighting addresses are refused and nothing in the batch applies
+        let response = sim.handle_line("@2 001-000 500-255", now);
+        assert_eq!(response.replies, vec!["NAK 2 invalid 500-255"]);
+        assert_eq!(sim.outputs()[&1].data, 5);
+
+        assert_eq!(sim.handle_line("HB", now).replies, vec!["HB"]);
+        assert_eq!(sim.handle_line("099-000", now), SimResponse::default());
+        assert!(sim.outputs().is_empty());
+        assert_eq!(sim.batches(), 3);
+    }
+
+    #[test]
+    fn test_injected_faults() {
+        let now = Instant::now();
+        let mut sim = PlcSimulator::new(SimOptions {
+            stall_every: Some(2),
+            drop_every: Some(3),
+            ..SimOptions::default()
+        });
+
+        assert_eq!(sim.handle_line("@1 001-005", now).replies, vec!["ACK 1"]);
+        assert!(sim.handle_line("@2 001-006", now).replies.is_empty());
+        let response = sim.handle_line("@3 001-007", now);
+        assert_eq!(response.replies, vec!["ACK 3"]);
+        assert!(response.disconnect);
+
+        let mut silent = PlcSimulator::new(SimOptions { silent: true, ..SimOptions::default() });
+        assert!(silent.handle_line("HB", now).replies.is_empty());
+        assert!(silent.handle_line("@1 001-005", now).replies.is_empty());
+        assert_eq!(silent.outputs()[&1].data, 5);
+    }
+}
@@ -0,0 +1,84 @@
+use std::future::Future;
+use std::pin::Pin;
+use anyhow::Result;
+use super::protocol::PlcEvent;
+
+/// Boxed future, so transports can sit behind `dyn PlcTransport`
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Wire protocol picked in Settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlcProtocol {
+    /// Space-separated "XXX-YYY" lines, as PLCComms.cs sent them
+    #[default]
+    AsciiTcp,
+    /// Holding registers and coils from plc_modbus_map.json
+    ModbusTcp,
+    /// The ASCII lines over RS-232/RS-485 on plc_port_name
+    Serial,
+}
+
+impl PlcProtocol {
+    /// Parse "ascii", "modbus" or "serial"; anything else is ASCII
+    pub fn from_setting(value: &str) -> Self {
+        match value.trim().to_lowercase().as_str() {
+            "modbus" => PlcProtocol::ModbusTcp,
+            "serial" => PlcProtocol::Serial,
+            _ => PlcProtocol::AsciiTcp,
+        }
+    }
+
+    pub fn as_setting(&self) -> &'static str {
+        match self {
+            PlcProtocol::AsciiTcp => "ascii",
+            PlcProtocol::ModbusTcp => "modbus",
+            PlcProtocol::Serial => "serial",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PlcProtocol::AsciiTcp => "ASCII TCP",
+            PlcProtocol::ModbusTcp => "Modbus TCP",
+            PlcProtocol::Serial => "Serial (RS-232/485)",
+        }
+    }
+}
+
+/// One way of getting water commands to the PLC
+///
+/// `PlcClient` owns the command queue and reconnection; a transport owns the
+/// connection and whatever its protocol needs to confirm delivery.
+pub trait PlcTransport: Send + Sync {
+    /// Protocol and address, for logs
+    fn describe(&self) -> String;
+
+    /// Open the connection, dropping any existing one first
+    fn connect(&self, timeout_ms: u64) -> BoxFuture<'_, Result<()>>;
+
+    fn disconnect(&self) -> BoxFuture<'_, ()>;
+
+    fn is_connected(&self) -> bool;
+
+    /// Send one batch of "XXX-YYY" water commands
+    fn send_batch<'a>(&'a self, commands: &'a [String]) -> BoxFuture<'a, Result<()>>;
+
+    /// Heartbeats and retries, called every pass of the send loop
+    ///
+    /// Returns an error when the link has gone down.
+    fn service(&self) -> BoxFuture<'_, Result<()>>;
+
+    /// Link events since the last call, oldest first
+    fn take_events(&self) -> Vec<PlcEvent>;
+
+    /// Batches sent and still waiting for an acknowledgement
+    fn unacknowledged(&self) -> usize {
+        0
+    }
+
+    /// Batches that were never acknowledged before the link was reopened,
+    /// oldest first; the sender replays or drops them by its policy
+    fn take_unconfirmed(&self) -> Vec<Vec<String>> {
+        Vec::new()
+    }
+}
@@ -0,0 +1,6 @@
+pub mod source;
+pub mod weather_gov;
+pub mod wind;
+
+pub use source::{parse_wind_line, poll, WindSource, WindSourceKind};
+pub use wind::{WindAlert, WindBand, WindLimit, WindMonitor, WindReading};
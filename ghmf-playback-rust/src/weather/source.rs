@@ -0,0 +1,266 @@
+use std::future::Future;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::time::Duration;
+use anyhow::{Context, Result};
+use crossbeam_channel::Sender;
+use tokio::io::{AsyncBufReadExt, BufReader as AsyncBufReader};
+use tokio::net::TcpStream;
+use super::weather_gov;
+use super::wind::WindReading;
+
+/// Boxed future, so sources can sit behind `dyn WindSource`
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// How long a local anemometer has to produce a line
+const LINE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where wind readings come from, picked in Settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WindSourceKind {
+    /// No wind limiting
+    #[default]
+    Off,
+    /// The weather.gov hourly forecast for the fountain
+    WeatherGov,
+    /// An anemometer sending "speed[,gust]" lines over TCP
+    Tcp,
+    /// The same lines from an anemometer on a serial port
+    Serial,
+    /// The first line of a text file, for testing
+    File,
+}
+
+impl WindSourceKind {
+    /// Parse "off", "weather_gov", "tcp", "serial" or "file"; anything else is off
+    pub fn from_setting(value: &str) -> Self {
+        match value.trim().to_lowercase().as_str() {
+            "weather_gov" => WindSourceKind::WeatherGov,
+            "tcp" => WindSourceKind::Tcp,
+            "serial" => WindSourceKind::Serial,
+            "file" => WindSourceKind::File,
+            _ => WindSourceKind::Off,
+        }
+    }
+
+    pub fn as_setting(&self) -> &'static str {
+        match self {
+            WindSourceKind::Off => "off",
+            WindSourceKind::WeatherGov => "weather_gov",
+            WindSourceKind::Tcp => "tcp",
+            WindSourceKind::Serial => "serial",
+            WindSourceKind::File => "file",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WindSourceKind::Off => "Off",
+            WindSourceKind::WeatherGov => "weather.gov forecast",
+            WindSourceKind::Tcp => "Anemometer (TCP)",
+            WindSourceKind::Serial => "Anemometer (serial)",
+            WindSourceKind::File => "File (testing)",
+        }
+    }
+
+    /// What `wind_source_address` holds for this source
+    pub fn address_hint(&self) -> &'static str {
+        match self {
+            WindSourceKind::Off | WindSourceKind::WeatherGov => "",
+            WindSourceKind::Tcp => "host:port",
+            WindSourceKind::Serial => "port name",
+            WindSourceKind::File => "file path",
+        }
+    }
+
+    /// Build the source; None when wind limiting is off
+    pub fn build(&self, address: &str) -> Option<Box<dyn WindSource>> {
+        let address = address.trim().to_string();
+        match self {
+            WindSourceKind::Off => None,
+            WindSourceKind::WeatherGov => Some(Box::new(WeatherGovSource::new(weather_gov::GRAND_HAVEN))),
+            WindSourceKind::Tcp => Some(Box::new(TcpAnemometer { address })),
+            WindSourceKind::Serial => Some(Box::new(SerialAnemometer { port_name: address, baud_rate: 9600 })),
+            WindSourceKind::File => Some(Box::new(FileSource { path: PathBuf::from(address) })),
+        }
+    }
+}
+
+/// Somewhere to read the current wind from
+pub trait WindSource: Send + Sync {
+    /// Source and address, for logs
+    fn describe(&self) -> String;
+
+    fn read(&self) -> BoxFuture<'_, Result<WindReading>>;
+}
+
+/// Parse an anemometer line: "12.5" or "12.5,18" (sustained, gust in mph)
+///
+/// NaN, infinite and negative values are errors rather than readings, so a
+/// faulty sensor shows up as a source error instead of calm air.
+pub fn parse_wind_line(line: &str) -> Result<WindReading> {
+    let mph = |field: &str, what: &str| {
+        field.parse::<f32>()
+            .ok()
+            .filter(|mph| mph.is_finite() && *mph >= 0.0)
+            .with_context(|| format!("Bad {} in '{}'", what, line.trim()))
+    };
+    let mut fields = line.trim().split([',', ' ']).filter(|field| !field.is_empty());
+    let speed_mph = mph(fields.next().context("Empty wind reading")?, "wind speed")?;
+    let gust_mph = fields.next().map(|field| mph(field, "gust")).transpose()?;
+    Ok(WindReading { speed_mph, gust_mph })
+}
+
+/// The current hour of the weather.gov forecast
+///
+/// Forecasts change hourly and say nothing about the fountain itself, so
+/// this is a fallback for sites without an anemometer.
+pub struct WeatherGovSource {
+    lat: f64,
+    lon: f64,
+}
+
+impl WeatherGovSource {
+    pub fn new((lat, lon): (f64, f64)) -> Self {
+        Self { lat, lon }
+    }
+}
+
+impl WindSource for WeatherGovSource {
+    fn describe(&self) -> String {
+        format!("weather.gov forecast for {},{}", self.lat, self.lon)
+    }
+
+    fn read(&self) -> BoxFuture<'_, Result<WindReading>> {
+        Box::pin(async move {
+            let periods = weather_gov::fetch_hourly(self.lat, self.lon).await?;
+            let current = &periods[0];
+            let speed_mph = current.wind_mph()
+                .with_context(|| format!("Unreadable forecast wind '{}'", current.wind_speed))?;
+            Ok(WindReading { speed_mph, gust_mph: current.gust_mph() })
+        })
+    }
+}
+
+/// An anemometer that streams readings to whoever connects
+pub struct TcpAnemometer {
+    address: String,
+}
+
+impl WindSource for TcpAnemometer {
+    fn describe(&self) -> String {
+        format!("anemometer at {}", self.address)
+    }
+
+    fn read(&self) -> BoxFuture<'_, Result<WindReading>> {
+        Box::pin(async move {
+            let line = tokio::time::timeout(LINE_TIMEOUT, async {
+                let stream = TcpStream::connect(&self.address).await
+                    .with_context(|| format!("Failed to connect to anemometer at {}", self.address))?;
+                let mut line = String::new();
+                AsyncBufReader::new(stream).read_line(&mut line).await?;
+                anyhow::Ok(line)
+            })
+            .await
+            .context("Anemometer sent nothing")??;
+            parse_wind_line(&line)
+        })
+    }
+}
+
+/// An anemometer on a serial port, 8N1
+pub struct SerialAnemometer {
+    port_name: String,
+    baud_rate: u32,
+}
+
+impl WindSource for SerialAnemometer {
+    fn describe(&self) -> String {
+        format!("anemometer on {} at {} baud", self.port_name, self.baud_rate)
+    }
+
+    fn read(&self) -> BoxFuture<'_, Result<WindReading>> {
+        let port_name = self.port_name.clone();
+        let baud_rate = self.baud_rate;
+        Box::pin(async move {
+            let line = tokio::task::spawn_blocking(move || -> Result<String> {
+                let port = serialport::new(&port_name, baud_rate)
+                    .timeout(LINE_TIMEOUT)
+                    .open()
+                    .with_context(|| format!("Failed to open {}", port_name))?;
+                let mut reader = BufReader::new(port);
+                let mut line = String::new();
+                // The first line may start mid-reading; use the second
+                reader.read_line(&mut line)?;
+                line.clear();
+                reader.read_line(&mut line)?;
+                Ok(line)
+            })
+            .await??;
+            parse_wind_line(&line)
+        })
+    }
+}
+
+/// A text file whose first line is a reading, so limits can be tried out
+/// by editing it
+pub struct FileSource {
+    path: PathBuf,
+}
+
+impl WindSource for FileSource {
+    fn describe(&self) -> String {
+        format!("file {}", self.path.display())
+    }
+
+    fn read(&self) -> BoxFuture<'_, Result<WindReading>> {
+        Box::pin(async move {
+            let text = tokio::fs::read_to_string(&self.path).await
+                .with_context(|| format!("Failed to read {}", self.path.display()))?;
+            parse_wind_line(text.lines().next().unwrap_or(""))
+        })
+    }
+}
+
+/// Read `source` every `interval` until the receiver goes away
+///
+/// Errors are sent too, so the operator hears about a dead source.
+pub async fn poll(source: Box<dyn WindSource>, interval: Duration, readings: Sender<Result<WindReading, String>>) {
+    tracing::info!("Reading wind from {}", source.describe());
+    loop {
+        let reading = source.read().await.map_err(|e| format!("{:#}", e));
+        if readings.send(reading).is_err() {
+            break;
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_wind_line() {
+        assert_eq!(parse_wind_line("12.5\r\n").unwrap(), WindReading { speed_mph: 12.5, gust_mph: None });
+        assert_eq!(parse_wind_line("12.5, 18").unwrap(), WindReading { speed_mph: 12.5, gust_mph: Some(18.0) });
+        assert!(parse_wind_line("").is_err());
+        assert!(parse_wind_line("fast,18").is_err());
+        assert!(parse_wind_line("NaN").is_err());
+        assert!(parse_wind_line("inf,18").is_err());
+        assert!(parse_wind_line("-3").is_err());
+        assert!(parse_wind_line("12,-1").is_err());
+        assert!(parse_wind_line("12,nan").is_err());
+        assert_eq!(WindSourceKind::from_setting("Weather_Gov"), WindSourceKind::WeatherGov);
+    }
+
+    #[tokio::test]
+    async fn test_file_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wind.txt");
+        std::fs::write(&path, "22,31\nignored\n").unwrap();
+        let source = WindSourceKind::File.build(path.to_str().unwrap()).unwrap();
+        assert_eq!(source.read().await.unwrap(), WindReading { speed_mph: 22.0, gust_mph: Some(31.0) });
+    }
+}
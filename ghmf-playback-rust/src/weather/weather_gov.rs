@@ -0,0 +1,112 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+/// Grand Haven, MI
+pub const GRAND_HAVEN: (f64, f64) = (43.0631, -86.2284);
+
+const USER_AGENT: &str = "GHMF-Playback/2.0 (contact@example.com)";
+
+// Weather.gov API structures
+#[derive(Deserialize, Debug)]
+struct PointsResponse {
+    properties: PointsProperties,
+}
+
+#[derive(Deserialize, Debug)]
+struct PointsProperties {
+    #[serde(rename = "forecastHourly")]
+    forecast_hourly: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct ForecastResponse {
+    properties: ForecastProperties,
+}
+
+#[derive(Deserialize, Debug)]
+struct ForecastProperties {
+    periods: Vec<ForecastPeriod>,
+}
+
+/// One hour of the weather.gov hourly forecast
+#[derive(Deserialize, Debug, Clone)]
+pub struct ForecastPeriod {
+    pub temperature: i32,
+    #[serde(rename = "shortForecast")]
+    pub short_forecast: String,
+    #[serde(rename = "startTime")]
+    pub start_time: String,
+    #[serde(rename = "probabilityOfPrecipitation")]
+    pub probability_of_precipitation: Option<ProbabilityValue>,
+    /// e.g. "5 mph" or "10 to 15 mph"
+    #[serde(rename = "windSpeed")]
+    pub wind_speed: String,
+    /// Only present when gusts are forecast, e.g. "25 mph"
+    #[serde(rename = "windGust", default)]
+    pub wind_gust: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ProbabilityValue {
+    pub value: Option<u32>,
+}
+
+impl ForecastPeriod {
+    /// Sustained wind in mph; the top of a "10 to 15 mph" range
+    pub fn wind_mph(&self) -> Option<f32> {
+        parse_mph(&self.wind_speed)
+    }
+
+    pub fn gust_mph(&self) -> Option<f32> {
+        self.wind_gust.as_deref().and_then(parse_mph)
+    }
+}
+
+/// Largest number in a weather.gov wind string
+fn parse_mph(text: &str) -> Option<f32> {
+    text.split_whitespace()
+        .filter_map(|word| word.parse::<f32>().ok())
+        .reduce(f32::max)
+}
+
+/// Fetch the hourly forecast for a location, current hour first
+pub async fn fetch_hourly(lat: f64, lon: f64) -> Result<Vec<ForecastPeriod>> {
+    let client = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .build()?;
+
+    // Step 1: Get the forecast URL for this location
+    let points_url = format!("https://api.weather.gov/points/{},{}", lat, lon);
+    let points_response: PointsResponse = client.get(&points_url)
+        .send()
+        .await?
+        .json()
+        .await
+        .context("Unexpected weather.gov points response")?;
+
+    // Step 2: Get hourly forecast
+    let forecast_response: ForecastResponse = client.get(&points_response.properties.forecast_hourly)
+        .send()
+        .await?
+        .json()
+        .await
+        .context("Unexpected weather.gov forecast response")?;
+
+    let periods = forecast_response.properties.periods;
+    if periods.is_empty() {
+        anyhow::bail!("No forecast data available");
+    }
+    Ok(periods)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mph() {
+        assert_eq!(parse_mph("5 mph"), Some(5.0));
+        assert_eq!(parse_mph("10 to 15 mph"), Some(15.0));
+        assert_eq!(parse_mph("calm"), None);
+    }
+}
@@ -0,0 +1,238 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+
+/// Wind at the fountain, in mph
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindReading {
+    pub speed_mph: f32,
+    pub gust_mph: Option<f32>,
+}
+
+impl fmt::Display for WindReading {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.0} mph", self.speed_mph)?;
+        if let Some(gust) = self.gust_mph {
+            write!(f, ", gusts {:.0}", gust)?;
+        }
+        Ok(())
+    }
+}
+
+fn default_height_scale() -> f32 {
+    1.0
+}
+
+/// A wind level and what it does to water heights, from `[[wind_bands]]`
+/// in the settings file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindBand {
+    pub name: String,
+    /// Sustained wind that puts the show in this band
+    pub wind_mph: f32,
+    /// Gusts that do the same, whatever the sustained wind
+    #[serde(default)]
+    pub gust_mph: Option<f32>,
+    /// Multiplier for height commands; 0 stops the jets
+    #[serde(default = "default_height_scale")]
+    pub height_scale: f32,
+    /// Cap on height commands after scaling
+    #[serde(default)]
+    pub max_height: Option<u16>,
+}
+
+impl WindBand {
+    fn applies(&self, reading: &WindReading) -> bool {
+        reading.speed_mph >= self.wind_mph
+            || matches!((self.gust_mph, reading.gust_mph), (Some(limit), Some(gust)) if gust >= limit)
+    }
+
+    pub fn limit(&self) -> WindLimit {
+        WindLimit {
+            scale: self.height_scale.clamp(0.0, 1.0),
+            max: self.max_height,
+            reason: format!("wind {}", self.name),
+        }
+    }
+}
+
+/// What the water guard does to height commands in the current wind
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindLimit {
+    pub scale: f32,
+    pub max: Option<u16>,
+    /// Shown with every command it changes
+    pub reason: String,
+}
+
+/// Something the operator needs to hear about the wind
+#[derive(Debug, Clone, PartialEq)]
+pub enum WindAlert {
+    /// A stricter band took effect
+    Raised { band: String, reading: WindReading },
+    /// The wind has dropped and stayed down; `band` is None when calm
+    Lowered { band: Option<String>, reading: WindReading },
+    /// No reading for too long; the current band is kept
+    Stale { since: Duration },
+    /// Readings are arriving again
+    Restored,
+    /// The source failed to give a reading
+    SourceError(String),
+}
+
+impl WindAlert {
+    /// True for alerts that mean heights are being held down or the wind
+    /// is unknown
+    pub fn is_warning(&self) -> bool {
+        !matches!(self, WindAlert::Lowered { .. } | WindAlert::Restored)
+    }
+}
+
+impl fmt::Display for WindAlert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WindAlert::Raised { band, reading } => write!(f, "Wind {}: {} limits in effect", reading, band),
+            WindAlert::Lowered { band: Some(band), reading } => write!(f, "Wind {}: eased to {} limits", reading, band),
+            WindAlert::Lowered { band: None, reading } => write!(f, "Wind {}: height limits lifted", reading),
+            WindAlert::Stale { since } => write!(f, "No wind reading for {} s; keeping current limits", since.as_secs()),
+            WindAlert::Restored => write!(f, "Wind readings restored"),
+            WindAlert::SourceError(e) => write!(f, "Wind source error: {}", e),
+        }
+    }
+}
+
+/// Picks the wind band from readings as they arrive
+///
+/// A stricter band applies at once; a gentler one only after the wind has
+/// stayed below the current band for `release`, so a lull between gusts
+/// does not bring the jets back up. A silent source keeps the band it had.
+#[derive(Debug, Clone)]
+pub struct WindMonitor {
+    /// Calmest first
+    bands: Vec<WindBand>,
+    release: Duration,
+    stale_after: Duration,
+    /// Index into `bands` plus one; 0 is calm
+    level: usize,
+    reading: Option<(WindReading, Instant)>,
+    /// The lower level readings have asked for, and since when
+    lowering: Option<(usize, Instant)>,
+    stale: bool,
+}
+
+impl WindMonitor {
+    pub fn new(mut bands: Vec<WindBand>, release: Duration, stale_after: Duration) -> Self {
+        bands.sort_by(|a, b| a.wind_mph.total_cmp(&b.wind_mph));
+        Self { bands, release, stale_after, level: 0, reading: None, lowering: None, stale: false }
+    }
+
+    /// True when there are bands to apply
+    pub fn is_enabled(&self) -> bool {
+        !self.bands.is_empty()
+    }
+
+    pub fn band(&self) -> Option<&WindBand> {
+        self.level.checked_sub(1).map(|index| &self.bands[index])
+    }
+
+    /// The height limit for the current band, if any
+    pub fn limit(&self) -> Option<WindLimit> {
+        self.band().map(WindBand::limit)
+    }
+
+    pub fn reading(&self) -> Option<WindReading> {
+        self.reading.map(|(reading, _)| reading)
+    }
+
+    /// Take a new reading; returns an alert when the band changes
+    pub fn update(&mut self, reading: WindReading, now: Instant) -> Option<WindAlert> {
+        let restored = std::mem::take(&mut self.stale);
+        self.reading = Some((reading, now));
+
+        let wanted = self.bands.iter().rposition(|band| band.applies(&reading)).map_or(0, |index| index + 1);
+        if wanted > self.level {
+            self.level = wanted;
+            self.lowering = None;
+            return Some(WindAlert::Raised { band: self.bands[wanted - 1].name.clone(), reading });
+        }
+        if wanted == self.level {
+            self.lowering = None;
+        } else {
+            // Ease off to the strictest level asked for during the release time
+            let (level, since) = match self.lowering {
+                Some((level, since)) => (level.max(wanted), since),
+                None => (wanted, now),
+            };
+            if now.duration_since(since) >= self.release {
+                self.level = level;
+                self.lowering = None;
+                return Some(WindAlert::Lowered { band: self.band().map(|band| band.name.clone()), reading });
+            }
+            self.lowering = Some((level, since));
+        }
+        restored.then_some(WindAlert::Restored)
+    }
+
+    /// Alert once when readings stop arriving
+    pub fn check_stale(&mut self, now: Instant) -> Option<WindAlert> {
+        let since = match self.reading {
+            Some((_, at)) => now.duration_since(at),
+            None => return None,
+        };
+        if since < self.stale_after || self.stale {
+            return None;
+        }
+        self.stale = true;
+        Some(WindAlert::Stale { since })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn band(name: &str, wind_mph: f32, gust_mph: Option<f32>, height_scale: f32) -> WindBand {
+        WindBand { name: name.to_string(), wind_mph, gust_mph, height_scale, max_height: None }
+    }
+
+    fn wind(speed_mph: f32, gust_mph: Option<f32>) -> WindReading {
+        WindReading { speed_mph, gust_mph }
+    }
+
+    #[test]
+    fn test_bands_raise_at_once_and_release_slowly() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut monitor = WindMonitor::new(
+            vec![band("High", 25.0, Some(35.0), 0.0), band("Caution", 15.0, Some(25.0), 0.75)],
+            Duration::from_secs(60),
+            Duration::from_secs(300),
+        );
+
+        assert_eq!(monitor.update(wind(10.0, None), at(0)), None);
+        assert!(monitor.limit().is_none());
+
+        // A gust alone is enough
+        assert_eq!(monitor.update(wind(12.0, Some(26.0)), at(10)).unwrap().to_string(),
+            "Wind 12 mph, gusts 26: Caution limits in effect");
+        assert_eq!(monitor.limit().unwrap().scale, 0.75);
+        assert!(matches!(monitor.update(wind(27.0, None), at(20)), Some(WindAlert::Raised { .. })));
+
+        // Calm has to last a minute, and a Caution reading in between
+        // stops it easing all the way
+        assert_eq!(monitor.update(wind(5.0, None), at(30)), None);
+        assert_eq!(monitor.update(wind(16.0, None), at(60)), None);
+        assert_eq!(monitor.band().unwrap().name, "High");
+        let alert = monitor.update(wind(5.0, None), at(90)).unwrap();
+        assert_eq!(alert, WindAlert::Lowered { band: Some("Caution".to_string()), reading: wind(5.0, None) });
+
+        assert_eq!(monitor.update(wind(5.0, None), at(100)), None);
+        assert!(matches!(monitor.update(wind(5.0, None), at(160)), Some(WindAlert::Lowered { band: None, .. })));
+
+        // Silence is reported once, then the next reading restores it
+        assert_eq!(monitor.check_stale(at(400)), None);
+        assert!(matches!(monitor.check_stale(at(500)), Some(WindAlert::Stale { .. })));
+        assert_eq!(monitor.check_stale(at(600)), None);
+        assert_eq!(monitor.update(wind(5.0, None), at(610)), Some(WindAlert::Restored));
+    }
+}